@@ -11,7 +11,8 @@ command -v node >/dev/null 2>&1 || { echo "❌ Node.js is required but not insta
 # Build WASM core
 echo "📦 Building WASM core..."
 cd core
-wasm-pack build --target web --out-dir ../web/lib/wasm --release
+# Instrumentation hooks are for native tooling; leave them out of the web build
+wasm-pack build --target web --out-dir ../web/lib/wasm --release -- --no-default-features --features wasm
 cd ..
 
 # Copy WASM binary to public folder for runtime loading
@@ -0,0 +1,48 @@
+//! Frame throughput.
+//!
+//! Compare `cargo bench` with `cargo bench --no-default-features` to see
+//! what the `instrumentation` hooks cost when they are compiled in but
+//! idle.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use gbemu_core::GameBoy;
+
+/// A 32KB ROM that copies WRAM around in a loop with the LCD and APU on,
+/// so every frame exercises the CPU, bus, PPU and APU
+fn busy_rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    
+    // Entry point: nop; jp 0x0150
+    rom[0x100..0x104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+    
+    let program: &[u8] = &[
+        0x3E, 0x80, 0xE0, 0x26, // ld a, 0x80 ; ldh (NR52), a
+        0x3E, 0xF0, 0xE0, 0x12, // ld a, 0xF0 ; ldh (NR12), a
+        0x3E, 0x87, 0xE0, 0x14, // ld a, 0x87 ; ldh (NR14), a
+        0x21, 0x00, 0xC0,       // loop: ld hl, 0xC000
+        0x11, 0x00, 0xD0,       // ld de, 0xD000
+        0x2A,                   // copy: ld a, (hl+)
+        0x12,                   // ld (de), a
+        0x13,                   // inc de
+        0x7C,                   // ld a, h
+        0xFE, 0xD0,             // cp 0xD0
+        0x20, 0xF8,             // jr nz, copy
+        0x18, 0xF0,             // jr loop
+    ];
+    rom[0x150..0x150 + program.len()].copy_from_slice(program);
+    rom
+}
+
+fn run_frame(c: &mut Criterion) {
+    let mut gb = GameBoy::new(&busy_rom()).unwrap();
+    
+    c.bench_function("run_frame", |b| {
+        b.iter(|| {
+            black_box(gb.run_frame());
+            gb.clear_audio_buffer();
+        })
+    });
+}
+
+criterion_group!(benches, run_frame);
+criterion_main!(benches);
@@ -0,0 +1,64 @@
+//! Run a ROM without a window and report what it produced.
+//!
+//! ```text
+//! cargo run --example headless -- [ROM] [FRAMES] [OUT.png]
+//! ```
+//!
+//! Without a ROM the built-in demo cartridge runs. The last frame is
+//! saved as a PNG if a path is given.
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use gbemu_core::demo_rom::DEMO_ROM;
+use gbemu_core::pipeline::encode_png;
+use gbemu_core::{GameBoy, SAMPLE_RATE, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    
+    let rom = match args.first().filter(|path| path.as_str() != "-") {
+        Some(path) => match fs::read(path) {
+            Ok(rom) => rom,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => DEMO_ROM.to_vec(),
+    };
+    let frames: u64 = args.get(1).and_then(|n| n.parse().ok()).unwrap_or(600);
+    
+    let mut gb = match GameBoy::new(&rom) {
+        Ok(gb) => gb,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    
+    let mut samples = 0;
+    let mut events = 0;
+    for _ in 0..frames {
+        let output = gb.run_frame();
+        samples += output.audio.len() / 2;
+        events += output.events.len();
+    }
+    
+    println!("{} frames ({:.1} s emulated)", gb.frame_count(), gb.frame_count() as f64 / 59.7275);
+    println!("audio: {} samples at {} Hz", samples, SAMPLE_RATE);
+    println!("LCD events: {}", events);
+    println!("last frame hash: {:016x}", gb.frame_hash());
+    
+    if let Some(path) = args.get(2) {
+        let png = encode_png(gb.framebuffer(), SCREEN_WIDTH, SCREEN_HEIGHT);
+        if let Err(e) = fs::write(path, png) {
+            eprintln!("{}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+        println!("saved {}", path);
+    }
+    
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,63 @@
+//! Print an instruction trace with registers.
+//!
+//! ```text
+//! cargo run --example trace -- [ROM] [COUNT]
+//! ```
+//!
+//! Without a ROM the built-in demo cartridge runs.
+
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+use gbemu_core::demo_rom::DEMO_ROM;
+use gbemu_core::GameBoy;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    
+    let rom = match args.first().filter(|path| path.as_str() != "-") {
+        Some(path) => match fs::read(path) {
+            Ok(rom) => rom,
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => DEMO_ROM.to_vec(),
+    };
+    let count: usize = args.get(1).and_then(|n| n.parse().ok()).unwrap_or(100);
+    
+    let mut gb = match GameBoy::new(&rom) {
+        Ok(gb) => gb,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    
+    for _ in 0..count {
+        // Disassemble before stepping, while the instruction's bank is
+        // still mapped
+        let pc = gb.cpu.regs.pc;
+        let (text, _) = gb.disassemble(pc);
+        let Some(step) = gb.step_iter().next() else {
+            println!("CPU idle for a whole frame, stopping");
+            break;
+        };
+        // An interrupt dispatched first runs its handler instead
+        let text = if step.pc == pc { text } else { gb.disassemble(step.pc).0 };
+        print_step(&step, &text);
+    }
+    
+    ExitCode::SUCCESS
+}
+
+fn print_step(step: &gbemu_core::ExecutedInstruction, text: &str) {
+    let r = &step.regs_after;
+    println!(
+        "{:02X}:{:04X}  {:<16} {:>2}  A={:02X} F={:02X} BC={:04X} DE={:04X} HL={:04X} SP={:04X}",
+        step.bank, step.pc, text, step.cycles,
+        r.a, r.f.bits(), r.bc(), r.de(), r.hl(), r.sp,
+    );
+}
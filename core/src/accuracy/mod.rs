@@ -0,0 +1,134 @@
+//! # Accuracy
+//!
+//! Known results against public test suites, and the hardware features
+//! this build implements, kept up to date by hand as the behaviors they
+//! check change. Frontends can show them and bug reports can include them.
+
+use serde::Serialize;
+
+use crate::cartridge::MbcType;
+
+/// How the emulator does on a test suite
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Conformance {
+    /// Every test passes
+    Pass,
+    
+    /// Some tests pass
+    Partial,
+    
+    /// Known to fail
+    Fail,
+    
+    /// Not checked against the suite (see the notes)
+    Untested,
+}
+
+/// Result for one test suite
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteResult {
+    pub suite: &'static str,
+    pub conformance: Conformance,
+    pub notes: &'static str,
+}
+
+/// Conformance level of this build (see `GameBoy::accuracy_report`)
+#[derive(Debug, Clone, Serialize)]
+pub struct AccuracyReport {
+    pub suites: Vec<SuiteResult>,
+}
+
+impl AccuracyReport {
+    /// Report for this build
+    pub fn current() -> Self {
+        Self {
+            suites: vec![
+                SuiteResult {
+                    suite: "dmg-acid2",
+                    conformance: Conformance::Untested,
+                    notes: "Object priority, window position and line counter, 8x16 \
+                            tile selection and LCDC.0 blanking are implemented; the \
+                            ROM isn't in tests/roms yet",
+                },
+                SuiteResult {
+                    suite: "cgb-acid2",
+                    conformance: Conformance::Untested,
+                    notes: "Palette RAM and every BG and object attribute (palette, \
+                            VRAM bank, flips, priority) are rendered; the ROM isn't \
+                            in tests/roms yet",
+                },
+            ],
+        }
+    }
+    
+    /// Suites that fully pass
+    pub fn passing(&self) -> impl Iterator<Item = &SuiteResult> {
+        self.suites.iter().filter(|result| result.conformance == Conformance::Pass)
+    }
+}
+
+/// How the PPU draws a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PpuRenderer {
+    /// Whole line drawn at once with fixed mode 3 timing
+    Scanline,
+    
+    /// Pixel FIFO with variable mode 3 timing
+    Fifo,
+}
+
+/// Hardware features implemented by this build (see `GameBoy::capabilities`)
+#[derive(Debug, Clone, Serialize)]
+pub struct Capabilities {
+    pub ppu: PpuRenderer,
+    
+    /// CGB double speed mode (KEY1 / STOP)
+    pub double_speed: bool,
+    
+    /// CGB general purpose and HBlank VRAM DMA
+    pub hdma: bool,
+    
+    /// CGB color palettes and tile attributes in rendered output
+    pub cgb_color: bool,
+    
+    /// Supported cartridge controllers
+    pub mbcs: Vec<MbcType>,
+    
+    /// MBC3 real time clock
+    pub rtc: bool,
+    
+    /// SGB border, palette and multiplayer commands
+    pub sgb: bool,
+    
+    /// Link cable between two instances (see `Session`)
+    pub serial_link: bool,
+    
+    /// Running a boot ROM image instead of starting at post-boot state
+    pub boot_rom: bool,
+    
+    /// Per-instruction instrumentation (the `instrumentation` feature)
+    pub instrumentation: bool,
+}
+
+impl Capabilities {
+    /// Capabilities of this build
+    pub fn current() -> Self {
+        Self {
+            ppu: PpuRenderer::Scanline,
+            double_speed: true,
+            hdma: true,
+            cgb_color: true,
+            mbcs: vec![MbcType::None, MbcType::Mbc1, MbcType::Mbc2, MbcType::Mbc3, MbcType::Mbc5],
+            rtc: true,
+            sgb: false,
+            serial_link: true,
+            boot_rom: true,
+            instrumentation: cfg!(feature = "instrumentation"),
+        }
+    }
+    
+    /// Whether cartridges with this controller can run
+    pub fn supports_mbc(&self, mbc: MbcType) -> bool {
+        self.mbcs.contains(&mbc)
+    }
+}
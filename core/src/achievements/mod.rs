@@ -0,0 +1,146 @@
+//! # Achievements
+//!
+//! The memory layout RetroAchievements (rcheevos) uses for Game Boy and
+//! Game Boy Color, so frontends can hand achievement runtimes a read
+//! callback without working out the address translation themselves.
+//!
+//! Addresses 0x0000-0xFFFF are the CPU address space as currently banked.
+//! On CGB, WRAM banks 2-7 follow at 0x10000-0x15FFF so achievements can
+//! watch them whichever bank SVBK has selected when the frame ends.
+//!
+//! Set an `AchievementHook` with `GameBoy::set_achievement_hook` to have it
+//! called once per frame (where rcheevos expects `rc_client_do_frame`).
+
+use crate::GameBoy;
+
+/// rcheevos console ID for Game Boy
+pub const CONSOLE_GAMEBOY: u32 = 4;
+
+/// rcheevos console ID for Game Boy Color
+pub const CONSOLE_GAMEBOY_COLOR: u32 = 6;
+
+/// Start of WRAM banks 2-7 in the flat address space (CGB only)
+const WRAM_BANKS_START: u32 = 0x10000;
+
+/// Size of one WRAM bank
+const WRAM_BANK_SIZE: u32 = 0x1000;
+
+/// What a memory region holds (rcheevos `RC_MEMORY_TYPE_*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryKind {
+    ReadOnly,
+    HardwareController,
+    VideoRam,
+    SaveRam,
+    SystemRam,
+    
+    /// Mirror of another region (`real_address` gives the original)
+    VirtualRam,
+}
+
+/// One region of the flat address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub start: u32,
+    
+    /// Last address in the region (inclusive)
+    pub end: u32,
+    
+    /// Address the region's first byte really lives at
+    pub real_address: u32,
+    
+    pub kind: MemoryKind,
+    pub description: &'static str,
+}
+
+const fn region(start: u32, end: u32, real_address: u32, kind: MemoryKind, description: &'static str) -> MemoryRegion {
+    MemoryRegion { start, end, real_address, kind, description }
+}
+
+/// Game Boy Color regions, in rcheevos order
+pub const GAMEBOY_COLOR_REGIONS: &[MemoryRegion] = &[
+    region(0x0000, 0x00FF, 0x0000, MemoryKind::HardwareController, "Interrupt vector"),
+    region(0x0100, 0x014F, 0x0100, MemoryKind::ReadOnly, "Cartridge header"),
+    region(0x0150, 0x3FFF, 0x0150, MemoryKind::ReadOnly, "Cartridge ROM (fixed)"),
+    region(0x4000, 0x7FFF, 0x4000, MemoryKind::ReadOnly, "Cartridge ROM (paged)"),
+    region(0x8000, 0x97FF, 0x8000, MemoryKind::VideoRam, "Tile RAM"),
+    region(0x9800, 0x9BFF, 0x9800, MemoryKind::VideoRam, "BG1 map data"),
+    region(0x9C00, 0x9FFF, 0x9C00, MemoryKind::VideoRam, "BG2 map data"),
+    region(0xA000, 0xBFFF, 0xA000, MemoryKind::SaveRam, "Cartridge RAM"),
+    region(0xC000, 0xCFFF, 0xC000, MemoryKind::SystemRam, "System RAM (fixed)"),
+    region(0xD000, 0xDFFF, 0xD000, MemoryKind::SystemRam, "System RAM (bank 1)"),
+    region(0xE000, 0xFDFF, 0xC000, MemoryKind::VirtualRam, "Echo RAM"),
+    region(0xFE00, 0xFE9F, 0xFE00, MemoryKind::VideoRam, "Sprite RAM"),
+    region(0xFEA0, 0xFEFF, 0xFEA0, MemoryKind::ReadOnly, "Unused"),
+    region(0xFF00, 0xFF7F, 0xFF00, MemoryKind::HardwareController, "Hardware I/O"),
+    region(0xFF80, 0xFFFE, 0xFF80, MemoryKind::SystemRam, "Quick RAM"),
+    region(0xFFFF, 0xFFFF, 0xFFFF, MemoryKind::HardwareController, "Interrupt enable"),
+    region(0x10000, 0x15FFF, 0x10000, MemoryKind::SystemRam, "System RAM (banks 2-7)"),
+];
+
+/// Game Boy regions: the Game Boy Color ones without WRAM banks 2-7
+pub const GAMEBOY_REGIONS: &[MemoryRegion] = GAMEBOY_COLOR_REGIONS.split_at(GAMEBOY_COLOR_REGIONS.len() - 1).0;
+
+/// Called once per frame with the memory achievements are evaluated on
+pub trait AchievementHook {
+    fn achievement_tick(&mut self, memory: &AchievementMemory<'_>);
+}
+
+/// Read-only view of a `GameBoy` in the rcheevos address space. Reads
+/// have no side effects on the emulated bus.
+pub struct AchievementMemory<'a> {
+    gb: &'a GameBoy,
+}
+
+impl<'a> AchievementMemory<'a> {
+    pub fn new(gb: &'a GameBoy) -> Self {
+        Self { gb }
+    }
+    
+    fn is_cgb(&self) -> bool {
+        self.gb.model().is_cgb()
+    }
+    
+    /// rcheevos console ID for the running model
+    pub fn console_id(&self) -> u32 {
+        if self.is_cgb() { CONSOLE_GAMEBOY_COLOR } else { CONSOLE_GAMEBOY }
+    }
+    
+    /// Regions of the address space for the running model
+    pub fn regions(&self) -> &'static [MemoryRegion] {
+        if self.is_cgb() { GAMEBOY_COLOR_REGIONS } else { GAMEBOY_REGIONS }
+    }
+    
+    /// Size of the address space (one past the last valid address)
+    pub fn size(&self) -> u32 {
+        self.regions().last().map_or(0, |region| region.end + 1)
+    }
+    
+    /// Read one byte, or `None` past the end of the address space
+    pub fn read(&self, address: u32) -> Option<u8> {
+        if let Ok(addr) = u16::try_from(address) {
+            return Some(self.gb.mmu.peek_byte(addr));
+        }
+        if address >= self.size() {
+            return None;
+        }
+        
+        // Banks 2-7 sit in WRAM after banks 0 and 1
+        let offset = (address - WRAM_BANKS_START + 2 * WRAM_BANK_SIZE) as usize;
+        self.gb.mmu.wram().get(offset).copied()
+    }
+    
+    /// Fill `buffer` from `address` on, stopping at the end of the address
+    /// space; returns the number of bytes read (rcheevos' read callback)
+    pub fn read_block(&self, address: u32, buffer: &mut [u8]) -> usize {
+        let mut count = 0;
+        for (byte, address) in buffer.iter_mut().zip(address..) {
+            match self.read(address) {
+                Some(value) => *byte = value,
+                None => break,
+            }
+            count += 1;
+        }
+        count
+    }
+}
@@ -0,0 +1,511 @@
+//! Sound channels assembled from the shared units
+//!
+//! Register offsets passed to `read_register`/`write_register` are relative
+//! to the channel's NRx0 address (0 = NRx0 ... 4 = NRx4).
+
+use serde::{Serialize, Deserialize};
+
+use super::debug::ChannelDebug;
+use super::units::{Duty, Envelope, LengthCounter, Sweep};
+use super::ChannelStatus;
+
+/// Square wave channel (channel 1 with sweep, channel 2 without)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SquareChannel {
+    pub(super) enabled: bool,
+    length: LengthCounter<64>,
+    duty: Duty,
+    envelope: Envelope,
+    sweep: Option<Sweep>,
+    frequency: u16,
+    frequency_timer: u32,
+}
+
+impl SquareChannel {
+    /// Create a square channel, optionally with the NR10 sweep unit
+    pub fn new(with_sweep: bool) -> Self {
+        Self {
+            sweep: with_sweep.then(Sweep::default),
+            ..Self::default()
+        }
+    }
+    
+    pub fn step(&mut self) {
+        if self.frequency_timer > 0 {
+            self.frequency_timer -= 1;
+        }
+        
+        if self.frequency_timer == 0 {
+            self.frequency_timer = (2048 - self.frequency as u32) * 4;
+            self.duty.advance();
+        }
+    }
+    
+    pub fn output(&self) -> f32 {
+        if !self.enabled || !self.envelope.dac_enabled() {
+            return 0.0;
+        }
+        
+        let volume = self.envelope.volume as f32 / 15.0;
+        
+        if self.duty.high() { volume } else { -volume }
+    }
+    
+    /// 4-bit value currently fed to the DAC (PCM12/PCM34)
+    pub fn digital_output(&self) -> u8 {
+        if self.enabled && self.duty.high() { self.envelope.volume } else { 0 }
+    }
+    
+    pub fn status(&self) -> ChannelStatus {
+        ChannelStatus {
+            enabled: self.enabled,
+            frequency_hz: 131072.0 / (2048 - self.frequency as u32) as f32,
+            volume: self.envelope.volume,
+            duty: Some(self.duty.pattern),
+            ..ChannelStatus::default()
+        }
+    }
+    
+    pub fn debug(&self) -> ChannelDebug {
+        ChannelDebug {
+            dac_enabled: self.envelope.dac_enabled(),
+            frequency: Some(self.frequency),
+            frequency_timer: self.frequency_timer,
+            output_hz: self.status().frequency_hz,
+            envelope: Some((&self.envelope).into()),
+            sweep: self.sweep.as_ref().map(Into::into),
+            ..ChannelDebug::new(self.enabled, &self.length)
+        }
+    }
+    
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+    
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+    
+    pub fn clock_sweep(&mut self) {
+        if let Some(sweep) = self.sweep.as_mut() {
+            if !sweep.clock(&mut self.frequency) {
+                self.enabled = false;
+            }
+        }
+    }
+    
+    /// Clear the channel for NR52 power-off, keeping the length counter
+    /// if `keep_length` is set (DMG)
+    pub fn power_off(&mut self, keep_length: bool) {
+        let counter = self.length.counter;
+        *self = Self::new(self.sweep.is_some());
+        if keep_length {
+            self.length.counter = counter;
+        }
+    }
+    
+    /// Load the length counter from NR11/NR21 without touching the duty,
+    /// as a write does while the APU is powered off
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load((value & 0x3F) as u16);
+    }
+    
+    fn trigger(&mut self, extra_clock: bool) {
+        self.enabled = self.envelope.dac_enabled();
+        self.length.trigger(extra_clock);
+        self.frequency_timer = (2048 - self.frequency as u32) * 4;
+        self.envelope.trigger();
+        
+        if let Some(sweep) = self.sweep.as_mut() {
+            if !sweep.trigger(self.frequency) {
+                self.enabled = false;
+            }
+        }
+    }
+    
+    pub fn read_register(&self, reg: u8) -> u8 {
+        match reg {
+            0 => self.sweep.as_ref().map_or(0xFF, Sweep::read),
+            1 => (self.duty.pattern << 6) | 0x3F,
+            2 => self.envelope.read(),
+            // Frequency low is write-only
+            3 => 0xFF,
+            4 => 0xBF | (if self.length.enabled { 0x40 } else { 0 }),
+            _ => 0xFF,
+        }
+    }
+    
+    pub fn write_register(&mut self, reg: u8, value: u8, extra_clock: bool) {
+        match reg {
+            0 => {
+                if let Some(sweep) = self.sweep.as_mut() {
+                    sweep.write(value);
+                }
+            }
+            1 => {
+                self.duty.pattern = (value >> 6) & 0x03;
+                self.length.load((value & 0x3F) as u16);
+            }
+            2 => {
+                self.envelope.write(value, self.enabled);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => {
+                self.frequency = (self.frequency & 0x700) | value as u16;
+            }
+            4 => {
+                self.frequency = (self.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
+                if self.length.set_enabled(value & 0x40 != 0, extra_clock) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.trigger(extra_clock);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Wave channel (channel 3)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct WaveChannel {
+    pub(super) enabled: bool,
+    dac_enabled: bool,
+    length: LengthCounter<256>,
+    frequency: u16,
+    frequency_timer: u32,
+    volume_code: u8,
+    pub(super) sample_index: u8,
+    pub(super) wave_ram: [u8; 16],
+    
+    /// Cycles since the channel last fetched from wave RAM
+    pub(super) cycles_since_fetch: u32,
+}
+
+impl WaveChannel {
+    /// Clear the channel for NR52 power-off. Wave RAM is kept on every
+    /// model; the length counter only if `keep_length` is set (DMG).
+    pub fn power_off(&mut self, keep_length: bool) {
+        let counter = self.length.counter;
+        *self = Self {
+            wave_ram: self.wave_ram,
+            ..Self::default()
+        };
+        if keep_length {
+            self.length.counter = counter;
+        }
+    }
+    
+    /// Load the length counter from NR31
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load(value as u16);
+    }
+    
+    pub fn step(&mut self) {
+        self.cycles_since_fetch = self.cycles_since_fetch.saturating_add(1);
+        
+        if self.frequency_timer > 0 {
+            self.frequency_timer -= 1;
+        }
+        
+        if self.frequency_timer == 0 {
+            self.frequency_timer = (2048 - self.frequency as u32) * 2;
+            self.sample_index = (self.sample_index + 1) & 31;
+            self.cycles_since_fetch = 0;
+        }
+    }
+    
+    pub fn output(&self) -> f32 {
+        if !self.enabled || !self.dac_enabled {
+            return 0.0;
+        }
+        
+        let byte = self.wave_ram[(self.sample_index / 2) as usize];
+        let sample = if self.sample_index & 1 == 0 {
+            byte >> 4
+        } else {
+            byte & 0x0F
+        };
+        
+        let shifted = match self.volume_code {
+            0 => 0,
+            1 => sample,
+            2 => sample >> 1,
+            3 => sample >> 2,
+            _ => 0,
+        };
+        
+        (shifted as f32 / 7.5) - 1.0
+    }
+    
+    /// 4-bit value currently fed to the DAC (PCM12/PCM34)
+    pub fn digital_output(&self) -> u8 {
+        if !self.enabled {
+            return 0;
+        }
+        
+        let byte = self.wave_ram[(self.sample_index / 2) as usize];
+        let sample = if self.sample_index & 1 == 0 { byte >> 4 } else { byte & 0x0F };
+        
+        match self.volume_code {
+            1 => sample,
+            2 => sample >> 1,
+            3 => sample >> 2,
+            _ => 0,
+        }
+    }
+    
+    pub fn status(&self) -> ChannelStatus {
+        // Map the output level shift onto the envelope's 0-15 scale
+        let volume = match self.volume_code {
+            1 => 15,
+            2 => 7,
+            3 => 3,
+            _ => 0,
+        };
+        
+        ChannelStatus {
+            enabled: self.enabled,
+            frequency_hz: 65536.0 / (2048 - self.frequency as u32) as f32,
+            volume,
+            ..ChannelStatus::default()
+        }
+    }
+    
+    pub fn debug(&self) -> ChannelDebug {
+        ChannelDebug {
+            dac_enabled: self.dac_enabled,
+            frequency: Some(self.frequency),
+            frequency_timer: self.frequency_timer,
+            output_hz: self.status().frequency_hz,
+            ..ChannelDebug::new(self.enabled, &self.length)
+        }
+    }
+    
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+    
+    fn trigger(&mut self, extra_clock: bool) {
+        self.enabled = self.dac_enabled;
+        self.length.trigger(extra_clock);
+        self.frequency_timer = (2048 - self.frequency as u32) * 2;
+        self.sample_index = 0;
+    }
+    
+    pub fn read_register(&self, reg: u8) -> u8 {
+        match reg {
+            0 => 0x7F | (if self.dac_enabled { 0x80 } else { 0 }),
+            // Length and frequency low are write-only
+            1 | 3 => 0xFF,
+            2 => 0x9F | (self.volume_code << 5),
+            4 => 0xBF | (if self.length.enabled { 0x40 } else { 0 }),
+            _ => 0xFF,
+        }
+    }
+    
+    pub fn write_register(&mut self, reg: u8, value: u8, extra_clock: bool) {
+        match reg {
+            0 => {
+                self.dac_enabled = value & 0x80 != 0;
+                if !self.dac_enabled {
+                    self.enabled = false;
+                }
+            }
+            1 => {
+                self.length.load(value as u16);
+            }
+            2 => {
+                self.volume_code = (value >> 5) & 0x03;
+            }
+            3 => {
+                self.frequency = (self.frequency & 0x700) | value as u16;
+            }
+            4 => {
+                self.frequency = (self.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
+                if self.length.set_enabled(value & 0x40 != 0, extra_clock) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.trigger(extra_clock);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Noise channel (channel 4)
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NoiseChannel {
+    pub(super) enabled: bool,
+    length: LengthCounter<64>,
+    envelope: Envelope,
+    frequency_timer: u32,
+    lfsr: u16,
+    clock_shift: u8,
+    width_mode: bool,
+    divisor_code: u8,
+}
+
+impl Default for NoiseChannel {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            length: LengthCounter::default(),
+            envelope: Envelope::default(),
+            frequency_timer: 0,
+            lfsr: 0x7FFF,
+            clock_shift: 0,
+            width_mode: false,
+            divisor_code: 0,
+        }
+    }
+}
+
+impl NoiseChannel {
+    /// Clear the channel for NR52 power-off, keeping the length counter
+    /// if `keep_length` is set (DMG)
+    pub fn power_off(&mut self, keep_length: bool) {
+        let counter = self.length.counter;
+        *self = Self::default();
+        if keep_length {
+            self.length.counter = counter;
+        }
+    }
+    
+    /// Load the length counter from NR41
+    pub fn write_length(&mut self, value: u8) {
+        self.length.load((value & 0x3F) as u16);
+    }
+    
+    /// Frequency timer period selected by NR43
+    fn period(&self) -> u32 {
+        let divisor = match self.divisor_code {
+            0 => 8,
+            n => (n as u32) * 16,
+        };
+        divisor << self.clock_shift
+    }
+    
+    pub fn step(&mut self) {
+        if self.frequency_timer > 0 {
+            self.frequency_timer -= 1;
+        }
+        
+        if self.frequency_timer == 0 {
+            self.frequency_timer = self.period();
+            
+            // Clock LFSR
+            let xor_result = (self.lfsr & 1) ^ ((self.lfsr >> 1) & 1);
+            self.lfsr = (self.lfsr >> 1) | (xor_result << 14);
+            
+            if self.width_mode {
+                self.lfsr = (self.lfsr & !0x40) | (xor_result << 6);
+            }
+        }
+    }
+    
+    pub fn output(&self) -> f32 {
+        if !self.enabled || !self.envelope.dac_enabled() {
+            return 0.0;
+        }
+        
+        let volume = self.envelope.volume as f32 / 15.0;
+        
+        if self.lfsr & 1 == 0 { volume } else { -volume }
+    }
+    
+    /// 4-bit value currently fed to the DAC (PCM12/PCM34)
+    pub fn digital_output(&self) -> u8 {
+        if self.enabled && self.lfsr & 1 == 0 { self.envelope.volume } else { 0 }
+    }
+    
+    /// Status of the channel; the frequency is the LFSR clock rate
+    pub fn status(&self) -> ChannelStatus {
+        ChannelStatus {
+            enabled: self.enabled,
+            frequency_hz: 4_194_304.0 / self.period() as f32,
+            volume: self.envelope.volume,
+            ..ChannelStatus::default()
+        }
+    }
+    
+    pub fn debug(&self) -> ChannelDebug {
+        ChannelDebug {
+            dac_enabled: self.envelope.dac_enabled(),
+            frequency_timer: self.frequency_timer,
+            output_hz: self.status().frequency_hz,
+            envelope: Some((&self.envelope).into()),
+            ..ChannelDebug::new(self.enabled, &self.length)
+        }
+    }
+    
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+    
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock();
+    }
+    
+    fn trigger(&mut self, extra_clock: bool) {
+        self.enabled = self.envelope.dac_enabled();
+        self.length.trigger(extra_clock);
+        self.frequency_timer = self.period();
+        self.envelope.trigger();
+        self.lfsr = 0x7FFF;
+    }
+    
+    pub fn read_register(&self, reg: u8) -> u8 {
+        match reg {
+            // NR40 doesn't exist; length is write-only
+            0 | 1 => 0xFF,
+            2 => self.envelope.read(),
+            3 => {
+                (self.clock_shift << 4)
+                    | (if self.width_mode { 0x08 } else { 0 })
+                    | self.divisor_code
+            }
+            4 => 0xBF | (if self.length.enabled { 0x40 } else { 0 }),
+            _ => 0xFF,
+        }
+    }
+    
+    pub fn write_register(&mut self, reg: u8, value: u8, extra_clock: bool) {
+        match reg {
+            1 => {
+                self.length.load((value & 0x3F) as u16);
+            }
+            2 => {
+                self.envelope.write(value, self.enabled);
+                if !self.envelope.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            3 => {
+                self.clock_shift = (value >> 4) & 0x0F;
+                self.width_mode = value & 0x08 != 0;
+                self.divisor_code = value & 0x07;
+            }
+            4 => {
+                if self.length.set_enabled(value & 0x40 != 0, extra_clock) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.trigger(extra_clock);
+                }
+            }
+            _ => {}
+        }
+    }
+}
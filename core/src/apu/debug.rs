@@ -0,0 +1,153 @@
+//! Internal APU timing for audio debugging panels (see `Apu::debug_state`)
+
+use serde::Serialize;
+
+use super::units::{Envelope, LengthCounter, Sweep};
+
+/// Frame sequencer and channel internals at one instant
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApuDebugState {
+    /// APU powered on (NR52 bit 7)
+    pub enabled: bool,
+    
+    /// Step (0-7) the next DIV-APU tick runs
+    pub frame_sequencer_step: u8,
+    
+    /// Units that step clocks
+    pub next_clocks: SequencerClocks,
+    
+    /// Channels 1-4
+    pub channels: [ChannelDebug; 4],
+}
+
+/// Units clocked by one frame sequencer step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SequencerClocks {
+    /// Length counters (256 Hz, even steps)
+    pub length: bool,
+    
+    /// Channel 1 sweep (128 Hz, steps 2 and 6)
+    pub sweep: bool,
+    
+    /// Volume envelopes (64 Hz, step 7)
+    pub envelope: bool,
+}
+
+impl SequencerClocks {
+    pub fn for_step(step: u8) -> Self {
+        Self {
+            length: step % 2 == 0,
+            sweep: step == 2 || step == 6,
+            envelope: step == 7,
+        }
+    }
+}
+
+/// Internals of one channel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelDebug {
+    /// Channel is playing (NR52 status bit)
+    pub enabled: bool,
+    
+    /// DAC powered
+    pub dac_enabled: bool,
+    
+    /// 11-bit frequency register (`None` for the noise channel)
+    pub frequency: Option<u16>,
+    
+    /// Cycles until the frequency timer next expires
+    pub frequency_timer: u32,
+    
+    /// Output frequency in Hz: the tone for channels 1-3, the LFSR clock
+    /// rate for channel 4
+    pub output_hz: f32,
+    
+    /// Length counter steps left
+    pub length_counter: u16,
+    
+    /// Length counter enabled (NRx4 bit 6)
+    pub length_enabled: bool,
+    
+    /// Volume envelope (channels 1, 2 and 4)
+    pub envelope: Option<EnvelopeDebug>,
+    
+    /// Frequency sweep (channel 1)
+    pub sweep: Option<SweepDebug>,
+}
+
+impl ChannelDebug {
+    pub(super) fn new<const MAX: u16>(enabled: bool, length: &LengthCounter<MAX>) -> Self {
+        Self {
+            enabled,
+            dac_enabled: false,
+            frequency: None,
+            frequency_timer: 0,
+            output_hz: 0.0,
+            length_counter: length.counter,
+            length_enabled: length.enabled,
+            envelope: None,
+            sweep: None,
+        }
+    }
+}
+
+/// Volume envelope internals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct EnvelopeDebug {
+    /// Current volume (0-15)
+    pub volume: u8,
+    
+    /// Direction (true = increase)
+    pub increase: bool,
+    
+    /// Period in envelope clocks (0 = stopped)
+    pub period: u8,
+    
+    /// Envelope clocks until the next volume change
+    pub timer: u8,
+}
+
+impl From<&Envelope> for EnvelopeDebug {
+    fn from(envelope: &Envelope) -> Self {
+        Self {
+            volume: envelope.volume,
+            increase: envelope.increase,
+            period: envelope.period,
+            timer: envelope.timer,
+        }
+    }
+}
+
+/// Frequency sweep internals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SweepDebug {
+    /// Sweep running (set on trigger when the period or shift is nonzero)
+    pub enabled: bool,
+    
+    /// Period in sweep clocks
+    pub period: u8,
+    
+    /// Sweep clocks until the next frequency update
+    pub timer: u8,
+    
+    /// Direction (true = frequency decreases)
+    pub negate: bool,
+    
+    pub shift: u8,
+    
+    /// Frequency the next update starts from
+    pub shadow_frequency: u16,
+}
+
+impl From<&Sweep> for SweepDebug {
+    fn from(sweep: &Sweep) -> Self {
+        Self {
+            enabled: sweep.enabled,
+            period: sweep.period,
+            timer: sweep.timer,
+            negate: sweep.negate,
+            shift: sweep.shift,
+            shadow_frequency: sweep.shadow_frequency,
+        }
+    }
+}
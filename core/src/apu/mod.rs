@@ -6,620 +6,396 @@
 //! - Channel 3: Wave output
 //! - Channel 4: Noise
 
+mod units;
+mod channels;
+mod recording;
+mod debug;
+
+use std::ops::RangeInclusive;
+
+use crate::mmu::IoDevice;
+use crate::GbModel;
 use serde::{Serialize, Deserialize};
 
+pub use channels::{NoiseChannel, SquareChannel, WaveChannel};
+pub use recording::{encode_wav, StemRecorder};
+pub use debug::{ApuDebugState, ChannelDebug, EnvelopeDebug, SequencerClocks, SweepDebug};
+use units::{soft_clip, Mixer};
+
 /// Audio sample rate
 pub const SAMPLE_RATE: u32 = 44100;
 
 /// CPU cycles per audio sample
 const CYCLES_PER_SAMPLE: u32 = 4_194_304 / SAMPLE_RATE;
 
-/// Frame sequencer rate (512 Hz)
-const FRAME_SEQUENCER_RATE: u32 = 4_194_304 / 512;
+/// Sample rate of the raw output mode (one sample per 2 T-cycles)
+pub const RAW_SAMPLE_RATE: u32 = 4_194_304 / RAW_CYCLES_PER_SAMPLE;
 
-/// APU state for serialization
-#[derive(Clone, Serialize, Deserialize)]
-pub struct ApuState {
-    pub enabled: bool,
-    pub frame_sequencer_step: u8,
-    pub channel1: Channel1State,
-    pub channel2: Channel2State,
-    pub channel3: Channel3State,
-    pub channel4: Channel4State,
-}
+/// CPU cycles per audio sample in raw output mode
+const RAW_CYCLES_PER_SAMPLE: u32 = 2;
 
-#[derive(Clone, Default, Serialize, Deserialize)]
-pub struct Channel1State {
-    pub enabled: bool,
-    pub dac_enabled: bool,
-    pub length_counter: u8,
-    pub frequency: u16,
-    pub duty: u8,
-    pub volume: u8,
-    pub envelope_timer: u8,
-    pub envelope_direction: bool,
-    pub envelope_period: u8,
-    pub sweep_timer: u8,
-    pub sweep_period: u8,
-    pub sweep_direction: bool,
-    pub sweep_shift: u8,
-    pub sweep_enabled: bool,
-    pub shadow_frequency: u16,
+/// Length of the fade around a pause, as a fraction of a second (1/200 s)
+const FADE_DIVISOR: u32 = 200;
+
+/// Cycles after a CH3 wave fetch during which DMG wave RAM stays reachable
+const WAVE_ACCESS_WINDOW: u32 = 2;
+
+/// How the APU turns channel output into samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AudioOutputMode {
+    /// Downsampled to `SAMPLE_RATE` for direct playback
+    #[default]
+    Resampled,
+    
+    /// One sample per 2 T-cycles (`RAW_SAMPLE_RATE`), for analysis or
+    /// external DSP chains that do their own decimation
+    Raw,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize)]
-pub struct Channel2State {
+/// Real-time state of one sound channel, for visualizers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChannelStatus {
+    /// Channel is playing (NR52 status bit)
     pub enabled: bool,
-    pub dac_enabled: bool,
-    pub length_counter: u8,
-    pub frequency: u16,
-    pub duty: u8,
+    
+    /// Tone frequency in Hz (LFSR clock rate for the noise channel)
+    pub frequency_hz: f32,
+    
+    /// Current volume (0-15)
     pub volume: u8,
-    pub envelope_timer: u8,
-    pub envelope_direction: bool,
-    pub envelope_period: u8,
+    
+    /// Duty pattern (0-3) for the square channels
+    pub duty: Option<u8>,
+    
+    /// Routed to the left output (NR51)
+    pub left: bool,
+    
+    /// Routed to the right output (NR51)
+    pub right: bool,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize)]
-pub struct Channel3State {
-    pub enabled: bool,
-    pub dac_enabled: bool,
-    pub length_counter: u16,
-    pub frequency: u16,
-    pub volume_code: u8,
-    pub sample_index: u8,
+/// Output buffer counters, in stereo samples (one left/right pair each)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioStats {
+    /// Samples produced since power-on
+    pub produced: u64,
+    
+    /// Samples taken by the frontend (cleared with `clear_buffer`)
+    pub consumed: u64,
+    
+    /// Samples discarded unread: dropped at the buffer cap, or cleared by
+    /// an output mode change
+    pub dropped: u64,
+    
+    /// Times the buffer reached its cap
+    pub overflows: u64,
+    
+    /// Samples currently buffered
+    pub buffered: usize,
+    
+    /// Most samples ever buffered at once
+    pub high_watermark: usize,
 }
 
-#[derive(Clone, Default, Serialize, Deserialize)]
-pub struct Channel4State {
+/// Version of `ApuState` written by this build
+const APU_STATE_VERSION: u32 = 1;
+
+/// APU state for serialization.
+///
+/// The channels carry their complete timing (frequency timers, duty and
+/// wave positions, wave RAM). Version 0 states predate the mixer and
+/// sample timing fields; loading one keeps the current values for those.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ApuState {
+    #[serde(default)]
+    pub version: u32,
     pub enabled: bool,
-    pub dac_enabled: bool,
-    pub length_counter: u8,
-    pub volume: u8,
-    pub envelope_timer: u8,
-    pub envelope_direction: bool,
-    pub envelope_period: u8,
-    pub lfsr: u16,
-    pub clock_shift: u8,
-    pub width_mode: bool,
-    pub divisor_code: u8,
+    pub frame_sequencer_step: u8,
+    pub channel1: SquareChannel,
+    pub channel2: SquareChannel,
+    pub channel3: WaveChannel,
+    pub channel4: NoiseChannel,
+    #[serde(default)]
+    pub nr50: u8,
+    #[serde(default)]
+    pub nr51: u8,
+    
+    /// Cycles towards the next output sample
+    #[serde(default)]
+    pub sample_timer: u32,
+    
+    /// Last output sample and fade-in progress, so playback continues
+    /// without a click
+    #[serde(default)]
+    pub last_output: (f32, f32),
+    #[serde(default)]
+    pub fade_in: u32,
 }
 
-/// Square wave channel with sweep (Channel 1)
-struct Channel1 {
+/// Audio Processing Unit
+pub struct Apu {
     enabled: bool,
-    dac_enabled: bool,
-    
-    // Length counter
-    length_counter: u8,
-    length_enabled: bool,
-    
-    // Frequency
-    frequency: u16,
-    frequency_timer: u32,
-    duty_position: u8,
-    duty: u8,
-    
-    // Volume envelope
-    volume: u8,
-    initial_volume: u8,
-    envelope_timer: u8,
-    envelope_direction: bool,
-    envelope_period: u8,
-    
-    // Sweep
-    sweep_timer: u8,
-    sweep_period: u8,
-    sweep_direction: bool,
-    sweep_shift: u8,
-    sweep_enabled: bool,
-    shadow_frequency: u16,
-}
-
-impl Default for Channel1 {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            dac_enabled: false,
-            length_counter: 0,
-            length_enabled: false,
-            frequency: 0,
-            frequency_timer: 0,
-            duty_position: 0,
-            duty: 0,
-            volume: 0,
-            initial_volume: 0,
-            envelope_timer: 0,
-            envelope_direction: false,
-            envelope_period: 0,
-            sweep_timer: 0,
-            sweep_period: 0,
-            sweep_direction: false,
-            sweep_shift: 0,
-            sweep_enabled: false,
-            shadow_frequency: 0,
-        }
-    }
+    
+    channel1: SquareChannel,
+    channel2: SquareChannel,
+    channel3: WaveChannel,
+    channel4: NoiseChannel,
+    
+    // Output control
+    mixer: Mixer,
+    
+    // Frame sequencer (clocked by DIV-APU, see `clock_div_apu`)
+    frame_sequencer_step: u8,
+    
+    // Sample generation
+    sample_timer: u32,
+    output_buffer: Vec<f32>,
+    output_mode: AudioOutputMode,
+    
+    /// Most stereo samples the output buffer holds before dropping the
+    /// oldest (`None` for one second at the output rate)
+    buffer_cap: Option<usize>,
+    
+    /// Output buffer counters
+    stats: AudioStats,
+    
+    /// Host volume applied to the final mix (1.0 = unchanged)
+    master_volume: f32,
+    
+    /// Output silence without stopping sample production
+    muted: bool,
+    
+    /// Compress peaks smoothly instead of clamping at ±1.0
+    soft_clip: bool,
+    
+    /// Produce samples at all (off for headless runs; the channels keep
+    /// running so register reads are unaffected)
+    sample_output: bool,
+    
+    /// Last sample pushed to the output buffer, faded out on pause
+    last_output: (f32, f32),
+    
+    /// Samples left in the fade-in after a pause
+    fade_in: u32,
+    
+    /// Per-channel stereo output, panned and scaled like the mix (only
+    /// filled while capture is enabled)
+    channel_buffers: Option<[Vec<f32>; 4]>,
+    
+    /// Hardware being emulated (wave RAM access while CH3 plays, power-off
+    /// behavior, PCM12/PCM34)
+    model: GbModel,
+    
+    /// CPU running in CGB double speed, as notified by the core
+    double_speed: bool,
 }
 
-impl Channel1 {
-    fn step(&mut self) {
-        if self.frequency_timer > 0 {
-            self.frequency_timer -= 1;
-        }
+impl Apu {
+    pub fn new(model: GbModel) -> Self {
+        let mut apu = Self {
+            enabled: true,
+            channel1: SquareChannel::new(true),
+            channel2: SquareChannel::new(false),
+            channel3: WaveChannel::default(),
+            channel4: NoiseChannel::default(),
+            mixer: Mixer::default(),
+            frame_sequencer_step: 0,
+            sample_timer: 0,
+            output_buffer: Vec::with_capacity(4096),
+            output_mode: AudioOutputMode::Resampled,
+            buffer_cap: None,
+            stats: AudioStats::default(),
+            master_volume: 1.0,
+            muted: false,
+            soft_clip: false,
+            sample_output: true,
+            last_output: (0.0, 0.0),
+            fade_in: 0,
+            channel_buffers: None,
+            model,
+            double_speed: false,
+        };
         
-        if self.frequency_timer == 0 {
-            self.frequency_timer = (2048 - self.frequency as u32) * 4;
-            self.duty_position = (self.duty_position + 1) & 7;
-        }
+        apu.init_registers();
+        
+        apu
     }
     
-    fn output(&self) -> f32 {
-        if !self.enabled || !self.dac_enabled {
-            return 0.0;
-        }
-        
-        let duty_table: [[u8; 8]; 4] = [
-            [0, 0, 0, 0, 0, 0, 0, 1], // 12.5%
-            [1, 0, 0, 0, 0, 0, 0, 1], // 25%
-            [1, 0, 0, 0, 0, 1, 1, 1], // 50%
-            [0, 1, 1, 1, 1, 1, 1, 0], // 75%
-        ];
+    /// Initialize registers to post-boot ROM values
+    fn init_registers(&mut self) {
+        self.write_register(0xFF10, 0x80); // NR10
+        self.write_register(0xFF11, 0xBF); // NR11
+        self.write_register(0xFF12, 0xF3); // NR12
+        self.write_register(0xFF16, 0x3F); // NR21
+        self.write_register(0xFF1C, 0x9F); // NR32
+        self.write_register(0xFF24, 0x77); // NR50
+        self.write_register(0xFF25, 0xF3); // NR51
         
-        let sample = duty_table[self.duty as usize][self.duty_position as usize];
-        let volume = self.volume as f32 / 15.0;
-        
-        if sample == 1 { volume } else { -volume }
+        // The boot chime leaves channel 1 running with its envelope
+        // decayed to silence, so NR52 reads back 0xF1 (the envelope's
+        // current volume is still 0 since the NR12 write didn't trigger)
+        self.channel1.enabled = true;
     }
     
-    fn clock_length(&mut self) {
-        if self.length_enabled && self.length_counter > 0 {
-            self.length_counter -= 1;
-            if self.length_counter == 0 {
-                self.enabled = false;
-            }
-        }
+    pub fn reset(&mut self) {
+        let output_mode = self.output_mode;
+        let buffer_cap = self.buffer_cap;
+        let (master_volume, muted, soft_clip) = (self.master_volume, self.muted, self.soft_clip);
+        let sample_output = self.sample_output;
+        let capture = self.channel_buffers.is_some();
+        *self = Self::new(self.model);
+        self.output_mode = output_mode;
+        self.buffer_cap = buffer_cap;
+        self.master_volume = master_volume;
+        self.muted = muted;
+        self.soft_clip = soft_clip;
+        self.sample_output = sample_output;
+        self.set_channel_capture(capture);
     }
     
-    fn clock_envelope(&mut self) {
-        if self.envelope_period == 0 {
-            return;
-        }
-        
-        if self.envelope_timer > 0 {
-            self.envelope_timer -= 1;
-        }
-        
-        if self.envelope_timer == 0 {
-            self.envelope_timer = self.envelope_period;
-            
-            if self.envelope_direction && self.volume < 15 {
-                self.volume += 1;
-            } else if !self.envelope_direction && self.volume > 0 {
-                self.volume -= 1;
-            }
-        }
+    /// Stop (or resume) producing samples, for headless runs that never
+    /// play audio. Sound emulation continues as before.
+    pub fn set_sample_output(&mut self, enabled: bool) {
+        self.sample_output = enabled;
     }
     
-    fn clock_sweep(&mut self) {
-        if self.sweep_timer > 0 {
-            self.sweep_timer -= 1;
-        }
-        
-        if self.sweep_timer == 0 {
-            self.sweep_timer = if self.sweep_period > 0 { self.sweep_period } else { 8 };
-            
-            if self.sweep_enabled && self.sweep_period > 0 {
-                let new_freq = self.calculate_sweep_frequency();
-                
-                if new_freq <= 2047 && self.sweep_shift > 0 {
-                    self.frequency = new_freq;
-                    self.shadow_frequency = new_freq;
-                    
-                    // Overflow check
-                    self.calculate_sweep_frequency();
-                }
-            }
+    /// Select resampled or raw 2 MHz output.
+    ///
+    /// Samples already in the buffer were produced at the old rate, so the
+    /// buffer is cleared on a change.
+    pub fn set_output_mode(&mut self, mode: AudioOutputMode) {
+        if mode != self.output_mode {
+            self.output_mode = mode;
+            self.sample_timer = 0;
+            self.stats.dropped += self.buffered_samples() as u64;
+            self.discard_buffer();
         }
     }
     
-    fn calculate_sweep_frequency(&mut self) -> u16 {
-        let delta = self.shadow_frequency >> self.sweep_shift;
-        
-        let new_freq = if self.sweep_direction {
-            self.shadow_frequency.wrapping_sub(delta)
-        } else {
-            self.shadow_frequency.wrapping_add(delta)
-        };
-        
-        if new_freq > 2047 {
-            self.enabled = false;
-        }
-        
-        new_freq
+    /// Limit the output buffer to `samples` stereo samples; once full, the
+    /// oldest samples are dropped (and counted in `stats`) so a frontend
+    /// that stops reading doesn't grow it forever. `None` restores the
+    /// default of one second at the output rate.
+    pub fn set_buffer_cap(&mut self, samples: Option<usize>) {
+        self.buffer_cap = samples.map(|samples| samples.max(1));
     }
     
-    fn trigger(&mut self) {
-        self.enabled = self.dac_enabled;
-        
-        if self.length_counter == 0 {
-            self.length_counter = 64;
-        }
-        
-        self.frequency_timer = (2048 - self.frequency as u32) * 4;
-        self.envelope_timer = self.envelope_period;
-        self.volume = self.initial_volume;
-        
-        // Sweep
-        self.shadow_frequency = self.frequency;
-        self.sweep_timer = if self.sweep_period > 0 { self.sweep_period } else { 8 };
-        self.sweep_enabled = self.sweep_period > 0 || self.sweep_shift > 0;
-        
-        if self.sweep_shift > 0 {
-            self.calculate_sweep_frequency();
-        }
-    }
-}
-
-/// Square wave channel (Channel 2)
-struct Channel2 {
-    enabled: bool,
-    dac_enabled: bool,
-    length_counter: u8,
-    length_enabled: bool,
-    frequency: u16,
-    frequency_timer: u32,
-    duty_position: u8,
-    duty: u8,
-    volume: u8,
-    initial_volume: u8,
-    envelope_timer: u8,
-    envelope_direction: bool,
-    envelope_period: u8,
-}
-
-impl Default for Channel2 {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            dac_enabled: false,
-            length_counter: 0,
-            length_enabled: false,
-            frequency: 0,
-            frequency_timer: 0,
-            duty_position: 0,
-            duty: 0,
-            volume: 0,
-            initial_volume: 0,
-            envelope_timer: 0,
-            envelope_direction: false,
-            envelope_period: 0,
-        }
-    }
-}
-
-impl Channel2 {
-    fn step(&mut self) {
-        if self.frequency_timer > 0 {
-            self.frequency_timer -= 1;
-        }
-        
-        if self.frequency_timer == 0 {
-            self.frequency_timer = (2048 - self.frequency as u32) * 4;
-            self.duty_position = (self.duty_position + 1) & 7;
-        }
+    /// Most stereo samples the output buffer holds
+    pub fn buffer_cap(&self) -> usize {
+        self.buffer_cap.unwrap_or(self.sample_rate() as usize)
     }
     
-    fn output(&self) -> f32 {
-        if !self.enabled || !self.dac_enabled {
-            return 0.0;
-        }
-        
-        let duty_table: [[u8; 8]; 4] = [
-            [0, 0, 0, 0, 0, 0, 0, 1],
-            [1, 0, 0, 0, 0, 0, 0, 1],
-            [1, 0, 0, 0, 0, 1, 1, 1],
-            [0, 1, 1, 1, 1, 1, 1, 0],
-        ];
-        
-        let sample = duty_table[self.duty as usize][self.duty_position as usize];
-        let volume = self.volume as f32 / 15.0;
-        
-        if sample == 1 { volume } else { -volume }
+    /// Scale the final mix (1.0 leaves it unchanged). Channel stems are
+    /// not affected. Negative values count as 0.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.master_volume = volume.max(0.0);
     }
     
-    fn clock_length(&mut self) {
-        if self.length_enabled && self.length_counter > 0 {
-            self.length_counter -= 1;
-            if self.length_counter == 0 {
-                self.enabled = false;
-            }
-        }
+    pub fn master_volume(&self) -> f32 {
+        self.master_volume
     }
     
-    fn clock_envelope(&mut self) {
-        if self.envelope_period == 0 {
-            return;
-        }
-        
-        if self.envelope_timer > 0 {
-            self.envelope_timer -= 1;
-        }
-        
-        if self.envelope_timer == 0 {
-            self.envelope_timer = self.envelope_period;
-            
-            if self.envelope_direction && self.volume < 15 {
-                self.volume += 1;
-            } else if !self.envelope_direction && self.volume > 0 {
-                self.volume -= 1;
-            }
-        }
+    /// Silence the output; samples keep being produced at the same rate
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
     }
     
-    fn trigger(&mut self) {
-        self.enabled = self.dac_enabled;
-        
-        if self.length_counter == 0 {
-            self.length_counter = 64;
-        }
-        
-        self.frequency_timer = (2048 - self.frequency as u32) * 4;
-        self.envelope_timer = self.envelope_period;
-        self.volume = self.initial_volume;
-    }
-}
-
-/// Wave channel (Channel 3)
-struct Channel3 {
-    enabled: bool,
-    dac_enabled: bool,
-    length_counter: u16,
-    length_enabled: bool,
-    frequency: u16,
-    frequency_timer: u32,
-    volume_code: u8,
-    sample_index: u8,
-    wave_ram: [u8; 16],
-}
-
-impl Default for Channel3 {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            dac_enabled: false,
-            length_counter: 0,
-            length_enabled: false,
-            frequency: 0,
-            frequency_timer: 0,
-            volume_code: 0,
-            sample_index: 0,
-            wave_ram: [0; 16],
-        }
-    }
-}
-
-impl Channel3 {
-    fn step(&mut self) {
-        if self.frequency_timer > 0 {
-            self.frequency_timer -= 1;
-        }
-        
-        if self.frequency_timer == 0 {
-            self.frequency_timer = (2048 - self.frequency as u32) * 2;
-            self.sample_index = (self.sample_index + 1) & 31;
-        }
+    pub fn is_muted(&self) -> bool {
+        self.muted
     }
     
-    fn output(&self) -> f32 {
-        if !self.enabled || !self.dac_enabled {
-            return 0.0;
-        }
-        
-        let byte = self.wave_ram[(self.sample_index / 2) as usize];
-        let sample = if self.sample_index & 1 == 0 {
-            byte >> 4
-        } else {
-            byte & 0x0F
-        };
-        
-        let shifted = match self.volume_code {
-            0 => 0,
-            1 => sample,
-            2 => sample >> 1,
-            3 => sample >> 2,
-            _ => 0,
-        };
-        
-        (shifted as f32 / 7.5) - 1.0
+    /// Compress peaks near ±1.0 smoothly instead of clamping them, which
+    /// distorts audibly when a boosted mix peaks
+    pub fn set_soft_clip(&mut self, enabled: bool) {
+        self.soft_clip = enabled;
     }
     
-    fn clock_length(&mut self) {
-        if self.length_enabled && self.length_counter > 0 {
-            self.length_counter -= 1;
-            if self.length_counter == 0 {
-                self.enabled = false;
-            }
-        }
+    pub fn soft_clip(&self) -> bool {
+        self.soft_clip
     }
     
-    fn trigger(&mut self) {
-        self.enabled = self.dac_enabled;
-        
-        if self.length_counter == 0 {
-            self.length_counter = 256;
+    /// Output buffer counters
+    pub fn stats(&self) -> AudioStats {
+        AudioStats {
+            buffered: self.buffered_samples(),
+            ..self.stats
         }
-        
-        self.frequency_timer = (2048 - self.frequency as u32) * 2;
-        self.sample_index = 0;
     }
-}
-
-/// Noise channel (Channel 4)
-struct Channel4 {
-    enabled: bool,
-    dac_enabled: bool,
-    length_counter: u8,
-    length_enabled: bool,
-    frequency_timer: u32,
-    volume: u8,
-    initial_volume: u8,
-    envelope_timer: u8,
-    envelope_direction: bool,
-    envelope_period: u8,
-    lfsr: u16,
-    clock_shift: u8,
-    width_mode: bool,
-    divisor_code: u8,
-}
-
-impl Default for Channel4 {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            dac_enabled: false,
-            length_counter: 0,
-            length_enabled: false,
-            frequency_timer: 0,
-            volume: 0,
-            initial_volume: 0,
-            envelope_timer: 0,
-            envelope_direction: false,
-            envelope_period: 0,
-            lfsr: 0x7FFF,
-            clock_shift: 0,
-            width_mode: false,
-            divisor_code: 0,
-        }
+    
+    /// Stereo samples in the output buffer
+    fn buffered_samples(&self) -> usize {
+        self.output_buffer.len() / 2
     }
-}
-
-impl Channel4 {
-    fn step(&mut self) {
-        if self.frequency_timer > 0 {
-            self.frequency_timer -= 1;
-        }
-        
-        if self.frequency_timer == 0 {
-            let divisor = match self.divisor_code {
-                0 => 8,
-                n => (n as u32) * 16,
-            };
-            self.frequency_timer = divisor << self.clock_shift;
-            
-            // Clock LFSR
-            let xor_result = (self.lfsr & 1) ^ ((self.lfsr >> 1) & 1);
-            self.lfsr = (self.lfsr >> 1) | (xor_result << 14);
-            
-            if self.width_mode {
-                self.lfsr = (self.lfsr & !0x40) | (xor_result << 6);
-            }
-        }
+    
+    /// Also produce a separate stereo buffer per channel (stems), for
+    /// recording with `StemRecorder`
+    pub fn set_channel_capture(&mut self, enabled: bool) {
+        self.channel_buffers = enabled.then(Default::default);
     }
     
-    fn output(&self) -> f32 {
-        if !self.enabled || !self.dac_enabled {
-            return 0.0;
-        }
-        
-        let sample = !(self.lfsr & 1) as u8;
-        let volume = self.volume as f32 / 15.0;
-        
-        if sample == 1 { volume } else { -volume }
+    /// Samples of one channel (0-3) since the buffers were last cleared,
+    /// stereo interleaved at the output sample rate. Empty unless channel
+    /// capture is enabled.
+    pub fn channel_buffer(&self, channel: usize) -> &[f32] {
+        self.channel_buffers.as_ref().map_or(&[], |buffers| &buffers[channel])
     }
     
-    fn clock_length(&mut self) {
-        if self.length_enabled && self.length_counter > 0 {
-            self.length_counter -= 1;
-            if self.length_counter == 0 {
-                self.enabled = false;
-            }
-        }
+    pub fn output_mode(&self) -> AudioOutputMode {
+        self.output_mode
     }
     
-    fn clock_envelope(&mut self) {
-        if self.envelope_period == 0 {
-            return;
-        }
-        
-        if self.envelope_timer > 0 {
-            self.envelope_timer -= 1;
-        }
-        
-        if self.envelope_timer == 0 {
-            self.envelope_timer = self.envelope_period;
-            
-            if self.envelope_direction && self.volume < 15 {
-                self.volume += 1;
-            } else if !self.envelope_direction && self.volume > 0 {
-                self.volume -= 1;
-            }
+    /// Rate of the samples in the output buffer (per channel)
+    pub fn sample_rate(&self) -> u32 {
+        match self.output_mode {
+            AudioOutputMode::Resampled => SAMPLE_RATE,
+            AudioOutputMode::Raw => RAW_SAMPLE_RATE,
         }
     }
     
-    fn trigger(&mut self) {
-        self.enabled = self.dac_enabled;
-        
-        if self.length_counter == 0 {
-            self.length_counter = 64;
+    fn cycles_per_sample(&self) -> u32 {
+        match self.output_mode {
+            AudioOutputMode::Resampled => CYCLES_PER_SAMPLE,
+            AudioOutputMode::Raw => RAW_CYCLES_PER_SAMPLE,
         }
-        
-        let divisor = match self.divisor_code {
-            0 => 8,
-            n => (n as u32) * 16,
-        };
-        self.frequency_timer = divisor << self.clock_shift;
-        self.envelope_timer = self.envelope_period;
-        self.volume = self.initial_volume;
-        self.lfsr = 0x7FFF;
     }
-}
-
-/// Audio Processing Unit
-pub struct Apu {
-    enabled: bool,
     
-    channel1: Channel1,
-    channel2: Channel2,
-    channel3: Channel3,
-    channel4: Channel4,
+    fn fade_samples(&self) -> u32 {
+        self.sample_rate() / FADE_DIVISOR
+    }
     
-    // Output control
-    left_volume: u8,
-    right_volume: u8,
-    left_enables: u8,
-    right_enables: u8,
+    /// Select the hardware model whose behavior to emulate
+    pub fn set_model(&mut self, model: GbModel) {
+        self.model = model;
+    }
     
-    // Frame sequencer
-    frame_sequencer_timer: u32,
-    frame_sequencer_step: u8,
+    /// Track the CPU speed. Cycles passed to `step` are already in real
+    /// (normal speed) time and the frame sequencer follows DIV, so this
+    /// only needs calling when the speed changes.
+    pub fn set_double_speed(&mut self, double_speed: bool) {
+        self.double_speed = double_speed;
+    }
     
-    // Sample generation
-    sample_timer: u32,
-    output_buffer: Vec<f32>,
-}
-
-impl Apu {
-    pub fn new() -> Self {
-        Self {
-            enabled: true,
-            channel1: Channel1::default(),
-            channel2: Channel2::default(),
-            channel3: Channel3::default(),
-            channel4: Channel4::default(),
-            left_volume: 7,
-            right_volume: 7,
-            left_enables: 0xFF,
-            right_enables: 0xFF,
-            frame_sequencer_timer: 0,
-            frame_sequencer_step: 0,
-            sample_timer: 0,
-            output_buffer: Vec::with_capacity(4096),
-        }
+    /// Whether the CPU is in CGB double speed
+    pub fn double_speed(&self) -> bool {
+        self.double_speed
     }
     
-    pub fn reset(&mut self) {
-        *self = Self::new();
+    /// Resolve a CPU access to wave RAM into a byte index.
+    ///
+    /// While channel 3 is playing, the CPU can only reach the byte the
+    /// channel is currently reading: always on CGB, but on DMG only in the
+    /// brief window right after the channel fetched it (otherwise reads
+    /// return 0xFF and writes are dropped).
+    fn wave_ram_index(&self, addr: u16) -> Option<usize> {
+        if !self.channel3.enabled {
+            return Some((addr - 0xFF30) as usize);
+        }
+        
+        if self.model.is_cgb() || self.channel3.cycles_since_fetch < WAVE_ACCESS_WINDOW {
+            Some((self.channel3.sample_index / 2) as usize)
+        } else {
+            None
+        }
     }
     
     pub fn step(&mut self, cycles: u32) {
@@ -627,6 +403,8 @@ impl Apu {
             return;
         }
         
+        let cycles_per_sample = self.cycles_per_sample();
+        
         for _ in 0..cycles {
             // Step channels
             self.channel1.step();
@@ -634,20 +412,78 @@ impl Apu {
             self.channel3.step();
             self.channel4.step();
             
-            // Frame sequencer
-            self.frame_sequencer_timer += 1;
-            if self.frame_sequencer_timer >= FRAME_SEQUENCER_RATE {
-                self.frame_sequencer_timer = 0;
-                self.clock_frame_sequencer();
-            }
-            
             // Generate samples
             self.sample_timer += 1;
-            if self.sample_timer >= CYCLES_PER_SAMPLE {
+            if self.sample_timer >= cycles_per_sample {
                 self.sample_timer = 0;
-                self.generate_sample();
+                if self.sample_output {
+                    self.generate_sample();
+                }
+            }
+        }
+    }
+    
+    /// Produce the samples for `cycles` without running the channels, as
+    /// while emulation is paused. The output fades from the last sample
+    /// to silence, and fades back in once `step` resumes.
+    pub fn step_silent(&mut self, cycles: u32) {
+        let cycles_per_sample = self.cycles_per_sample();
+        let total = self.sample_timer + cycles;
+        self.sample_timer = total % cycles_per_sample;
+        
+        let fade = self.fade_samples().max(1);
+        let (left, right) = self.last_output;
+        for i in 0..total / cycles_per_sample {
+            let gain = fade.saturating_sub(i + 1) as f32 / fade as f32;
+            self.push_output(left * gain, right * gain);
+            
+            if let Some(buffers) = self.channel_buffers.as_mut() {
+                for buffer in buffers.iter_mut() {
+                    buffer.extend_from_slice(&[0.0, 0.0]);
+                }
             }
         }
+        
+        self.fade_in = fade;
+    }
+    
+    fn push_output(&mut self, left: f32, right: f32) {
+        let cap = self.buffer_cap();
+        if self.buffered_samples() >= cap {
+            self.drop_oldest(cap);
+        }
+        
+        self.output_buffer.push(left);
+        self.output_buffer.push(right);
+        self.last_output = (left, right);
+        
+        self.stats.produced += 1;
+        self.stats.high_watermark = self.stats.high_watermark.max(self.buffered_samples());
+    }
+    
+    /// Make room in a full buffer. A quarter of the cap goes at once so
+    /// the shift is paid rarely rather than on every sample.
+    fn drop_oldest(&mut self, cap: usize) {
+        let samples = (cap / 4).max(1).min(self.buffered_samples());
+        self.output_buffer.drain(..samples * 2);
+        for buffer in self.channel_buffers.iter_mut().flatten() {
+            let len = buffer.len().min(samples * 2);
+            buffer.drain(..len);
+        }
+        
+        self.stats.dropped += samples as u64;
+        self.stats.overflows += 1;
+    }
+    
+    /// Advance the frame sequencer on a DIV-APU tick.
+    ///
+    /// The sequencer has no timer of its own: it is clocked by falling edges
+    /// of a DIV bit (see `Timer::take_div_apu_ticks`), which keeps it at
+    /// 512 Hz in both CPU speeds and lets DIV writes clock it early.
+    pub fn clock_div_apu(&mut self) {
+        if self.enabled {
+            self.clock_frame_sequencer();
+        }
     }
     
     fn clock_frame_sequencer(&mut self) {
@@ -689,105 +525,63 @@ impl Apu {
         self.frame_sequencer_step = (self.frame_sequencer_step + 1) & 7;
     }
     
-    fn generate_sample(&mut self) {
-        let ch1 = self.channel1.output();
-        let ch2 = self.channel2.output();
-        let ch3 = self.channel3.output();
-        let ch4 = self.channel4.output();
-        
-        // Mix channels
-        let mut left = 0.0f32;
-        let mut right = 0.0f32;
-        
-        if self.left_enables & 0x01 != 0 { left += ch1; }
-        if self.left_enables & 0x02 != 0 { left += ch2; }
-        if self.left_enables & 0x04 != 0 { left += ch3; }
-        if self.left_enables & 0x08 != 0 { left += ch4; }
+    /// Final mix stage: host volume, mute and clipping
+    fn master(&self, (left, right): (f32, f32)) -> (f32, f32) {
+        if self.muted {
+            return (0.0, 0.0);
+        }
         
-        if self.right_enables & 0x10 != 0 { right += ch1; }
-        if self.right_enables & 0x20 != 0 { right += ch2; }
-        if self.right_enables & 0x40 != 0 { right += ch3; }
-        if self.right_enables & 0x80 != 0 { right += ch4; }
+        let limit = |sample: f32| {
+            let sample = sample * self.master_volume;
+            if self.soft_clip { soft_clip(sample) } else { sample.clamp(-1.0, 1.0) }
+        };
+        (limit(left), limit(right))
+    }
+    
+    fn generate_sample(&mut self) {
+        let outputs = [
+            self.channel1.output(),
+            self.channel2.output(),
+            self.channel3.output(),
+            self.channel4.output(),
+        ];
+        let (mut left, mut right) = self.master(self.mixer.mix(outputs));
         
-        // Apply master volume
-        left *= (self.left_volume as f32 + 1.0) / 32.0;
-        right *= (self.right_volume as f32 + 1.0) / 32.0;
+        if self.fade_in > 0 {
+            let fade = self.fade_samples().max(1);
+            let gain = fade.saturating_sub(self.fade_in) as f32 / fade as f32;
+            left *= gain;
+            right *= gain;
+            self.fade_in -= 1;
+        }
         
-        // Clamp
-        left = left.clamp(-1.0, 1.0);
-        right = right.clamp(-1.0, 1.0);
+        self.push_output(left, right);
         
-        self.output_buffer.push(left);
-        self.output_buffer.push(right);
+        if let Some(buffers) = self.channel_buffers.as_mut() {
+            for (ch, buffer) in buffers.iter_mut().enumerate() {
+                let mut solo = [0.0; 4];
+                solo[ch] = outputs[ch];
+                let (left, right) = self.mixer.mix(solo);
+                buffer.push(left);
+                buffer.push(right);
+            }
+        }
     }
     
+    /// Read a sound register as seen by the CPU
+    /// (write-only and unused bits read back as 1)
     pub fn read_register(&self, addr: u16) -> u8 {
         match addr {
-            // NR10 - Channel 1 Sweep
-            0xFF10 => {
-                0x80 | (self.channel1.sweep_period << 4)
-                    | (if self.channel1.sweep_direction { 0x08 } else { 0 })
-                    | self.channel1.sweep_shift
-            }
-            // NR11 - Channel 1 Length/Duty
-            0xFF11 => (self.channel1.duty << 6) | 0x3F,
-            // NR12 - Channel 1 Envelope
-            0xFF12 => {
-                (self.channel1.initial_volume << 4)
-                    | (if self.channel1.envelope_direction { 0x08 } else { 0 })
-                    | self.channel1.envelope_period
-            }
-            // NR13 - Channel 1 Frequency low (write only)
-            0xFF13 => 0xFF,
-            // NR14 - Channel 1 Frequency high
-            0xFF14 => 0xBF | (if self.channel1.length_enabled { 0x40 } else { 0 }),
-            
-            // NR21 - Channel 2 Length/Duty
-            0xFF16 => (self.channel2.duty << 6) | 0x3F,
-            // NR22 - Channel 2 Envelope
-            0xFF17 => {
-                (self.channel2.initial_volume << 4)
-                    | (if self.channel2.envelope_direction { 0x08 } else { 0 })
-                    | self.channel2.envelope_period
-            }
-            // NR23 - Channel 2 Frequency low (write only)
-            0xFF18 => 0xFF,
-            // NR24 - Channel 2 Frequency high
-            0xFF19 => 0xBF | (if self.channel2.length_enabled { 0x40 } else { 0 }),
-            
-            // NR30 - Channel 3 DAC
-            0xFF1A => 0x7F | (if self.channel3.dac_enabled { 0x80 } else { 0 }),
-            // NR31 - Channel 3 Length (write only)
-            0xFF1B => 0xFF,
-            // NR32 - Channel 3 Volume
-            0xFF1C => 0x9F | (self.channel3.volume_code << 5),
-            // NR33 - Channel 3 Frequency low (write only)
-            0xFF1D => 0xFF,
-            // NR34 - Channel 3 Frequency high
-            0xFF1E => 0xBF | (if self.channel3.length_enabled { 0x40 } else { 0 }),
-            
-            // NR41 - Channel 4 Length (write only)
-            0xFF20 => 0xFF,
-            // NR42 - Channel 4 Envelope
-            0xFF21 => {
-                (self.channel4.initial_volume << 4)
-                    | (if self.channel4.envelope_direction { 0x08 } else { 0 })
-                    | self.channel4.envelope_period
-            }
-            // NR43 - Channel 4 Polynomial counter
-            0xFF22 => {
-                (self.channel4.clock_shift << 4)
-                    | (if self.channel4.width_mode { 0x08 } else { 0 })
-                    | self.channel4.divisor_code
-            }
-            // NR44 - Channel 4 Control
-            0xFF23 => 0xBF | (if self.channel4.length_enabled { 0x40 } else { 0 }),
+            0xFF10..=0xFF14 => self.channel1.read_register((addr - 0xFF10) as u8),
+            0xFF15..=0xFF19 => self.channel2.read_register((addr - 0xFF15) as u8),
+            0xFF1A..=0xFF1E => self.channel3.read_register((addr - 0xFF1A) as u8),
+            0xFF1F..=0xFF23 => self.channel4.read_register((addr - 0xFF1F) as u8),
             
             // NR50 - Master volume
-            0xFF24 => (self.left_volume << 4) | self.right_volume,
+            0xFF24 => self.mixer.read_nr50(),
             
             // NR51 - Sound panning
-            0xFF25 => self.left_enables | self.right_enables,
+            0xFF25 => self.mixer.read_nr51(),
             
             // NR52 - Sound on/off
             0xFF26 => {
@@ -799,148 +593,56 @@ impl Apu {
                     | (if self.channel1.enabled { 0x01 } else { 0 })
             }
             
+            // PCM12/PCM34 - Channel DAC inputs (CGB only)
+            0xFF76 if self.model.is_cgb() => {
+                self.channel1.digital_output() | (self.channel2.digital_output() << 4)
+            }
+            0xFF77 if self.model.is_cgb() => {
+                self.channel3.digital_output() | (self.channel4.digital_output() << 4)
+            }
+            
             // Wave RAM
-            0xFF30..=0xFF3F => self.channel3.wave_ram[(addr - 0xFF30) as usize],
+            0xFF30..=0xFF3F => match self.wave_ram_index(addr) {
+                Some(index) => self.channel3.wave_ram[index],
+                None => 0xFF,
+            },
             
             _ => 0xFF,
         }
     }
     
+    /// Write a sound register
     pub fn write_register(&mut self, addr: u16, value: u8) {
         if !self.enabled && addr != 0xFF26 && !(0xFF30..=0xFF3F).contains(&addr) {
+            // The DMG keeps its length counters powered, so the length
+            // bits of NRx1 can still be written
+            if !self.model.is_cgb() {
+                match addr {
+                    0xFF11 => self.channel1.write_length(value),
+                    0xFF16 => self.channel2.write_length(value),
+                    0xFF1B => self.channel3.write_length(value),
+                    0xFF20 => self.channel4.write_length(value),
+                    _ => {}
+                }
+            }
             return;
         }
         
+        // Length is clocked on even frame sequencer steps; the counter holds
+        // the next step, so an odd value means the next step skips length
+        let extra_clock = self.frame_sequencer_step & 1 == 1;
+        
         match addr {
-            // NR10 - Channel 1 Sweep
-            0xFF10 => {
-                self.channel1.sweep_period = (value >> 4) & 0x07;
-                self.channel1.sweep_direction = value & 0x08 != 0;
-                self.channel1.sweep_shift = value & 0x07;
-            }
-            // NR11 - Channel 1 Length/Duty
-            0xFF11 => {
-                self.channel1.duty = (value >> 6) & 0x03;
-                self.channel1.length_counter = 64 - (value & 0x3F);
-            }
-            // NR12 - Channel 1 Envelope
-            0xFF12 => {
-                self.channel1.initial_volume = (value >> 4) & 0x0F;
-                self.channel1.envelope_direction = value & 0x08 != 0;
-                self.channel1.envelope_period = value & 0x07;
-                self.channel1.dac_enabled = value & 0xF8 != 0;
-                if !self.channel1.dac_enabled {
-                    self.channel1.enabled = false;
-                }
-            }
-            // NR13 - Channel 1 Frequency low
-            0xFF13 => {
-                self.channel1.frequency = (self.channel1.frequency & 0x700) | value as u16;
-            }
-            // NR14 - Channel 1 Frequency high
-            0xFF14 => {
-                self.channel1.frequency = (self.channel1.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
-                self.channel1.length_enabled = value & 0x40 != 0;
-                if value & 0x80 != 0 {
-                    self.channel1.trigger();
-                }
-            }
-            
-            // NR21 - Channel 2 Length/Duty
-            0xFF16 => {
-                self.channel2.duty = (value >> 6) & 0x03;
-                self.channel2.length_counter = 64 - (value & 0x3F);
-            }
-            // NR22 - Channel 2 Envelope
-            0xFF17 => {
-                self.channel2.initial_volume = (value >> 4) & 0x0F;
-                self.channel2.envelope_direction = value & 0x08 != 0;
-                self.channel2.envelope_period = value & 0x07;
-                self.channel2.dac_enabled = value & 0xF8 != 0;
-                if !self.channel2.dac_enabled {
-                    self.channel2.enabled = false;
-                }
-            }
-            // NR23 - Channel 2 Frequency low
-            0xFF18 => {
-                self.channel2.frequency = (self.channel2.frequency & 0x700) | value as u16;
-            }
-            // NR24 - Channel 2 Frequency high
-            0xFF19 => {
-                self.channel2.frequency = (self.channel2.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
-                self.channel2.length_enabled = value & 0x40 != 0;
-                if value & 0x80 != 0 {
-                    self.channel2.trigger();
-                }
-            }
-            
-            // NR30 - Channel 3 DAC
-            0xFF1A => {
-                self.channel3.dac_enabled = value & 0x80 != 0;
-                if !self.channel3.dac_enabled {
-                    self.channel3.enabled = false;
-                }
-            }
-            // NR31 - Channel 3 Length
-            0xFF1B => {
-                self.channel3.length_counter = 256 - value as u16;
-            }
-            // NR32 - Channel 3 Volume
-            0xFF1C => {
-                self.channel3.volume_code = (value >> 5) & 0x03;
-            }
-            // NR33 - Channel 3 Frequency low
-            0xFF1D => {
-                self.channel3.frequency = (self.channel3.frequency & 0x700) | value as u16;
-            }
-            // NR34 - Channel 3 Frequency high
-            0xFF1E => {
-                self.channel3.frequency = (self.channel3.frequency & 0xFF) | ((value as u16 & 0x07) << 8);
-                self.channel3.length_enabled = value & 0x40 != 0;
-                if value & 0x80 != 0 {
-                    self.channel3.trigger();
-                }
-            }
-            
-            // NR41 - Channel 4 Length
-            0xFF20 => {
-                self.channel4.length_counter = 64 - (value & 0x3F);
-            }
-            // NR42 - Channel 4 Envelope
-            0xFF21 => {
-                self.channel4.initial_volume = (value >> 4) & 0x0F;
-                self.channel4.envelope_direction = value & 0x08 != 0;
-                self.channel4.envelope_period = value & 0x07;
-                self.channel4.dac_enabled = value & 0xF8 != 0;
-                if !self.channel4.dac_enabled {
-                    self.channel4.enabled = false;
-                }
-            }
-            // NR43 - Channel 4 Polynomial counter
-            0xFF22 => {
-                self.channel4.clock_shift = (value >> 4) & 0x0F;
-                self.channel4.width_mode = value & 0x08 != 0;
-                self.channel4.divisor_code = value & 0x07;
-            }
-            // NR44 - Channel 4 Control
-            0xFF23 => {
-                self.channel4.length_enabled = value & 0x40 != 0;
-                if value & 0x80 != 0 {
-                    self.channel4.trigger();
-                }
-            }
+            0xFF10..=0xFF14 => self.channel1.write_register((addr - 0xFF10) as u8, value, extra_clock),
+            0xFF15..=0xFF19 => self.channel2.write_register((addr - 0xFF15) as u8, value, extra_clock),
+            0xFF1A..=0xFF1E => self.channel3.write_register((addr - 0xFF1A) as u8, value, extra_clock),
+            0xFF1F..=0xFF23 => self.channel4.write_register((addr - 0xFF1F) as u8, value, extra_clock),
             
             // NR50 - Master volume
-            0xFF24 => {
-                self.left_volume = (value >> 4) & 0x07;
-                self.right_volume = value & 0x07;
-            }
+            0xFF24 => self.mixer.write_nr50(value),
             
             // NR51 - Sound panning
-            0xFF25 => {
-                self.left_enables = value & 0x0F;
-                self.right_enables = value & 0xF0;
-            }
+            0xFF25 => self.mixer.write_nr51(value),
             
             // NR52 - Sound on/off
             0xFF26 => {
@@ -948,138 +650,128 @@ impl Apu {
                 self.enabled = value & 0x80 != 0;
                 
                 if was_enabled && !self.enabled {
-                    // Clear all registers when disabled
-                    self.channel1 = Channel1::default();
-                    self.channel2 = Channel2::default();
-                    self.channel3 = Channel3::default();
-                    self.channel4 = Channel4::default();
+                    // Clear all registers when disabled. Wave RAM survives
+                    // on every model, length counters only on the DMG.
+                    let keep_length = !self.model.is_cgb();
+                    self.channel1.power_off(keep_length);
+                    self.channel2.power_off(keep_length);
+                    self.channel3.power_off(keep_length);
+                    self.channel4.power_off(keep_length);
+                    self.mixer = Mixer::default();
+                } else if !was_enabled && self.enabled {
+                    // Frame sequencer restarts from step 0 on power-up
+                    self.frame_sequencer_step = 0;
                 }
             }
             
             // Wave RAM
             0xFF30..=0xFF3F => {
-                self.channel3.wave_ram[(addr - 0xFF30) as usize] = value;
+                if let Some(index) = self.wave_ram_index(addr) {
+                    self.channel3.wave_ram[index] = value;
+                }
             }
             
             _ => {}
         }
     }
     
+    /// Current state of channels 1-4
+    pub fn channel_status(&self) -> [ChannelStatus; 4] {
+        let mut status = [
+            self.channel1.status(),
+            self.channel2.status(),
+            self.channel3.status(),
+            self.channel4.status(),
+        ];
+        
+        for (ch, channel) in status.iter_mut().enumerate() {
+            channel.left = self.mixer.left[ch];
+            channel.right = self.mixer.right[ch];
+        }
+        
+        status
+    }
+    
+    /// Frame sequencer position and channel timers, for audio debugging
+    /// panels. Timers count T-cycles at single speed.
+    pub fn debug_state(&self) -> ApuDebugState {
+        ApuDebugState {
+            enabled: self.enabled,
+            frame_sequencer_step: self.frame_sequencer_step,
+            next_clocks: SequencerClocks::for_step(self.frame_sequencer_step),
+            channels: [
+                self.channel1.debug(),
+                self.channel2.debug(),
+                self.channel3.debug(),
+                self.channel4.debug(),
+            ],
+        }
+    }
+    
     pub fn output_buffer(&self) -> &[f32] {
         &self.output_buffer
     }
     
+    /// Clear the output (and channel) buffers after the frontend has
+    /// read them
     pub fn clear_buffer(&mut self) {
+        self.stats.consumed += self.buffered_samples() as u64;
+        self.discard_buffer();
+    }
+    
+    fn discard_buffer(&mut self) {
         self.output_buffer.clear();
+        for buffer in self.channel_buffers.iter_mut().flatten() {
+            buffer.clear();
+        }
     }
     
     pub fn state(&self) -> ApuState {
         ApuState {
+            version: APU_STATE_VERSION,
             enabled: self.enabled,
             frame_sequencer_step: self.frame_sequencer_step,
-            channel1: Channel1State {
-                enabled: self.channel1.enabled,
-                dac_enabled: self.channel1.dac_enabled,
-                length_counter: self.channel1.length_counter,
-                frequency: self.channel1.frequency,
-                duty: self.channel1.duty,
-                volume: self.channel1.volume,
-                envelope_timer: self.channel1.envelope_timer,
-                envelope_direction: self.channel1.envelope_direction,
-                envelope_period: self.channel1.envelope_period,
-                sweep_timer: self.channel1.sweep_timer,
-                sweep_period: self.channel1.sweep_period,
-                sweep_direction: self.channel1.sweep_direction,
-                sweep_shift: self.channel1.sweep_shift,
-                sweep_enabled: self.channel1.sweep_enabled,
-                shadow_frequency: self.channel1.shadow_frequency,
-            },
-            channel2: Channel2State {
-                enabled: self.channel2.enabled,
-                dac_enabled: self.channel2.dac_enabled,
-                length_counter: self.channel2.length_counter,
-                frequency: self.channel2.frequency,
-                duty: self.channel2.duty,
-                volume: self.channel2.volume,
-                envelope_timer: self.channel2.envelope_timer,
-                envelope_direction: self.channel2.envelope_direction,
-                envelope_period: self.channel2.envelope_period,
-            },
-            channel3: Channel3State {
-                enabled: self.channel3.enabled,
-                dac_enabled: self.channel3.dac_enabled,
-                length_counter: self.channel3.length_counter,
-                frequency: self.channel3.frequency,
-                volume_code: self.channel3.volume_code,
-                sample_index: self.channel3.sample_index,
-            },
-            channel4: Channel4State {
-                enabled: self.channel4.enabled,
-                dac_enabled: self.channel4.dac_enabled,
-                length_counter: self.channel4.length_counter,
-                volume: self.channel4.volume,
-                envelope_timer: self.channel4.envelope_timer,
-                envelope_direction: self.channel4.envelope_direction,
-                envelope_period: self.channel4.envelope_period,
-                lfsr: self.channel4.lfsr,
-                clock_shift: self.channel4.clock_shift,
-                width_mode: self.channel4.width_mode,
-                divisor_code: self.channel4.divisor_code,
-            },
+            channel1: self.channel1.clone(),
+            channel2: self.channel2.clone(),
+            channel3: self.channel3.clone(),
+            channel4: self.channel4.clone(),
+            nr50: self.mixer.read_nr50(),
+            nr51: self.mixer.read_nr51(),
+            sample_timer: self.sample_timer,
+            last_output: self.last_output,
+            fade_in: self.fade_in,
         }
     }
     
     pub fn load_state(&mut self, state: ApuState) {
         self.enabled = state.enabled;
         self.frame_sequencer_step = state.frame_sequencer_step;
+        self.channel1 = state.channel1;
+        self.channel2 = state.channel2;
+        self.channel3 = state.channel3;
+        self.channel4 = state.channel4;
         
-        // Channel 1
-        self.channel1.enabled = state.channel1.enabled;
-        self.channel1.dac_enabled = state.channel1.dac_enabled;
-        self.channel1.length_counter = state.channel1.length_counter;
-        self.channel1.frequency = state.channel1.frequency;
-        self.channel1.duty = state.channel1.duty;
-        self.channel1.volume = state.channel1.volume;
-        self.channel1.envelope_timer = state.channel1.envelope_timer;
-        self.channel1.envelope_direction = state.channel1.envelope_direction;
-        self.channel1.envelope_period = state.channel1.envelope_period;
-        self.channel1.sweep_timer = state.channel1.sweep_timer;
-        self.channel1.sweep_period = state.channel1.sweep_period;
-        self.channel1.sweep_direction = state.channel1.sweep_direction;
-        self.channel1.sweep_shift = state.channel1.sweep_shift;
-        self.channel1.sweep_enabled = state.channel1.sweep_enabled;
-        self.channel1.shadow_frequency = state.channel1.shadow_frequency;
-        
-        // Channel 2
-        self.channel2.enabled = state.channel2.enabled;
-        self.channel2.dac_enabled = state.channel2.dac_enabled;
-        self.channel2.length_counter = state.channel2.length_counter;
-        self.channel2.frequency = state.channel2.frequency;
-        self.channel2.duty = state.channel2.duty;
-        self.channel2.volume = state.channel2.volume;
-        self.channel2.envelope_timer = state.channel2.envelope_timer;
-        self.channel2.envelope_direction = state.channel2.envelope_direction;
-        self.channel2.envelope_period = state.channel2.envelope_period;
-        
-        // Channel 3
-        self.channel3.enabled = state.channel3.enabled;
-        self.channel3.dac_enabled = state.channel3.dac_enabled;
-        self.channel3.length_counter = state.channel3.length_counter;
-        self.channel3.frequency = state.channel3.frequency;
-        self.channel3.volume_code = state.channel3.volume_code;
-        self.channel3.sample_index = state.channel3.sample_index;
-        
-        // Channel 4
-        self.channel4.enabled = state.channel4.enabled;
-        self.channel4.dac_enabled = state.channel4.dac_enabled;
-        self.channel4.length_counter = state.channel4.length_counter;
-        self.channel4.volume = state.channel4.volume;
-        self.channel4.envelope_timer = state.channel4.envelope_timer;
-        self.channel4.envelope_direction = state.channel4.envelope_direction;
-        self.channel4.envelope_period = state.channel4.envelope_period;
-        self.channel4.lfsr = state.channel4.lfsr;
-        self.channel4.clock_shift = state.channel4.clock_shift;
-        self.channel4.width_mode = state.channel4.width_mode;
-        self.channel4.divisor_code = state.channel4.divisor_code;
+        if state.version >= 1 {
+            self.mixer.write_nr50(state.nr50);
+            self.mixer.write_nr51(state.nr51);
+            // A state from the other output mode can't continue its timing
+            self.sample_timer = state.sample_timer % self.cycles_per_sample();
+            self.last_output = state.last_output;
+            self.fade_in = state.fade_in;
+        }
+    }
+}
+
+impl IoDevice for Apu {
+    // Includes the unused holes (0xFF15, 0xFF1F, 0xFF27-0xFF2F), which read 0xFF;
+    // PCM12/PCM34 are read-only
+    const IO_RANGES: &'static [RangeInclusive<u16>] = &[0xFF10..=0xFF3F, 0xFF76..=0xFF77];
+    
+    fn read_io(&self, addr: u16) -> u8 {
+        self.read_register(addr)
+    }
+    
+    fn write_io(&mut self, addr: u16, value: u8) {
+        self.write_register(addr, value);
     }
 }
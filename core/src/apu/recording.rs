@@ -0,0 +1,75 @@
+//! Multitrack recording to WAV
+//!
+//! `StemRecorder` collects the final mix and each channel's stem (see
+//! `Apu::set_channel_capture`) so a soundtrack can be remixed later.
+
+/// Collects the mix and the four channel stems of a recording session
+pub struct StemRecorder {
+    sample_rate: u32,
+    mix: Vec<f32>,
+    stems: [Vec<f32>; 4],
+}
+
+impl StemRecorder {
+    /// Start a recording at the APU's current sample rate
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            mix: Vec::new(),
+            stems: Default::default(),
+        }
+    }
+    
+    /// Append everything the APU produced since its buffers were last
+    /// cleared. Call once per frame, before `GameBoy::clear_audio_buffer`.
+    pub fn capture(&mut self, apu: &super::Apu) {
+        self.mix.extend_from_slice(apu.output_buffer());
+        for (ch, stem) in self.stems.iter_mut().enumerate() {
+            stem.extend_from_slice(apu.channel_buffer(ch));
+        }
+    }
+    
+    /// Length of the recording in seconds
+    pub fn duration(&self) -> f32 {
+        (self.mix.len() / 2) as f32 / self.sample_rate as f32
+    }
+    
+    /// Finish the recording: the mix followed by channels 1-4, each as a
+    /// 16-bit stereo WAV file
+    pub fn finish(self) -> [Vec<u8>; 5] {
+        let [ch1, ch2, ch3, ch4] = &self.stems;
+        [&self.mix, ch1, ch2, ch3, ch4].map(|samples| encode_wav(samples, self.sample_rate))
+    }
+}
+
+/// Encode interleaved stereo samples as a 16-bit PCM WAV file
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    const CHANNELS: u16 = 2;
+    const BITS: u16 = 16;
+    
+    let data_len = (samples.len() * 2) as u32;
+    let block_align = CHANNELS * BITS / 8;
+    
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&CHANNELS.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * block_align as u32).to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&BITS.to_le_bytes());
+    
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for &sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&value.to_le_bytes());
+    }
+    
+    wav
+}
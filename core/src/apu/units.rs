@@ -0,0 +1,369 @@
+//! Building blocks shared by the sound channels
+//!
+//! Each unit owns the state and quirks of one piece of channel hardware so
+//! that every channel using it behaves identically.
+
+use serde::{Serialize, Deserialize};
+
+/// Square wave duty patterns
+const DUTY_TABLE: [[u8; 8]; 4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1], // 12.5%
+    [1, 0, 0, 0, 0, 0, 0, 1], // 25%
+    [1, 0, 0, 0, 0, 1, 1, 1], // 50%
+    [0, 1, 1, 1, 1, 1, 1, 0], // 75%
+];
+
+/// Length counter (NRx1 length load, NRx4 bit 6)
+///
+/// `MAX` is the full length: 64 for the square and noise channels, 256 for
+/// the wave channel.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LengthCounter<const MAX: u16> {
+    /// Remaining length
+    pub counter: u16,
+    
+    /// Length enabled (NRx4 bit 6)
+    pub enabled: bool,
+}
+
+impl<const MAX: u16> LengthCounter<MAX> {
+    /// Load the counter from the length bits of NRx1
+    pub fn load(&mut self, length: u16) {
+        self.counter = MAX - length;
+    }
+    
+    /// Clock the counter; returns true when it expires
+    pub fn clock(&mut self) -> bool {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            return self.counter == 0;
+        }
+        false
+    }
+    
+    /// Update the enable bit from an NRx4 write.
+    ///
+    /// When `extra_clock` is set (the frame sequencer's next step doesn't
+    /// clock length), enabling length clocks the counter immediately.
+    /// Returns true if that clock expired the counter.
+    pub fn set_enabled(&mut self, enabled: bool, extra_clock: bool) -> bool {
+        let was_enabled = self.enabled;
+        self.enabled = enabled;
+        
+        extra_clock && !was_enabled && enabled && self.clock()
+    }
+    
+    /// Reload an empty counter on trigger (one less than the maximum if
+    /// length is enabled and the next frame sequencer step skips length)
+    pub fn trigger(&mut self, extra_clock: bool) {
+        if self.counter == 0 {
+            self.counter = if extra_clock && self.enabled { MAX - 1 } else { MAX };
+        }
+    }
+}
+
+/// Volume envelope (NRx2)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Current volume (0-15)
+    pub volume: u8,
+    
+    /// Volume loaded on trigger
+    pub initial_volume: u8,
+    
+    /// Direction (true = increase)
+    pub increase: bool,
+    
+    /// Sweep period (0 = disabled)
+    pub period: u8,
+    
+    /// Envelope timer
+    pub timer: u8,
+}
+
+impl Envelope {
+    /// Read NRx2
+    pub fn read(&self) -> u8 {
+        (self.initial_volume << 4)
+            | (if self.increase { 0x08 } else { 0 })
+            | self.period
+    }
+    
+    /// Write NRx2; `playing` is whether the owning channel is enabled
+    pub fn write(&mut self, value: u8, playing: bool) {
+        let increase = value & 0x08 != 0;
+        
+        if playing {
+            self.apply_zombie_mode(increase);
+        }
+        
+        self.initial_volume = (value >> 4) & 0x0F;
+        self.increase = increase;
+        self.period = value & 0x07;
+    }
+    
+    /// Volume change after an NRx2 write to a playing channel ("zombie mode").
+    ///
+    /// The envelope hardware isn't reset by the write, so the current volume
+    /// is nudged instead: +1 if the old period was 0, otherwise +2 if the old
+    /// mode was decreasing, and inverted (16 - v) if the direction flips.
+    fn apply_zombie_mode(&mut self, new_increase: bool) {
+        let mut volume = self.volume;
+        
+        if self.period == 0 {
+            volume = volume.wrapping_add(1);
+        } else if !self.increase {
+            volume = volume.wrapping_add(2);
+        }
+        
+        if self.increase != new_increase {
+            volume = 16u8.wrapping_sub(volume);
+        }
+        
+        self.volume = volume & 0x0F;
+    }
+    
+    /// The channel DAC is powered when NRx2 bits 3-7 are not all zero
+    pub fn dac_enabled(&self) -> bool {
+        self.initial_volume != 0 || self.increase
+    }
+    
+    /// Restart the envelope on trigger
+    pub fn trigger(&mut self) {
+        self.timer = self.period;
+        self.volume = self.initial_volume;
+    }
+    
+    /// Clock the envelope (frame sequencer step 7)
+    pub fn clock(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+        
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        
+        if self.timer == 0 {
+            self.timer = self.period;
+            
+            if self.increase && self.volume < 15 {
+                self.volume += 1;
+            } else if !self.increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+}
+
+/// Frequency sweep (NR10, channel 1 only)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Sweep {
+    /// Sweep timer
+    pub timer: u8,
+    
+    /// Sweep period
+    pub period: u8,
+    
+    /// Direction (true = frequency decreases)
+    pub negate: bool,
+    
+    /// Shift amount
+    pub shift: u8,
+    
+    /// Internal enable flag
+    pub enabled: bool,
+    
+    /// Shadow frequency register
+    pub shadow_frequency: u16,
+}
+
+impl Sweep {
+    /// Read NR10
+    pub fn read(&self) -> u8 {
+        0x80 | (self.period << 4)
+            | (if self.negate { 0x08 } else { 0 })
+            | self.shift
+    }
+    
+    /// Write NR10
+    pub fn write(&mut self, value: u8) {
+        self.period = (value >> 4) & 0x07;
+        self.negate = value & 0x08 != 0;
+        self.shift = value & 0x07;
+    }
+    
+    fn reload_timer(&mut self) {
+        self.timer = if self.period > 0 { self.period } else { 8 };
+    }
+    
+    /// Compute the next frequency from the shadow register
+    fn calculate(&self) -> u16 {
+        let delta = self.shadow_frequency >> self.shift;
+        
+        if self.negate {
+            self.shadow_frequency.wrapping_sub(delta)
+        } else {
+            self.shadow_frequency.wrapping_add(delta)
+        }
+    }
+    
+    /// Restart the sweep on trigger; returns false if the initial overflow
+    /// check disables the channel
+    pub fn trigger(&mut self, frequency: u16) -> bool {
+        self.shadow_frequency = frequency;
+        self.reload_timer();
+        self.enabled = self.period > 0 || self.shift > 0;
+        
+        self.shift == 0 || self.calculate() <= 2047
+    }
+    
+    /// Clock the sweep (frame sequencer steps 2 and 6), updating `frequency`.
+    /// Returns false if the frequency overflowed and the channel must stop.
+    pub fn clock(&mut self, frequency: &mut u16) -> bool {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        
+        if self.timer != 0 {
+            return true;
+        }
+        
+        self.reload_timer();
+        
+        if !self.enabled || self.period == 0 {
+            return true;
+        }
+        
+        let new_freq = self.calculate();
+        if new_freq > 2047 {
+            return false;
+        }
+        
+        if self.shift > 0 {
+            *frequency = new_freq;
+            self.shadow_frequency = new_freq;
+            
+            // Overflow check
+            if self.calculate() > 2047 {
+                return false;
+            }
+        }
+        
+        true
+    }
+}
+
+/// Square wave duty generator (NRx1 bits 6-7)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Duty {
+    /// Selected duty pattern (0-3)
+    pub pattern: u8,
+    
+    /// Position within the 8-step waveform
+    pub position: u8,
+}
+
+impl Duty {
+    /// Advance to the next waveform step
+    pub fn advance(&mut self) {
+        self.position = (self.position + 1) & 7;
+    }
+    
+    /// Whether the waveform is currently high
+    pub fn high(&self) -> bool {
+        DUTY_TABLE[self.pattern as usize][self.position as usize] == 1
+    }
+}
+
+/// Stereo mixer (NR50 master volume, NR51 panning)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Mixer {
+    /// Left (SO2) master volume (0-7)
+    pub left_volume: u8,
+    
+    /// Right (SO1) master volume (0-7)
+    pub right_volume: u8,
+    
+    /// Cartridge VIN routed to the left output (NR50 bit 7)
+    pub vin_left: bool,
+    
+    /// Cartridge VIN routed to the right output (NR50 bit 3)
+    pub vin_right: bool,
+    
+    /// Channels 1-4 routed to the left output (NR51 bits 4-7)
+    pub left: [bool; 4],
+    
+    /// Channels 1-4 routed to the right output (NR51 bits 0-3)
+    pub right: [bool; 4],
+}
+
+impl Mixer {
+    /// Read NR50
+    pub fn read_nr50(&self) -> u8 {
+        (if self.vin_left { 0x80 } else { 0 })
+            | (self.left_volume << 4)
+            | (if self.vin_right { 0x08 } else { 0 })
+            | self.right_volume
+    }
+    
+    /// Write NR50
+    pub fn write_nr50(&mut self, value: u8) {
+        self.vin_left = value & 0x80 != 0;
+        self.left_volume = (value >> 4) & 0x07;
+        self.vin_right = value & 0x08 != 0;
+        self.right_volume = value & 0x07;
+    }
+    
+    /// Read NR51
+    pub fn read_nr51(&self) -> u8 {
+        (0..4).fold(0, |acc, ch| {
+            acc | (if self.right[ch] { 1 << ch } else { 0 })
+                | (if self.left[ch] { 0x10 << ch } else { 0 })
+        })
+    }
+    
+    /// Write NR51
+    pub fn write_nr51(&mut self, value: u8) {
+        for ch in 0..4 {
+            self.right[ch] = value & (1 << ch) != 0;
+            self.left[ch] = value & (0x10 << ch) != 0;
+        }
+    }
+    
+    /// Mix the four channel outputs into a (left, right) pair.
+    ///
+    /// No cartridge supported here drives VIN, so the VIN bits only
+    /// affect what NR50 reads back.
+    pub fn mix(&self, channels: [f32; 4]) -> (f32, f32) {
+        let mut left = 0.0f32;
+        let mut right = 0.0f32;
+        
+        for (ch, sample) in channels.iter().enumerate() {
+            if self.left[ch] { left += sample; }
+            if self.right[ch] { right += sample; }
+        }
+        
+        // Apply master volume
+        left *= (self.left_volume as f32 + 1.0) / 32.0;
+        right *= (self.right_volume as f32 + 1.0) / 32.0;
+        
+        (left.clamp(-1.0, 1.0), right.clamp(-1.0, 1.0))
+    }
+}
+
+/// Level where `soft_clip` starts compressing
+const SOFT_CLIP_KNEE: f32 = 0.75;
+
+/// Linear up to the knee, then curving smoothly towards ±1.0 (matching
+/// slope at the knee so there's no audible corner)
+pub fn soft_clip(sample: f32) -> f32 {
+    let level = sample.abs();
+    if level <= SOFT_CLIP_KNEE {
+        return sample;
+    }
+    
+    let headroom = 1.0 - SOFT_CLIP_KNEE;
+    let compressed = SOFT_CLIP_KNEE + headroom * ((level - SOFT_CLIP_KNEE) / headroom).tanh();
+    compressed.copysign(sample)
+}
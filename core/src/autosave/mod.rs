@@ -0,0 +1,62 @@
+//! # Autosave
+//!
+//! Decides when battery RAM is worth writing out: once the game has
+//! written to it and then left it alone for a number of frames. Saving
+//! right after each write would catch saves half written and, for games
+//! that touch SRAM every frame, save every frame.
+
+/// Debounces SRAM writes into save requests (see `GbConfig::autosave_frames`)
+#[derive(Debug, Clone, Default)]
+pub struct Autosave {
+    /// Frames without writes before saving (0 = off)
+    quiet_frames: u32,
+    
+    /// Written since the last save
+    dirty: bool,
+    
+    /// Frames since the last write
+    idle: u32,
+}
+
+impl Autosave {
+    pub fn new(quiet_frames: u32) -> Self {
+        Self { quiet_frames, ..Default::default() }
+    }
+    
+    /// Frames without writes before saving (0 = off)
+    pub fn quiet_frames(&self) -> u32 {
+        self.quiet_frames
+    }
+    
+    /// Change the quiet period, keeping any unsaved writes
+    pub fn set_quiet_frames(&mut self, quiet_frames: u32) {
+        self.quiet_frames = quiet_frames;
+    }
+    
+    /// Record the end of a frame in which SRAM was or wasn't `written`;
+    /// returns whether the save should be written now
+    pub fn end_frame(&mut self, written: bool) -> bool {
+        if written {
+            self.dirty = true;
+            self.idle = 0;
+            return false;
+        }
+        if !self.dirty || self.quiet_frames == 0 {
+            return false;
+        }
+        
+        self.idle += 1;
+        if self.idle < self.quiet_frames {
+            return false;
+        }
+        self.dirty = false;
+        true
+    }
+    
+    /// Forget unsaved writes (after the frontend saved or loaded SRAM
+    /// itself)
+    pub fn clear(&mut self) {
+        self.dirty = false;
+        self.idle = 0;
+    }
+}
@@ -0,0 +1,84 @@
+//! Battery Save Deltas
+//!
+//! Compact differences between two versions of a battery save, so saves
+//! can be synced without sending the whole RAM every time. A delta is the
+//! new save length followed by runs of changed bytes (all little endian):
+//!
+//! ```text
+//! length: u32
+//! (offset: u32, count: u32, bytes: [u8; count])*
+//! ```
+
+/// Unchanged bytes shorter than this don't split a run (a run header
+/// costs 8 bytes)
+const MERGE_GAP: usize = 8;
+
+/// Encode the changes that turn `old` into `new`. Bytes past the end of
+/// `old` count as changed.
+pub fn diff(old: &[u8], new: &[u8]) -> Vec<u8> {
+    let mut delta = (new.len() as u32).to_le_bytes().to_vec();
+    let changed = |i: usize| old.get(i) != Some(&new[i]);
+    
+    let mut i = 0;
+    while i < new.len() {
+        if !changed(i) {
+            i += 1;
+            continue;
+        }
+        
+        // Extend the run until MERGE_GAP unchanged bytes in a row
+        let start = i;
+        let mut end = i + 1;
+        while end < new.len() {
+            match (end..(end + MERGE_GAP).min(new.len())).find(|&j| changed(j)) {
+                Some(next) => end = next + 1,
+                None => break,
+            }
+        }
+        
+        delta.extend_from_slice(&(start as u32).to_le_bytes());
+        delta.extend_from_slice(&((end - start) as u32).to_le_bytes());
+        delta.extend_from_slice(&new[start..end]);
+        i = end;
+    }
+    
+    delta
+}
+
+/// Apply a delta from `diff`, resizing `data` to the new length. A new
+/// length over `max_len` is rejected, and `data` is left alone unless
+/// every run checks out.
+pub fn apply(data: &mut Vec<u8>, delta: &[u8], max_len: usize) -> Result<(), String> {
+    let mut reader = delta;
+    let length = read_u32(&mut reader)?;
+    if length > max_len {
+        return Err(format!("Save delta is for a {} byte save, over the {} byte limit", length, max_len));
+    }
+    
+    let mut runs = Vec::new();
+    while !reader.is_empty() {
+        let offset = read_u32(&mut reader)?;
+        let count = read_u32(&mut reader)?;
+        let in_range = offset.checked_add(count).is_some_and(|end| end <= length);
+        if count > reader.len() || !in_range {
+            return Err("Save delta run is out of range".to_string());
+        }
+        
+        let (bytes, rest) = reader.split_at(count);
+        runs.push((offset, bytes));
+        reader = rest;
+    }
+    
+    data.resize(length, 0);
+    for (offset, bytes) in runs {
+        data[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+    
+    Ok(())
+}
+
+fn read_u32(reader: &mut &[u8]) -> Result<usize, String> {
+    let (bytes, rest) = reader.split_first_chunk::<4>().ok_or("Truncated save delta")?;
+    *reader = rest;
+    Ok(u32::from_le_bytes(*bytes) as usize)
+}
@@ -0,0 +1,379 @@
+//! Memory Bank Controllers
+//!
+//! Each mapper decodes writes to 0x0000-0x7FFF into its bank registers and
+//! reports which banks are selected. The cartridge turns that into byte
+//! offsets after every control write, so reads never go through the mapper.
+
+use super::{RamMapping, Rtc};
+
+/// Bank registers shared by every mapper (and stored in save states)
+#[derive(Debug, Clone)]
+pub struct BankRegisters {
+    /// Current ROM bank (9-bit for MBC5)
+    pub rom_bank: u16,
+    
+    /// Current RAM bank (MBC1: upper ROM bank bits)
+    pub ram_bank: u8,
+    
+    /// RAM enabled
+    pub ram_enabled: bool,
+    
+    /// MBC1 banking mode (0 = ROM, 1 = RAM)
+    pub banking_mode: u8,
+}
+
+impl Default for BankRegisters {
+    fn default() -> Self {
+        Self {
+            rom_bank: 1,
+            ram_bank: 0,
+            ram_enabled: false,
+            banking_mode: 0,
+        }
+    }
+}
+
+/// A Memory Bank Controller
+pub trait Mbc {
+    /// Handle a write to 0x0000-0x7FFF
+    fn write_control(&mut self, addr: u16, value: u8);
+    
+    /// ROM banks selected for 0x0000-0x3FFF and 0x4000-0x7FFF (before
+    /// wrapping to the ROM size)
+    fn rom_banks(&self) -> (usize, usize);
+    
+    /// What 0xA000-0xBFFF is mapped to (before checking the RAM size)
+    fn ram_mapping(&self) -> RamMapping;
+    
+    fn registers(&self) -> &BankRegisters;
+    
+    fn registers_mut(&mut self) -> &mut BankRegisters;
+    
+    /// Return the registers to their power-on values
+    fn reset(&mut self) {
+        *self.registers_mut() = BankRegisters::default();
+    }
+    
+    /// Address bits decoded within the RAM area
+    fn ram_address_mask(&self) -> usize {
+        0x1FFF
+    }
+    
+    /// Data bits stored per RAM byte (the rest read back as 1)
+    fn ram_data_mask(&self) -> u8 {
+        0xFF
+    }
+    
+    fn rtc(&self) -> Option<&Rtc> {
+        None
+    }
+    
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        None
+    }
+}
+
+/// ROM only
+#[derive(Default)]
+pub struct NoMbc {
+    registers: BankRegisters,
+}
+
+impl Mbc for NoMbc {
+    fn write_control(&mut self, _addr: u16, _value: u8) {}
+    
+    fn rom_banks(&self) -> (usize, usize) {
+        (0, 1)
+    }
+    
+    fn ram_mapping(&self) -> RamMapping {
+        if self.registers.ram_enabled { RamMapping::Bank(0) } else { RamMapping::Disabled }
+    }
+    
+    fn registers(&self) -> &BankRegisters {
+        &self.registers
+    }
+    
+    fn registers_mut(&mut self) -> &mut BankRegisters {
+        &mut self.registers
+    }
+}
+
+/// MBC1.
+///
+/// BANK1 (`rom_bank`) is a 5-bit register that reads as 1 when written
+/// with 0; BANK2 (`ram_bank`) supplies ROM bank bits 5-6, or bits 4-5 on
+/// MBC1M multicarts, which leave BANK1 bit 4 unconnected. Bank numbers
+/// are wrapped to the ROM size by the cartridge.
+#[derive(Default)]
+pub struct Mbc1 {
+    registers: BankRegisters,
+    
+    /// MBC1M wiring (1MB multicart)
+    multicart: bool,
+}
+
+impl Mbc1 {
+    pub fn new(multicart: bool) -> Self {
+        Self {
+            multicart,
+            ..Self::default()
+        }
+    }
+    
+    /// Bit position of BANK2 in the ROM bank number
+    fn high_shift(&self) -> usize {
+        if self.multicart { 4 } else { 5 }
+    }
+}
+
+impl Mbc for Mbc1 {
+    fn write_control(&mut self, addr: u16, value: u8) {
+        let regs = &mut self.registers;
+        match addr {
+            // RAM enable
+            0x0000..=0x1FFF => {
+                regs.ram_enabled = (value & 0x0F) == 0x0A;
+            }
+            // BANK1: ROM bank low bits (the 0 check sees all 5 bits)
+            0x2000..=0x3FFF => {
+                regs.rom_bank = (value & 0x1F).max(1) as u16;
+            }
+            // RAM bank / ROM bank high bits
+            0x4000..=0x5FFF => {
+                regs.ram_bank = value & 0x03;
+            }
+            // Banking mode
+            0x6000..=0x7FFF => {
+                regs.banking_mode = value & 0x01;
+            }
+            _ => {}
+        }
+    }
+    
+    fn rom_banks(&self) -> (usize, usize) {
+        let regs = &self.registers;
+        let shift = self.high_shift();
+        let high = (regs.ram_bank as usize & 0x03) << shift;
+        let low_mask = (1 << shift) - 1;
+        
+        // Bank 0 area shows BANK2 alone (0x20/0x40/0x60) in mode 1
+        let bank0 = if regs.banking_mode == 1 { high } else { 0 };
+        
+        let bank = (regs.rom_bank.max(1) as usize & low_mask) | high;
+        (bank0, bank)
+    }
+    
+    fn ram_mapping(&self) -> RamMapping {
+        let regs = &self.registers;
+        if !regs.ram_enabled {
+            RamMapping::Disabled
+        } else if regs.banking_mode == 1 {
+            RamMapping::Bank(regs.ram_bank as usize & 0x03)
+        } else {
+            RamMapping::Bank(0)
+        }
+    }
+    
+    fn registers(&self) -> &BankRegisters {
+        &self.registers
+    }
+    
+    fn registers_mut(&mut self) -> &mut BankRegisters {
+        &mut self.registers
+    }
+}
+
+/// MBC2 (built-in 512x4 bit RAM)
+#[derive(Default)]
+pub struct Mbc2 {
+    registers: BankRegisters,
+}
+
+impl Mbc for Mbc2 {
+    fn write_control(&mut self, addr: u16, value: u8) {
+        let regs = &mut self.registers;
+        match addr {
+            // RAM enable (bit 8 of address must be 0)
+            0x0000..=0x3FFF if addr & 0x0100 == 0 => {
+                regs.ram_enabled = (value & 0x0F) == 0x0A;
+            }
+            // ROM bank (bit 8 of address must be 1)
+            0x0000..=0x3FFF => {
+                regs.rom_bank = (value & 0x0F).max(1) as u16;
+            }
+            _ => {}
+        }
+    }
+    
+    fn rom_banks(&self) -> (usize, usize) {
+        (0, (self.registers.rom_bank as usize).max(1) & 0x0F)
+    }
+    
+    fn ram_mapping(&self) -> RamMapping {
+        if self.registers.ram_enabled { RamMapping::Bank(0) } else { RamMapping::Disabled }
+    }
+    
+    fn registers(&self) -> &BankRegisters {
+        &self.registers
+    }
+    
+    fn registers_mut(&mut self) -> &mut BankRegisters {
+        &mut self.registers
+    }
+    
+    fn ram_address_mask(&self) -> usize {
+        0x1FF
+    }
+    
+    fn ram_data_mask(&self) -> u8 {
+        0x0F
+    }
+}
+
+/// MBC3 (optional RTC)
+#[derive(Default)]
+pub struct Mbc3 {
+    registers: BankRegisters,
+    
+    /// Real time clock, if the cartridge has one
+    rtc: Option<Rtc>,
+    
+    /// RTC register selected (0 = RAM bank selected)
+    rtc_register: u8,
+}
+
+impl Mbc3 {
+    pub fn new(has_rtc: bool) -> Self {
+        Self {
+            rtc: has_rtc.then(Rtc::default),
+            ..Self::default()
+        }
+    }
+}
+
+impl Mbc for Mbc3 {
+    fn write_control(&mut self, addr: u16, value: u8) {
+        let regs = &mut self.registers;
+        match addr {
+            // RAM/RTC enable
+            0x0000..=0x1FFF => {
+                regs.ram_enabled = (value & 0x0F) == 0x0A;
+            }
+            // ROM bank
+            0x2000..=0x3FFF => {
+                regs.rom_bank = (value & 0x7F).max(1) as u16;
+            }
+            // RAM bank / RTC register select
+            0x4000..=0x5FFF => {
+                if value <= 0x03 {
+                    regs.ram_bank = value;
+                    self.rtc_register = 0;
+                } else if (0x08..=0x0C).contains(&value) {
+                    self.rtc_register = value;
+                }
+            }
+            // Latch clock data
+            0x6000..=0x7FFF => {
+                if let Some(ref mut rtc) = self.rtc {
+                    if value == 0x01 && rtc.latch_ready {
+                        rtc.latch();
+                    }
+                    rtc.latch_ready = value == 0x00;
+                }
+            }
+            _ => {}
+        }
+    }
+    
+    fn rom_banks(&self) -> (usize, usize) {
+        (0, (self.registers.rom_bank as usize).max(1) & 0x7F)
+    }
+    
+    fn ram_mapping(&self) -> RamMapping {
+        if self.rtc_register != 0 && self.rtc.is_some() {
+            RamMapping::Rtc(self.rtc_register)
+        } else if !self.registers.ram_enabled {
+            RamMapping::Disabled
+        } else {
+            RamMapping::Bank(self.registers.ram_bank as usize & 0x03)
+        }
+    }
+    
+    fn registers(&self) -> &BankRegisters {
+        &self.registers
+    }
+    
+    fn registers_mut(&mut self) -> &mut BankRegisters {
+        &mut self.registers
+    }
+    
+    fn reset(&mut self) {
+        self.registers = BankRegisters::default();
+        self.rtc_register = 0;
+    }
+    
+    fn rtc(&self) -> Option<&Rtc> {
+        self.rtc.as_ref()
+    }
+    
+    fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.rtc.as_mut()
+    }
+}
+
+/// MBC5.
+///
+/// The ROM bank register is 9 bits, written as the low 8 bits at
+/// 0x2000-0x2FFF and bit 8 at 0x3000-0x3FFF. Unlike MBC1 and MBC3,
+/// writing 0 really maps bank 0 at 0x4000. Bank numbers are wrapped to the
+/// ROM size by the cartridge.
+#[derive(Default)]
+pub struct Mbc5 {
+    registers: BankRegisters,
+}
+
+impl Mbc for Mbc5 {
+    fn write_control(&mut self, addr: u16, value: u8) {
+        let regs = &mut self.registers;
+        match addr {
+            // RAM enable
+            0x0000..=0x1FFF => {
+                regs.ram_enabled = (value & 0x0F) == 0x0A;
+            }
+            // ROM bank low 8 bits
+            0x2000..=0x2FFF => {
+                regs.rom_bank = (regs.rom_bank & 0x100) | value as u16;
+            }
+            // ROM bank bit 8
+            0x3000..=0x3FFF => {
+                regs.rom_bank = (regs.rom_bank & 0xFF) | ((value as u16 & 0x01) << 8);
+            }
+            // RAM bank
+            0x4000..=0x5FFF => {
+                regs.ram_bank = value & 0x0F;
+            }
+            _ => {}
+        }
+    }
+    
+    fn rom_banks(&self) -> (usize, usize) {
+        (0, self.registers.rom_bank as usize & 0x1FF)
+    }
+    
+    fn ram_mapping(&self) -> RamMapping {
+        if self.registers.ram_enabled {
+            RamMapping::Bank(self.registers.ram_bank as usize & 0x0F)
+        } else {
+            RamMapping::Disabled
+        }
+    }
+    
+    fn registers(&self) -> &BankRegisters {
+        &self.registers
+    }
+    
+    fn registers_mut(&mut self) -> &mut BankRegisters {
+        &mut self.registers
+    }
+}
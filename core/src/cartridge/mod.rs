@@ -7,8 +7,14 @@
 //! - MBC3 (max 2MB ROM, 32KB RAM, RTC)
 //! - MBC5 (max 8MB ROM, 128KB RAM)
 
+mod mbc;
+pub mod delta;
+
 use serde::{Serialize, Deserialize};
 
+use crate::clock::{ClockSource, HostClock};
+use mbc::{Mbc, Mbc1, Mbc2, Mbc3, Mbc5, NoMbc};
+
 /// Cartridge header offsets
 const TITLE_START: usize = 0x0134;
 const TITLE_END: usize = 0x0143;
@@ -17,6 +23,13 @@ const CARTRIDGE_TYPE: usize = 0x0147;
 #[allow(dead_code)]
 const ROM_SIZE: usize = 0x0148;
 const RAM_SIZE: usize = 0x0149;
+const HEADER_CHECKSUM: usize = 0x014D;
+
+/// Most external RAM a mapper can address (16 banks of 8KB, MBC5)
+const MAX_RAM_SIZE: usize = 0x20000;
+
+/// RTC registers and timestamp appended to battery saves
+const RTC_SAVE_SIZE: usize = 48;
 
 /// MBC types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,6 +41,19 @@ pub enum MbcType {
     Mbc5,
 }
 
+/// External RAM area (0xA000-0xBFFF) mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RamMapping {
+    /// RAM disabled or absent, or the selected bank doesn't exist (reads 0xFF)
+    Disabled,
+    
+    /// RAM bank
+    Bank(usize),
+    
+    /// MBC3 RTC register (0x08-0x0C)
+    Rtc(u8),
+}
+
 /// RTC register (for MBC3)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Rtc {
@@ -97,6 +123,30 @@ impl Rtc {
         }
     }
     
+    /// Advance the clock by a number of seconds at once, as if it had kept
+    /// running (e.g. while the game was switched off)
+    pub fn advance(&mut self, seconds: u64) {
+        if self.is_halted() {
+            return;
+        }
+        
+        let total = seconds
+            + self.seconds as u64
+            + self.minutes as u64 * 60
+            + self.hours as u64 * 3600
+            + self.days() as u64 * 86400;
+        
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+        
+        let days = total / 86400;
+        if days >= 512 {
+            self.days_high |= 0x80;
+        }
+        self.set_days((days % 512) as u16);
+    }
+    
     /// Latch current time
     pub fn latch(&mut self) {
         self.latched[0] = self.seconds;
@@ -144,9 +194,12 @@ pub struct CartridgeState {
 
 /// Game Boy Cartridge
 pub struct Cartridge {
-    /// ROM data
+    /// ROM data, padded to whole banks
     rom: Vec<u8>,
     
+    /// Size of the ROM as loaded, before padding
+    rom_size: usize,
+    
     /// External RAM
     ram: Vec<u8>,
     
@@ -162,32 +215,78 @@ pub struct Cartridge {
     /// Has battery backup
     has_battery: bool,
     
-    /// Has RTC (for future RTC persistence)
-    #[allow(dead_code)]
+    /// Has RTC
     has_rtc: bool,
     
-    /// Current ROM bank (14-bit for MBC5)
-    rom_bank: u16,
+    /// Memory bank controller
+    mbc: Box<dyn Mbc>,
+    
+    /// Byte offsets into the ROM of the banks mapped at 0x0000-0x3FFF and
+    /// 0x4000-0x7FFF
+    rom_offsets: [usize; 2],
+    
+    /// Current 0xA000-0xBFFF mapping (Disabled for banks the RAM lacks)
+    ram_mapping: RamMapping,
+    
+    /// Byte offset into the RAM of the mapped bank
+    ram_base: usize,
+    
+    /// RAM bank the MBC selects, before wrapping to the RAM size
+    ram_selected: Option<usize>,
+    
+    /// Address bits decoded within the RAM area, limited to the RAM size
+    /// (every RAM size is a power of two, so this also wraps small RAMs)
+    ram_address_mask: usize,
     
-    /// Current RAM bank
-    ram_bank: u8,
+    /// Data bits stored per RAM byte
+    ram_data_mask: u8,
     
-    /// RAM enabled
-    ram_enabled: bool,
+    /// Grow the RAM when written past its declared size
+    sram_auto_grow: bool,
     
-    /// MBC1 banking mode (0 = ROM, 1 = RAM)
-    banking_mode: u8,
+    /// Highest RAM offset written past the declared size
+    sram_overflow: Option<usize>,
     
-    /// RTC for MBC3
-    rtc: Option<Rtc>,
+    /// RAM or RTC written since `take_ram_written`
+    ram_written: bool,
     
-    /// RTC register selected
-    rtc_register: u8,
+    /// Unsupported cartridge type byte that was mapped to a guess
+    unknown_type: Option<u8>,
+    
+    /// Wall clock used to timestamp saves and catch the RTC up on load
+    clock: Box<dyn ClockSource>,
+}
+
+/// Cartridge type bytes with a known mapper
+fn is_supported_type(cart_type: u8) -> bool {
+    matches!(cart_type, 0x00..=0x03 | 0x05 | 0x06 | 0x0F..=0x13 | 0x19..=0x1E)
+}
+
+/// MBC1M multicarts are 1MB and repeat the boot logo at the start of
+/// each 256KB game (bank 0x10 and up)
+fn is_mbc1_multicart(rom: &[u8]) -> bool {
+    const LOGO: std::ops::Range<usize> = 0x0104..0x0134;
+    const GAME_SIZE: usize = 0x40000;
+    
+    rom.len() == 4 * GAME_SIZE
+        && rom[GAME_SIZE + LOGO.start..GAME_SIZE + LOGO.end] == rom[LOGO]
 }
 
 impl Cartridge {
     /// Create a cartridge from ROM data
     pub fn from_rom(data: &[u8]) -> Result<Self, String> {
+        Self::load(data, false)
+    }
+    
+    /// Create a cartridge from ROM data, treating an unsupported
+    /// cartridge type as MBC5 with battery-backed RAM (the declared size,
+    /// or 32KB if none) instead of failing. Homebrew and bootlegs often
+    /// carry nonstandard type bytes; see `unknown_type`.
+    pub fn from_rom_with_fallback(data: &[u8]) -> Result<Self, String> {
+        Self::load(data, true)
+    }
+    
+    fn load(data: &[u8], allow_unknown: bool) -> Result<Self, String> {
         if data.len() < 0x150 {
             return Err("ROM too small".to_string());
         }
@@ -223,8 +322,13 @@ impl Cartridge {
             0x1C => (MbcType::Mbc5, false, false),
             0x1D => (MbcType::Mbc5, false, false),
             0x1E => (MbcType::Mbc5, true, false),
+            _ if allow_unknown => {
+                log::warn!("Unsupported cartridge type 0x{:02X}, guessing MBC5 with battery RAM", cart_type);
+                (MbcType::Mbc5, true, false)
+            }
             _ => return Err(format!("Unsupported cartridge type: 0x{:02X}", cart_type)),
         };
+        let unknown_type = (allow_unknown && !is_supported_type(cart_type)).then_some(cart_type);
         
         // Calculate RAM size
         let ram_size = match data[RAM_SIZE] {
@@ -240,21 +344,62 @@ impl Cartridge {
         // MBC2 has internal 512 nibble RAM
         let ram_size = if mbc_type == MbcType::Mbc2 { 512 } else { ram_size };
         
-        Ok(Self {
-            rom: data.to_vec(),
+        // A guessed mapper gets RAM even if the header declares none
+        let ram_size = if unknown_type.is_some() && ram_size == 0 { 32 * 1024 } else { ram_size };
+        
+        let mbc: Box<dyn Mbc> = match mbc_type {
+            MbcType::None => Box::new(NoMbc::default()),
+            MbcType::Mbc1 => Box::new(Mbc1::new(is_mbc1_multicart(data))),
+            MbcType::Mbc2 => Box::new(Mbc2::default()),
+            MbcType::Mbc3 => Box::new(Mbc3::new(has_rtc)),
+            MbcType::Mbc5 => Box::new(Mbc5::default()),
+        };
+        
+        // Pad to a power of two banks (open bus), like the smallest ROM
+        // chip that holds the data, so bank numbers wrap by masking off
+        // the address lines the chip doesn't have
+        let mut rom = data.to_vec();
+        rom.resize(rom.len().div_ceil(0x4000).next_power_of_two() * 0x4000, 0xFF);
+        
+        let mut cartridge = Self {
+            rom,
+            rom_size: data.len(),
             ram: vec![0; ram_size],
             title,
             mbc_type,
             is_cgb,
             has_battery,
             has_rtc,
-            rom_bank: 1,
-            ram_bank: 0,
-            ram_enabled: false,
-            banking_mode: 0,
-            rtc: if has_rtc { Some(Rtc::default()) } else { None },
-            rtc_register: 0,
-        })
+            ram_address_mask: mbc.ram_address_mask() & ram_size.saturating_sub(1),
+            ram_data_mask: mbc.ram_data_mask(),
+            mbc,
+            rom_offsets: [0, 0x4000],
+            ram_mapping: RamMapping::Disabled,
+            ram_base: 0,
+            ram_selected: None,
+            sram_auto_grow: false,
+            sram_overflow: None,
+            ram_written: false,
+            unknown_type,
+            clock: Box::new(HostClock),
+        };
+        cartridge.update_banks();
+        Ok(cartridge)
+    }
+    
+    /// An empty cartridge slot: every ROM read returns 0xFF, so a boot
+    /// ROM's logo check fails and it locks up like real hardware
+    pub fn empty() -> Self {
+        let mut data = vec![0xFF; 0x8000];
+        data[CGB_FLAG] = 0x00;
+        data[CARTRIDGE_TYPE] = 0x00;
+        data[RAM_SIZE] = 0x00;
+        
+        let mut cartridge = Self::from_rom(&data).expect("ROM-only header is supported");
+        cartridge.rom.fill(0xFF);
+        cartridge.rom_size = 0;
+        cartridge.title.clear();
+        cartridge
     }
     
     /// Get game title
@@ -267,282 +412,228 @@ impl Cartridge {
         self.is_cgb
     }
     
+    /// ROM data as loaded (without the padding to whole banks)
+    pub fn rom(&self) -> &[u8] {
+        &self.rom[..self.rom_size]
+    }
+    
+    /// The header checksum at 0x014D matches bytes 0x0134-0x014C (the
+    /// boot ROM refuses to start otherwise)
+    pub fn header_checksum_valid(&self) -> bool {
+        let checksum = self.rom[TITLE_START..HEADER_CHECKSUM].iter()
+            .fold(0u8, |sum, &byte| sum.wrapping_sub(byte).wrapping_sub(1));
+        checksum == self.rom[HEADER_CHECKSUM]
+    }
+    
+    /// Recompute the mapped bank offsets from the MBC registers
+    fn update_banks(&mut self) {
+        let (bank0, bank) = self.rom_banks();
+        self.rom_offsets = [bank0 * 0x4000, bank * 0x4000];
+        
+        let mapping = self.mbc.ram_mapping();
+        self.ram_selected = match mapping {
+            RamMapping::Bank(bank) => Some(bank),
+            _ => None,
+        };
+        
+        // Banks past the end of the RAM are open bus rather than aliases
+        // of lower banks
+        self.ram_mapping = match mapping {
+            RamMapping::Bank(bank) if bank * 0x2000 >= self.ram.len() => RamMapping::Disabled,
+            mapping => mapping,
+        };
+        
+        if let RamMapping::Bank(bank) = self.ram_mapping {
+            self.ram_base = bank * 0x2000;
+        }
+    }
+    
+    /// Bank number bits decoded by the ROM (its bank count is a power of
+    /// two)
+    fn rom_bank_mask(&self) -> usize {
+        self.rom.len() / 0x4000 - 1
+    }
+    
+    /// ROM banks actually visible at 0x0000-0x3FFF and 0x4000-0x7FFF
+    pub fn rom_banks(&self) -> (usize, usize) {
+        let (bank0, bank) = self.mbc.rom_banks();
+        let mask = self.rom_bank_mask();
+        (bank0 & mask, bank & mask)
+    }
+    
+    /// What the CPU currently sees at 0xA000-0xBFFF
+    pub fn ram_mapping(&self) -> RamMapping {
+        self.ram_mapping
+    }
+    
+    /// Check if the cartridge has battery-backed RAM or RTC
+    pub fn has_battery(&self) -> bool {
+        self.has_battery
+    }
+    
+    /// Get the MBC type
+    pub fn mbc_type(&self) -> MbcType {
+        self.mbc_type
+    }
+    
     /// Read from ROM area
     pub fn read_rom(&self, addr: u16) -> u8 {
-        match self.mbc_type {
-            MbcType::None => {
-                self.rom.get(addr as usize).copied().unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc1 => {
-                let offset = if addr < 0x4000 {
-                    // Bank 0 (or bank 0x20/0x40/0x60 in mode 1)
-                    if self.banking_mode == 1 {
-                        let bank = (self.ram_bank as usize & 0x03) << 5;
-                        bank * 0x4000 + addr as usize
-                    } else {
-                        addr as usize
-                    }
-                } else {
-                    // Bank N
-                    let bank = (self.rom_bank as usize & 0x1F)
-                        | ((self.ram_bank as usize & 0x03) << 5);
-                    let bank = if bank & 0x1F == 0 { bank + 1 } else { bank };
-                    bank * 0x4000 + (addr as usize - 0x4000)
-                };
-                self.rom.get(offset % self.rom.len()).copied().unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc2 => {
-                let offset = if addr < 0x4000 {
-                    addr as usize
-                } else {
-                    let bank = (self.rom_bank as usize).max(1) & 0x0F;
-                    bank * 0x4000 + (addr as usize - 0x4000)
-                };
-                self.rom.get(offset % self.rom.len()).copied().unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc3 => {
-                let offset = if addr < 0x4000 {
-                    addr as usize
-                } else {
-                    let bank = (self.rom_bank as usize).max(1) & 0x7F;
-                    bank * 0x4000 + (addr as usize - 0x4000)
-                };
-                self.rom.get(offset % self.rom.len()).copied().unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc5 => {
-                let offset = if addr < 0x4000 {
-                    addr as usize
-                } else {
-                    let bank = self.rom_bank as usize;
-                    bank * 0x4000 + (addr as usize - 0x4000)
-                };
-                self.rom.get(offset % self.rom.len()).copied().unwrap_or(0xFF)
-            }
-        }
+        let offset = self.rom_offsets[(addr >> 14) as usize & 1] + (addr as usize & 0x3FFF);
+        self.rom[offset]
+    }
+    
+    /// Read `addr` (0x0000-0x7FFF) as if `bank` were mapped, without
+    /// touching the MBC
+    pub fn read_rom_bank(&self, bank: usize, addr: u16) -> u8 {
+        let bank = bank & self.rom_bank_mask();
+        self.rom[bank * 0x4000 + (addr as usize & 0x3FFF)]
     }
     
     /// Write to ROM area (MBC control)
     pub fn write_rom(&mut self, addr: u16, value: u8) {
-        match self.mbc_type {
-            MbcType::None => {}
-            
-            MbcType::Mbc1 => {
-                match addr {
-                    // RAM enable
-                    0x0000..=0x1FFF => {
-                        self.ram_enabled = (value & 0x0F) == 0x0A;
-                    }
-                    // ROM bank low bits
-                    0x2000..=0x3FFF => {
-                        let bank = value & 0x1F;
-                        self.rom_bank = (self.rom_bank & 0x60) | bank as u16;
-                    }
-                    // RAM bank / ROM bank high bits
-                    0x4000..=0x5FFF => {
-                        self.ram_bank = value & 0x03;
-                    }
-                    // Banking mode
-                    0x6000..=0x7FFF => {
-                        self.banking_mode = value & 0x01;
-                    }
-                    _ => {}
-                }
-            }
-            
-            MbcType::Mbc2 => {
-                match addr {
-                    // RAM enable (bit 8 of address must be 0)
-                    0x0000..=0x3FFF if addr & 0x0100 == 0 => {
-                        self.ram_enabled = (value & 0x0F) == 0x0A;
-                    }
-                    // ROM bank (bit 8 of address must be 1)
-                    0x0000..=0x3FFF if addr & 0x0100 != 0 => {
-                        self.rom_bank = (value & 0x0F).max(1) as u16;
-                    }
-                    _ => {}
-                }
-            }
-            
-            MbcType::Mbc3 => {
-                match addr {
-                    // RAM/RTC enable
-                    0x0000..=0x1FFF => {
-                        self.ram_enabled = (value & 0x0F) == 0x0A;
-                    }
-                    // ROM bank
-                    0x2000..=0x3FFF => {
-                        self.rom_bank = (value & 0x7F).max(1) as u16;
-                    }
-                    // RAM bank / RTC register select
-                    0x4000..=0x5FFF => {
-                        if value <= 0x03 {
-                            self.ram_bank = value;
-                            self.rtc_register = 0;
-                        } else if value >= 0x08 && value <= 0x0C {
-                            self.rtc_register = value;
-                        }
-                    }
-                    // Latch clock data
-                    0x6000..=0x7FFF => {
-                        if let Some(ref mut rtc) = self.rtc {
-                            if value == 0x01 && rtc.latch_ready {
-                                rtc.latch();
-                            }
-                            rtc.latch_ready = value == 0x00;
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            
-            MbcType::Mbc5 => {
-                match addr {
-                    // RAM enable
-                    0x0000..=0x1FFF => {
-                        self.ram_enabled = (value & 0x0F) == 0x0A;
-                    }
-                    // ROM bank low 8 bits
-                    0x2000..=0x2FFF => {
-                        self.rom_bank = (self.rom_bank & 0x100) | value as u16;
-                    }
-                    // ROM bank bit 8
-                    0x3000..=0x3FFF => {
-                        self.rom_bank = (self.rom_bank & 0xFF) | ((value as u16 & 0x01) << 8);
-                    }
-                    // RAM bank
-                    0x4000..=0x5FFF => {
-                        self.ram_bank = value & 0x0F;
-                    }
-                    _ => {}
-                }
-            }
-        }
+        self.mbc.write_control(addr, value);
+        self.update_banks();
     }
     
     /// Read from RAM area
     pub fn read_ram(&self, addr: u16) -> u8 {
-        if !self.ram_enabled || self.ram.is_empty() {
-            // Check for RTC read (MBC3)
-            if self.rtc_register != 0 {
-                if let Some(ref rtc) = self.rtc {
-                    return rtc.read(self.rtc_register);
-                }
-            }
-            return 0xFF;
-        }
-        
-        match self.mbc_type {
-            MbcType::None => {
-                self.ram.get((addr - 0xA000) as usize).copied().unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc1 => {
-                let bank = if self.banking_mode == 1 {
-                    self.ram_bank as usize & 0x03
-                } else {
-                    0
-                };
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                self.ram.get(offset % self.ram.len()).copied().unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc2 => {
-                // MBC2 only has 512 nibbles (only lower 4 bits valid)
-                let offset = (addr as usize - 0xA000) & 0x1FF;
-                self.ram.get(offset).map(|&v| v | 0xF0).unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc3 => {
-                if self.rtc_register != 0 {
-                    if let Some(ref rtc) = self.rtc {
-                        return rtc.read(self.rtc_register);
-                    }
-                }
-                let bank = self.ram_bank as usize & 0x03;
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                self.ram.get(offset % self.ram.len()).copied().unwrap_or(0xFF)
-            }
-            
-            MbcType::Mbc5 => {
-                let bank = self.ram_bank as usize & 0x0F;
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                self.ram.get(offset % self.ram.len()).copied().unwrap_or(0xFF)
+        match self.ram_mapping {
+            RamMapping::Disabled => 0xFF,
+            RamMapping::Rtc(reg) => self.mbc.rtc().map_or(0xFF, |rtc| rtc.read(reg)),
+            RamMapping::Bank(_) => {
+                let offset = self.ram_base + (addr as usize & self.ram_address_mask);
+                self.ram.get(offset).map_or(0xFF, |&byte| byte | !self.ram_data_mask)
             }
         }
     }
     
+    /// Get cartridge RAM (all banks)
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+    
+    /// Get mutable cartridge RAM (all banks)
+    pub fn ram_mut(&mut self) -> &mut [u8] {
+        &mut self.ram
+    }
+    
+    /// Read `addr` (0xA000-0xBFFF) from a RAM bank, whether or not it is
+    /// mapped or enabled
+    pub fn read_ram_bank(&self, bank: usize, addr: u16) -> u8 {
+        let offset = bank * 0x2000 + (addr as usize & self.ram_address_mask);
+        self.ram.get(offset).map_or(0xFF, |&byte| byte | !self.ram_data_mask)
+    }
+    
     /// Write to RAM area
     pub fn write_ram(&mut self, addr: u16, value: u8) {
-        if !self.ram_enabled {
-            return;
-        }
-        
-        // Check for RTC write (MBC3)
-        if self.rtc_register != 0 {
-            if let Some(ref mut rtc) = self.rtc {
-                rtc.write(self.rtc_register, value);
-                return;
+        if let Some(bank) = self.ram_selected {
+            // MBC2 RAM is built in, so its size is never misreported
+            let offset = bank * 0x2000 + (addr as usize & 0x1FFF);
+            if offset >= self.ram.len() && self.mbc_type != MbcType::Mbc2 {
+                self.ram_overflow(offset);
             }
         }
         
-        if self.ram.is_empty() {
-            return;
-        }
-        
-        match self.mbc_type {
-            MbcType::None => {
-                if let Some(byte) = self.ram.get_mut((addr - 0xA000) as usize) {
-                    *byte = value;
-                }
-            }
-            
-            MbcType::Mbc1 => {
-                let bank = if self.banking_mode == 1 {
-                    self.ram_bank as usize & 0x03
-                } else {
-                    0
-                };
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                let len = self.ram.len();
-                if let Some(byte) = self.ram.get_mut(offset % len) {
-                    *byte = value;
+        match self.ram_mapping {
+            RamMapping::Disabled => {}
+            RamMapping::Rtc(reg) => {
+                if self.mbc.registers().ram_enabled {
+                    if let Some(rtc) = self.mbc.rtc_mut() {
+                        rtc.write(reg, value);
+                        self.ram_written = true;
+                    }
                 }
             }
-            
-            MbcType::Mbc2 => {
-                let offset = (addr as usize - 0xA000) & 0x1FF;
+            RamMapping::Bank(_) => {
+                let offset = self.ram_base + (addr as usize & self.ram_address_mask);
                 if let Some(byte) = self.ram.get_mut(offset) {
-                    *byte = value & 0x0F;
-                }
-            }
-            
-            MbcType::Mbc3 => {
-                let bank = self.ram_bank as usize & 0x03;
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                let len = self.ram.len();
-                if let Some(byte) = self.ram.get_mut(offset % len) {
-                    *byte = value;
-                }
-            }
-            
-            MbcType::Mbc5 => {
-                let bank = self.ram_bank as usize & 0x0F;
-                let offset = bank * 0x2000 + (addr as usize - 0xA000);
-                let len = self.ram.len();
-                if let Some(byte) = self.ram.get_mut(offset % len) {
-                    *byte = value;
+                    *byte = value & self.ram_data_mask;
+                    self.ram_written = true;
                 }
             }
         }
     }
     
+    /// Whether the game wrote RAM or the RTC since the last call
+    pub fn take_ram_written(&mut self) -> bool {
+        std::mem::take(&mut self.ram_written)
+    }
+    
+    /// Record a write past the RAM size declared in the header, growing
+    /// the RAM to cover it if enabled
+    fn ram_overflow(&mut self, offset: usize) {
+        if self.sram_overflow.is_none() {
+            log::warn!(
+                "SRAM write at offset {:#X} beyond the {} bytes declared in the header",
+                offset,
+                self.ram.len()
+            );
+        }
+        self.sram_overflow = Some(self.sram_overflow.map_or(offset, |max| max.max(offset)));
+        
+        if self.sram_auto_grow {
+            self.resize_ram((offset + 1).next_power_of_two().max(0x2000));
+        }
+    }
+    
+    fn resize_ram(&mut self, size: usize) {
+        self.ram.resize(size, 0);
+        self.ram_address_mask = self.mbc.ram_address_mask() & (size - 1);
+        self.update_banks();
+    }
+    
+    /// Grow the RAM when a game writes past the size declared in its
+    /// header (bootlegs that misreport it) instead of wrapping
+    pub fn set_sram_auto_grow(&mut self, enabled: bool) {
+        self.sram_auto_grow = enabled;
+    }
+    
+    /// Highest RAM offset the game wrote past the size declared in the
+    /// header, if any. A save from such a game is likely incomplete unless
+    /// auto-grow is enabled.
+    pub fn sram_overflow(&self) -> Option<usize> {
+        self.sram_overflow
+    }
+    
+    /// The unsupported cartridge type byte, if the mapper is a guess (see
+    /// `from_rom_with_fallback`)
+    pub fn unknown_type(&self) -> Option<u8> {
+        self.unknown_type
+    }
+    
+    /// Choose the wall clock used for RTC save timestamps
+    pub fn set_clock_source(&mut self, clock: Box<dyn ClockSource>) {
+        self.clock = clock;
+    }
+    
+    /// Current time on the wall clock, in seconds since the Unix epoch
+    pub fn wall_time(&self) -> u64 {
+        self.clock.now()
+    }
+    
+    /// Remove the clock source (leaving the host clock), e.g. to move it to
+    /// another cartridge
+    pub fn take_clock_source(&mut self) -> Box<dyn ClockSource> {
+        std::mem::replace(&mut self.clock, Box::new(HostClock))
+    }
+    
+    /// Get the RTC, if the cartridge has one
+    pub fn rtc(&self) -> Option<&Rtc> {
+        self.mbc.rtc()
+    }
+    
+    /// Get the RTC mutably, if the cartridge has one
+    pub fn rtc_mut(&mut self) -> Option<&mut Rtc> {
+        self.mbc.rtc_mut()
+    }
+    
     /// Tick RTC (call at appropriate intervals)
     pub fn tick_rtc(&mut self, cycles: u32) {
-        if let Some(ref mut rtc) = self.rtc {
+        if !self.has_rtc {
+            return;
+        }
+        
+        if let Some(rtc) = self.mbc.rtc_mut() {
             // Accumulate sub-second cycles
             rtc.sub_seconds += cycles;
             
@@ -564,8 +655,10 @@ impl Cartridge {
         let mut data = self.ram.clone();
         
         // Include RTC state if present
-        if let Some(ref rtc) = self.rtc {
-            // Append RTC data (48 bytes for compatibility with other emulators)
+        if let Some(rtc) = self.mbc.rtc() {
+            // Append RTC data (RTC_SAVE_SIZE bytes, for compatibility with
+            // other emulators)
+            let timestamp = self.clock.now();
             let rtc_data = [
                 rtc.seconds as u32,
                 rtc.minutes as u32,
@@ -577,9 +670,9 @@ impl Cartridge {
                 rtc.latched[2] as u32,
                 rtc.latched[3] as u32,
                 rtc.latched[4] as u32,
-                // Unix timestamp placeholder
-                0,
-                0,
+                // 64-bit Unix timestamp of the save
+                timestamp as u32,
+                (timestamp >> 32) as u32,
             ];
             
             for val in rtc_data {
@@ -590,8 +683,24 @@ impl Cartridge {
         Some(data)
     }
     
+    /// Largest battery save this cartridge can produce: its RAM (as much
+    /// as a mapper can address, if it may still grow) and the RTC
+    pub fn max_save_size(&self) -> usize {
+        let ram = if self.sram_auto_grow { self.ram.len().max(MAX_RAM_SIZE) } else { self.ram.len() };
+        ram + if self.mbc.rtc().is_some() { RTC_SAVE_SIZE } else { 0 }
+    }
+    
     /// Load RAM (for battery backup)
     pub fn load_ram(&mut self, data: &[u8]) -> Result<(), String> {
+        // A save written after the RAM grew is larger than the header says
+        let grown = self.sram_auto_grow
+            && self.mbc.rtc().is_none()
+            && data.len() > self.ram.len()
+            && data.len().is_power_of_two();
+        if grown {
+            self.resize_ram(data.len());
+        }
+        
         if self.ram.is_empty() {
             return Ok(());
         }
@@ -605,8 +714,8 @@ impl Cartridge {
         self.ram.copy_from_slice(&data[..ram_size]);
         
         // Load RTC state if present
-        if let Some(ref mut rtc) = self.rtc {
-            if data.len() >= ram_size + 48 {
+        if let Some(rtc) = self.mbc.rtc_mut() {
+            if data.len() >= ram_size + RTC_SAVE_SIZE {
                 let rtc_offset = ram_size;
                 let read_u32 = |offset: usize| {
                     u32::from_le_bytes([
@@ -627,31 +736,56 @@ impl Cartridge {
                 rtc.latched[2] = read_u32(28);
                 rtc.latched[3] = read_u32(32);
                 rtc.latched[4] = read_u32(36);
+                
+                // Catch up on the time since the save was written (old
+                // saves without a timestamp are left as they are)
+                let saved_at = u64::from_le_bytes(
+                    data[rtc_offset + 40..rtc_offset + 48].try_into().unwrap()
+                );
+                let now = self.clock.now();
+                if saved_at != 0 && now > saved_at {
+                    rtc.advance(now - saved_at);
+                }
             }
         }
         
         Ok(())
     }
     
+    /// Return the bank registers to their power-on values, as the
+    /// console's reset line does. RAM and the RTC are left alone.
+    pub fn reset_mapper(&mut self) {
+        self.mbc.reset();
+        self.update_banks();
+    }
+    
     /// Get state for serialization
     pub fn state(&self) -> CartridgeState {
+        let regs = self.mbc.registers();
         CartridgeState {
-            rom_bank: self.rom_bank,
-            ram_bank: self.ram_bank,
-            ram_enabled: self.ram_enabled,
-            banking_mode: self.banking_mode,
+            rom_bank: regs.rom_bank,
+            ram_bank: regs.ram_bank,
+            ram_enabled: regs.ram_enabled,
+            banking_mode: regs.banking_mode,
             ram: self.ram.clone(),
-            rtc: self.rtc.clone(),
+            rtc: self.mbc.rtc().cloned(),
         }
     }
     
     /// Load state
     pub fn load_state(&mut self, state: CartridgeState) {
-        self.rom_bank = state.rom_bank;
-        self.ram_bank = state.ram_bank;
-        self.ram_enabled = state.ram_enabled;
-        self.banking_mode = state.banking_mode;
+        let regs = self.mbc.registers_mut();
+        regs.rom_bank = state.rom_bank;
+        regs.ram_bank = state.ram_bank;
+        regs.ram_enabled = state.ram_enabled;
+        regs.banking_mode = state.banking_mode;
         self.ram = state.ram;
-        self.rtc = state.rtc;
+        self.ram_address_mask = self.mbc.ram_address_mask() & self.ram.len().saturating_sub(1);
+        
+        if let (Some(rtc), Some(saved)) = (self.mbc.rtc_mut(), state.rtc) {
+            *rtc = saved;
+        }
+        
+        self.update_banks();
     }
 }
\ No newline at end of file
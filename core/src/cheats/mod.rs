@@ -0,0 +1,11 @@
+//! # Cheats
+//!
+//! Tools for finding cheat addresses in a running game.
+//!
+//! `CheatSearch` is a RAM scanner: it snapshots WRAM and cartridge RAM
+//! (every bank, not just the mapped ones) and narrows a candidate list by
+//! comparing successive snapshots.
+
+mod search;
+
+pub use search::{Candidate, CheatSearch, Comparison};
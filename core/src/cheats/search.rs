@@ -0,0 +1,172 @@
+//! RAM search across successive snapshots
+
+use std::fmt;
+
+use crate::game_map::Region;
+use crate::GameBoy;
+
+/// WRAM bank size
+const WRAM_BANK_SIZE: usize = 0x1000;
+
+/// Cartridge RAM bank size
+const SRAM_BANK_SIZE: usize = 0x2000;
+
+/// How a byte must relate to its previous snapshot to stay a candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// Now equal to the value
+    Equal(u8),
+    
+    /// Different from the previous snapshot
+    Changed,
+    
+    /// Same as the previous snapshot
+    Unchanged,
+    
+    /// Greater than the previous snapshot
+    Increased,
+    
+    /// Less than the previous snapshot
+    Decreased,
+    
+    /// Changed by exactly this amount (wrapping, so -1 matches 0x00 to 0xFF)
+    Delta(i16),
+}
+
+impl Comparison {
+    fn matches(&self, old: u8, new: u8) -> bool {
+        match *self {
+            Comparison::Equal(value) => new == value,
+            Comparison::Changed => new != old,
+            Comparison::Unchanged => new == old,
+            Comparison::Increased => new > old,
+            Comparison::Decreased => new < old,
+            Comparison::Delta(delta) => new == old.wrapping_add(delta as u8),
+        }
+    }
+}
+
+/// A remaining candidate address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    /// WRAM or SRAM
+    pub region: Region,
+    
+    /// Bank the byte lives in
+    pub bank: usize,
+    
+    /// CPU address when that bank is mapped
+    pub address: u16,
+    
+    /// Value in the latest snapshot
+    pub value: u8,
+}
+
+impl fmt::Display for Candidate {
+    /// Formats as a `GameMap` location, e.g. `WRAM:1:0xD012`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let region = if self.region == Region::Sram { "SRAM" } else { "WRAM" };
+        write!(f, "{}:{}:0x{:04X}", region, self.bank, self.address)
+    }
+}
+
+/// A RAM search in progress
+pub struct CheatSearch {
+    /// WRAM followed by cartridge RAM, as of the last snapshot
+    snapshot: Vec<u8>,
+    
+    /// Length of the WRAM part of the snapshot
+    wram_len: usize,
+    
+    /// Snapshot offsets still matching every comparison so far
+    candidates: Vec<u32>,
+}
+
+impl CheatSearch {
+    /// Start a search with every WRAM and SRAM byte as a candidate
+    pub fn new(gb: &GameBoy) -> Self {
+        let (snapshot, wram_len) = Self::take_snapshot(gb);
+        let candidates = (0..snapshot.len() as u32).collect();
+        
+        Self {
+            snapshot,
+            wram_len,
+            candidates,
+        }
+    }
+    
+    /// Start over from the current memory
+    pub fn reset(&mut self, gb: &GameBoy) {
+        *self = Self::new(gb);
+    }
+    
+    fn take_snapshot(gb: &GameBoy) -> (Vec<u8>, usize) {
+        let wram = gb.mmu.wram();
+        let sram = gb.mmu.cartridge().ram();
+        
+        let mut snapshot = Vec::with_capacity(wram.len() + sram.len());
+        snapshot.extend_from_slice(wram);
+        snapshot.extend_from_slice(sram);
+        (snapshot, wram.len())
+    }
+    
+    /// Take a new snapshot and keep the candidates whose bytes pass
+    /// `comparison` against the previous one. Returns how many remain.
+    pub fn filter(&mut self, gb: &GameBoy, comparison: Comparison) -> Result<usize, String> {
+        let (snapshot, wram_len) = Self::take_snapshot(gb);
+        if snapshot.len() != self.snapshot.len() || wram_len != self.wram_len {
+            return Err("Memory size changed since the search started".to_string());
+        }
+        
+        let old = &self.snapshot;
+        self.candidates.retain(|&offset| {
+            let offset = offset as usize;
+            comparison.matches(old[offset], snapshot[offset])
+        });
+        self.snapshot = snapshot;
+        
+        Ok(self.candidates.len())
+    }
+    
+    /// Number of remaining candidates
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+    
+    /// No candidates left
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+    
+    /// The remaining candidates, WRAM first, in address order within each
+    /// bank
+    pub fn candidates(&self) -> Vec<Candidate> {
+        self.candidates.iter()
+            .map(|&offset| self.candidate(offset as usize))
+            .collect()
+    }
+    
+    fn candidate(&self, offset: usize) -> Candidate {
+        let value = self.snapshot[offset];
+        
+        if offset < self.wram_len {
+            // Bank 0 is fixed at C000; the others switch in at D000
+            let bank = offset / WRAM_BANK_SIZE;
+            let base = if bank == 0 { 0xC000 } else { 0xD000 };
+            Candidate {
+                region: Region::Wram,
+                bank,
+                address: base + (offset % WRAM_BANK_SIZE) as u16,
+                value,
+            }
+        } else {
+            let offset = offset - self.wram_len;
+            Candidate {
+                region: Region::Sram,
+                bank: offset / SRAM_BANK_SIZE,
+                address: 0xA000 + (offset % SRAM_BANK_SIZE) as u16,
+                value,
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+//! # Clock Sources
+//!
+//! Wall-clock time for features that follow real time, such as the MBC3
+//! RTC catching up on the time that passed between play sessions. Tests
+//! and TAS tools inject a fixed or scripted clock to stay deterministic.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use serde::{Serialize, Deserialize};
+
+/// Source of wall-clock time
+pub trait ClockSource {
+    /// Current time in seconds since the Unix epoch
+    fn now(&self) -> u64;
+}
+
+/// The host's system clock
+pub struct HostClock;
+
+impl ClockSource for HostClock {
+    #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+    fn now(&self) -> u64 {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+    
+    #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+    fn now(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs())
+    }
+}
+
+/// A clock stopped at a given time
+pub struct FixedClock(pub u64);
+
+impl ClockSource for FixedClock {
+    fn now(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A clock moved by hand. Clones share the same time, so a test can keep
+/// one and hand another to the emulator.
+#[derive(Clone, Default)]
+pub struct ScriptedClock {
+    time: Rc<Cell<u64>>,
+}
+
+impl ScriptedClock {
+    pub fn new(time: u64) -> Self {
+        Self { time: Rc::new(Cell::new(time)) }
+    }
+    
+    /// Set the current time
+    pub fn set(&self, time: u64) {
+        self.time.set(time);
+    }
+    
+    /// Move the clock forward
+    pub fn advance(&self, seconds: u64) {
+        self.time.set(self.time.get() + seconds);
+    }
+}
+
+impl ClockSource for ScriptedClock {
+    fn now(&self) -> u64 {
+        self.time.get()
+    }
+}
+
+/// Clock chosen in `GbConfig` (use `GameBoy::set_clock_source` for a
+/// scripted or custom one)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ClockKind {
+    /// The host's system clock
+    #[default]
+    Host,
+    
+    /// Always the given Unix time
+    Fixed(u64),
+}
+
+impl ClockKind {
+    /// Create the clock source
+    pub fn source(&self) -> Box<dyn ClockSource> {
+        match *self {
+            ClockKind::Host => Box::new(HostClock),
+            ClockKind::Fixed(time) => Box::new(FixedClock(time)),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! # Configuration
+//! 
+//! Options a frontend chooses when creating a `GameBoy`.
+
+use crate::cartridge::Cartridge;
+use crate::clock::ClockKind;
+use crate::cpu::InterruptTiming;
+use crate::joypad::JoypadIrq;
+use crate::serial::DisconnectedPolicy;
+use serde::{Serialize, Deserialize};
+
+/// Emulator configuration.
+///
+/// Serializable so frontends can persist it; missing fields take their
+/// defaults, so older saved configs keep loading.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GbConfig {
+    /// What externally clocked serial transfers do with no link partner
+    pub serial_disconnected: DisconnectedPolicy,
+    
+    /// Power-on contents of WRAM and HRAM
+    pub ram_init: RamInit,
+    
+    /// Optional hardware quirks
+    pub accuracy: AccuracyProfile,
+    
+    /// Grow cartridge RAM when a game writes past the size its header
+    /// declares, instead of wrapping (for bootlegs that misreport it)
+    pub sram_auto_grow: bool,
+    
+    /// Load ROMs with an unsupported cartridge type byte as MBC5 with
+    /// battery-backed RAM instead of rejecting them (for homebrew and
+    /// bootlegs with nonstandard headers); see `GameBoy::unknown_cartridge_type`
+    pub allow_unknown_mapper: bool,
+    
+    /// Let Left+Right and Up+Down be held together, which a real d-pad
+    /// can't do (for TAS tools that want the resulting glitches)
+    pub allow_opposing_directions: bool,
+    
+    /// When the joypad interrupt fires: on any press (the default), on
+    /// clean edges of the selected lines, or with contact bounce
+    pub joypad_irq: JoypadIrq,
+    
+    /// Identifies this instance among several built from the same config
+    /// (e.g. the players of a `Session`). It is mixed into a
+    /// `RamInit::Random` seed, so such instances power on with different
+    /// but reproducible RAM; instance 0 uses the seed unchanged.
+    pub instance_id: u32,
+    
+    /// Wall clock for the cartridge RTC (save timestamps and catching up
+    /// on load)
+    pub rtc_clock: ClockKind,
+    
+    /// Cycle costs of interrupt dispatch and leaving HALT
+    pub interrupt_timing: InterruptTiming,
+    
+    /// CPU clock multiplier. At 2 or more the CPU runs that many cycles
+    /// for each cycle the rest of the system (PPU, APU, timer, DMA,
+    /// serial) sees, so games that lag get more work done per frame while
+    /// video, sound and timers keep their speed. 0 and 1 leave it off.
+    pub overclock: u32,
+    
+    /// Save battery RAM once the game has written it and then left it
+    /// alone for this many frames, delivered as
+    /// `EmulatorEvent::Autosave` (see `GameBoy::take_autosave`). 0 leaves
+    /// it off.
+    pub autosave_frames: u32,
+    
+    /// What holding A+B+Select+Start does
+    pub soft_reset: SoftReset,
+}
+
+impl GbConfig {
+    /// Parse a ROM, guessing the mapper for unknown cartridge types if
+    /// allowed
+    pub(crate) fn cartridge(&self, rom_data: &[u8]) -> Result<Cartridge, String> {
+        if self.allow_unknown_mapper {
+            Cartridge::from_rom_with_fallback(rom_data)
+        } else {
+            Cartridge::from_rom(rom_data)
+        }
+    }
+}
+
+/// Hardware quirks that can be switched on individually.
+///
+/// The default leaves them all off, matching the simpler behavior the core
+/// has always had; `AccuracyProfile::accurate()` turns everything on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AccuracyProfile {
+    /// Model-specific reads from 0xFEA0-0xFEFF (DMG: 0x00, or 0xFF while
+    /// the PPU owns OAM; CGB: a nibble pattern of the address) and OAM DMA
+    /// sources above 0xDFFF reading the echo of WRAM (DMG) or external RAM
+    /// (CGB)
+    pub unusable_memory: bool,
+    
+    /// Reads nothing responds to (disabled cartridge RAM, undefined I/O
+    /// registers, and 0xFEA0-0xFEFF without `unusable_memory`) return the
+    /// last value on the bus instead of 0xFF
+    #[serde(default)]
+    pub open_bus: bool,
+}
+
+impl AccuracyProfile {
+    /// Every quirk enabled
+    pub fn accurate() -> Self {
+        Self {
+            unusable_memory: true,
+            open_bus: true,
+        }
+    }
+}
+
+/// Response to the A+B+Select+Start soft reset combination, which many
+/// games document and flash carts implement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SoftReset {
+    /// Leave it to the game
+    #[default]
+    Off,
+    
+    /// Report it as `EmulatorEvent::SoftReset` (see
+    /// `GameBoy::take_soft_reset`)
+    Event,
+    
+    /// Reset the console, keeping the buttons held, and report it
+    Reset,
+}
+
+/// Power-on contents of WRAM and HRAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RamInit {
+    /// All zeroes
+    #[default]
+    Zeroed,
+    
+    /// 0x55/0xAA stripes, similar to what DMG RAM tends to power on with
+    Pattern,
+    
+    /// Pseudo-random bytes from a seed (the same seed gives the same RAM)
+    Random(u64),
+}
+
+impl RamInit {
+    /// The power-on contents for a given instance (see
+    /// `GbConfig::instance_id`)
+    pub fn for_instance(self, instance_id: u32) -> Self {
+        match self {
+            RamInit::Random(seed) => {
+                RamInit::Random(seed ^ (instance_id as u64).wrapping_mul(0xD1B5_4A32_D192_ED03))
+            }
+            other => other,
+        }
+    }
+    
+    /// Fill `ram` with the power-on contents. `salt` separates regions so
+    /// WRAM and HRAM don't get the same random bytes.
+    pub fn fill(&self, ram: &mut [u8], salt: u64) {
+        match *self {
+            RamInit::Zeroed => ram.fill(0),
+            
+            RamInit::Pattern => {
+                for (i, byte) in ram.iter_mut().enumerate() {
+                    *byte = if (i / 8) % 2 == 0 { 0x55 } else { 0xAA };
+                }
+            }
+            
+            RamInit::Random(seed) => {
+                // SplitMix64
+                let mut state = seed ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+                for chunk in ram.chunks_mut(8) {
+                    state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                    z ^= z >> 31;
+                    chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! # Coverage
+//!
+//! Which ROM bytes the CPU has executed, for test authors measuring how
+//! much of a game a replay exercises. Every byte of an executed
+//! instruction (opcode and operands) is marked, at its offset in the ROM
+//! file, whichever bank it was mapped through. Code run from RAM or the
+//! boot ROM isn't counted.
+//!
+//! Bitmaps have one bit per ROM byte, least significant bit first: byte
+//! `n` of the ROM is bit `n % 8` of bitmap byte `n / 8`.
+
+/// Bytes in a ROM bank
+pub const BANK_SIZE: usize = 0x4000;
+
+/// Executed ROM bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    /// One bit per ROM byte
+    bitmap: Vec<u8>,
+    
+    /// Size of the ROM in bytes
+    rom_size: usize,
+}
+
+impl Coverage {
+    /// Empty coverage for a ROM of `rom_size` bytes
+    pub fn new(rom_size: usize) -> Self {
+        Self {
+            bitmap: vec![0; rom_size.div_ceil(8)],
+            rom_size,
+        }
+    }
+    
+    /// Mark the ROM byte at `offset` as executed
+    pub(crate) fn mark(&mut self, offset: usize) {
+        if offset < self.rom_size {
+            self.bitmap[offset / 8] |= 1 << (offset % 8);
+        }
+    }
+    
+    /// Whether the ROM byte at `offset` was executed
+    pub fn is_executed(&self, offset: usize) -> bool {
+        offset < self.rom_size && self.bitmap[offset / 8] & (1 << (offset % 8)) != 0
+    }
+    
+    /// Number of ROM bytes executed
+    pub fn executed_bytes(&self) -> usize {
+        self.bitmap.iter().map(|byte| byte.count_ones() as usize).sum()
+    }
+    
+    /// Share of the ROM executed, in percent
+    pub fn percentage(&self) -> f64 {
+        percent(self.executed_bytes(), self.rom_size)
+    }
+    
+    /// Number of 16KB banks in the ROM
+    pub fn banks(&self) -> usize {
+        self.rom_size.div_ceil(BANK_SIZE)
+    }
+    
+    /// Bitmap of the whole ROM
+    pub fn bitmap(&self) -> &[u8] {
+        &self.bitmap
+    }
+    
+    /// Bitmap of one bank (empty past the last bank)
+    pub fn bank_bitmap(&self, bank: usize) -> &[u8] {
+        let start = (bank * BANK_SIZE / 8).min(self.bitmap.len());
+        let end = ((bank + 1) * BANK_SIZE / 8).min(self.bitmap.len());
+        &self.bitmap[start..end]
+    }
+    
+    /// Share of one bank executed, in percent
+    pub fn bank_percentage(&self, bank: usize) -> f64 {
+        let size = self.rom_size.saturating_sub(bank * BANK_SIZE).min(BANK_SIZE);
+        let executed = self.bank_bitmap(bank).iter().map(|byte| byte.count_ones() as usize).sum();
+        percent(executed, size)
+    }
+    
+    /// Forget everything executed so far
+    pub fn clear(&mut self) {
+        self.bitmap.fill(0);
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 * 100.0 / whole as f64 }
+}
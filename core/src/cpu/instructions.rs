@@ -182,7 +182,10 @@ impl Cpu {
             // ========== STOP ==========
             0x10 => {
                 self.fetch_byte(mmu); // consume next byte
-                self.stopped = true;
+                // An armed KEY1 turns STOP into a CGB speed switch
+                if !mmu.switch_speed() {
+                    self.stopped = true;
+                }
                 4
             }
             
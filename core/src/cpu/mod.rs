@@ -5,6 +5,7 @@
 
 mod instructions;
 mod cb_instructions;
+pub mod opcodes;
 
 use crate::mmu::Mmu;
 use crate::GbModel;
@@ -53,7 +54,7 @@ impl<'de> Deserialize<'de> for Flags {
 }
 
 /// CPU Registers
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Registers {
     pub a: u8,
     pub f: Flags,
@@ -67,23 +68,6 @@ pub struct Registers {
     pub pc: u16,
 }
 
-impl Default for Registers {
-    fn default() -> Self {
-        Self {
-            a: 0,
-            f: Flags::empty(),
-            b: 0,
-            c: 0,
-            d: 0,
-            e: 0,
-            h: 0,
-            l: 0,
-            sp: 0,
-            pc: 0,
-        }
-    }
-}
-
 impl Registers {
     /// Get AF register pair
     pub fn af(&self) -> u16 {
@@ -131,6 +115,27 @@ impl Registers {
     }
 }
 
+/// Cycle costs of interrupt handling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterruptTiming {
+    /// Dispatching an interrupt: two idle M-cycles, pushing PC and the
+    /// jump to the vector (20 cycles on both DMG and CGB)
+    pub dispatch_cycles: u32,
+    
+    /// Extra cycles to leave HALT once an interrupt is pending, whether or
+    /// not it is then dispatched
+    pub halt_exit_cycles: u32,
+}
+
+impl Default for InterruptTiming {
+    fn default() -> Self {
+        Self {
+            dispatch_cycles: 20,
+            halt_exit_cycles: 4,
+        }
+    }
+}
+
 /// CPU state for serialization
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CpuState {
@@ -161,6 +166,12 @@ pub struct Cpu {
     
     /// HALT bug active (PC not incremented on next instruction)
     pub halt_bug: bool,
+    
+    /// Cycle costs of interrupt handling
+    interrupt_timing: InterruptTiming,
+    
+    /// Address and opcode of the instruction the last step executed
+    last_executed: Option<(u16, u8)>,
 }
 
 impl Cpu {
@@ -173,9 +184,22 @@ impl Cpu {
             halted: false,
             stopped: false,
             halt_bug: false,
+            interrupt_timing: InterruptTiming::default(),
+            last_executed: None,
         }
     }
     
+    /// Address and opcode of the instruction the last `step` executed;
+    /// `None` if it dispatched an interrupt or idled in HALT or STOP
+    pub fn last_executed(&self) -> Option<(u16, u8)> {
+        self.last_executed
+    }
+    
+    /// Choose the cycle costs of interrupt handling
+    pub fn set_interrupt_timing(&mut self, timing: InterruptTiming) {
+        self.interrupt_timing = timing;
+    }
+    
     /// Reset CPU to initial state
     pub fn reset(&mut self) {
         self.regs = Registers::default();
@@ -190,8 +214,9 @@ impl Cpu {
     pub fn init_for_model(&mut self, model: GbModel) {
         match model {
             GbModel::Dmg | GbModel::Pocket => {
-                // DMG boot ROM leaves these values
-                self.regs.a = 0x01;
+                // DMG boot ROM leaves these values; the Pocket's differs
+                // only in A, which games use to detect it
+                self.regs.a = if model == GbModel::Pocket { 0xFF } else { 0x01 };
                 self.regs.f = Flags::Z | Flags::H | Flags::C;
                 self.regs.b = 0x00;
                 self.regs.c = 0x13;
@@ -199,32 +224,52 @@ impl Cpu {
                 self.regs.e = 0xD8;
                 self.regs.h = 0x01;
                 self.regs.l = 0x4D;
-                self.regs.sp = 0xFFFE;
-                self.regs.pc = 0x0100;
             }
-            GbModel::Cgb | GbModel::CgbDmg => {
-                // CGB boot ROM leaves these values
-                self.regs.a = 0x11;
-                self.regs.f = Flags::Z;
+            GbModel::Sgb | GbModel::Sgb2 => {
+                // SGB boot ROM leaves these values (A = 0xFF on the SGB2)
+                self.regs.a = if model == GbModel::Sgb2 { 0xFF } else { 0x01 };
+                self.regs.f = Flags::empty();
                 self.regs.b = 0x00;
+                self.regs.c = 0x14;
+                self.regs.d = 0x00;
+                self.regs.e = 0x00;
+                self.regs.h = 0xC0;
+                self.regs.l = 0x60;
+            }
+            GbModel::Cgb | GbModel::CgbDmg | GbModel::Agb => {
+                // CGB boot ROM leaves these values; on the AGB bit 0 of B
+                // is set and the flags are cleared, which games use to
+                // detect it
+                let agb = model == GbModel::Agb;
+                self.regs.a = 0x11;
+                self.regs.f = if agb { Flags::empty() } else { Flags::Z };
+                self.regs.b = if agb { 0x01 } else { 0x00 };
                 self.regs.c = 0x00;
                 self.regs.d = 0xFF;
                 self.regs.e = 0x56;
                 self.regs.h = 0x00;
                 self.regs.l = 0x0D;
-                self.regs.sp = 0xFFFE;
-                self.regs.pc = 0x0100;
             }
         }
+        self.regs.sp = 0xFFFE;
+        self.regs.pc = 0x0100;
     }
     
     /// Execute one instruction and return cycles consumed
     pub fn step(&mut self, mmu: &mut Mmu) -> u32 {
-        // Handle scheduled IME enable
-        if self.ime_scheduled {
+        // EI takes effect once the instruction after it has run, unless
+        // that instruction was a DI
+        let enable_ime = self.ime_scheduled;
+        let cycles = self.step_instruction(mmu);
+        if enable_ime && self.ime_scheduled {
             self.ime_scheduled = false;
             self.ime = true;
         }
+        cycles
+    }
+    
+    fn step_instruction(&mut self, mmu: &mut Mmu) -> u32 {
+        self.last_executed = None;
         
         // Check for interrupts
         if let Some(cycles) = self.handle_interrupts(mmu) {
@@ -238,15 +283,18 @@ impl Cpu {
         
         // If stopped, return 4 cycles
         if self.stopped {
-            // Check if any button pressed to exit STOP
-            if mmu.read_byte(0xFF00) & 0x0F != 0x0F {
+            // A button pressed on a selected line exits STOP. Checked on
+            // the joypad directly: this isn't a bus read by the program.
+            if mmu.joypad().selected_pressed() {
                 self.stopped = false;
             }
             return 4;
         }
         
         // Fetch opcode
+        let pc = self.regs.pc;
         let opcode = self.fetch_byte(mmu);
+        self.last_executed = Some((pc, opcode));
         
         // Execute instruction
         self.execute(opcode, mmu)
@@ -262,7 +310,9 @@ impl Cpu {
             return None;
         }
         
-        // Wake from HALT even if IME is disabled
+        // Wake from HALT even if IME is disabled; leaving HALT costs an
+        // extra M-cycle
+        let wake_cycles = if self.halted { self.interrupt_timing.halt_exit_cycles } else { 0 };
         if self.halted {
             self.halted = false;
             // If IME is disabled, the HALT bug may trigger
@@ -273,7 +323,7 @@ impl Cpu {
         
         // Only service interrupt if IME is enabled
         if !self.ime {
-            return None;
+            return (wake_cycles > 0).then_some(wake_cycles);
         }
         
         // Find highest priority interrupt (bit 0 is highest)
@@ -303,8 +353,7 @@ impl Cpu {
         };
         self.regs.pc = vector;
         
-        // Interrupt handling takes 20 cycles
-        Some(20)
+        Some(self.interrupt_timing.dispatch_cycles + wake_cycles)
     }
     
     /// Fetch byte at PC and increment PC
@@ -680,7 +729,7 @@ impl Cpu {
     
     /// Swap nibbles
     fn swap(&mut self, value: u8) -> u8 {
-        let result = (value >> 4) | (value << 4);
+        let result = value.rotate_left(4);
         
         self.regs.f = Flags::empty();
         if result == 0 {
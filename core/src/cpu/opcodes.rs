@@ -0,0 +1,603 @@
+//! Opcode table
+//!
+//! Length, timing and mnemonic of every instruction, for the disassembler
+//! and tracers. `execute` keeps its own match for speed; the table is
+//! checked against it instruction by instruction (tests/cpu.rs), so the
+//! two can't drift apart.
+//!
+//! Operands in mnemonics use the same notation as the comments in
+//! `execute`: `n` (8-bit immediate), `nn` (16-bit immediate) and `e`
+//! (signed 8-bit offset).
+
+/// Static description of one instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Opcode {
+    pub mnemonic: &'static str,
+    
+    /// Bytes including the opcode (and the 0xCB prefix)
+    pub length: u8,
+    
+    /// T-cycles, or for conditional branches the cycles when not taken
+    pub cycles: u8,
+    
+    /// T-cycles when a conditional branch is taken (same as `cycles` for
+    /// everything else)
+    pub branch_cycles: u8,
+}
+
+impl Opcode {
+    /// Whether the instruction is a conditional jump, call or return
+    pub fn is_conditional(&self) -> bool {
+        self.branch_cycles != self.cycles
+    }
+}
+
+const fn op(mnemonic: &'static str, length: u8, cycles: u8) -> Opcode {
+    Opcode { mnemonic, length, cycles, branch_cycles: cycles }
+}
+
+const fn branch(mnemonic: &'static str, length: u8, cycles: u8, taken: u8) -> Opcode {
+    Opcode { mnemonic, length, cycles, branch_cycles: taken }
+}
+
+/// The instruction starting with `bytes` (an 0xCB prefix looks at the
+/// second byte; missing bytes read as 0)
+pub fn lookup(bytes: &[u8]) -> &'static Opcode {
+    match bytes.first() {
+        Some(0xCB) => &CB_OPCODES[bytes.get(1).copied().unwrap_or(0) as usize],
+        Some(&opcode) => &OPCODES[opcode as usize],
+        None => &OPCODES[0],
+    }
+}
+
+/// Disassemble the instruction at the start of `bytes`, located at `pc`.
+/// Returns the text and the instruction length. Relative jumps show their
+/// target address.
+pub fn disassemble(bytes: &[u8], pc: u16) -> (String, u8) {
+    let opcode = lookup(bytes);
+    let byte = |i: usize| bytes.get(i).copied().unwrap_or(0);
+    let word = u16::from_le_bytes([byte(1), byte(2)]);
+    let offset = byte(1) as i8;
+    
+    let text = match opcode.mnemonic.split_once(' ') {
+        Some((name, operands)) => {
+            let operands: Vec<String> = operands.split(", ").map(|operand| match operand {
+                "nn" => format!("${:04X}", word),
+                "(nn)" => format!("(${:04X})", word),
+                "n" => format!("${:02X}", byte(1)),
+                "(n)" => format!("(${:02X})", byte(1)),
+                "e" if name == "JR" => {
+                    format!("${:04X}", pc.wrapping_add(2).wrapping_add(offset as u16))
+                }
+                "e" => format!("{:+}", offset),
+                "SP+e" => format!("SP{:+}", offset),
+                other => other.to_string(),
+            }).collect();
+            format!("{} {}", name, operands.join(", "))
+        }
+        None => opcode.mnemonic.to_string(),
+    };
+    
+    (text, opcode.length)
+}
+
+/// Unprefixed instructions. The 0xCB entry covers only the prefix; see
+/// `CB_OPCODES` for the whole instruction.
+pub const OPCODES: [Opcode; 256] = [
+    op("NOP", 1, 4), // 00
+    op("LD BC, nn", 3, 12), // 01
+    op("LD (BC), A", 1, 8), // 02
+    op("INC BC", 1, 8), // 03
+    op("INC B", 1, 4), // 04
+    op("DEC B", 1, 4), // 05
+    op("LD B, n", 2, 8), // 06
+    op("RLCA", 1, 4), // 07
+    op("LD (nn), SP", 3, 20), // 08
+    op("ADD HL, BC", 1, 8), // 09
+    op("LD A, (BC)", 1, 8), // 0A
+    op("DEC BC", 1, 8), // 0B
+    op("INC C", 1, 4), // 0C
+    op("DEC C", 1, 4), // 0D
+    op("LD C, n", 2, 8), // 0E
+    op("RRCA", 1, 4), // 0F
+    op("STOP", 2, 4), // 10
+    op("LD DE, nn", 3, 12), // 11
+    op("LD (DE), A", 1, 8), // 12
+    op("INC DE", 1, 8), // 13
+    op("INC D", 1, 4), // 14
+    op("DEC D", 1, 4), // 15
+    op("LD D, n", 2, 8), // 16
+    op("RLA", 1, 4), // 17
+    op("JR e", 2, 12), // 18
+    op("ADD HL, DE", 1, 8), // 19
+    op("LD A, (DE)", 1, 8), // 1A
+    op("DEC DE", 1, 8), // 1B
+    op("INC E", 1, 4), // 1C
+    op("DEC E", 1, 4), // 1D
+    op("LD E, n", 2, 8), // 1E
+    op("RRA", 1, 4), // 1F
+    branch("JR NZ, e", 2, 8, 12), // 20
+    op("LD HL, nn", 3, 12), // 21
+    op("LD (HL+), A", 1, 8), // 22
+    op("INC HL", 1, 8), // 23
+    op("INC H", 1, 4), // 24
+    op("DEC H", 1, 4), // 25
+    op("LD H, n", 2, 8), // 26
+    op("DAA", 1, 4), // 27
+    branch("JR Z, e", 2, 8, 12), // 28
+    op("ADD HL, HL", 1, 8), // 29
+    op("LD A, (HL+)", 1, 8), // 2A
+    op("DEC HL", 1, 8), // 2B
+    op("INC L", 1, 4), // 2C
+    op("DEC L", 1, 4), // 2D
+    op("LD L, n", 2, 8), // 2E
+    op("CPL", 1, 4), // 2F
+    branch("JR NC, e", 2, 8, 12), // 30
+    op("LD SP, nn", 3, 12), // 31
+    op("LD (HL-), A", 1, 8), // 32
+    op("INC SP", 1, 8), // 33
+    op("INC (HL)", 1, 12), // 34
+    op("DEC (HL)", 1, 12), // 35
+    op("LD (HL), n", 2, 12), // 36
+    op("SCF", 1, 4), // 37
+    branch("JR C, e", 2, 8, 12), // 38
+    op("ADD HL, SP", 1, 8), // 39
+    op("LD A, (HL-)", 1, 8), // 3A
+    op("DEC SP", 1, 8), // 3B
+    op("INC A", 1, 4), // 3C
+    op("DEC A", 1, 4), // 3D
+    op("LD A, n", 2, 8), // 3E
+    op("CCF", 1, 4), // 3F
+    op("LD B, B", 1, 4), // 40
+    op("LD B, C", 1, 4), // 41
+    op("LD B, D", 1, 4), // 42
+    op("LD B, E", 1, 4), // 43
+    op("LD B, H", 1, 4), // 44
+    op("LD B, L", 1, 4), // 45
+    op("LD B, (HL)", 1, 8), // 46
+    op("LD B, A", 1, 4), // 47
+    op("LD C, B", 1, 4), // 48
+    op("LD C, C", 1, 4), // 49
+    op("LD C, D", 1, 4), // 4A
+    op("LD C, E", 1, 4), // 4B
+    op("LD C, H", 1, 4), // 4C
+    op("LD C, L", 1, 4), // 4D
+    op("LD C, (HL)", 1, 8), // 4E
+    op("LD C, A", 1, 4), // 4F
+    op("LD D, B", 1, 4), // 50
+    op("LD D, C", 1, 4), // 51
+    op("LD D, D", 1, 4), // 52
+    op("LD D, E", 1, 4), // 53
+    op("LD D, H", 1, 4), // 54
+    op("LD D, L", 1, 4), // 55
+    op("LD D, (HL)", 1, 8), // 56
+    op("LD D, A", 1, 4), // 57
+    op("LD E, B", 1, 4), // 58
+    op("LD E, C", 1, 4), // 59
+    op("LD E, D", 1, 4), // 5A
+    op("LD E, E", 1, 4), // 5B
+    op("LD E, H", 1, 4), // 5C
+    op("LD E, L", 1, 4), // 5D
+    op("LD E, (HL)", 1, 8), // 5E
+    op("LD E, A", 1, 4), // 5F
+    op("LD H, B", 1, 4), // 60
+    op("LD H, C", 1, 4), // 61
+    op("LD H, D", 1, 4), // 62
+    op("LD H, E", 1, 4), // 63
+    op("LD H, H", 1, 4), // 64
+    op("LD H, L", 1, 4), // 65
+    op("LD H, (HL)", 1, 8), // 66
+    op("LD H, A", 1, 4), // 67
+    op("LD L, B", 1, 4), // 68
+    op("LD L, C", 1, 4), // 69
+    op("LD L, D", 1, 4), // 6A
+    op("LD L, E", 1, 4), // 6B
+    op("LD L, H", 1, 4), // 6C
+    op("LD L, L", 1, 4), // 6D
+    op("LD L, (HL)", 1, 8), // 6E
+    op("LD L, A", 1, 4), // 6F
+    op("LD (HL), B", 1, 8), // 70
+    op("LD (HL), C", 1, 8), // 71
+    op("LD (HL), D", 1, 8), // 72
+    op("LD (HL), E", 1, 8), // 73
+    op("LD (HL), H", 1, 8), // 74
+    op("LD (HL), L", 1, 8), // 75
+    op("HALT", 1, 4), // 76
+    op("LD (HL), A", 1, 8), // 77
+    op("LD A, B", 1, 4), // 78
+    op("LD A, C", 1, 4), // 79
+    op("LD A, D", 1, 4), // 7A
+    op("LD A, E", 1, 4), // 7B
+    op("LD A, H", 1, 4), // 7C
+    op("LD A, L", 1, 4), // 7D
+    op("LD A, (HL)", 1, 8), // 7E
+    op("LD A, A", 1, 4), // 7F
+    op("ADD A, B", 1, 4), // 80
+    op("ADD A, C", 1, 4), // 81
+    op("ADD A, D", 1, 4), // 82
+    op("ADD A, E", 1, 4), // 83
+    op("ADD A, H", 1, 4), // 84
+    op("ADD A, L", 1, 4), // 85
+    op("ADD A, (HL)", 1, 8), // 86
+    op("ADD A, A", 1, 4), // 87
+    op("ADC A, B", 1, 4), // 88
+    op("ADC A, C", 1, 4), // 89
+    op("ADC A, D", 1, 4), // 8A
+    op("ADC A, E", 1, 4), // 8B
+    op("ADC A, H", 1, 4), // 8C
+    op("ADC A, L", 1, 4), // 8D
+    op("ADC A, (HL)", 1, 8), // 8E
+    op("ADC A, A", 1, 4), // 8F
+    op("SUB B", 1, 4), // 90
+    op("SUB C", 1, 4), // 91
+    op("SUB D", 1, 4), // 92
+    op("SUB E", 1, 4), // 93
+    op("SUB H", 1, 4), // 94
+    op("SUB L", 1, 4), // 95
+    op("SUB (HL)", 1, 8), // 96
+    op("SUB A", 1, 4), // 97
+    op("SBC A, B", 1, 4), // 98
+    op("SBC A, C", 1, 4), // 99
+    op("SBC A, D", 1, 4), // 9A
+    op("SBC A, E", 1, 4), // 9B
+    op("SBC A, H", 1, 4), // 9C
+    op("SBC A, L", 1, 4), // 9D
+    op("SBC A, (HL)", 1, 8), // 9E
+    op("SBC A, A", 1, 4), // 9F
+    op("AND B", 1, 4), // A0
+    op("AND C", 1, 4), // A1
+    op("AND D", 1, 4), // A2
+    op("AND E", 1, 4), // A3
+    op("AND H", 1, 4), // A4
+    op("AND L", 1, 4), // A5
+    op("AND (HL)", 1, 8), // A6
+    op("AND A", 1, 4), // A7
+    op("XOR B", 1, 4), // A8
+    op("XOR C", 1, 4), // A9
+    op("XOR D", 1, 4), // AA
+    op("XOR E", 1, 4), // AB
+    op("XOR H", 1, 4), // AC
+    op("XOR L", 1, 4), // AD
+    op("XOR (HL)", 1, 8), // AE
+    op("XOR A", 1, 4), // AF
+    op("OR B", 1, 4), // B0
+    op("OR C", 1, 4), // B1
+    op("OR D", 1, 4), // B2
+    op("OR E", 1, 4), // B3
+    op("OR H", 1, 4), // B4
+    op("OR L", 1, 4), // B5
+    op("OR (HL)", 1, 8), // B6
+    op("OR A", 1, 4), // B7
+    op("CP B", 1, 4), // B8
+    op("CP C", 1, 4), // B9
+    op("CP D", 1, 4), // BA
+    op("CP E", 1, 4), // BB
+    op("CP H", 1, 4), // BC
+    op("CP L", 1, 4), // BD
+    op("CP (HL)", 1, 8), // BE
+    op("CP A", 1, 4), // BF
+    branch("RET NZ", 1, 8, 20), // C0
+    op("POP BC", 1, 12), // C1
+    branch("JP NZ, nn", 3, 12, 16), // C2
+    op("JP nn", 3, 16), // C3
+    branch("CALL NZ, nn", 3, 12, 24), // C4
+    op("PUSH BC", 1, 16), // C5
+    op("ADD A, n", 2, 8), // C6
+    op("RST 00H", 1, 16), // C7
+    branch("RET Z", 1, 8, 20), // C8
+    op("RET", 1, 16), // C9
+    branch("JP Z, nn", 3, 12, 16), // CA
+    op("PREFIX CB", 1, 4), // CB
+    branch("CALL Z, nn", 3, 12, 24), // CC
+    op("CALL nn", 3, 24), // CD
+    op("ADC A, n", 2, 8), // CE
+    op("RST 08H", 1, 16), // CF
+    branch("RET NC", 1, 8, 20), // D0
+    op("POP DE", 1, 12), // D1
+    branch("JP NC, nn", 3, 12, 16), // D2
+    op("ILLEGAL", 1, 4), // D3
+    branch("CALL NC, nn", 3, 12, 24), // D4
+    op("PUSH DE", 1, 16), // D5
+    op("SUB n", 2, 8), // D6
+    op("RST 10H", 1, 16), // D7
+    branch("RET C", 1, 8, 20), // D8
+    op("RETI", 1, 16), // D9
+    branch("JP C, nn", 3, 12, 16), // DA
+    op("ILLEGAL", 1, 4), // DB
+    branch("CALL C, nn", 3, 12, 24), // DC
+    op("ILLEGAL", 1, 4), // DD
+    op("SBC A, n", 2, 8), // DE
+    op("RST 18H", 1, 16), // DF
+    op("LDH (n), A", 2, 12), // E0
+    op("POP HL", 1, 12), // E1
+    op("LDH (C), A", 1, 8), // E2
+    op("ILLEGAL", 1, 4), // E3
+    op("ILLEGAL", 1, 4), // E4
+    op("PUSH HL", 1, 16), // E5
+    op("AND n", 2, 8), // E6
+    op("RST 20H", 1, 16), // E7
+    op("ADD SP, e", 2, 16), // E8
+    op("JP HL", 1, 4), // E9
+    op("LD (nn), A", 3, 16), // EA
+    op("ILLEGAL", 1, 4), // EB
+    op("ILLEGAL", 1, 4), // EC
+    op("ILLEGAL", 1, 4), // ED
+    op("XOR n", 2, 8), // EE
+    op("RST 28H", 1, 16), // EF
+    op("LDH A, (n)", 2, 12), // F0
+    op("POP AF", 1, 12), // F1
+    op("LDH A, (C)", 1, 8), // F2
+    op("DI", 1, 4), // F3
+    op("ILLEGAL", 1, 4), // F4
+    op("PUSH AF", 1, 16), // F5
+    op("OR n", 2, 8), // F6
+    op("RST 30H", 1, 16), // F7
+    op("LD HL, SP+e", 2, 12), // F8
+    op("LD SP, HL", 1, 8), // F9
+    op("LD A, (nn)", 3, 16), // FA
+    op("EI", 1, 4), // FB
+    op("ILLEGAL", 1, 4), // FC
+    op("ILLEGAL", 1, 4), // FD
+    op("CP n", 2, 8), // FE
+    op("RST 38H", 1, 16), // FF
+];
+
+/// CB-prefixed instructions, lengths and cycles including the prefix
+pub const CB_OPCODES: [Opcode; 256] = [
+    op("RLC B", 2, 8), // CB 00
+    op("RLC C", 2, 8), // CB 01
+    op("RLC D", 2, 8), // CB 02
+    op("RLC E", 2, 8), // CB 03
+    op("RLC H", 2, 8), // CB 04
+    op("RLC L", 2, 8), // CB 05
+    op("RLC (HL)", 2, 16), // CB 06
+    op("RLC A", 2, 8), // CB 07
+    op("RRC B", 2, 8), // CB 08
+    op("RRC C", 2, 8), // CB 09
+    op("RRC D", 2, 8), // CB 0A
+    op("RRC E", 2, 8), // CB 0B
+    op("RRC H", 2, 8), // CB 0C
+    op("RRC L", 2, 8), // CB 0D
+    op("RRC (HL)", 2, 16), // CB 0E
+    op("RRC A", 2, 8), // CB 0F
+    op("RL B", 2, 8), // CB 10
+    op("RL C", 2, 8), // CB 11
+    op("RL D", 2, 8), // CB 12
+    op("RL E", 2, 8), // CB 13
+    op("RL H", 2, 8), // CB 14
+    op("RL L", 2, 8), // CB 15
+    op("RL (HL)", 2, 16), // CB 16
+    op("RL A", 2, 8), // CB 17
+    op("RR B", 2, 8), // CB 18
+    op("RR C", 2, 8), // CB 19
+    op("RR D", 2, 8), // CB 1A
+    op("RR E", 2, 8), // CB 1B
+    op("RR H", 2, 8), // CB 1C
+    op("RR L", 2, 8), // CB 1D
+    op("RR (HL)", 2, 16), // CB 1E
+    op("RR A", 2, 8), // CB 1F
+    op("SLA B", 2, 8), // CB 20
+    op("SLA C", 2, 8), // CB 21
+    op("SLA D", 2, 8), // CB 22
+    op("SLA E", 2, 8), // CB 23
+    op("SLA H", 2, 8), // CB 24
+    op("SLA L", 2, 8), // CB 25
+    op("SLA (HL)", 2, 16), // CB 26
+    op("SLA A", 2, 8), // CB 27
+    op("SRA B", 2, 8), // CB 28
+    op("SRA C", 2, 8), // CB 29
+    op("SRA D", 2, 8), // CB 2A
+    op("SRA E", 2, 8), // CB 2B
+    op("SRA H", 2, 8), // CB 2C
+    op("SRA L", 2, 8), // CB 2D
+    op("SRA (HL)", 2, 16), // CB 2E
+    op("SRA A", 2, 8), // CB 2F
+    op("SWAP B", 2, 8), // CB 30
+    op("SWAP C", 2, 8), // CB 31
+    op("SWAP D", 2, 8), // CB 32
+    op("SWAP E", 2, 8), // CB 33
+    op("SWAP H", 2, 8), // CB 34
+    op("SWAP L", 2, 8), // CB 35
+    op("SWAP (HL)", 2, 16), // CB 36
+    op("SWAP A", 2, 8), // CB 37
+    op("SRL B", 2, 8), // CB 38
+    op("SRL C", 2, 8), // CB 39
+    op("SRL D", 2, 8), // CB 3A
+    op("SRL E", 2, 8), // CB 3B
+    op("SRL H", 2, 8), // CB 3C
+    op("SRL L", 2, 8), // CB 3D
+    op("SRL (HL)", 2, 16), // CB 3E
+    op("SRL A", 2, 8), // CB 3F
+    op("BIT 0, B", 2, 8), // CB 40
+    op("BIT 0, C", 2, 8), // CB 41
+    op("BIT 0, D", 2, 8), // CB 42
+    op("BIT 0, E", 2, 8), // CB 43
+    op("BIT 0, H", 2, 8), // CB 44
+    op("BIT 0, L", 2, 8), // CB 45
+    op("BIT 0, (HL)", 2, 12), // CB 46
+    op("BIT 0, A", 2, 8), // CB 47
+    op("BIT 1, B", 2, 8), // CB 48
+    op("BIT 1, C", 2, 8), // CB 49
+    op("BIT 1, D", 2, 8), // CB 4A
+    op("BIT 1, E", 2, 8), // CB 4B
+    op("BIT 1, H", 2, 8), // CB 4C
+    op("BIT 1, L", 2, 8), // CB 4D
+    op("BIT 1, (HL)", 2, 12), // CB 4E
+    op("BIT 1, A", 2, 8), // CB 4F
+    op("BIT 2, B", 2, 8), // CB 50
+    op("BIT 2, C", 2, 8), // CB 51
+    op("BIT 2, D", 2, 8), // CB 52
+    op("BIT 2, E", 2, 8), // CB 53
+    op("BIT 2, H", 2, 8), // CB 54
+    op("BIT 2, L", 2, 8), // CB 55
+    op("BIT 2, (HL)", 2, 12), // CB 56
+    op("BIT 2, A", 2, 8), // CB 57
+    op("BIT 3, B", 2, 8), // CB 58
+    op("BIT 3, C", 2, 8), // CB 59
+    op("BIT 3, D", 2, 8), // CB 5A
+    op("BIT 3, E", 2, 8), // CB 5B
+    op("BIT 3, H", 2, 8), // CB 5C
+    op("BIT 3, L", 2, 8), // CB 5D
+    op("BIT 3, (HL)", 2, 12), // CB 5E
+    op("BIT 3, A", 2, 8), // CB 5F
+    op("BIT 4, B", 2, 8), // CB 60
+    op("BIT 4, C", 2, 8), // CB 61
+    op("BIT 4, D", 2, 8), // CB 62
+    op("BIT 4, E", 2, 8), // CB 63
+    op("BIT 4, H", 2, 8), // CB 64
+    op("BIT 4, L", 2, 8), // CB 65
+    op("BIT 4, (HL)", 2, 12), // CB 66
+    op("BIT 4, A", 2, 8), // CB 67
+    op("BIT 5, B", 2, 8), // CB 68
+    op("BIT 5, C", 2, 8), // CB 69
+    op("BIT 5, D", 2, 8), // CB 6A
+    op("BIT 5, E", 2, 8), // CB 6B
+    op("BIT 5, H", 2, 8), // CB 6C
+    op("BIT 5, L", 2, 8), // CB 6D
+    op("BIT 5, (HL)", 2, 12), // CB 6E
+    op("BIT 5, A", 2, 8), // CB 6F
+    op("BIT 6, B", 2, 8), // CB 70
+    op("BIT 6, C", 2, 8), // CB 71
+    op("BIT 6, D", 2, 8), // CB 72
+    op("BIT 6, E", 2, 8), // CB 73
+    op("BIT 6, H", 2, 8), // CB 74
+    op("BIT 6, L", 2, 8), // CB 75
+    op("BIT 6, (HL)", 2, 12), // CB 76
+    op("BIT 6, A", 2, 8), // CB 77
+    op("BIT 7, B", 2, 8), // CB 78
+    op("BIT 7, C", 2, 8), // CB 79
+    op("BIT 7, D", 2, 8), // CB 7A
+    op("BIT 7, E", 2, 8), // CB 7B
+    op("BIT 7, H", 2, 8), // CB 7C
+    op("BIT 7, L", 2, 8), // CB 7D
+    op("BIT 7, (HL)", 2, 12), // CB 7E
+    op("BIT 7, A", 2, 8), // CB 7F
+    op("RES 0, B", 2, 8), // CB 80
+    op("RES 0, C", 2, 8), // CB 81
+    op("RES 0, D", 2, 8), // CB 82
+    op("RES 0, E", 2, 8), // CB 83
+    op("RES 0, H", 2, 8), // CB 84
+    op("RES 0, L", 2, 8), // CB 85
+    op("RES 0, (HL)", 2, 16), // CB 86
+    op("RES 0, A", 2, 8), // CB 87
+    op("RES 1, B", 2, 8), // CB 88
+    op("RES 1, C", 2, 8), // CB 89
+    op("RES 1, D", 2, 8), // CB 8A
+    op("RES 1, E", 2, 8), // CB 8B
+    op("RES 1, H", 2, 8), // CB 8C
+    op("RES 1, L", 2, 8), // CB 8D
+    op("RES 1, (HL)", 2, 16), // CB 8E
+    op("RES 1, A", 2, 8), // CB 8F
+    op("RES 2, B", 2, 8), // CB 90
+    op("RES 2, C", 2, 8), // CB 91
+    op("RES 2, D", 2, 8), // CB 92
+    op("RES 2, E", 2, 8), // CB 93
+    op("RES 2, H", 2, 8), // CB 94
+    op("RES 2, L", 2, 8), // CB 95
+    op("RES 2, (HL)", 2, 16), // CB 96
+    op("RES 2, A", 2, 8), // CB 97
+    op("RES 3, B", 2, 8), // CB 98
+    op("RES 3, C", 2, 8), // CB 99
+    op("RES 3, D", 2, 8), // CB 9A
+    op("RES 3, E", 2, 8), // CB 9B
+    op("RES 3, H", 2, 8), // CB 9C
+    op("RES 3, L", 2, 8), // CB 9D
+    op("RES 3, (HL)", 2, 16), // CB 9E
+    op("RES 3, A", 2, 8), // CB 9F
+    op("RES 4, B", 2, 8), // CB A0
+    op("RES 4, C", 2, 8), // CB A1
+    op("RES 4, D", 2, 8), // CB A2
+    op("RES 4, E", 2, 8), // CB A3
+    op("RES 4, H", 2, 8), // CB A4
+    op("RES 4, L", 2, 8), // CB A5
+    op("RES 4, (HL)", 2, 16), // CB A6
+    op("RES 4, A", 2, 8), // CB A7
+    op("RES 5, B", 2, 8), // CB A8
+    op("RES 5, C", 2, 8), // CB A9
+    op("RES 5, D", 2, 8), // CB AA
+    op("RES 5, E", 2, 8), // CB AB
+    op("RES 5, H", 2, 8), // CB AC
+    op("RES 5, L", 2, 8), // CB AD
+    op("RES 5, (HL)", 2, 16), // CB AE
+    op("RES 5, A", 2, 8), // CB AF
+    op("RES 6, B", 2, 8), // CB B0
+    op("RES 6, C", 2, 8), // CB B1
+    op("RES 6, D", 2, 8), // CB B2
+    op("RES 6, E", 2, 8), // CB B3
+    op("RES 6, H", 2, 8), // CB B4
+    op("RES 6, L", 2, 8), // CB B5
+    op("RES 6, (HL)", 2, 16), // CB B6
+    op("RES 6, A", 2, 8), // CB B7
+    op("RES 7, B", 2, 8), // CB B8
+    op("RES 7, C", 2, 8), // CB B9
+    op("RES 7, D", 2, 8), // CB BA
+    op("RES 7, E", 2, 8), // CB BB
+    op("RES 7, H", 2, 8), // CB BC
+    op("RES 7, L", 2, 8), // CB BD
+    op("RES 7, (HL)", 2, 16), // CB BE
+    op("RES 7, A", 2, 8), // CB BF
+    op("SET 0, B", 2, 8), // CB C0
+    op("SET 0, C", 2, 8), // CB C1
+    op("SET 0, D", 2, 8), // CB C2
+    op("SET 0, E", 2, 8), // CB C3
+    op("SET 0, H", 2, 8), // CB C4
+    op("SET 0, L", 2, 8), // CB C5
+    op("SET 0, (HL)", 2, 16), // CB C6
+    op("SET 0, A", 2, 8), // CB C7
+    op("SET 1, B", 2, 8), // CB C8
+    op("SET 1, C", 2, 8), // CB C9
+    op("SET 1, D", 2, 8), // CB CA
+    op("SET 1, E", 2, 8), // CB CB
+    op("SET 1, H", 2, 8), // CB CC
+    op("SET 1, L", 2, 8), // CB CD
+    op("SET 1, (HL)", 2, 16), // CB CE
+    op("SET 1, A", 2, 8), // CB CF
+    op("SET 2, B", 2, 8), // CB D0
+    op("SET 2, C", 2, 8), // CB D1
+    op("SET 2, D", 2, 8), // CB D2
+    op("SET 2, E", 2, 8), // CB D3
+    op("SET 2, H", 2, 8), // CB D4
+    op("SET 2, L", 2, 8), // CB D5
+    op("SET 2, (HL)", 2, 16), // CB D6
+    op("SET 2, A", 2, 8), // CB D7
+    op("SET 3, B", 2, 8), // CB D8
+    op("SET 3, C", 2, 8), // CB D9
+    op("SET 3, D", 2, 8), // CB DA
+    op("SET 3, E", 2, 8), // CB DB
+    op("SET 3, H", 2, 8), // CB DC
+    op("SET 3, L", 2, 8), // CB DD
+    op("SET 3, (HL)", 2, 16), // CB DE
+    op("SET 3, A", 2, 8), // CB DF
+    op("SET 4, B", 2, 8), // CB E0
+    op("SET 4, C", 2, 8), // CB E1
+    op("SET 4, D", 2, 8), // CB E2
+    op("SET 4, E", 2, 8), // CB E3
+    op("SET 4, H", 2, 8), // CB E4
+    op("SET 4, L", 2, 8), // CB E5
+    op("SET 4, (HL)", 2, 16), // CB E6
+    op("SET 4, A", 2, 8), // CB E7
+    op("SET 5, B", 2, 8), // CB E8
+    op("SET 5, C", 2, 8), // CB E9
+    op("SET 5, D", 2, 8), // CB EA
+    op("SET 5, E", 2, 8), // CB EB
+    op("SET 5, H", 2, 8), // CB EC
+    op("SET 5, L", 2, 8), // CB ED
+    op("SET 5, (HL)", 2, 16), // CB EE
+    op("SET 5, A", 2, 8), // CB EF
+    op("SET 6, B", 2, 8), // CB F0
+    op("SET 6, C", 2, 8), // CB F1
+    op("SET 6, D", 2, 8), // CB F2
+    op("SET 6, E", 2, 8), // CB F3
+    op("SET 6, H", 2, 8), // CB F4
+    op("SET 6, L", 2, 8), // CB F5
+    op("SET 6, (HL)", 2, 16), // CB F6
+    op("SET 6, A", 2, 8), // CB F7
+    op("SET 7, B", 2, 8), // CB F8
+    op("SET 7, C", 2, 8), // CB F9
+    op("SET 7, D", 2, 8), // CB FA
+    op("SET 7, E", 2, 8), // CB FB
+    op("SET 7, H", 2, 8), // CB FC
+    op("SET 7, L", 2, 8), // CB FD
+    op("SET 7, (HL)", 2, 16), // CB FE
+    op("SET 7, A", 2, 8), // CB FF
+];
@@ -0,0 +1,141 @@
+//! # Demo ROM
+//!
+//! A tiny 32KB ROM-only cartridge, generated at compile time, for doc
+//! examples, the `examples/` programs and quick experiments without a
+//! commercial ROM. It scrolls a checkerboard diagonally, counting frames
+//! in WRAM from its VBlank handler, and plays a steady 512 Hz square wave
+//! on channel 1.
+//!
+//! The header has no Nintendo logo, so a real boot ROM refuses it; start
+//! it without one (`GameBoy::new`). Everything in it is original to this
+//! crate and under the crate's license.
+
+/// Size of the ROM (no banking)
+pub const ROM_SIZE: usize = 0x8000;
+
+/// WRAM byte the VBlank handler increments once per frame (wrapping);
+/// also written to SCX and SCY
+pub const FRAME_COUNTER: u16 = 0xC000;
+
+/// Address of the VBlank handler in `PROGRAM`
+const VBLANK_HANDLER: u16 = 0x01A9;
+
+/// Main program at 0x0150
+const PROGRAM: [u8; 103] = [
+    0xF3,                         // di
+    0x31, 0xFE, 0xFF,             // ld sp, $FFFE
+    // wait:
+    0xF0, 0x44,                   // ldh a, (LY)
+    0xFE, 0x90,                   // cp 144
+    0x38, 0xFA,                   // jr c, wait
+    0xAF,                         // xor a
+    0xE0, 0x40,                   // ldh (LCDC), a: LCD off
+    0x21, 0x10, 0x80,             // ld hl, $8010: tile 1
+    0x06, 0x08,                   // ld b, 8
+    // tile:
+    0x3E, 0xAA,                   // ld a, $AA
+    0x22,                         // ld (hl+), a
+    0x3E, 0x55,                   // ld a, $55
+    0x22,                         // ld (hl+), a
+    0x05,                         // dec b
+    0x20, 0xF7,                   // jr nz, tile
+    0x21, 0x00, 0x98,             // ld hl, $9800: background map
+    // map:
+    0x7D,                         // ld a, l
+    0x07, 0x07, 0x07,             // rlca x3: bit 5 to bit 0
+    0xAD,                         // xor l
+    0xE6, 0x01,                   // and 1: checkerboard of tiles 0 and 1
+    0x22,                         // ld (hl+), a
+    0x7C,                         // ld a, h
+    0xFE, 0x9C,                   // cp $9C
+    0x20, 0xF3,                   // jr nz, map
+    0x3E, 0xE4,                   // ld a, $E4
+    0xE0, 0x47,                   // ldh (BGP), a
+    0x3E, 0x80,                   // ld a, $80
+    0xE0, 0x26,                   // ldh (NR52), a: sound on
+    0x3E, 0x77,                   // ld a, $77
+    0xE0, 0x24,                   // ldh (NR50), a: full volume
+    0x3E, 0x11,                   // ld a, $11
+    0xE0, 0x25,                   // ldh (NR51), a: channel 1 both sides
+    0x3E, 0x80,                   // ld a, $80
+    0xE0, 0x11,                   // ldh (NR11), a: 50% duty
+    0x3E, 0xF0,                   // ld a, $F0
+    0xE0, 0x12,                   // ldh (NR12), a: volume 15
+    0xAF,                         // xor a
+    0xE0, 0x13,                   // ldh (NR13), a
+    0xEA, 0x00, 0xC0,             // ld ($C000), a: frame counter
+    0x3E, 0x87,                   // ld a, $87
+    0xE0, 0x14,                   // ldh (NR14), a: trigger 512 Hz
+    0x3E, 0x91,                   // ld a, $91
+    0xE0, 0x40,                   // ldh (LCDC), a: LCD on
+    0x3E, 0x01,                   // ld a, $01
+    0xE0, 0xFF,                   // ldh (IE), a: VBlank
+    0xFB,                         // ei
+    // main:
+    0x76,                         // halt
+    0x18, 0xFD,                   // jr main
+    // vblank:
+    0xF5,                         // push af
+    0xFA, 0x00, 0xC0,             // ld a, ($C000)
+    0x3C,                         // inc a
+    0xEA, 0x00, 0xC0,             // ld ($C000), a
+    0xE0, 0x42,                   // ldh (SCY), a
+    0xE0, 0x43,                   // ldh (SCX), a
+    0xF1,                         // pop af
+    0xD9,                         // reti
+];
+
+const _: () = assert!(PROGRAM[(VBLANK_HANDLER - 0x150) as usize] == 0xF5, "VBLANK_HANDLER is out of date");
+
+/// The demo cartridge image
+pub static DEMO_ROM: [u8; ROM_SIZE] = build();
+
+const fn build() -> [u8; ROM_SIZE] {
+    let mut rom = [0u8; ROM_SIZE];
+    
+    // Entry point: nop; jp $0150
+    rom[0x100] = 0x00;
+    rom[0x101] = 0xC3;
+    rom[0x102] = 0x50;
+    rom[0x103] = 0x01;
+    
+    // VBlank vector: jp vblank
+    rom[0x40] = 0xC3;
+    rom[0x41] = VBLANK_HANDLER as u8;
+    rom[0x42] = (VBLANK_HANDLER >> 8) as u8;
+    
+    let title = b"GBEMU DEMO";
+    let mut i = 0;
+    while i < title.len() {
+        rom[0x134 + i] = title[i];
+        i += 1;
+    }
+    // 0x147-0x149: ROM only, 32KB, no RAM (all zero)
+    
+    let mut i = 0;
+    while i < PROGRAM.len() {
+        rom[0x150 + i] = PROGRAM[i];
+        i += 1;
+    }
+    
+    let mut header = 0u8;
+    let mut i = 0x134;
+    while i <= 0x14C {
+        header = header.wrapping_sub(rom[i]).wrapping_sub(1);
+        i += 1;
+    }
+    rom[0x14D] = header;
+    
+    let mut global = 0u16;
+    let mut i = 0;
+    while i < ROM_SIZE {
+        if i != 0x14E && i != 0x14F {
+            global = global.wrapping_add(rom[i] as u16);
+        }
+        i += 1;
+    }
+    rom[0x14E] = (global >> 8) as u8;
+    rom[0x14F] = global as u8;
+    
+    rom
+}
@@ -0,0 +1,225 @@
+//! # Learning Environment
+//!
+//! A gym-style wrapper for reinforcement learning: each `step` holds one
+//! action for a fixed number of frames and returns an observation of the
+//! last one. Episodes are deterministic for a given ROM, seed and action
+//! sequence.
+
+use crate::config::{GbConfig, RamInit};
+use crate::joypad::InputState;
+use crate::ppu::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::GameBoy;
+
+/// Format of the observation returned by `Env::step`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObservationMode {
+    /// RGBA8888, 4 bytes per pixel
+    Rgba,
+    
+    /// Luma, 1 byte per pixel
+    #[default]
+    Grayscale,
+    
+    /// Color indices, 1 byte per pixel (see `Ppu::framebuffer_indexed`)
+    Indexed,
+}
+
+/// Environment options
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    /// Frames each action is held for (at least 1)
+    pub frame_skip: u32,
+    
+    /// Observation format
+    pub observation: ObservationMode,
+    
+    /// Downscale factor; must divide both 160 and 144 (1, 2, 4, 8 or 16).
+    /// Grayscale and RGBA observations average each block, indexed
+    /// observations take its top-left pixel.
+    pub downscale: usize,
+    
+    /// Episode length in frames (`None` = never done)
+    pub max_frames: Option<u64>,
+    
+    /// Seed for the power-on RAM contents; `None` keeps the emulator's
+    /// `ram_init`
+    pub seed: Option<u64>,
+    
+    /// Emulator options
+    pub emulator: GbConfig,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            frame_skip: 1,
+            observation: ObservationMode::default(),
+            downscale: 1,
+            max_frames: None,
+            seed: None,
+            emulator: GbConfig::default(),
+        }
+    }
+}
+
+/// Reinforcement learning environment around a `GameBoy`
+pub struct Env {
+    rom: Vec<u8>,
+    config: EnvConfig,
+    gb: GameBoy,
+    
+    /// Frames run this episode
+    episode_frames: u64,
+    
+    /// Last observation
+    observation: Vec<u8>,
+}
+
+impl Env {
+    /// Create an environment and start the first episode
+    pub fn new(rom_data: &[u8], config: EnvConfig) -> Result<Self, String> {
+        if config.frame_skip == 0 {
+            return Err("frame_skip must be at least 1".to_string());
+        }
+        if config.downscale == 0
+            || SCREEN_WIDTH % config.downscale != 0
+            || SCREEN_HEIGHT % config.downscale != 0
+        {
+            return Err(format!(
+                "Downscale factor {} doesn't divide {}x{}",
+                config.downscale, SCREEN_WIDTH, SCREEN_HEIGHT
+            ));
+        }
+        
+        let gb = GameBoy::with_config(rom_data, Self::emulator_config(&config))?;
+        
+        let mut env = Self {
+            rom: rom_data.to_vec(),
+            config,
+            gb,
+            episode_frames: 0,
+            observation: Vec::new(),
+        };
+        env.observe();
+        Ok(env)
+    }
+    
+    fn emulator_config(config: &EnvConfig) -> GbConfig {
+        let mut emulator = config.emulator.clone();
+        if let Some(seed) = config.seed {
+            emulator.ram_init = RamInit::Random(seed);
+        }
+        emulator
+    }
+    
+    /// Start a new episode from power-on; returns the first observation
+    pub fn reset(&mut self) -> &[u8] {
+        self.gb = GameBoy::with_config(&self.rom, Self::emulator_config(&self.config))
+            .expect("ROM was already loaded once");
+        self.episode_frames = 0;
+        self.observe();
+        &self.observation
+    }
+    
+    /// Start a new episode with a different seed
+    pub fn reset_with_seed(&mut self, seed: u64) -> &[u8] {
+        self.config.seed = Some(seed);
+        self.reset()
+    }
+    
+    /// Hold `action` for `frame_skip` frames (fewer if the episode ends).
+    ///
+    /// `action` has one bit per button, numbered like `Button` (bit 0 =
+    /// Right ... bit 7 = Start). Returns the observation, the number of
+    /// frames run and whether the episode is done.
+    pub fn step(&mut self, action: u8) -> (&[u8], u32, bool) {
+        let input = InputState::from_bits(action);
+        let mut frames = 0;
+        
+        while frames < self.config.frame_skip && !self.is_done() {
+            self.gb.frame_advance(input);
+            self.episode_frames += 1;
+            frames += 1;
+        }
+        
+        self.observe();
+        (&self.observation, frames, self.is_done())
+    }
+    
+    /// The episode has reached `max_frames`
+    pub fn is_done(&self) -> bool {
+        self.config.max_frames.is_some_and(|max| self.episode_frames >= max)
+    }
+    
+    /// Observation dimensions as (width, height, bytes per pixel)
+    pub fn observation_shape(&self) -> (usize, usize, usize) {
+        let channels = match self.config.observation {
+            ObservationMode::Rgba => 4,
+            ObservationMode::Grayscale | ObservationMode::Indexed => 1,
+        };
+        (
+            SCREEN_WIDTH / self.config.downscale,
+            SCREEN_HEIGHT / self.config.downscale,
+            channels,
+        )
+    }
+    
+    /// Frames run this episode
+    pub fn episode_frames(&self) -> u64 {
+        self.episode_frames
+    }
+    
+    /// The emulator, e.g. to read game memory for rewards
+    pub fn game_boy(&self) -> &GameBoy {
+        &self.gb
+    }
+    
+    /// Mutable access to the emulator
+    pub fn game_boy_mut(&mut self) -> &mut GameBoy {
+        &mut self.gb
+    }
+    
+    /// Build the observation from the current frame
+    fn observe(&mut self) {
+        let scale = self.config.downscale;
+        let (width, height, channels) = self.observation_shape();
+        self.observation.clear();
+        self.observation.reserve(width * height * channels);
+        
+        let rgba = self.gb.framebuffer();
+        let indexed = self.gb.framebuffer_indexed();
+        
+        for y in 0..height {
+            for x in 0..width {
+                let block = (0..scale).flat_map(|dy| {
+                    (0..scale).map(move |dx| (y * scale + dy) * SCREEN_WIDTH + x * scale + dx)
+                });
+                let area = (scale * scale) as u32;
+                
+                match self.config.observation {
+                    ObservationMode::Indexed => {
+                        self.observation.push(indexed[y * scale * SCREEN_WIDTH + x * scale]);
+                    }
+                    ObservationMode::Grayscale => {
+                        let sum: u32 = block
+                            .map(|pixel| {
+                                let p = &rgba[pixel * 4..pixel * 4 + 3];
+                                (p[0] as u32 * 299 + p[1] as u32 * 587 + p[2] as u32 * 114) / 1000
+                            })
+                            .sum();
+                        self.observation.push((sum / area) as u8);
+                    }
+                    ObservationMode::Rgba => {
+                        let mut sum = [0u32; 4];
+                        for pixel in block {
+                            for (total, &value) in sum.iter_mut().zip(&rgba[pixel * 4..pixel * 4 + 4]) {
+                                *total += value as u32;
+                            }
+                        }
+                        self.observation.extend(sum.iter().map(|total| (total / area) as u8));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! # Game Maps
+//!
+//! Named memory locations for a specific game, so trackers, reward
+//! functions and scripts don't hardcode addresses.
+//!
+//! A map is a JSON object of `name: "REGION[:BANK]:ADDRESS TYPE"` entries:
+//!
+//! ```json
+//! {
+//!     "player_x": "WRAM:0xC0A0 u8",
+//!     "money": "WRAM:2:0xD350 u16",
+//!     "save_flag": "SRAM:1:0xA000 u8"
+//! }
+//! ```
+//!
+//! Regions are ROM, VRAM, SRAM, WRAM, OAM, IO and HRAM. Without a bank the
+//! value is read through the current mapping; with one, that bank is read
+//! directly. Types are u8, u16, u32, i8, i16 and i32 (little endian).
+
+use std::collections::BTreeMap;
+
+use crate::GameBoy;
+
+/// Memory region of a named location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Rom,
+    Vram,
+    Sram,
+    Wram,
+    Oam,
+    Io,
+    Hram,
+}
+
+impl Region {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_uppercase().as_str() {
+            "ROM" => Ok(Region::Rom),
+            "VRAM" => Ok(Region::Vram),
+            "SRAM" => Ok(Region::Sram),
+            "WRAM" => Ok(Region::Wram),
+            "OAM" => Ok(Region::Oam),
+            "IO" => Ok(Region::Io),
+            "HRAM" => Ok(Region::Hram),
+            _ => Err(format!("Unknown region: {}", name)),
+        }
+    }
+    
+    /// CPU addresses belonging to the region
+    fn range(&self) -> std::ops::RangeInclusive<u16> {
+        match self {
+            Region::Rom => 0x0000..=0x7FFF,
+            Region::Vram => 0x8000..=0x9FFF,
+            Region::Sram => 0xA000..=0xBFFF,
+            Region::Wram => 0xC000..=0xDFFF,
+            Region::Oam => 0xFE00..=0xFE9F,
+            Region::Io => 0xFF00..=0xFF7F,
+            Region::Hram => 0xFF80..=0xFFFE,
+        }
+    }
+    
+    /// Regions that can be read from a specific bank
+    fn is_banked(&self) -> bool {
+        matches!(self, Region::Rom | Region::Vram | Region::Sram | Region::Wram)
+    }
+}
+
+/// Type of a named value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    U8,
+    U16,
+    U32,
+    I8,
+    I16,
+    I32,
+}
+
+impl ValueType {
+    fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "u8" => Ok(ValueType::U8),
+            "u16" => Ok(ValueType::U16),
+            "u32" => Ok(ValueType::U32),
+            "i8" => Ok(ValueType::I8),
+            "i16" => Ok(ValueType::I16),
+            "i32" => Ok(ValueType::I32),
+            _ => Err(format!("Unknown type: {}", name)),
+        }
+    }
+    
+    /// Size in bytes
+    pub fn size(&self) -> u16 {
+        match self {
+            ValueType::U8 | ValueType::I8 => 1,
+            ValueType::U16 | ValueType::I16 => 2,
+            ValueType::U32 | ValueType::I32 => 4,
+        }
+    }
+    
+    /// Interpret little-endian bytes
+    fn decode(&self, bytes: u32) -> i64 {
+        match self {
+            ValueType::U8 | ValueType::U16 | ValueType::U32 => bytes as i64,
+            ValueType::I8 => bytes as u8 as i8 as i64,
+            ValueType::I16 => bytes as u16 as i16 as i64,
+            ValueType::I32 => bytes as i32 as i64,
+        }
+    }
+}
+
+/// A named memory location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub region: Region,
+    
+    /// Bank to read from, or `None` for whatever is currently mapped
+    pub bank: Option<usize>,
+    
+    /// CPU address of the first byte
+    pub address: u16,
+    
+    pub value_type: ValueType,
+}
+
+impl Symbol {
+    /// Parse `"REGION[:BANK]:ADDRESS TYPE"`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut words = spec.split_whitespace();
+        let (Some(location), Some(value_type), None) = (words.next(), words.next(), words.next()) else {
+            return Err(format!("Expected \"REGION[:BANK]:ADDRESS TYPE\", got \"{}\"", spec));
+        };
+        
+        let parts: Vec<&str> = location.split(':').collect();
+        let (region, bank, address) = match parts.as_slice() {
+            [region, address] => (*region, None, *address),
+            [region, bank, address] => (*region, Some(parse_number(bank)? as usize), *address),
+            _ => return Err(format!("Invalid location: {}", location)),
+        };
+        
+        let symbol = Self {
+            region: Region::parse(region)?,
+            bank,
+            address: parse_number(address)?
+                .try_into()
+                .map_err(|_| format!("Address out of range: {}", address))?,
+            value_type: ValueType::parse(value_type)?,
+        };
+        
+        let last = symbol.address as u32 + symbol.value_type.size() as u32 - 1;
+        let range = symbol.region.range();
+        if !range.contains(&symbol.address) || last > *range.end() as u32 {
+            return Err(format!("{} is outside {:?}", location, symbol.region));
+        }
+        if symbol.bank.is_some() && !symbol.region.is_banked() {
+            return Err(format!("{:?} has no banks", symbol.region));
+        }
+        
+        Ok(symbol)
+    }
+    
+    /// Read the value from a running Game Boy
+    pub fn read(&self, gb: &GameBoy) -> i64 {
+        let bytes = (0..self.value_type.size())
+            .rev()
+            .fold(0u32, |value, i| (value << 8) | self.read_byte(gb, self.address + i) as u32);
+        self.value_type.decode(bytes)
+    }
+    
+    fn read_byte(&self, gb: &GameBoy, addr: u16) -> u8 {
+        let Some(bank) = self.bank else {
+            return gb.mmu.peek_byte(addr);
+        };
+        
+        let cartridge = gb.mmu.cartridge();
+        match self.region {
+            Region::Rom => cartridge.read_rom_bank(bank, addr),
+            Region::Sram => cartridge.read_ram_bank(bank, addr),
+            Region::Vram => {
+                let offset = bank * 0x2000 + (addr - 0x8000) as usize;
+                gb.mmu.vram().get(offset).copied().unwrap_or(0xFF)
+            }
+            Region::Wram => {
+                // C000-CFFF is always bank 0
+                let offset = match addr {
+                    0xC000..=0xCFFF => (addr - 0xC000) as usize,
+                    _ => bank * 0x1000 + (addr - 0xD000) as usize,
+                };
+                gb.mmu.wram().get(offset).copied().unwrap_or(0xFF)
+            }
+            _ => gb.mmu.peek_byte(addr),
+        }
+    }
+}
+
+/// Parse a decimal or `0x`-prefixed hexadecimal number
+fn parse_number(text: &str) -> Result<u32, String> {
+    let result = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    result.map_err(|_| format!("Invalid number: {}", text))
+}
+
+/// Named memory locations of a game
+#[derive(Debug, Clone, Default)]
+pub struct GameMap {
+    symbols: BTreeMap<String, Symbol>,
+}
+
+impl GameMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Load a map from a JSON object of `name: "REGION[:BANK]:ADDRESS TYPE"`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let entries: BTreeMap<String, String> = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid game map: {}", e))?;
+        
+        let mut map = Self::new();
+        for (name, spec) in entries {
+            map.insert(&name, &spec)?;
+        }
+        Ok(map)
+    }
+    
+    /// Add or replace a named location
+    pub fn insert(&mut self, name: &str, spec: &str) -> Result<(), String> {
+        let symbol = Symbol::parse(spec).map_err(|e| format!("{}: {}", name, e))?;
+        self.symbols.insert(name.to_string(), symbol);
+        Ok(())
+    }
+    
+    /// Look up a named location
+    pub fn get(&self, name: &str) -> Option<&Symbol> {
+        self.symbols.get(name)
+    }
+    
+    /// Names of every location, sorted
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.symbols.keys().map(String::as_str)
+    }
+}
@@ -6,6 +6,11 @@
 //! - D-pad: Up, Down, Left, Right
 //! - Action: A, B, Start, Select
 
+#[cfg(feature = "instrumentation")]
+use std::cell::Cell;
+use std::ops::RangeInclusive;
+
+use crate::mmu::IoDevice;
 use serde::{Serialize, Deserialize};
 
 /// Button codes
@@ -22,6 +27,17 @@ pub enum Button {
 }
 
 impl Button {
+    /// The direction on the other side of the d-pad, for directions
+    pub fn opposite(self) -> Option<Self> {
+        match self {
+            Button::Right => Some(Button::Left),
+            Button::Left => Some(Button::Right),
+            Button::Up => Some(Button::Down),
+            Button::Down => Some(Button::Up),
+            _ => None,
+        }
+    }
+    
     /// Create button from numeric code
     pub fn from_code(code: u8) -> Option<Self> {
         match code {
@@ -38,11 +54,144 @@ impl Button {
     }
 }
 
+/// Complete input state for one frame (true = pressed)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputState {
+    pub right: bool,
+    pub left: bool,
+    pub up: bool,
+    pub down: bool,
+    pub a: bool,
+    pub b: bool,
+    pub select: bool,
+    pub start: bool,
+}
+
+impl InputState {
+    /// Build from a bitmask with one bit per button code (bit 0 = Right
+    /// ... bit 7 = Start, 1 = pressed)
+    pub fn from_bits(bits: u8) -> Self {
+        let pressed = |button: Button| bits & (1 << button as u8) != 0;
+        Self {
+            right: pressed(Button::Right),
+            left: pressed(Button::Left),
+            up: pressed(Button::Up),
+            down: pressed(Button::Down),
+            a: pressed(Button::A),
+            b: pressed(Button::B),
+            select: pressed(Button::Select),
+            start: pressed(Button::Start),
+        }
+    }
+    
+    /// Check if a button is pressed
+    pub fn is_pressed(&self, button: Button) -> bool {
+        match button {
+            Button::Right => self.right,
+            Button::Left => self.left,
+            Button::Up => self.up,
+            Button::Down => self.down,
+            Button::A => self.a,
+            Button::B => self.b,
+            Button::Select => self.select,
+            Button::Start => self.start,
+        }
+    }
+}
+
+/// Measured delay between button presses and the game reading them
+#[derive(Debug, Clone, Default)]
+pub struct InputLatency {
+    /// Cycle of the press still waiting to be read
+    pending: Option<u64>,
+    
+    /// Latency of each press in CPU cycles
+    samples: Vec<u64>,
+}
+
+impl InputLatency {
+    /// Latency of every measured press, in CPU cycles, oldest first
+    pub fn samples(&self) -> &[u64] {
+        &self.samples
+    }
+    
+    /// Latency at the given percentile (0-100), in CPU cycles
+    pub fn percentile(&self, percentile: f32) -> Option<u64> {
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (sorted.len() as f32 - 1.0)).round();
+        sorted.get(rank as usize).copied()
+    }
+    
+    /// Mean latency in milliseconds
+    pub fn mean_ms(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mean = self.samples.iter().sum::<u64>() as f64 / self.samples.len() as f64;
+        Some(mean * 1000.0 / crate::CPU_CLOCK_HZ as f64)
+    }
+    
+    /// Forget all measurements
+    pub fn clear(&mut self) {
+        self.pending = None;
+        self.samples.clear();
+    }
+    
+    /// Start timing a press at `cycle`, unless one is already being timed
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn press(&mut self, cycle: u64) -> bool {
+        if self.pending.is_some() {
+            return false;
+        }
+        self.pending = Some(cycle);
+        true
+    }
+    
+    /// The pending press was read at `cycle`
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn observed(&mut self, cycle: u64) {
+        if let Some(start) = self.pending.take() {
+            self.samples.push(cycle - start);
+        }
+    }
+    
+    /// A press is waiting to be read
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+}
+
+/// When the joypad interrupt is requested
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum JoypadIrq {
+    /// On every button press, whatever P1 selects
+    #[default]
+    AnyPress,
+    
+    /// Once per high-to-low transition of a selected input line: a press
+    /// on a selected row, or selecting a row while one of its buttons is
+    /// held
+    Edge,
+    
+    /// Like `Edge`, but the contacts bounce: each transition is followed by
+    /// up to `bounces` more, one every `interval` cycles while the line
+    /// stays low
+    Bounce { bounces: u8, interval: u32 },
+}
+
 /// Joypad state for serialization
 #[derive(Clone, Serialize, Deserialize)]
 pub struct JoypadState {
     pub buttons: u8,
     pub interrupt_pending: bool,
+    #[serde(default)]
+    pub select: u8,
+    #[serde(default)]
+    pub bounces_left: u8,
+    #[serde(default)]
+    pub bounce_timer: u32,
 }
 
 /// Joypad implementation
@@ -54,6 +203,32 @@ pub struct Joypad {
     
     /// Interrupt pending flag
     interrupt_pending: bool,
+    
+    /// P1 select lines (bits 4-5, 0 = selected)
+    select: u8,
+    
+    /// Allow Left+Right and Up+Down at the same time
+    allow_opposing: bool,
+    
+    /// Interrupt model
+    irq: JoypadIrq,
+    
+    /// Contact bounces still to come from the last press (`JoypadIrq::Bounce`)
+    bounces_left: u8,
+    
+    /// Cycles until the next bounce
+    bounce_timer: u32,
+    
+    /// A+B+Select+Start just became held (see `take_soft_reset_combo`)
+    soft_reset_combo: bool,
+    
+    /// Button whose press is being timed (see `InputLatency`)
+    #[cfg(feature = "instrumentation")]
+    watched: Option<Button>,
+    
+    /// JOYP was read with the watched button's line selected
+    #[cfg(feature = "instrumentation")]
+    watched_read: Cell<bool>,
 }
 
 impl Joypad {
@@ -61,23 +236,63 @@ impl Joypad {
         Self {
             buttons: 0xFF, // All buttons released
             interrupt_pending: false,
+            select: 0x30,
+            allow_opposing: false,
+            irq: JoypadIrq::default(),
+            bounces_left: 0,
+            bounce_timer: 0,
+            soft_reset_combo: false,
+            #[cfg(feature = "instrumentation")]
+            watched: None,
+            #[cfg(feature = "instrumentation")]
+            watched_read: Cell::new(false),
         }
     }
     
     pub fn reset(&mut self) {
         self.buttons = 0xFF;
         self.interrupt_pending = false;
+        self.select = 0x30;
+        self.bounces_left = 0;
+        self.bounce_timer = 0;
+        self.soft_reset_combo = false;
+    }
+    
+    /// Choose when the joypad interrupt is requested
+    pub fn set_irq(&mut self, irq: JoypadIrq) {
+        self.irq = irq;
+        self.bounces_left = 0;
+    }
+    
+    /// Allow opposing directions to be held together. A real d-pad can't
+    /// do this and some games glitch when it happens, so by default
+    /// pressing a direction releases its opposite.
+    pub fn set_allow_opposing_directions(&mut self, allow: bool) {
+        self.allow_opposing = allow;
     }
     
     /// Press a button
     pub fn press(&mut self, button: Button) {
+        if !self.allow_opposing {
+            if let Some(opposite) = button.opposite() {
+                self.release(opposite);
+            }
+        }
+        
         let old_buttons = self.buttons;
+        let old_lines = self.lines();
         self.buttons &= !(1 << (button as u8));
         
         // Trigger interrupt on button press
-        if old_buttons != self.buttons {
+        if self.irq == JoypadIrq::AnyPress && old_buttons != self.buttons {
             self.interrupt_pending = true;
         }
+        self.lines_changed(old_lines);
+        
+        // The last of A, B, Select and Start going down completes the combo
+        if old_buttons & 0xF0 != 0 && self.buttons & 0xF0 == 0 {
+            self.soft_reset_combo = true;
+        }
     }
     
     /// Release a button
@@ -90,21 +305,123 @@ impl Joypad {
         self.buttons & (1 << (button as u8)) == 0
     }
     
-    /// Read joypad register based on selection
-    pub fn read(&self, select: u8) -> u8 {
-        let mut result = select | 0xC0; // Bits 6-7 always 1
+    /// Press and release buttons to match a complete input state
+    pub fn set_input(&mut self, input: InputState) {
+        for button in (0..8).filter_map(Button::from_code) {
+            if input.is_pressed(button) {
+                self.press(button);
+            } else {
+                self.release(button);
+            }
+        }
+    }
+    
+    /// Input lines P10-P13 as the CPU sees them with the current
+    /// selection (bit = 0 means a selected button is pressed)
+    pub fn lines(&self) -> u8 {
+        let mut lines = 0x0F;
         
         // Select buttons (bit 5 = 0)
-        if select & 0x20 == 0 {
-            result &= 0xF0 | ((self.buttons >> 4) & 0x0F);
+        if self.select & 0x20 == 0 {
+            lines &= self.buttons >> 4;
         }
         
         // Select d-pad (bit 4 = 0)
-        if select & 0x10 == 0 {
-            result &= 0xF0 | (self.buttons & 0x0F);
+        if self.select & 0x10 == 0 {
+            lines &= self.buttons & 0x0F;
+        }
+        
+        lines
+    }
+    
+    /// Value of the joypad register (0xFF00)
+    pub fn read(&self) -> u8 {
+        // Bits 6-7 always 1, lines read high when nothing is pressed
+        0xC0 | self.select | self.lines()
+    }
+    
+    /// A pressed button is on a selected line, which is what wakes the
+    /// CPU from STOP
+    pub fn selected_pressed(&self) -> bool {
+        self.lines() != 0x0F
+    }
+    
+    /// Watch for the game reading `button` through JOYP
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn watch(&mut self, button: Button) {
+        self.watched = Some(button);
+        self.watched_read.set(false);
+    }
+    
+    /// The CPU read JOYP; note it if the watched button's line is selected
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn cpu_read(&self) {
+        if let Some(button) = self.watched {
+            // Directions are on P14 (bit 4), buttons on P15 (bit 5)
+            let line = if (button as u8) < 4 { 0x10 } else { 0x20 };
+            if self.select & line == 0 {
+                self.watched_read.set(true);
+            }
+        }
+    }
+    
+    /// Check whether the watched button has been read since `watch`
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn take_watched_read(&mut self) -> bool {
+        if self.watched_read.take() {
+            self.watched = None;
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// Request the interrupt if a selected line went low (except with
+    /// `JoypadIrq::AnyPress`, which only looks at presses)
+    fn lines_changed(&mut self, old_lines: u8) {
+        if self.irq == JoypadIrq::AnyPress || old_lines & !self.lines() == 0 {
+            return;
+        }
+        
+        self.interrupt_pending = true;
+        if let JoypadIrq::Bounce { bounces, interval } = self.irq {
+            self.bounces_left = bounces;
+            self.bounce_timer = interval;
+        }
+    }
+    
+    /// Advance contact bounce by `cycles` (single-speed cycles)
+    pub fn step(&mut self, cycles: u32) {
+        if self.bounces_left == 0 {
+            return;
+        }
+        
+        self.bounce_timer = self.bounce_timer.saturating_sub(cycles);
+        if self.bounce_timer > 0 {
+            return;
         }
         
-        result
+        // A released button or deselected row stops bouncing
+        if self.selected_pressed() {
+            self.interrupt_pending = true;
+            self.bounces_left -= 1;
+            if let JoypadIrq::Bounce { interval, .. } = self.irq {
+                self.bounce_timer = interval;
+            }
+        } else {
+            self.bounces_left = 0;
+        }
+    }
+    
+    /// Check and clear whether A+B+Select+Start became held together
+    pub fn take_soft_reset_combo(&mut self) -> bool {
+        std::mem::take(&mut self.soft_reset_combo)
+    }
+    
+    /// Set the held buttons without raising interrupts (bit = 0 means
+    /// pressed)
+    pub(crate) fn hold(&mut self, buttons: u8) {
+        self.buttons = buttons;
     }
     
     /// Check and clear interrupt flag
@@ -127,6 +444,9 @@ impl Joypad {
         JoypadState {
             buttons: self.buttons,
             interrupt_pending: self.interrupt_pending,
+            select: self.select,
+            bounces_left: self.bounces_left,
+            bounce_timer: self.bounce_timer,
         }
     }
     
@@ -134,5 +454,23 @@ impl Joypad {
     pub fn load_state(&mut self, state: JoypadState) {
         self.buttons = state.buttons;
         self.interrupt_pending = state.interrupt_pending;
+        self.select = state.select;
+        self.bounces_left = state.bounces_left;
+        self.bounce_timer = state.bounce_timer;
+    }
+}
+
+impl IoDevice for Joypad {
+    const IO_RANGES: &'static [RangeInclusive<u16>] = &[0xFF00..=0xFF00];
+    
+    fn read_io(&self, _addr: u16) -> u8 {
+        self.read()
+    }
+    
+    fn write_io(&mut self, _addr: u16, value: u8) {
+        // Only bits 4-5 are writable (select lines)
+        let old_lines = self.lines();
+        self.select = value & 0x30;
+        self.lines_changed(old_lines);
     }
 }
\ No newline at end of file
@@ -12,6 +12,47 @@
 //! - **Timer**: DIV, TIMA, TMA, TAC registers
 //! - **Cartridge**: MBC1, MBC2, MBC3 (with RTC), MBC5 support
 //! - **Joypad**: Button input handling
+//! - **Session**: Several instances linked by cable
+//! - **Env**: Reinforcement learning environment wrapper
+//! - **GameMap**: Named game-specific memory locations
+//! - **Clock**: Injectable wall-clock time
+//! - **Cheats**: RAM search for finding cheat addresses
+//! - **Peripheral**: Hardware add-ons with a shared lifecycle
+//! - **Accuracy**: Known results against public test suites
+//! - **RomInfo**: Header details, hashes and database lookup
+//! - **MemoryDump**: Banked memory snapshots for editors
+//! - **Movie**: Input recordings that replay deterministically
+//! - **Autosave**: Battery saves once the game stops writing them
+//! - **Achievements**: RetroAchievements (rcheevos) memory layout
+//! - **Coverage**: Which ROM bytes have been executed
+//! - **DemoRom**: A generated test cartridge for examples and experiments
+//! - **Pipeline**: Resampling, scaling and PNG encoding on a worker thread
+//!   (not on wasm)
+//! 
+//! ## Features
+//! 
+//! - `wasm` (default): JavaScript bindings
+//! - `instrumentation` (default): bus capture, input latency tracking,
+//!   power statistics, write telemetry and ROM coverage. These hooks run
+//!   on every step, so builds that don't need them (such as the web
+//!   frontend) disable the feature and the hooks compile out completely.
+//! - `rom-hash` (default): CRC32/SHA-1 hashes in `RomInfo` and database
+//!   lookup
+//! 
+//! ## Example
+//! 
+//! ```
+//! use gbemu_core::demo_rom::DEMO_ROM;
+//! use gbemu_core::{GameBoy, SCREEN_HEIGHT, SCREEN_WIDTH};
+//! 
+//! let mut gb = GameBoy::new(&DEMO_ROM)?;
+//! for _ in 0..60 {
+//!     let output = gb.run_frame();
+//!     assert_eq!(output.video.len(), SCREEN_WIDTH * SCREEN_HEIGHT * 4);
+//!     // Hand output.video and output.audio to the frontend here
+//! }
+//! # Ok::<(), String>(())
+//! ```
 
 #![allow(clippy::new_without_default)]
 
@@ -23,6 +64,24 @@ pub mod cartridge;
 pub mod timer;
 pub mod joypad;
 pub mod serial;
+pub mod session;
+pub mod config;
+pub mod env;
+pub mod game_map;
+pub mod clock;
+pub mod cheats;
+pub mod peripheral;
+pub mod accuracy;
+pub mod rom_info;
+pub mod memory_dump;
+pub mod movie;
+pub mod demo_rom;
+pub mod autosave;
+pub mod achievements;
+#[cfg(feature = "instrumentation")]
+pub mod coverage;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pipeline;
 
 #[cfg(feature = "wasm")]
 mod wasm;
@@ -34,14 +93,19 @@ use apu::Apu;
 use timer::Timer;
 use joypad::Joypad;
 use cartridge::Cartridge;
-use serial::Serial;
+use autosave::Autosave;
+use achievements::{AchievementHook, AchievementMemory};
+use serial::{ExternalClock, Serial};
+use clock::ClockSource;
+use std::collections::{BTreeMap, BTreeSet};
 
 use serde::{Serialize, Deserialize};
 
 /// Game Boy model type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GbModel {
     /// Original Game Boy (DMG)
+    #[default]
     Dmg,
     /// Game Boy Pocket
     Pocket,
@@ -49,33 +113,131 @@ pub enum GbModel {
     Cgb,
     /// Game Boy Color in DMG compatibility mode
     CgbDmg,
+    /// Super Game Boy (runs like a DMG; SGB features aren't emulated)
+    Sgb,
+    /// Super Game Boy 2
+    Sgb2,
+    /// Game Boy Advance running a CGB game
+    Agb,
 }
 
-impl Default for GbModel {
-    fn default() -> Self {
-        Self::Dmg
+impl GbModel {
+    /// Color hardware (CGB or AGB), in either mode
+    pub fn is_cgb(self) -> bool {
+        matches!(self, GbModel::Cgb | GbModel::CgbDmg | GbModel::Agb)
+    }
+    
+    /// Super Game Boy, the DMG CPU running inside an SNES
+    pub fn is_sgb(self) -> bool {
+        matches!(self, GbModel::Sgb | GbModel::Sgb2)
+    }
+    
+    /// Model to emulate for a cartridge (CGB for CGB-enhanced games)
+    fn for_cartridge(cartridge: &Cartridge) -> Self {
+        if cartridge.is_cgb() {
+            GbModel::Cgb
+        } else {
+            GbModel::Dmg
+        }
+    }
+    
+    /// Model whose boot ROM this is (CGB boot ROMs are 2304 bytes and run
+    /// DMG games in compatibility mode)
+    fn for_boot_rom(cartridge: &Cartridge, boot_rom: &[u8]) -> Self {
+        match (boot_rom.len(), cartridge.is_cgb()) {
+            (0x100, _) => GbModel::Dmg,
+            (_, true) => GbModel::Cgb,
+            (_, false) => GbModel::CgbDmg,
+        }
     }
 }
 
 /// Main emulator state
+///
+/// The joypad, serial port, timer and APU are memory-mapped peripherals
+/// owned by the MMU's I/O bus; use the accessors below to reach them.
 pub struct GameBoy {
     pub cpu: Cpu,
     pub mmu: Mmu,
     pub ppu: Ppu,
-    pub apu: Apu,
-    pub timer: Timer,
-    pub joypad: Joypad,
-    pub serial: Serial,
     pub model: GbModel,
     
     /// Cycles executed this frame
     cycles_this_frame: u32,
     
+    /// The last `step` reached the start of VBlank
+    vblank_started: bool,
+    
     /// Total cycles executed
     total_cycles: u64,
     
+    /// Single-speed cycles since power-on (emulated wall time, unlike
+    /// `total_cycles` which counts double-speed cycles twice)
+    elapsed_cycles: u64,
+    
     /// Frame counter
     frame_count: u64,
+    
+    /// Options chosen at creation
+    config: GbConfig,
+    
+    /// Named memory locations for `value`
+    game_map: GameMap,
+    
+    /// Input latency measurements, when enabled
+    #[cfg(feature = "instrumentation")]
+    input_latency: Option<InputLatency>,
+    
+    /// Executed ROM bytes (see `set_coverage`)
+    #[cfg(feature = "instrumentation")]
+    coverage: Option<Coverage>,
+    
+    /// CPU and LCD activity since power-on
+    #[cfg(feature = "instrumentation")]
+    power_stats: PowerStats,
+    
+    /// Plugged-in hardware add-ons, stepped in the order they were added
+    peripherals: Vec<Box<dyn Peripheral>>,
+    
+    /// Wall-clock time the emulation was paused at (see `set_paused`)
+    paused_at: Option<u64>,
+    
+    /// Writes waiting for an HBlank or VBlank (see `write_at`)
+    scheduled_writes: Vec<(SyncPoint, u16, u8)>,
+    
+    /// PC addresses `run_frame_until_breakpoint` stops at
+    breakpoints: BTreeSet<u16>,
+    
+    /// Stopped at a breakpoint partway through a frame
+    breakpoint_hit: Option<u16>,
+    
+    /// Overclocked CPU cycles not yet passed on to the rest of the system
+    overclock_debt: u32,
+    
+    /// When to save battery RAM by itself
+    autosave: Autosave,
+    
+    /// Battery save made by `autosave`, waiting for `take_autosave`
+    pending_autosave: Option<Vec<u8>>,
+    
+    /// Called at the end of every frame (see `achievements`)
+    achievement_hook: Option<Box<dyn AchievementHook>>,
+    
+    /// The soft reset combination was pressed (see `GbConfig::soft_reset`)
+    soft_reset: bool,
+    
+    /// Input movie being recorded (see `start_movie`)
+    movie: Option<Movie>,
+}
+
+/// Point in the frame a scheduled write waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncPoint {
+    /// The start of the next HBlank (mode 3 ending)
+    NextHBlank,
+    
+    /// The start of the next VBlank (LY reaching 144)
+    NextVBlank,
 }
 
 /// Cycles per frame at ~59.7 FPS
@@ -86,119 +248,711 @@ pub const CYCLES_PER_FRAME: u32 = 70224;
 pub const CPU_CLOCK_HZ: u32 = 4_194_304;
 
 impl GameBoy {
-    /// Create a new Game Boy instance with a ROM
+    /// Create a new Game Boy instance with a ROM, starting from the state
+    /// the boot ROM leaves behind
+    ///
+    /// ```
+    /// use gbemu_core::{demo_rom::DEMO_ROM, GameBoy};
+    ///
+    /// let gb = GameBoy::new(&DEMO_ROM)?;
+    /// assert_eq!(gb.cpu.regs.pc, 0x0100);
+    /// assert!(GameBoy::new(&[0; 16]).is_err());
+    /// # Ok::<(), String>(())
+    /// ```
     pub fn new(rom_data: &[u8]) -> Result<Self, String> {
-        let cartridge = Cartridge::from_rom(rom_data)?;
-        let model = if cartridge.is_cgb() {
-            GbModel::Cgb
-        } else {
-            GbModel::Dmg
+        Self::with_config(rom_data, GbConfig::default())
+    }
+    
+    /// Create a new Game Boy instance with a ROM and configuration
+    pub fn with_config(rom_data: &[u8], config: GbConfig) -> Result<Self, String> {
+        let cartridge = config.cartridge(rom_data)?;
+        let model = GbModel::for_cartridge(&cartridge);
+        Ok(Self::from_cartridge(cartridge, model, config))
+    }
+    
+    /// Power on through a boot ROM (256 bytes for DMG, 2304 for CGB)
+    /// instead of starting from its hand-off state.
+    ///
+    /// With `rom_data` of `None` the cartridge slot is empty and the boot
+    /// ROM locks up at the logo check, as on hardware. The header isn't
+    /// validated beyond what the mapper needs, so ROMs with a corrupted
+    /// logo or checksum reach the boot ROM's own checks.
+    pub fn with_boot_rom(rom_data: Option<&[u8]>, boot_rom: &[u8], config: GbConfig) -> Result<Self, String> {
+        let cartridge = match rom_data {
+            Some(data) => config.cartridge(data)?,
+            None => Cartridge::empty(),
         };
+        let model = GbModel::for_boot_rom(&cartridge, boot_rom);
         
+        let mut gb = Self::from_cartridge(cartridge, model, config);
+        gb.mmu.set_boot_rom(Some(boot_rom.to_vec()))?;
+        gb.reset();
+        Ok(gb)
+    }
+    
+    fn from_cartridge(cartridge: Cartridge, model: GbModel, config: GbConfig) -> Self {
         let mut gb = Self {
             cpu: Cpu::new(),
             mmu: Mmu::new(cartridge, model),
             ppu: Ppu::new(model),
-            apu: Apu::new(),
-            timer: Timer::new(),
-            joypad: Joypad::new(),
-            serial: Serial::new(),
             model,
             cycles_this_frame: 0,
+            vblank_started: false,
             total_cycles: 0,
+            elapsed_cycles: 0,
             frame_count: 0,
+            config,
+            game_map: GameMap::new(),
+            #[cfg(feature = "instrumentation")]
+            input_latency: None,
+            #[cfg(feature = "instrumentation")]
+            coverage: None,
+            #[cfg(feature = "instrumentation")]
+            power_stats: PowerStats::default(),
+            peripherals: Vec::new(),
+            paused_at: None,
+            scheduled_writes: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            breakpoint_hit: None,
+            overclock_debt: 0,
+            autosave: Autosave::default(),
+            pending_autosave: None,
+            achievement_hook: None,
+            soft_reset: false,
+            movie: None,
         };
         
         // Initialize CPU registers based on model
         gb.cpu.init_for_model(model);
+        gb.apply_settings();
+        gb.mmu.cartridge_mut().set_clock_source(gb.config.rtc_clock.source());
+        gb.mmu.init_ram();
         
-        Ok(gb)
+        gb
     }
     
-    /// Reset the emulator
+    /// Get the configuration
+    pub fn config(&self) -> &GbConfig {
+        &self.config
+    }
+    
+    /// Switch to a new configuration without resetting. Power-on RAM
+    /// contents apply from the next reset; the RTC clock source is only
+    /// replaced if `rtc_clock` changed.
+    pub fn apply_config(&mut self, config: GbConfig) {
+        let clock_changed = config.rtc_clock != self.config.rtc_clock;
+        self.config = config;
+        self.apply_settings();
+        
+        if clock_changed {
+            self.mmu.cartridge_mut().set_clock_source(self.config.rtc_clock.source());
+        }
+    }
+    
+    /// Push the configuration to the components
+    fn apply_settings(&mut self) {
+        let config = &self.config;
+        self.mmu.serial_mut().set_disconnected_policy(config.serial_disconnected);
+        self.mmu.set_ram_init(config.ram_init.for_instance(config.instance_id));
+        self.mmu.set_accuracy(config.accuracy);
+        self.mmu.cartridge_mut().set_sram_auto_grow(config.sram_auto_grow);
+        self.mmu.joypad_mut().set_allow_opposing_directions(config.allow_opposing_directions);
+        self.mmu.joypad_mut().set_irq(config.joypad_irq);
+        self.cpu.set_interrupt_timing(config.interrupt_timing);
+        self.autosave.set_quiet_frames(config.autosave_frames);
+    }
+    
+    /// Press the reset button: the CPU, the hardware registers and the
+    /// mapper's bank registers return to their power-on values, while WRAM,
+    /// HRAM and cartridge RAM keep their contents, as they stay powered on
+    /// a real console. Games use this to tell a reset from a cold start.
     pub fn reset(&mut self) {
+        self.record_movie(MovieInput::Reset);
+        self.reset_line();
+    }
+    
+    /// Switch the console off and on again: like `reset`, but WRAM and
+    /// HRAM power on with `GbConfig::ram_init` contents and cartridge RAM
+    /// without a battery is lost. The cartridge, configuration and host
+    /// hooks stay, so this matches a fresh `with_config`.
+    pub fn power_cycle(&mut self) {
+        self.record_movie(MovieInput::PowerCycle);
+        self.mmu.init_ram();
+        if !self.mmu.cartridge().has_battery() {
+            self.mmu.cartridge_mut().ram_mut().fill(0);
+        }
+        self.reset_line();
+    }
+    
+    /// Reset everything the console's reset line reaches
+    fn reset_line(&mut self) {
         self.cpu.reset();
-        self.cpu.init_for_model(self.model);
-        self.mmu.reset();
+        self.mmu.warm_reset();
+        self.mmu.cartridge_mut().reset_mapper();
+        
+        if self.mmu.boot_rom_mapped() {
+            // The boot ROM starts from 0x0000 with the LCD off and DIV
+            // counting from zero
+            self.mmu.lcd_mut().lcdc = 0x00;
+            self.mmu.timer_mut().write_div();
+        } else {
+            self.cpu.init_for_model(self.model);
+        }
+        
         self.ppu.reset();
-        self.apu.reset();
-        self.timer.reset();
-        self.joypad.reset();
-        self.serial.reset();
         self.cycles_this_frame = 0;
         self.total_cycles = 0;
+        self.elapsed_cycles = 0;
         self.frame_count = 0;
+        self.scheduled_writes.clear();
+        self.breakpoint_hit = None;
+        self.overclock_debt = 0;
+        #[cfg(feature = "instrumentation")]
+        self.reset_power_stats();
+        
+        for peripheral in &mut self.peripherals {
+            peripheral.reset();
+        }
+    }
+    
+    /// Reset only the APU to its post-boot state, for recovering from
+    /// stuck or glitched audio. Host settings (output mode, volume, buffer
+    /// cap) are kept and the CPU carries on.
+    pub fn reset_apu(&mut self) {
+        let double_speed = self.mmu.double_speed();
+        self.mmu.apu_mut().reset();
+        self.mmu.apu_mut().set_double_speed(double_speed);
+    }
+    
+    /// Reset only the PPU and LCD registers to their post-boot state, for
+    /// recovering from a corrupted picture. VRAM and OAM belong to the game
+    /// and are left alone; the frame restarts at line 0.
+    pub fn reset_ppu(&mut self) {
+        self.ppu.reset();
+        self.mmu.lcd_mut().reset();
+    }
+    
+    /// Reset only the timer registers (DIV, TIMA, TMA, TAC), keeping the
+    /// current CPU speed
+    pub fn reset_timer(&mut self) {
+        let double_speed = self.mmu.double_speed();
+        self.mmu.timer_mut().reset();
+        self.mmu.timer_mut().set_double_speed(double_speed);
+    }
+    
+    /// Reset only the joypad (all buttons released)
+    pub fn reset_joypad(&mut self) {
+        self.mmu.joypad_mut().reset();
     }
     
-    /// Run a single CPU step and synchronize all components
+    /// Reset only the serial port, abandoning any transfer in progress.
+    /// Link and clock hooks stay connected.
+    pub fn reset_serial(&mut self) {
+        self.mmu.serial_mut().reset();
+    }
+    
+    /// Create a new Game Boy instance with a ROM and its battery save.
+    ///
+    /// The save is applied before the first instruction runs, for games
+    /// that read SRAM during their boot path.
+    pub fn new_with_sram(rom_data: &[u8], sram: Option<&[u8]>) -> Result<Self, String> {
+        let mut gb = Self::new(rom_data)?;
+        
+        if let Some(data) = sram {
+            if !gb.mmu.cartridge().has_battery() {
+                return Err("Cartridge has no battery-backed save RAM".to_string());
+            }
+            gb.load_sram(data)?;
+        }
+        
+        Ok(gb)
+    }
+    
+    /// Model being emulated
+    pub fn model(&self) -> GbModel {
+        self.model
+    }
+    
+    /// Switch to a different model and reset, keeping the cartridge (and
+    /// its RAM), configuration and host hooks. Running a CGB-only game on
+    /// a DMG model is allowed, as on hardware.
+    pub fn set_model(&mut self, model: GbModel) {
+        self.model = model;
+        self.mmu.set_model(model);
+        self.ppu = Ppu::new(model);
+        self.reset_line();
+    }
+    
+    /// Swap in a new ROM and reset, keeping the configuration and any
+    /// host hooks (serial clock, RTC clock source, audio output mode)
+    pub fn load_rom(&mut self, rom_data: &[u8]) -> Result<(), String> {
+        let mut cartridge = self.config.cartridge(rom_data)?;
+        cartridge.set_clock_source(self.mmu.cartridge_mut().take_clock_source());
+        let model = match self.mmu.boot_rom() {
+            Some(boot_rom) => GbModel::for_boot_rom(&cartridge, boot_rom),
+            None => GbModel::for_cartridge(&cartridge),
+        };
+        
+        self.model = model;
+        self.mmu.load_cartridge(cartridge, model);
+        self.apply_settings();
+        self.ppu = Ppu::new(model);
+        self.reset_line();
+        
+        #[cfg(feature = "instrumentation")]
+        if self.coverage.is_some() {
+            self.set_coverage(true);
+        }
+        
+        Ok(())
+    }
+    
+    /// Run a single CPU step and synchronize all components. Returns the
+    /// CPU cycles executed; with `GbConfig::overclock` the rest of the
+    /// system may advance by fewer, or not at all.
     pub fn step(&mut self) -> u32 {
+        #[cfg(feature = "instrumentation")]
+        self.mmu.set_bus_capture_cycle(self.total_cycles);
+        
+        #[cfg(feature = "instrumentation")]
+        let (halted, stopped, double_speed) =
+            (self.cpu.halted, self.cpu.stopped, self.mmu.double_speed());
+        
+        // Input arrives between steps, so the combination is handled
+        // before the next instruction
+        if self.mmu.joypad_mut().take_soft_reset_combo() {
+            self.soft_reset_combo();
+        }
+        
+        // Banks the instruction is fetched through, for coverage
+        #[cfg(feature = "instrumentation")]
+        let rom_banks = (self.coverage.is_some() && !self.mmu.boot_rom_mapped())
+            .then(|| self.mmu.cartridge().rom_banks());
+        
         // Execute one CPU instruction
+        self.vblank_started = false;
         let cycles = self.cpu.step(&mut self.mmu);
+        #[cfg(feature = "instrumentation")]
+        if let Some(banks) = rom_banks {
+            self.record_coverage(banks);
+        }
+        let system_cycles = self.underclock(cycles);
+        if system_cycles == 0 {
+            return cycles;
+        }
         
         // Synchronize all components
-        self.sync_components(cycles);
+        self.sync_components(system_cycles);
+        
+        #[cfg(feature = "instrumentation")]
+        self.count_power(system_cycles, halted, stopped, double_speed);
+        
+        for peripheral in &mut self.peripherals {
+            peripheral.step(system_cycles, &mut self.mmu);
+        }
         
         cycles
     }
     
+    /// Cycles the rest of the system sees for `cycles` of CPU time: all of
+    /// them normally, a whole number of M-cycles per `GbConfig::overclock`
+    /// times as many when overclocked
+    fn underclock(&mut self, cycles: u32) -> u32 {
+        let factor = self.config.overclock.max(1);
+        if factor == 1 {
+            return cycles;
+        }
+        
+        self.overclock_debt += cycles;
+        let system_cycles = self.overclock_debt / (4 * factor) * 4;
+        self.overclock_debt -= system_cycles * factor;
+        system_cycles
+    }
+    
+    /// Add a step to the power statistics
+    #[cfg(feature = "instrumentation")]
+    fn count_power(&mut self, cycles: u32, halted: bool, stopped: bool, double_speed: bool) {
+        let real_cycles = if double_speed { cycles / 2 } else { cycles } as u64;
+        let stats = &mut self.power_stats;
+        
+        if stopped {
+            stats.stopped_cycles += real_cycles;
+        } else if halted {
+            stats.halted_cycles += real_cycles;
+        } else {
+            stats.executing_cycles += real_cycles;
+        }
+        
+        if self.mmu.lcd().lcdc & 0x80 != 0 {
+            stats.lcd_on_cycles += real_cycles;
+        }
+        if double_speed {
+            stats.double_speed_cycles += real_cycles;
+        }
+        if double_speed != self.mmu.double_speed() {
+            stats.speed_switches += 1;
+        }
+    }
+    
     /// Synchronize all components with CPU cycles
     fn sync_components(&mut self, cycles: u32) {
+        // The PPU and APU run at the same rate in both CPU speeds, so in
+        // double speed they see half as many cycles
+        let real_cycles = if self.mmu.double_speed() { cycles / 2 } else { cycles };
+        
+        // The serial clock is derived from DIV as it was before this step
+        let div = self.mmu.timer().div_counter();
+        
         // Update timer
-        let timer_interrupt = self.timer.step(cycles);
+        let timer_interrupt = self.mmu.timer_mut().step(cycles);
         if timer_interrupt {
             self.mmu.request_interrupt(0x04); // Timer interrupt
         }
         
         // Update OAM DMA (one byte per M-cycle = 4 T-cycles)
-        for _ in 0..(cycles / 4).max(1) {
-            self.mmu.step_dma();
-        }
+        self.mmu.advance_dma(cycles);
         
         // Update PPU
-        let ppu_result = self.ppu.step(cycles, &mut self.mmu);
+        let ppu_result = self.ppu.step(real_cycles, &mut self.mmu);
         if ppu_result.vblank_interrupt {
             self.mmu.request_interrupt(0x01); // VBlank
+            self.vblank_started = true;
         }
         if ppu_result.stat_interrupt {
             self.mmu.request_interrupt(0x02); // STAT
         }
-        
-        // Process audio register writes
-        for (addr, value) in self.mmu.take_audio_writes() {
-            self.apu.write_register(addr, value);
+        if !self.scheduled_writes.is_empty() {
+            self.run_scheduled_writes(ppu_result.hblank_started, ppu_result.vblank_interrupt);
         }
         
-        // Update APU
-        self.apu.step(cycles);
+        // Update APU (frame sequencer is clocked by DIV)
+        self.mmu.apu_mut().step(real_cycles);
+        for _ in 0..self.mmu.timer_mut().take_div_apu_ticks() {
+            self.mmu.apu_mut().clock_div_apu();
+        }
         
         // Update serial
-        let serial_interrupt = self.serial.step(cycles);
+        let serial_interrupt = self.mmu.serial_mut().step(cycles, div);
         if serial_interrupt {
             self.mmu.request_interrupt(0x08); // Serial
         }
         
-        // Update joypad (check for interrupt)
-        if self.joypad.check_interrupt() {
+        // Update joypad (contact bounce runs in real time; check for interrupt)
+        self.mmu.joypad_mut().step(real_cycles);
+        if self.mmu.joypad_mut().check_interrupt() {
             self.mmu.request_interrupt(0x10); // Joypad
         }
         
-        self.cycles_this_frame += cycles;
+        // The RTC runs off its own crystal, at the same rate in both speeds
+        self.mmu.cartridge_mut().tick_rtc(real_cycles);
+        
+        self.cycles_this_frame += real_cycles;
         self.total_cycles += cycles as u64;
+        self.elapsed_cycles += real_cycles as u64;
+        
+        #[cfg(feature = "instrumentation")]
+        if let Some(latency) = self.input_latency.as_mut() {
+            if latency.is_pending() && self.mmu.joypad_mut().take_watched_read() {
+                latency.observed(self.total_cycles);
+            }
+        }
     }
     
-    /// Run until the next frame is complete
-    /// Returns the framebuffer
-    pub fn run_frame(&mut self) -> &[u8] {
-        self.cycles_this_frame = 0;
+    /// Make the writes due at the HBlank or VBlank that just started (all
+    /// of them while the LCD is off, since nothing is being drawn)
+    fn run_scheduled_writes(&mut self, hblank: bool, vblank: bool) {
+        let lcd_off = self.mmu.lcd().lcdc & 0x80 == 0;
+        let mmu = &mut self.mmu;
         
-        while self.cycles_this_frame < CYCLES_PER_FRAME {
+        self.scheduled_writes.retain(|&(at, addr, value)| {
+            let due = lcd_off || match at {
+                SyncPoint::NextHBlank => hblank,
+                SyncPoint::NextVBlank => vblank,
+            };
+            if due {
+                mmu.write_byte(addr, value);
+            }
+            !due
+        });
+    }
+    
+    /// Write `value` to `addr` (through the bus, like a CPU write) when
+    /// the PPU next reaches `at`, so scripts and trainers can touch VRAM
+    /// and OAM without racing mode 3. Writes due together happen in the
+    /// order they were scheduled.
+    pub fn write_at(&mut self, at: SyncPoint, addr: u16, value: u8) {
+        self.scheduled_writes.push((at, addr, value));
+    }
+    
+    /// Run until the next frame is complete and return everything it
+    /// produced. The audio buffer is cleared first, so the returned
+    /// samples belong to this frame only, and pending events are drained.
+    ///
+    /// ```
+    /// use gbemu_core::demo_rom::{DEMO_ROM, FRAME_COUNTER};
+    /// use gbemu_core::GameBoy;
+    ///
+    /// let mut gb = GameBoy::new(&DEMO_ROM)?;
+    /// gb.run_frame();
+    /// let output = gb.run_frame();
+    /// assert_eq!(output.frame, 2);
+    /// assert!(output.audio.iter().any(|&sample| sample != 0.0));
+    ///
+    /// // The demo counts frames in WRAM from its VBlank handler
+    /// assert!(gb.mmu.read_byte(FRAME_COUNTER) > 0);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn run_frame(&mut self) -> FrameOutput<'_> {
+        self.clear_audio_buffer();
+        self.run_frame_video();
+        
+        let events = self.take_lcd_event().map(EmulatorEvent::Lcd).into_iter()
+            .chain(self.take_soft_reset().then_some(EmulatorEvent::SoftReset))
+            .chain(self.take_autosave().map(EmulatorEvent::Autosave))
+            .collect();
+        FrameOutput {
+            video: self.ppu.framebuffer(),
+            audio: self.mmu.apu().output_buffer(),
+            events,
+            frame: self.frame_count,
+        }
+    }
+    
+    /// Run until the next frame is complete and return the framebuffer,
+    /// leaving audio to accumulate until `clear_audio_buffer` and events
+    /// to their `take_` getters (what `run_frame` did before `FrameOutput`)
+    pub fn run_frame_video(&mut self) -> &[u8] {
+        if self.is_paused() {
+            self.mmu.apu_mut().step_silent(CYCLES_PER_FRAME);
+            return self.ppu.framebuffer();
+        }
+        
+        self.record_movie(MovieInput::Frame(!self.mmu.joypad().buttons()));
+        loop {
             self.step();
+            if self.frame_complete() {
+                break;
+            }
         }
         
-        self.frame_count += 1;
+        self.end_frame();
         self.ppu.framebuffer()
     }
     
+    /// Count a completed frame
+    pub(crate) fn end_frame(&mut self) {
+        self.frame_count += 1;
+        #[cfg(feature = "instrumentation")]
+        self.mmu.end_telemetry_frame();
+        
+        let written = self.mmu.cartridge_mut().take_ram_written();
+        if self.autosave.end_frame(written) {
+            self.pending_autosave = self.save_sram();
+        }
+        
+        if let Some(mut hook) = self.achievement_hook.take() {
+            hook.achievement_tick(&AchievementMemory::new(self));
+            self.achievement_hook = Some(hook);
+        }
+    }
+    
+    /// Whether the last step ended a frame, which happens when the PPU
+    /// starts VBlank. The LCD produces no VBlank while it is off, so then
+    /// a frame is `CYCLES_PER_FRAME` cycles, with the overshoot carried
+    /// into the next one; the same limit at twice the length covers a
+    /// game switching the LCD on and off faster than it draws a frame.
+    fn frame_complete(&mut self) -> bool {
+        if self.vblank_started {
+            self.cycles_this_frame = 0;
+            return true;
+        }
+        
+        let lcd_off = self.mmu.lcd().lcdc & 0x80 == 0;
+        if self.cycles_this_frame >= CYCLES_PER_FRAME
+            && (lcd_off || self.cycles_this_frame >= 2 * CYCLES_PER_FRAME)
+        {
+            self.cycles_this_frame -= CYCLES_PER_FRAME;
+            return true;
+        }
+        false
+    }
+    
+    /// Like `run_frame`, but stop before executing an instruction at a
+    /// breakpoint and return its address. Calling again resumes the same
+    /// frame from that instruction; `None` means the frame completed.
+    pub fn run_frame_until_breakpoint(&mut self) -> Option<u16> {
+        let mut resuming = self.breakpoint_hit.take().is_some();
+        if !resuming {
+            self.record_movie(MovieInput::Frame(!self.mmu.joypad().buttons()));
+        }
+        
+        loop {
+            // A halted CPU stays on the same PC; only break once
+            let pc = self.cpu.regs.pc;
+            if !resuming && !self.cpu.halted && self.breakpoints.contains(&pc) {
+                self.breakpoint_hit = Some(pc);
+                return Some(pc);
+            }
+            
+            resuming = false;
+            self.step();
+            if self.frame_complete() {
+                break;
+            }
+        }
+        
+        self.end_frame();
+        None
+    }
+    
+    /// Run until the CPU is about to execute the instruction at `addr`,
+    /// for at most `max_cycles`. Returns the cycles run (0 if it is already
+    /// there), or `None` on timeout. Frames completed on the way are
+    /// counted; the next `run_frame` finishes the frame this stopped in.
+    pub fn run_until_pc(&mut self, addr: u16, max_cycles: u64) -> Option<u64> {
+        let mut cycles = 0;
+        
+        // A halted CPU hasn't reached the instruction at its PC yet
+        while self.cpu.halted || self.cpu.regs.pc != addr {
+            if cycles >= max_cycles {
+                return None;
+            }
+            cycles += self.step() as u64;
+            if self.frame_complete() {
+                self.end_frame();
+            }
+        }
+        
+        self.breakpoint_hit = None;
+        Some(cycles)
+    }
+    
+    /// Run whole frames until `frame_count` reaches `frame`, such as to
+    /// skip to a title screen deterministically. Returns the frames run:
+    /// 0 if it is already there or emulation is paused.
+    pub fn run_until_frame(&mut self, frame: u64) -> u64 {
+        let start = self.frame_count;
+        while self.frame_count < frame && !self.is_paused() {
+            self.run_frame_video();
+        }
+        self.frame_count - start
+    }
+    
+    /// Step lazily through execution, yielding each instruction the CPU
+    /// runs. Steps that dispatch an interrupt or idle in HALT or STOP still
+    /// run but aren't yielded; the iterator ends if a frame's worth of
+    /// cycles passes without an instruction (a CPU that never wakes).
+    /// Like `step`, this doesn't count frames.
+    ///
+    /// ```
+    /// use gbemu_core::{demo_rom::DEMO_ROM, GameBoy};
+    ///
+    /// let mut gb = GameBoy::new(&DEMO_ROM)?;
+    /// let trace: Vec<u16> = gb.step_iter().take(3).map(|step| step.pc).collect();
+    /// // nop; jp $0150; di
+    /// assert_eq!(trace, [0x0100, 0x0101, 0x0150]);
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn step_iter(&mut self) -> StepIter<'_> {
+        StepIter { gb: self }
+    }
+    
+    /// Disassemble the instruction at `addr` as currently mapped, without
+    /// touching the bus. Returns the text and the instruction length.
+    ///
+    /// ```
+    /// use gbemu_core::{demo_rom::DEMO_ROM, GameBoy};
+    ///
+    /// let gb = GameBoy::new(&DEMO_ROM)?;
+    /// assert_eq!(gb.disassemble(0x0101), ("JP $0150".to_string(), 3));
+    /// # Ok::<(), String>(())
+    /// ```
+    pub fn disassemble(&self, addr: u16) -> (String, u8) {
+        let bytes: Vec<u8> = (0..3).map(|i| self.mmu.peek_byte(addr.wrapping_add(i))).collect();
+        cpu::opcodes::disassemble(&bytes, addr)
+    }
+    
+    /// Bank mapped at `addr`: the ROM bank in 0x0000-0x7FFF, the WRAM
+    /// bank in 0xD000-0xDFFF, otherwise 0
+    fn bank_at(&self, addr: u16) -> usize {
+        let map = self.mmu.memory_map();
+        match addr {
+            0x0000..=0x3FFF => map.rom_bank0,
+            0x4000..=0x7FFF => map.rom_bank,
+            0xD000..=0xDFFF => map.wram_bank as usize,
+            _ => 0,
+        }
+    }
+    
+    /// Stop `run_frame_until_breakpoint` before the instruction at `addr`
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+    
+    /// Remove a breakpoint; returns false if there was none at `addr`
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.breakpoints.remove(&addr)
+    }
+    
+    /// Remove every breakpoint
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+    
+    /// Breakpoint addresses, in ascending order
+    pub fn breakpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.breakpoints.iter().copied()
+    }
+    
+    /// Pause or resume emulation.
+    ///
+    /// While paused, `run_frame` (and `frame_advance`) leave the machine
+    /// untouched and return the last frame with a frame's worth of silent
+    /// audio, fading out on pause and back in on resume so neither pops.
+    /// Memory and registers stay accessible, and `step` still executes
+    /// single instructions for a debugger. The RTC is caught up on the
+    /// wall-clock time spent paused, as it is when a save is loaded.
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused == self.is_paused() {
+            return;
+        }
+        
+        let now = self.mmu.cartridge().wall_time();
+        match self.paused_at.take() {
+            None => self.paused_at = Some(now),
+            Some(paused_at) => {
+                if let Some(rtc) = self.mmu.cartridge_mut().rtc_mut() {
+                    rtc.advance(now.saturating_sub(paused_at));
+                }
+            }
+        }
+    }
+    
+    /// Whether emulation is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+    
+    /// Run one frame while recording every CPU and DMA bus access
+    #[cfg(feature = "instrumentation")]
+    pub fn capture_bus_frame(&mut self) -> Vec<BusAccess> {
+        self.start_bus_capture();
+        self.run_frame_video();
+        self.stop_bus_capture()
+    }
+    
+    /// Start recording bus accesses (see `Mmu::start_bus_capture`)
+    #[cfg(feature = "instrumentation")]
+    pub fn start_bus_capture(&mut self) {
+        self.mmu.start_bus_capture(self.total_cycles);
+    }
+    
+    /// Stop recording bus accesses and return them
+    #[cfg(feature = "instrumentation")]
+    pub fn stop_bus_capture(&mut self) -> Vec<BusAccess> {
+        self.mmu.stop_bus_capture()
+    }
+    
     /// Run for a specific number of cycles
     pub fn run_cycles(&mut self, target_cycles: u32) {
         let mut cycles_run = 0;
@@ -209,14 +963,209 @@ impl GameBoy {
     
     /// Press a button
     pub fn press_button(&mut self, button: Button) {
-        self.joypad.press(button);
-        self.mmu.update_joypad(&self.joypad);
+        #[cfg(feature = "instrumentation")]
+        self.time_press(button);
+        self.mmu.joypad_mut().press(button);
+    }
+    
+    /// Start timing a newly pressed button if latency tracking is on
+    #[cfg(feature = "instrumentation")]
+    fn time_press(&mut self, button: Button) {
+        let Some(latency) = self.input_latency.as_mut() else {
+            return;
+        };
+        if !self.mmu.joypad().is_pressed(button) && latency.press(self.total_cycles) {
+            self.mmu.joypad_mut().watch(button);
+        }
+    }
+    
+    /// Measure the cycles between a button press and the game's next JOYP
+    /// read with that button's line selected (see `input_latency`)
+    #[cfg(feature = "instrumentation")]
+    pub fn set_input_latency_tracking(&mut self, enabled: bool) {
+        self.input_latency = enabled.then(InputLatency::default);
+    }
+    
+    /// Input latency measured so far, if tracking is enabled
+    #[cfg(feature = "instrumentation")]
+    pub fn input_latency(&self) -> Option<&InputLatency> {
+        self.input_latency.as_ref()
+    }
+    
+    /// Plug in a peripheral. Names must be unique.
+    pub fn add_peripheral(&mut self, peripheral: Box<dyn Peripheral>) -> Result<(), String> {
+        if self.peripheral(peripheral.name()).is_some() {
+            return Err(format!("Peripheral already connected: {}", peripheral.name()));
+        }
+        
+        self.peripherals.push(peripheral);
+        Ok(())
+    }
+    
+    /// Unplug a peripheral by name
+    pub fn remove_peripheral(&mut self, name: &str) -> Option<Box<dyn Peripheral>> {
+        let index = self.peripherals.iter().position(|p| p.name() == name)?;
+        Some(self.peripherals.remove(index))
+    }
+    
+    /// Get a peripheral by name
+    pub fn peripheral(&self, name: &str) -> Option<&dyn Peripheral> {
+        self.peripherals.iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+    }
+    
+    /// Get a mutable peripheral by name
+    pub fn peripheral_mut(&mut self, name: &str) -> Option<&mut (dyn Peripheral + 'static)> {
+        self.peripherals.iter_mut()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_mut())
+    }
+    
+    /// CPU and LCD activity since power-on (or the last reset), for
+    /// estimating how hard a program works the battery
+    #[cfg(feature = "instrumentation")]
+    pub fn power_stats(&self) -> &PowerStats {
+        &self.power_stats
+    }
+    
+    /// Start counting power statistics from zero
+    #[cfg(feature = "instrumentation")]
+    pub fn reset_power_stats(&mut self) {
+        self.power_stats = PowerStats::default();
+    }
+    
+    /// Start counting bank switches, DMA transfers and audio, timer and
+    /// serial register writes per frame (from zero), or stop
+    #[cfg(feature = "instrumentation")]
+    pub fn set_telemetry(&mut self, enabled: bool) {
+        self.mmu.set_telemetry(enabled);
+    }
+    
+    /// Write telemetry for the last frame and all frames so far, if
+    /// enabled with `set_telemetry`
+    #[cfg(feature = "instrumentation")]
+    pub fn stats(&self) -> Option<&Telemetry> {
+        self.mmu.telemetry()
+    }
+    
+    /// Start recording which ROM bytes are executed (from nothing), or
+    /// stop. Loading another ROM starts over.
+    #[cfg(feature = "instrumentation")]
+    pub fn set_coverage(&mut self, enabled: bool) {
+        self.coverage = enabled.then(|| Coverage::new(self.mmu.cartridge().rom().len()));
+    }
+    
+    /// ROM bytes executed since `set_coverage`, if enabled
+    #[cfg(feature = "instrumentation")]
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+    
+    /// Mark the instruction the last step executed, if it ran from ROM
+    /// mapped through `(bank0, bank)`
+    #[cfg(feature = "instrumentation")]
+    fn record_coverage(&mut self, (bank0, bank): (usize, usize)) {
+        let (Some(coverage), Some((pc, opcode))) = (self.coverage.as_mut(), self.cpu.last_executed()) else {
+            return;
+        };
+        if pc >= 0x8000 {
+            return;
+        }
+        
+        let bank_of = |addr: u16| if addr < 0x4000 { bank0 } else { bank };
+        let cartridge = self.mmu.cartridge();
+        let next = pc + 1;
+        let operand = if next < 0x8000 { cartridge.read_rom_bank(bank_of(next), next) } else { 0 };
+        let length = cpu::opcodes::lookup(&[opcode, operand]).length as u16;
+        
+        for addr in pc..(pc + length).min(0x8000) {
+            coverage.mark(bank_of(addr) * coverage::BANK_SIZE + (addr as usize & 0x3FFF));
+        }
     }
     
     /// Release a button
     pub fn release_button(&mut self, button: Button) {
-        self.joypad.release(button);
-        self.mmu.update_joypad(&self.joypad);
+        self.mmu.joypad_mut().release(button);
+    }
+    
+    /// Start recording an input movie from the current state, replacing
+    /// any recording in progress. Frames run with `run_frame`,
+    /// `run_frame_video`, `frame_advance` or `run_frame_until_breakpoint`
+    /// are recorded along with calls to `reset` and `power_cycle`.
+    pub fn start_movie(&mut self) {
+        self.movie = Some(Movie::new(self.save_state_with_config()));
+    }
+    
+    /// Stop recording and return the movie, if one was being recorded
+    pub fn stop_movie(&mut self) -> Option<Movie> {
+        self.movie.take()
+    }
+    
+    fn record_movie(&mut self, input: MovieInput) {
+        if let Some(movie) = self.movie.as_mut() {
+            movie.inputs.push(input);
+        }
+    }
+    
+    /// Hold exactly `input` for one frame and return its output (see
+    /// `run_frame`)
+    pub fn frame_advance(&mut self, input: InputState) -> FrameOutput<'_> {
+        #[cfg(feature = "instrumentation")]
+        for button in (0..8).filter_map(Button::from_code) {
+            if input.is_pressed(button) {
+                self.time_press(button);
+            }
+        }
+        self.mmu.joypad_mut().set_input(input);
+        self.run_frame()
+    }
+    
+    /// Get the APU
+    pub fn apu(&self) -> &Apu {
+        self.mmu.apu()
+    }
+    
+    /// Get mutable APU
+    pub fn apu_mut(&mut self) -> &mut Apu {
+        self.mmu.apu_mut()
+    }
+    
+    /// Get the timer
+    pub fn timer(&self) -> &Timer {
+        self.mmu.timer()
+    }
+    
+    /// Get the joypad
+    pub fn joypad(&self) -> &Joypad {
+        self.mmu.joypad()
+    }
+    
+    /// Get the serial port
+    pub fn serial(&self) -> &Serial {
+        self.mmu.serial()
+    }
+    
+    /// Get mutable serial port
+    pub fn serial_mut(&mut self) -> &mut Serial {
+        self.mmu.serial_mut()
+    }
+    
+    /// Drive externally clocked serial transfers from a custom source
+    /// (replaces the disconnected policy while installed)
+    pub fn set_serial_clock(&mut self, clock: Option<Box<dyn ExternalClock>>) {
+        self.mmu.serial_mut().set_external_clock(clock);
+    }
+    
+    /// Replace the wall clock used by the cartridge RTC, e.g. with a
+    /// `ScriptedClock` for deterministic tests
+    pub fn set_clock_source(&mut self, clock: Box<dyn ClockSource>) {
+        self.mmu.cartridge_mut().set_clock_source(clock);
+    }
+    
+    /// Describe what is currently mapped at each memory region
+    pub fn memory_map(&self) -> mmu::MemoryMapReport {
+        self.mmu.memory_map()
     }
     
     /// Get the current framebuffer (RGBA8888, 160x144)
@@ -224,14 +1173,93 @@ impl GameBoy {
         self.ppu.framebuffer()
     }
     
+    /// Take the finished frame into `buffer`, giving the PPU the caller's
+    /// buffer to render the next frame into (see `Ppu::swap_framebuffer`)
+    pub fn swap_framebuffer(&mut self, buffer: &mut Vec<u8>) -> Result<(), String> {
+        self.ppu.swap_framebuffer(buffer)
+    }
+    
+    /// Get the current frame as color indices, one byte per pixel
+    /// (see `Ppu::framebuffer_indexed`)
+    pub fn framebuffer_indexed(&self) -> &[u8] {
+        self.ppu.framebuffer_indexed()
+    }
+    
+    /// Which public test suites this build passes
+    pub fn accuracy_report(&self) -> AccuracyReport {
+        AccuracyReport::current()
+    }
+    
+    /// Hardware features and quirks this build implements, so frontends
+    /// can disable options that wouldn't work
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::current()
+    }
+    
+    /// 64-bit FNV-1a hash of the current frame (RGBA), stable across
+    /// platforms and releases, for comparing output against golden values
+    pub fn frame_hash(&self) -> u64 {
+        self.ppu.framebuffer().iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+    }
+    
+    /// Decode a background tile map with its CGB attributes, for debug
+    /// views (see `Ppu::tile_map`)
+    pub fn tile_map(&self, area: TileMapArea) -> Vec<TileMapEntry> {
+        self.ppu.tile_map(&self.mmu, area)
+    }
+    
+    /// Read a CGB palette color as RGB555 (see `CgbPalettes::color`)
+    pub fn cgb_palette(&self, index: usize) -> u16 {
+        self.mmu.palettes().color(index)
+    }
+    
+    /// Overwrite a CGB palette color for palette editors; in CGB mode it
+    /// shows from the next line drawn (see `CgbPalettes::write_color`)
+    pub fn write_cgb_palette(&mut self, index: usize, rgb555: u16) {
+        self.mmu.palettes_mut().write_color(index, rgb555);
+    }
+    
+    /// Draw the scroll viewport, window area and sprite boxes of each
+    /// frame into a debug overlay for layer inspectors (see
+    /// `debug_overlay`)
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.ppu.set_debug_overlay(enabled);
+    }
+    
+    /// Overlay for the last complete frame, RGBA8888 at
+    /// `OVERLAY_SIZE`x`OVERLAY_SIZE` in background map space so it lines
+    /// up with a rendered `tile_map`; `None` unless enabled
+    pub fn debug_overlay(&self) -> Option<&[u8]> {
+        self.ppu.debug_overlay()
+    }
+    
+    /// Take the latest LCD power change since the last call. After
+    /// `LcdEvent::Disabled` the framebuffer stops updating; after
+    /// `LcdEvent::Enabled` the next frame comes out blank.
+    pub fn take_lcd_event(&mut self) -> Option<LcdEvent> {
+        self.ppu.take_lcd_event()
+    }
+    
     /// Get audio samples
     pub fn audio_buffer(&self) -> &[f32] {
-        self.apu.output_buffer()
+        self.mmu.apu().output_buffer()
     }
     
     /// Clear audio buffer after reading
     pub fn clear_audio_buffer(&mut self) {
-        self.apu.clear_buffer();
+        self.mmu.apu_mut().clear_buffer();
+    }
+    
+    /// Select resampled or raw 2 MHz audio output
+    pub fn set_audio_output_mode(&mut self, mode: AudioOutputMode) {
+        self.mmu.apu_mut().set_output_mode(mode);
+    }
+    
+    /// Sample rate of the audio buffer for the current output mode
+    pub fn audio_sample_rate(&self) -> u32 {
+        self.mmu.apu().sample_rate()
     }
     
     /// Save SRAM (battery-backed save)
@@ -241,42 +1269,183 @@ impl GameBoy {
     
     /// Load SRAM
     pub fn load_sram(&mut self, data: &[u8]) -> Result<(), String> {
+        self.autosave.clear();
+        self.pending_autosave = None;
         self.mmu.cartridge_mut().load_ram(data)
     }
     
+    /// Call `hook` at the end of every frame with the memory achievements
+    /// are evaluated on (`None` removes it)
+    pub fn set_achievement_hook(&mut self, hook: Option<Box<dyn AchievementHook>>) {
+        self.achievement_hook = hook;
+    }
+    
+    /// Memory in the RetroAchievements address space, for reads outside
+    /// the per-frame hook
+    pub fn achievement_memory(&self) -> AchievementMemory<'_> {
+        AchievementMemory::new(self)
+    }
+    
+    /// Respond to A+B+Select+Start as configured
+    fn soft_reset_combo(&mut self) {
+        match self.config.soft_reset {
+            SoftReset::Off => return,
+            SoftReset::Event => {}
+            SoftReset::Reset => {
+                // Replays reach this from the recorded input, so it isn't
+                // recorded as a reset of its own
+                let buttons = self.mmu.joypad().buttons();
+                self.reset_line();
+                self.mmu.joypad_mut().hold(buttons);
+            }
+        }
+        self.soft_reset = true;
+    }
+    
+    /// Check and clear whether the soft reset combination was pressed
+    /// (see `GbConfig::soft_reset`). `run_frame` reports it as
+    /// `EmulatorEvent::SoftReset` instead.
+    pub fn take_soft_reset(&mut self) -> bool {
+        std::mem::take(&mut self.soft_reset)
+    }
+    
+    /// Take the battery save made by autosave (see
+    /// `GbConfig::autosave_frames`), if one is waiting. `run_frame` hands
+    /// it out as `EmulatorEvent::Autosave` instead.
+    pub fn take_autosave(&mut self) -> Option<Vec<u8>> {
+        self.pending_autosave.take()
+    }
+    
+    /// Changes to the battery save since `baseline` (an earlier
+    /// `save_sram`), for syncing saves incrementally (see
+    /// `cartridge::delta`)
+    pub fn sram_delta(&self, baseline: &[u8]) -> Option<Vec<u8>> {
+        self.save_sram().map(|sram| cartridge::delta::diff(baseline, &sram))
+    }
+    
+    /// Apply a delta from `sram_delta` to the current battery save. A
+    /// delta that doesn't fit this cartridge's save changes nothing.
+    pub fn apply_sram_delta(&mut self, delta: &[u8]) -> Result<(), String> {
+        let mut sram = self.save_sram().ok_or("Cartridge has no battery-backed save RAM")?;
+        let max_len = self.mmu.cartridge().max_save_size();
+        cartridge::delta::apply(&mut sram, delta, max_len)?;
+        self.load_sram(&sram)
+    }
+    
     /// Create a save state
+    ///
+    /// ```
+    /// use gbemu_core::{demo_rom::DEMO_ROM, GameBoy};
+    ///
+    /// let mut gb = GameBoy::new(&DEMO_ROM)?;
+    /// gb.run_frame();
+    /// let state = gb.save_state();
+    /// let expected = gb.run_frame().video.to_vec();
+    ///
+    /// gb.load_state(&state)?;
+    /// assert_eq!(gb.run_frame().video, expected.as_slice());
+    /// # Ok::<(), String>(())
+    /// ```
     pub fn save_state(&self) -> Vec<u8> {
-        let state = SaveState {
+        encode_state(&self.snapshot())
+    }
+    
+    /// Create a save state that also records the configuration, which
+    /// `load_state` applies when loading it
+    pub fn save_state_with_config(&self) -> Vec<u8> {
+        let mut state = self.snapshot();
+        state.config = Some(self.config.clone());
+        encode_state(&state)
+    }
+    
+    /// Create a quick-resume state for frequent autosaves: like
+    /// `save_state` but without VRAM and the framebuffer, so it is smaller
+    /// and faster to produce. `load_state` loads it, keeping the VRAM that
+    /// is already there; the picture is redrawn over the next frame, but
+    /// tiles and maps are only right again once the game rewrites them
+    /// (or immediately, when resuming into the same running game).
+    pub fn save_quick_state(&self) -> Vec<u8> {
+        let mut state = self.snapshot();
+        state.mmu.vram.clear();
+        state.ppu.framebuffer.clear();
+        state.ppu.indexed.clear();
+        encode_state(&state)
+    }
+    
+    fn snapshot(&self) -> SaveState {
+        SaveState {
             cpu: self.cpu.state(),
             mmu: self.mmu.state(),
             ppu: self.ppu.state(),
-            apu: self.apu.state(),
-            timer: self.timer.state(),
-            joypad: self.joypad.state(),
+            apu: self.mmu.apu().state(),
+            timer: self.mmu.timer().state(),
+            joypad: self.mmu.joypad().state(),
             model: self.model,
             cycles_this_frame: self.cycles_this_frame,
             total_cycles: self.total_cycles,
+            elapsed_cycles: Some(self.elapsed_cycles),
             frame_count: self.frame_count,
-        };
-        
-        serde_json::to_vec(&state).unwrap_or_default()
+            instance_id: self.config.instance_id,
+            config: None,
+            cartridge: None,
+            serial: Some(self.mmu.serial().state()),
+            peripherals: self.peripherals.iter()
+                .filter_map(|p| Some((p.name().to_string(), p.save_state()?)))
+                .collect(),
+        }
     }
     
-    /// Load a save state
-    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+    /// Load a save state. Every part is checked before anything is
+    /// applied, so a state that can't be loaded leaves the emulator as it
+    /// was.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
         let state: SaveState = serde_json::from_slice(data)
-            .map_err(|e| format!("Failed to parse save state: {}", e))?;
+            .map_err(|e| StateError::Parse(e.to_string()))?;
+        
+        self.mmu.check_state(&state.mmu)?;
+        self.ppu.check_state(&state.ppu)?;
+        for peripheral in &self.peripherals {
+            if let Some(data) = state.peripherals.get(peripheral.name()) {
+                peripheral.check_state(data).map_err(|message| StateError::Peripheral {
+                    name: peripheral.name().to_string(),
+                    message,
+                })?;
+            }
+        }
         
-        self.cpu.load_state(state.cpu);
         self.mmu.load_state(state.mmu)?;
-        self.ppu.load_state(state.ppu);
-        self.apu.load_state(state.apu);
-        self.timer.load_state(state.timer);
-        self.joypad.load_state(state.joypad);
+        self.cpu.load_state(state.cpu);
+        self.ppu.load_state(state.ppu)?;
+        self.mmu.apu_mut().load_state(state.apu);
+        self.mmu.timer_mut().load_state(state.timer);
+        let double_speed = self.mmu.double_speed();
+        self.mmu.timer_mut().set_double_speed(double_speed);
+        self.mmu.apu_mut().set_double_speed(double_speed);
+        self.mmu.joypad_mut().load_state(state.joypad);
+        if let Some(cartridge) = state.cartridge {
+            self.mmu.cartridge_mut().load_state(cartridge);
+        }
+        if let Some(serial) = state.serial {
+            self.mmu.serial_mut().load_state(serial);
+        }
+        for peripheral in &mut self.peripherals {
+            if let Some(data) = state.peripherals.get(peripheral.name()) {
+                peripheral.load_state(data).map_err(|message| StateError::Peripheral {
+                    name: peripheral.name().to_string(),
+                    message,
+                })?;
+            }
+        }
         self.model = state.model;
         self.cycles_this_frame = state.cycles_this_frame;
         self.total_cycles = state.total_cycles;
+        self.elapsed_cycles = state.elapsed_cycles.unwrap_or(state.total_cycles);
+        if let Some(config) = state.config {
+            self.apply_config(config);
+        }
+        self.config.instance_id = state.instance_id;
         self.frame_count = state.frame_count;
+        self.breakpoint_hit = None;
         
         Ok(())
     }
@@ -286,6 +1455,12 @@ impl GameBoy {
         self.mmu.cartridge().title()
     }
     
+    /// Header details of the loaded ROM, with its hashes when the
+    /// `rom-hash` feature is on (hashing reads the whole ROM)
+    pub fn rom_info(&self) -> RomInfo {
+        RomInfo::new(self.mmu.cartridge())
+    }
+    
     /// Check if the game is a CGB game
     pub fn is_cgb_game(&self) -> bool {
         self.mmu.cartridge().is_cgb()
@@ -300,11 +1475,96 @@ impl GameBoy {
     pub fn total_cycles(&self) -> u64 {
         self.total_cycles
     }
+    
+    /// Time that has passed on the emulated console since power-on (or
+    /// the last reset), independent of CPU speed
+    pub fn emulated_time(&self) -> std::time::Duration {
+        let nanos = self.elapsed_cycles as u128 * 1_000_000_000 / CPU_CLOCK_HZ as u128;
+        std::time::Duration::from_nanos(nanos as u64)
+    }
+    
+    /// Current MBC3 RTC registers, if the cartridge has a clock
+    pub fn rtc_state(&self) -> Option<Rtc> {
+        self.mmu.cartridge().rtc().cloned()
+    }
+    
+    /// Replace the RTC registers, e.g. to correct drift or to skip ahead
+    /// (see `Rtc::advance`)
+    pub fn set_rtc_state(&mut self, rtc: Rtc) -> Result<(), String> {
+        let current = self.mmu.cartridge_mut().rtc_mut()
+            .ok_or("Cartridge has no real-time clock")?;
+        *current = rtc;
+        Ok(())
+    }
+    
+    /// Instance ID from the configuration (restored by `load_state`)
+    pub fn instance_id(&self) -> u32 {
+        self.config.instance_id
+    }
+    
+    /// Seed of the random power-on RAM contents, after mixing in the
+    /// instance ID, or `None` if RAM isn't randomized. Include it in bug
+    /// reports to reproduce a run.
+    pub fn seed(&self) -> Option<u64> {
+        match self.mmu.ram_init() {
+            RamInit::Random(seed) => Some(seed),
+            _ => None,
+        }
+    }
+    
+    /// Highest cartridge RAM offset written past the size declared in the
+    /// ROM header (see `GbConfig::sram_auto_grow`)
+    pub fn sram_overflow(&self) -> Option<usize> {
+        self.mmu.cartridge().sram_overflow()
+    }
+    
+    /// The cartridge type byte of a ROM loaded with a guessed mapper (see
+    /// `GbConfig::allow_unknown_mapper`)
+    pub fn unknown_cartridge_type(&self) -> Option<u8> {
+        self.mmu.cartridge().unknown_type()
+    }
+    
+    /// Set the named memory locations used by `value`
+    pub fn set_game_map(&mut self, map: GameMap) {
+        self.game_map = map;
+    }
+    
+    /// Get the current game map
+    pub fn game_map(&self) -> &GameMap {
+        &self.game_map
+    }
+    
+    /// Dump every bank of a memory region behind a header describing the
+    /// bank layout (see `memory_dump`)
+    pub fn dump_memory(&self, region: Region) -> Vec<u8> {
+        memory_dump::dump(self, region)
+    }
+    
+    /// Load a dump taken with `dump_memory` back into the same region.
+    /// Fails if the bank layout doesn't match this Game Boy; ROM and I/O
+    /// can be dumped but not restored.
+    pub fn restore_memory(&mut self, region: Region, data: &[u8]) -> Result<(), String> {
+        memory_dump::restore(self, region, data)
+    }
+    
+    /// Read a named value from the game map
+    pub fn value<T: TryFrom<i64>>(&self, name: &str) -> Result<T, String> {
+        let symbol = self.game_map.get(name)
+            .ok_or_else(|| format!("No such value in game map: {}", name))?;
+        let value = symbol.read(self);
+        T::try_from(value).map_err(|_| format!("{} = {} doesn't fit the requested type", name, value))
+    }
 }
 
-/// Serializable save state
+/// Serialize a save state. Every part is plain data with string map
+/// keys, which JSON can always represent.
+fn encode_state(state: &SaveState) -> Vec<u8> {
+    serde_json::to_vec(state).expect("save states always serialize to JSON")
+}
+
+/// Serializable save state (see `GameBoy::save_state`)
 #[derive(serde::Serialize, serde::Deserialize)]
-struct SaveState {
+pub struct SaveState {
     cpu: cpu::CpuState,
     mmu: mmu::MmuState,
     ppu: ppu::PpuState,
@@ -314,13 +1574,249 @@ struct SaveState {
     model: GbModel,
     cycles_this_frame: u32,
     total_cycles: u64,
+    #[serde(default)]
+    elapsed_cycles: Option<u64>,
     frame_count: u64,
+    #[serde(default)]
+    instance_id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    config: Option<GbConfig>,
+    /// Cartridge state of older saves (now part of `MmuState`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cartridge: Option<cartridge::CartridgeState>,
+    #[serde(default)]
+    serial: Option<serial::SerialState>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    peripherals: BTreeMap<String, Vec<u8>>,
+}
+
+impl SaveState {
+    /// Convert a state saved by older versions (before LCD, serial and CGB
+    /// palette registers moved out of the MMU's I/O array) to the current
+    /// layout, as returned by `GameBoy::save_state`.
+    ///
+    /// States are still JSON, so there is no binary encoding to convert
+    /// to: the input and output are both JSON and only the sections
+    /// differ. Missing sections are rebuilt from the legacy I/O bytes where
+    /// possible. Old states carry no cartridge section, so loading the
+    /// result keeps the running cartridge's banks and RAM.
+    pub fn from_legacy_json(data: &[u8]) -> Result<Vec<u8>, String> {
+        let mut value: serde_json::Value = serde_json::from_slice(data)
+            .map_err(|e| format!("Failed to parse save state: {}", e))?;
+        
+        // CGB palette RAM used to be kept by the PPU
+        let ppu_palette = |key: &str| -> Option<Vec<u8>> {
+            let data: Vec<u8> = serde_json::from_value(value.get("ppu")?.get(key)?.clone()).ok()?;
+            (data.len() == 64).then_some(data)
+        };
+        let legacy_palettes = (ppu_palette("bg_palette_data"), ppu_palette("obj_palette_data"));
+        
+        let mmu = value.get_mut("mmu")
+            .and_then(serde_json::Value::as_object_mut)
+            .ok_or("Save state has no MMU section")?;
+        let io: Vec<u8> = mmu.get("io")
+            .and_then(|io| serde_json::from_value(io.clone()).ok())
+            .ok_or("Save state has no I/O registers")?;
+        let reg = |addr: usize| io.get(addr).copied().unwrap_or(0xFF);
+        
+        if !mmu.contains_key("lcd") {
+            let lcd = ppu::LcdRegisters {
+                lcdc: reg(0x40),
+                stat: reg(0x41) & 0x7F,
+                scy: reg(0x42),
+                scx: reg(0x43),
+                ly: reg(0x44),
+                lyc: reg(0x45),
+                bgp: reg(0x47),
+                obp0: reg(0x48),
+                obp1: reg(0x49),
+                wy: reg(0x4A),
+                wx: reg(0x4B),
+            };
+            mmu.insert("lcd".to_string(), serde_json::to_value(lcd).map_err(|e| e.to_string())?);
+        }
+        
+        if !mmu.contains_key("palettes") {
+            // Bytes written through BCPD/OCPD only reached the I/O array,
+            // so just the indices and the PPU's copy can be recovered
+            let mut palettes = ppu::CgbPalettes::new();
+            palettes.bg_index = reg(0x68) & 0xBF;
+            palettes.obj_index = reg(0x6A) & 0xBF;
+            let rows = |data: &[u8]| std::array::from_fn(|i| std::array::from_fn(|j| data[i * 8 + j]));
+            if let Some(data) = &legacy_palettes.0 {
+                palettes.bg_data = rows(data);
+            }
+            if let Some(data) = &legacy_palettes.1 {
+                palettes.obj_data = rows(data);
+            }
+            mmu.insert("palettes".to_string(), serde_json::to_value(palettes).map_err(|e| e.to_string())?);
+        }
+        
+        if value.get("serial").map_or(true, serde_json::Value::is_null) {
+            // A transfer in progress restarts from its first bit
+            let control = reg(0x02) & 0x83;
+            let serial = serial::SerialState {
+                data: reg(0x01),
+                control,
+                bits_remaining: if control & 0x80 != 0 { 8 } else { 0 },
+            };
+            value["serial"] = serde_json::to_value(serial).map_err(|e| e.to_string())?;
+        }
+        
+        let state: SaveState = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to convert save state: {}", e))?;
+        serde_json::to_vec(&state).map_err(|e| e.to_string())
+    }
+}
+
+/// An instruction run by the CPU (see `GameBoy::step_iter`)
+#[derive(Debug, Clone)]
+pub struct ExecutedInstruction {
+    /// Address of the opcode
+    pub pc: u16,
+    
+    /// Bank mapped at `pc` when the instruction started
+    pub bank: usize,
+    
+    /// First opcode byte (0xCB for CB-prefixed instructions)
+    pub opcode: u8,
+    
+    /// Cycles the instruction took
+    pub cycles: u32,
+    
+    /// Registers after the instruction
+    pub regs_after: cpu::Registers,
+}
+
+/// Iterator returned by `GameBoy::step_iter`
+pub struct StepIter<'a> {
+    gb: &'a mut GameBoy,
+}
+
+impl Iterator for StepIter<'_> {
+    type Item = ExecutedInstruction;
+    
+    fn next(&mut self) -> Option<ExecutedInstruction> {
+        let mut idle = 0;
+        while idle < CYCLES_PER_FRAME {
+            let bank = self.gb.bank_at(self.gb.cpu.regs.pc);
+            let cycles = self.gb.step();
+            
+            if let Some((pc, opcode)) = self.gb.cpu.last_executed() {
+                return Some(ExecutedInstruction {
+                    pc,
+                    bank,
+                    opcode,
+                    cycles,
+                    regs_after: self.gb.cpu.regs.clone(),
+                });
+            }
+            idle += cycles;
+        }
+        None
+    }
+}
+
+/// Output of `GameBoy::run_frame` and `GameBoy::frame_advance`
+#[derive(Debug)]
+pub struct FrameOutput<'a> {
+    /// Framebuffer (RGBA8888, 160x144)
+    pub video: &'a [u8],
+    
+    /// Audio samples produced during the frame (stereo interleaved)
+    pub audio: &'a [f32],
+    
+    /// Events raised during the frame, oldest first
+    pub events: Vec<EmulatorEvent>,
+    
+    /// `frame_count` after the frame
+    pub frame: u64,
+}
+
+/// Something that happened during a frame that a frontend may react to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmulatorEvent {
+    /// The LCD was switched off or on (see `GameBoy::take_lcd_event`)
+    Lcd(LcdEvent),
+    
+    /// Battery RAM to write to disk, in `save_sram` format (see
+    /// `GbConfig::autosave_frames`)
+    Autosave(Vec<u8>),
+    
+    /// A+B+Select+Start was pressed (see `GbConfig::soft_reset`)
+    SoftReset,
+}
+
+/// CPU and LCD activity counters (see `GameBoy::power_stats`).
+///
+/// Times are in single-speed cycles (4194304 per second) whatever the CPU
+/// speed, so they add up to elapsed time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PowerStats {
+    /// Time spent executing instructions
+    pub executing_cycles: u64,
+    
+    /// Time spent in HALT waiting for an interrupt
+    pub halted_cycles: u64,
+    
+    /// Time spent in STOP
+    pub stopped_cycles: u64,
+    
+    /// Time the LCD was switched on
+    pub lcd_on_cycles: u64,
+    
+    /// Time spent in CGB double speed
+    pub double_speed_cycles: u64,
+    
+    /// Number of CGB speed switches
+    pub speed_switches: u32,
+}
+
+impl PowerStats {
+    /// Total time counted
+    pub fn total_cycles(&self) -> u64 {
+        self.executing_cycles + self.halted_cycles + self.stopped_cycles
+    }
+    
+    /// Fraction of the time the CPU was executing (0.0-1.0)
+    pub fn cpu_active_ratio(&self) -> f64 {
+        self.ratio(self.executing_cycles)
+    }
+    
+    /// Fraction of the time the LCD was on (0.0-1.0)
+    pub fn lcd_on_ratio(&self) -> f64 {
+        self.ratio(self.lcd_on_cycles)
+    }
+    
+    fn ratio(&self, cycles: u64) -> f64 {
+        match self.total_cycles() {
+            0 => 0.0,
+            total => cycles as f64 / total as f64,
+        }
+    }
 }
 
 // Re-export public types
-pub use joypad::Button;
-pub use ppu::{SCREEN_WIDTH, SCREEN_HEIGHT};
-pub use apu::SAMPLE_RATE;
+pub use joypad::{Button, InputLatency, InputState, JoypadIrq};
+pub use ppu::{CgbPalettes, LcdEvent, TileAttributes, TileMapArea, TileMapEntry, OVERLAY_SIZE, SCREEN_WIDTH, SCREEN_HEIGHT};
+pub use session::Session;
+pub use env::{Env, EnvConfig, ObservationMode};
+pub use game_map::{GameMap, Region};
+pub use cheats::{CheatSearch, Comparison};
+#[cfg(feature = "instrumentation")]
+pub use mmu::{BusAccess, FrameActivity, Telemetry};
+#[cfg(feature = "instrumentation")]
+pub use coverage::Coverage;
+pub use mmu::StateError;
+pub use peripheral::Peripheral;
+pub use accuracy::{AccuracyReport, Capabilities, Conformance, PpuRenderer};
+pub use rom_info::{preview_frame, RomInfo};
+pub use cartridge::Rtc;
+pub use movie::{Movie, MovieInput};
+pub use config::{AccuracyProfile, GbConfig, RamInit, SoftReset};
+pub use apu::{AudioOutputMode, AudioStats, StemRecorder, SAMPLE_RATE, RAW_SAMPLE_RATE};
+#[cfg(not(target_arch = "wasm32"))]
+pub use pipeline::Pipeline;
 
 #[cfg(feature = "wasm")]
 pub use wasm::*;
\ No newline at end of file
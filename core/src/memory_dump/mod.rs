@@ -0,0 +1,202 @@
+//! # Memory Dumps
+//!
+//! Whole-region snapshots of live memory, so graphics and level editors
+//! can save VRAM, WRAM or OAM to a file, edit it and load it back.
+//!
+//! A dump is a 14-byte header followed by every bank of the region back
+//! to back (all little endian):
+//!
+//! | Offset | Size | Contents                              |
+//! |--------|------|---------------------------------------|
+//! | 0      | 4    | Magic `GBMD`                          |
+//! | 4      | 1    | Format version (1)                    |
+//! | 5      | 1    | Region code (see `DumpHeader`)        |
+//! | 6      | 2    | Number of banks                       |
+//! | 8      | 2    | Bank selected when the dump was taken |
+//! | 10     | 4    | Bytes per bank                        |
+//!
+//! Restoring only replaces memory contents; bank selection registers are
+//! left alone, so the selected bank in the header is informational.
+
+use crate::cartridge::RamMapping;
+use crate::game_map::Region;
+use crate::GameBoy;
+
+/// Identifies a memory dump
+const MAGIC: &[u8; 4] = b"GBMD";
+
+/// Current dump format version
+const VERSION: u8 = 1;
+
+/// Size of the header in bytes
+pub const HEADER_SIZE: usize = 14;
+
+/// Bank layout of a memory dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DumpHeader {
+    pub region: Region,
+    
+    /// Number of banks in the dump
+    pub banks: u16,
+    
+    /// Bank mapped into the CPU address space when the dump was taken
+    pub selected_bank: u16,
+    
+    /// Bytes per bank
+    pub bank_size: u32,
+}
+
+impl DumpHeader {
+    /// Layout of a region on a running Game Boy
+    pub fn of(gb: &GameBoy, region: Region) -> Self {
+        let mmu = &gb.mmu;
+        let map = mmu.memory_map();
+        let cartridge = mmu.cartridge();
+        
+        let (bank_size, total, selected) = match region {
+            Region::Rom => (0x4000, cartridge.rom().len(), map.rom_bank),
+            Region::Vram => (0x2000, mmu.vram().len(), map.vram_bank as usize),
+            Region::Sram => {
+                // MBC2's built-in RAM is a single 512-byte bank
+                let ram = cartridge.ram().len();
+                let selected = match map.external_ram {
+                    RamMapping::Bank(bank) => bank,
+                    _ => 0,
+                };
+                (ram.min(0x2000), ram, selected)
+            }
+            Region::Wram => (0x1000, mmu.wram().len(), map.wram_bank.max(1) as usize),
+            Region::Oam => (mmu.oam().len(), mmu.oam().len(), 0),
+            Region::Io => (0x80, 0x80, 0),
+            Region::Hram => (mmu.hram().len(), mmu.hram().len(), 0),
+        };
+        
+        Self {
+            region,
+            banks: if bank_size == 0 { 0 } else { total.div_ceil(bank_size) as u16 },
+            selected_bank: selected as u16,
+            bank_size: bank_size as u32,
+        }
+    }
+    
+    /// Parse the header at the start of a dump
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < HEADER_SIZE || &data[0..4] != MAGIC {
+            return Err("Not a memory dump".to_string());
+        }
+        if data[4] != VERSION {
+            return Err(format!("Unsupported memory dump version: {}", data[4]));
+        }
+        
+        Ok(Self {
+            region: region_from_code(data[5])?,
+            banks: u16::from_le_bytes([data[6], data[7]]),
+            selected_bank: u16::from_le_bytes([data[8], data[9]]),
+            bank_size: u32::from_le_bytes([data[10], data[11], data[12], data[13]]),
+        })
+    }
+    
+    /// Encode the header
+    pub fn to_bytes(&self) -> [u8; HEADER_SIZE] {
+        let mut bytes = [0; HEADER_SIZE];
+        bytes[0..4].copy_from_slice(MAGIC);
+        bytes[4] = VERSION;
+        bytes[5] = region_code(self.region);
+        bytes[6..8].copy_from_slice(&self.banks.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.selected_bank.to_le_bytes());
+        bytes[10..14].copy_from_slice(&self.bank_size.to_le_bytes());
+        bytes
+    }
+    
+    /// Size of the memory following the header
+    pub fn data_size(&self) -> usize {
+        self.banks as usize * self.bank_size as usize
+    }
+}
+
+fn region_code(region: Region) -> u8 {
+    match region {
+        Region::Rom => 0,
+        Region::Vram => 1,
+        Region::Sram => 2,
+        Region::Wram => 3,
+        Region::Oam => 4,
+        Region::Io => 5,
+        Region::Hram => 6,
+    }
+}
+
+fn region_from_code(code: u8) -> Result<Region, String> {
+    match code {
+        0 => Ok(Region::Rom),
+        1 => Ok(Region::Vram),
+        2 => Ok(Region::Sram),
+        3 => Ok(Region::Wram),
+        4 => Ok(Region::Oam),
+        5 => Ok(Region::Io),
+        6 => Ok(Region::Hram),
+        _ => Err(format!("Unknown region code: {}", code)),
+    }
+}
+
+/// Dump every bank of a region, header first
+pub fn dump(gb: &GameBoy, region: Region) -> Vec<u8> {
+    let header = DumpHeader::of(gb, region);
+    let mmu = &gb.mmu;
+    
+    let mut data = header.to_bytes().to_vec();
+    match region {
+        Region::Rom => data.extend_from_slice(mmu.cartridge().rom()),
+        Region::Vram => data.extend_from_slice(mmu.vram()),
+        Region::Sram => data.extend_from_slice(mmu.cartridge().ram()),
+        Region::Wram => data.extend_from_slice(mmu.wram()),
+        Region::Oam => data.extend_from_slice(mmu.oam()),
+        Region::Io => data.extend((0xFF00..=0xFF7F).map(|addr| mmu.peek_byte(addr))),
+        Region::Hram => data.extend_from_slice(mmu.hram()),
+    }
+    
+    // Pad a ROM that isn't a whole number of banks
+    data.resize(HEADER_SIZE + header.data_size(), 0xFF);
+    data
+}
+
+/// Replace the contents of a region with a dump of the same layout
+pub fn restore(gb: &mut GameBoy, region: Region, data: &[u8]) -> Result<(), String> {
+    if matches!(region, Region::Rom | Region::Io) {
+        return Err(format!("{:?} can't be restored", region));
+    }
+    
+    let header = DumpHeader::parse(data)?;
+    if header.region != region {
+        return Err(format!("Dump is of {:?}, not {:?}", header.region, region));
+    }
+    
+    let expected = DumpHeader::of(gb, region);
+    if (header.banks, header.bank_size) != (expected.banks, expected.bank_size) {
+        return Err(format!(
+            "Dump has {} banks of {} bytes, but {:?} has {} banks of {} bytes",
+            header.banks, header.bank_size, region, expected.banks, expected.bank_size,
+        ));
+    }
+    
+    let contents = &data[HEADER_SIZE..];
+    if contents.len() != header.data_size() {
+        return Err(format!("Expected {} bytes after the header, got {}", header.data_size(), contents.len()));
+    }
+    
+    let mmu = &mut gb.mmu;
+    match region {
+        Region::Rom | Region::Io => unreachable!(),
+        Region::Vram => {
+            for (bank, bytes) in contents.chunks(header.bank_size as usize).enumerate() {
+                mmu.write_vram_bank(bank, 0, bytes)?;
+            }
+        }
+        Region::Sram => mmu.cartridge_mut().ram_mut().copy_from_slice(contents),
+        Region::Wram => mmu.wram_mut().copy_from_slice(contents),
+        Region::Oam => mmu.oam_mut().copy_from_slice(contents),
+        Region::Hram => mmu.hram_mut().copy_from_slice(contents),
+    }
+    
+    Ok(())
+}
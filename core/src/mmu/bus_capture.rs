@@ -0,0 +1,79 @@
+//! Bus activity capture
+//!
+//! Records every CPU and DMA access to the bus for offline analysis. PPU
+//! fetches aren't recorded since they don't go over the CPU bus.
+
+/// Accesses kept per capture before recording stops
+const MAX_ACCESSES: usize = 1 << 20;
+
+/// One bus access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusAccess {
+    /// Cycles since the capture started, at the start of the instruction
+    /// (or DMA step) that made the access
+    pub cycle: u32,
+    
+    /// Address
+    pub addr: u16,
+    
+    /// Value read or written
+    pub value: u8,
+    
+    /// Write rather than read
+    pub write: bool,
+}
+
+impl BusAccess {
+    /// Size of one access in `to_bytes` output
+    pub const ENCODED_SIZE: usize = 8;
+    
+    /// Pack a capture into 8 bytes per access: cycle (u32 LE), address
+    /// (u16 LE), value, then 1 for writes or 0 for reads
+    pub fn to_bytes(accesses: &[BusAccess]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(accesses.len() * Self::ENCODED_SIZE);
+        for access in accesses {
+            bytes.extend_from_slice(&access.cycle.to_le_bytes());
+            bytes.extend_from_slice(&access.addr.to_le_bytes());
+            bytes.push(access.value);
+            bytes.push(access.write as u8);
+        }
+        bytes
+    }
+}
+
+/// A capture in progress
+pub(super) struct BusCapture {
+    /// Total cycle count when the capture started
+    start: u64,
+    
+    /// Cycle stamped on new accesses
+    cycle: u32,
+    
+    accesses: Vec<BusAccess>,
+}
+
+impl BusCapture {
+    pub(super) fn new(now: u64) -> Self {
+        Self {
+            start: now,
+            cycle: 0,
+            accesses: Vec::new(),
+        }
+    }
+    
+    #[cold]
+    pub(super) fn set_cycle(&mut self, now: u64) {
+        self.cycle = now.saturating_sub(self.start).min(u32::MAX as u64) as u32;
+    }
+    
+    #[cold]
+    pub(super) fn record(&mut self, addr: u16, value: u8, write: bool) {
+        if self.accesses.len() < MAX_ACCESSES {
+            self.accesses.push(BusAccess { cycle: self.cycle, addr, value, write });
+        }
+    }
+    
+    pub(super) fn into_accesses(self) -> Vec<BusAccess> {
+        self.accesses
+    }
+}
@@ -0,0 +1,136 @@
+//! I/O register routing
+//!
+//! Every memory-mapped peripheral registers the address ranges it decodes.
+//! The bus builds a lookup table from those ranges and forwards CPU reads
+//! and writes straight to the owning component, so no register is mirrored
+//! in a separate array that can go stale.
+
+use std::ops::RangeInclusive;
+
+use crate::apu::Apu;
+use crate::joypad::Joypad;
+use crate::ppu::{CgbPalettes, LcdRegisters};
+use crate::serial::Serial;
+use crate::timer::Timer;
+use crate::GbModel;
+
+use super::IO_SIZE;
+
+/// A component that owns a set of I/O registers
+pub trait IoDevice {
+    /// I/O addresses (0xFF00-0xFF7F) decoded by this device
+    const IO_RANGES: &'static [RangeInclusive<u16>];
+    
+    /// Read one of this device's registers
+    fn read_io(&self, addr: u16) -> u8;
+    
+    /// Write one of this device's registers
+    fn write_io(&mut self, addr: u16, value: u8);
+}
+
+/// Owner of a single I/O address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IoPort {
+    /// Not decoded by any component (handled by the MMU itself)
+    Unmapped,
+    Joypad,
+    Serial,
+    Timer,
+    Apu,
+    Lcd,
+    Palettes,
+}
+
+/// I/O bus connecting the CPU to the memory-mapped peripherals
+pub struct IoBus {
+    /// Routing table indexed by `addr & 0x7F`
+    ports: [IoPort; IO_SIZE],
+    
+    pub joypad: Joypad,
+    pub serial: Serial,
+    pub timer: Timer,
+    pub apu: Apu,
+    pub lcd: LcdRegisters,
+    pub palettes: CgbPalettes,
+}
+
+impl IoBus {
+    /// Create the bus and register every component's address ranges
+    pub fn new(model: GbModel) -> Self {
+        let mut bus = Self {
+            ports: [IoPort::Unmapped; IO_SIZE],
+            joypad: Joypad::new(),
+            serial: Serial::new(),
+            timer: Timer::new(),
+            apu: Apu::new(model),
+            lcd: LcdRegisters::new(),
+            palettes: CgbPalettes::new(),
+        };
+        
+        bus.register::<Joypad>(IoPort::Joypad);
+        bus.register::<Serial>(IoPort::Serial);
+        bus.register::<Timer>(IoPort::Timer);
+        bus.register::<Apu>(IoPort::Apu);
+        bus.register::<LcdRegisters>(IoPort::Lcd);
+        bus.register::<CgbPalettes>(IoPort::Palettes);
+        
+        bus.set_model(model);
+        
+        bus
+    }
+    
+    /// Select model-specific behavior on the components that have any
+    pub fn set_model(&mut self, model: GbModel) {
+        self.apu.set_model(model);
+        self.serial.set_cgb_mode(model.is_cgb());
+        self.palettes.set_cgb_mode(model.is_cgb());
+    }
+    
+    /// Map a device's address ranges to its port
+    fn register<D: IoDevice>(&mut self, port: IoPort) {
+        for range in D::IO_RANGES {
+            for addr in range.clone() {
+                let slot = &mut self.ports[(addr & 0x7F) as usize];
+                debug_assert_eq!(*slot, IoPort::Unmapped, "I/O address {:04X} registered twice", addr);
+                *slot = port;
+            }
+        }
+    }
+    
+    /// Reset all components on the bus
+    pub fn reset(&mut self) {
+        self.joypad.reset();
+        self.serial.reset();
+        self.timer.reset();
+        self.apu.reset();
+        self.lcd.reset();
+        self.palettes.reset();
+    }
+    
+    /// Read a register; returns `None` if no component decodes `addr`
+    pub fn read(&self, addr: u16) -> Option<u8> {
+        match self.ports[(addr & 0x7F) as usize] {
+            IoPort::Unmapped => None,
+            IoPort::Joypad => Some(self.joypad.read_io(addr)),
+            IoPort::Serial => Some(self.serial.read_io(addr)),
+            IoPort::Timer => Some(self.timer.read_io(addr)),
+            IoPort::Apu => Some(self.apu.read_io(addr)),
+            IoPort::Lcd => Some(self.lcd.read_io(addr)),
+            IoPort::Palettes => Some(self.palettes.read_io(addr)),
+        }
+    }
+    
+    /// Write a register; returns `false` if no component decodes `addr`
+    pub fn write(&mut self, addr: u16, value: u8) -> bool {
+        match self.ports[(addr & 0x7F) as usize] {
+            IoPort::Unmapped => return false,
+            IoPort::Joypad => self.joypad.write_io(addr, value),
+            IoPort::Serial => self.serial.write_io(addr, value),
+            IoPort::Timer => self.timer.write_io(addr, value),
+            IoPort::Apu => self.apu.write_io(addr, value),
+            IoPort::Lcd => self.lcd.write_io(addr, value),
+            IoPort::Palettes => self.palettes.write_io(addr, value),
+        }
+        true
+    }
+}
@@ -17,10 +17,34 @@
 //! - 0xFF80-0xFFFE: High RAM (HRAM)
 //! - 0xFFFF: Interrupt Enable Register
 
-use crate::cartridge::Cartridge;
+mod io_bus;
+#[cfg(feature = "instrumentation")]
+mod bus_capture;
+#[cfg(feature = "instrumentation")]
+mod telemetry;
+
+pub use io_bus::{IoBus, IoDevice};
+#[cfg(feature = "instrumentation")]
+pub use bus_capture::BusAccess;
+#[cfg(feature = "instrumentation")]
+pub use telemetry::{FrameActivity, Telemetry};
+
+#[cfg(feature = "instrumentation")]
+use bus_capture::BusCapture;
+
+use crate::apu::Apu;
+use crate::config::{AccuracyProfile, RamInit};
+use crate::cartridge::{Cartridge, CartridgeState, MbcType, RamMapping};
 use crate::joypad::Joypad;
+use crate::ppu::{CgbPalettes, LcdRegisters};
+use crate::serial::Serial;
+use crate::timer::Timer;
 use crate::GbModel;
 use serde::{Serialize, Deserialize};
+use std::cell::Cell;
+use std::fmt;
+#[cfg(feature = "instrumentation")]
+use std::cell::RefCell;
 
 /// VRAM size per bank (8KB)
 const VRAM_SIZE: usize = 0x2000;
@@ -37,6 +61,53 @@ const HRAM_SIZE: usize = 0x7F;
 /// I/O registers size
 const IO_SIZE: usize = 0x80;
 
+/// Snapshot of what is currently mapped into the address space
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryMapReport {
+    /// Cartridge memory bank controller
+    pub mbc: MbcType,
+    
+    /// ROM bank at 0x0000-0x3FFF
+    pub rom_bank0: usize,
+    
+    /// ROM bank at 0x4000-0x7FFF
+    pub rom_bank: usize,
+    
+    /// External RAM area (0xA000-0xBFFF)
+    pub external_ram: RamMapping,
+    
+    /// VRAM bank at 0x8000-0x9FFF
+    pub vram_bank: u8,
+    
+    /// WRAM bank at 0xD000-0xDFFF
+    pub wram_bank: u8,
+    
+    /// Boot ROM overlays 0x0000-0x00FF (and 0x0200-0x08FF on CGB)
+    pub boot_rom_mapped: bool,
+    
+    /// OAM DMA in progress
+    pub oam_dma_active: bool,
+    
+    /// CGB HDMA in progress
+    pub hdma: Option<HdmaReport>,
+}
+
+/// CGB HDMA transfer in progress
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HdmaReport {
+    /// Next source address
+    pub source: u16,
+    
+    /// Next VRAM destination address
+    pub dest: u16,
+    
+    /// 16-byte blocks left to copy
+    pub blocks_remaining: u16,
+    
+    /// Copying one block per HBlank (otherwise general purpose)
+    pub hblank: bool,
+}
+
 /// MMU state for serialization
 #[derive(Clone, Serialize, Deserialize)]
 pub struct MmuState {
@@ -56,6 +127,69 @@ pub struct MmuState {
     pub hdma_dest: u16,
     pub hdma_length: u8,
     pub hdma_hblank: bool,
+    #[serde(default = "LcdRegisters::new")]
+    pub lcd: LcdRegisters,
+    #[serde(default = "CgbPalettes::new")]
+    pub palettes: CgbPalettes,
+    #[serde(default)]
+    pub ram_init: RamInit,
+    #[serde(default)]
+    pub boot_rom_mapped: bool,
+    
+    /// Model the state was saved on (absent in older states)
+    #[serde(default)]
+    pub model: Option<GbModel>,
+    
+    /// Cartridge banking and RAM (older states keep it in `SaveState`)
+    #[serde(default)]
+    pub cartridge: Option<CartridgeState>,
+    
+    /// T-cycles towards the next OAM DMA M-cycle
+    #[serde(default)]
+    pub dma_cycles: u32,
+}
+
+/// Why a save state can't be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The data isn't a save state
+    Parse(String),
+    
+    /// The state was saved on a different model
+    ModelMismatch { saved: GbModel, current: GbModel },
+    
+    /// A memory area has the wrong size for this model
+    SizeMismatch { area: &'static str, expected: usize, found: usize },
+    
+    /// A compressed area ends partway through a record
+    Truncated { area: &'static str },
+    
+    /// A peripheral rejected its part of the state
+    Peripheral { name: String, message: String },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Parse(message) => write!(f, "Failed to parse save state: {}", message),
+            StateError::ModelMismatch { saved, current } => {
+                write!(f, "State was saved on {:?}, but this is {:?}", saved, current)
+            }
+            StateError::SizeMismatch { area, expected, found } => {
+                write!(f, "{} size mismatch: expected {} bytes, found {}", area, expected, found)
+            }
+            StateError::Truncated { area } => write!(f, "Truncated {} in save state", area),
+            StateError::Peripheral { name, message } => write!(f, "{}: {}", name, message),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+impl From<StateError> for String {
+    fn from(error: StateError) -> Self {
+        error.to_string()
+    }
 }
 
 /// Memory Management Unit
@@ -75,9 +209,12 @@ pub struct Mmu {
     /// High RAM
     hram: [u8; HRAM_SIZE],
     
-    /// I/O Registers
+    /// I/O Registers not owned by a component on the I/O bus
     io: [u8; IO_SIZE],
     
+    /// Memory-mapped peripherals (joypad, serial, timer, APU, LCD registers)
+    io_bus: IoBus,
+    
     /// Interrupt Enable register (0xFFFF)
     ie: u8,
     
@@ -99,6 +236,9 @@ pub struct Mmu {
     /// DMA source address
     dma_source: u16,
     
+    /// T-cycles towards the next DMA M-cycle, left over from `advance_dma`
+    dma_cycles: u32,
+    
     /// HDMA is active (CGB only)
     hdma_active: bool,
     
@@ -108,23 +248,41 @@ pub struct Mmu {
     /// HDMA destination address
     hdma_dest: u16,
     
-    /// HDMA remaining length
+    /// HDMA remaining length in blocks minus one, as read from FF55
+    /// (0xFF once a transfer has completed)
     hdma_length: u8,
     
     /// HDMA mode (true = HBlank, false = General)
     hdma_hblank: bool,
     
-    /// Button state (raw state of all 8 buttons, bit=0 means pressed)
-    button_state: u8,
+    /// Power-on contents of WRAM and HRAM
+    ram_init: RamInit,
+    
+    /// Optional hardware quirks
+    accuracy: AccuracyProfile,
+    
+    /// Last value read or written on the bus
+    open_bus: Cell<u8>,
+    
+    /// Boot ROM run at power-on, if one was supplied
+    boot_rom: Option<Vec<u8>>,
+    
+    /// Boot ROM overlays the cartridge (until a write to 0xFF50)
+    boot_rom_mapped: bool,
     
-    /// Pending audio register writes (addr, value)
-    audio_writes: Vec<(u16, u8)>,
+    /// Bus activity being recorded, if a capture is running
+    #[cfg(feature = "instrumentation")]
+    bus_capture: Option<RefCell<BusCapture>>,
+    
+    /// Write counts per frame, if telemetry is enabled
+    #[cfg(feature = "instrumentation")]
+    telemetry: Option<Telemetry>,
 }
 
 impl Mmu {
     /// Create a new MMU
     pub fn new(cartridge: Cartridge, model: GbModel) -> Self {
-        let is_cgb = matches!(model, GbModel::Cgb | GbModel::CgbDmg);
+        let is_cgb = model.is_cgb();
         
         let vram_banks = if is_cgb { 2 } else { 1 };
         let wram_banks = if is_cgb { 8 } else { 2 };
@@ -136,6 +294,7 @@ impl Mmu {
             oam: [0; OAM_SIZE],
             hram: [0; HRAM_SIZE],
             io: [0; IO_SIZE],
+            io_bus: IoBus::new(model),
             ie: 0,
             model,
             vram_bank: 0,
@@ -143,13 +302,21 @@ impl Mmu {
             dma_active: false,
             dma_byte: 0,
             dma_source: 0,
+            dma_cycles: 0,
             hdma_active: false,
             hdma_source: 0,
             hdma_dest: 0,
-            hdma_length: 0,
+            hdma_length: 0xFF,
             hdma_hblank: false,
-            button_state: 0xFF,
-            audio_writes: Vec::with_capacity(16),
+            ram_init: RamInit::Zeroed,
+            accuracy: AccuracyProfile::default(),
+            open_bus: Cell::new(0xFF),
+            boot_rom: None,
+            boot_rom_mapped: false,
+            #[cfg(feature = "instrumentation")]
+            bus_capture: None,
+            #[cfg(feature = "instrumentation")]
+            telemetry: None,
         };
         
         // Initialize I/O registers to post-boot values
@@ -160,86 +327,198 @@ impl Mmu {
     
     /// Initialize I/O registers to post-boot ROM values
     fn init_io_registers(&mut self) {
-        // These are the values after the boot ROM completes
-        self.io[0x00] = 0xCF; // JOYP
-        self.io[0x01] = 0x00; // SB
-        self.io[0x02] = 0x7E; // SC
-        self.io[0x04] = 0xAB; // DIV
-        self.io[0x05] = 0x00; // TIMA
-        self.io[0x06] = 0x00; // TMA
-        self.io[0x07] = 0xF8; // TAC
+        // These are the values after the boot ROM completes.
+        // Joypad, serial, timer, audio and LCD registers are initialized
+        // by their owning components on the I/O bus.
         self.io[0x0F] = 0xE1; // IF
         
-        // Audio registers
-        self.io[0x10] = 0x80; // NR10
-        self.io[0x11] = 0xBF; // NR11
-        self.io[0x12] = 0xF3; // NR12
-        self.io[0x13] = 0xFF; // NR13
-        self.io[0x14] = 0xBF; // NR14
-        self.io[0x16] = 0x3F; // NR21
-        self.io[0x17] = 0x00; // NR22
-        self.io[0x18] = 0xFF; // NR23
-        self.io[0x19] = 0xBF; // NR24
-        self.io[0x1A] = 0x7F; // NR30
-        self.io[0x1B] = 0xFF; // NR31
-        self.io[0x1C] = 0x9F; // NR32
-        self.io[0x1D] = 0xFF; // NR33
-        self.io[0x1E] = 0xBF; // NR34
-        self.io[0x20] = 0xFF; // NR41
-        self.io[0x21] = 0x00; // NR42
-        self.io[0x22] = 0x00; // NR43
-        self.io[0x23] = 0xBF; // NR44
-        self.io[0x24] = 0x77; // NR50
-        self.io[0x25] = 0xF3; // NR51
-        self.io[0x26] = 0xF1; // NR52
-        
-        // LCD registers
-        self.io[0x40] = 0x91; // LCDC
-        self.io[0x41] = 0x85; // STAT
-        self.io[0x42] = 0x00; // SCY
-        self.io[0x43] = 0x00; // SCX
-        self.io[0x44] = 0x00; // LY
-        self.io[0x45] = 0x00; // LYC
-        self.io[0x47] = 0xFC; // BGP
-        self.io[0x48] = 0xFF; // OBP0
-        self.io[0x49] = 0xFF; // OBP1
-        self.io[0x4A] = 0x00; // WY
-        self.io[0x4B] = 0x00; // WX
-        
         // CGB-specific
-        if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-            self.io[0x4D] = 0xFF; // KEY1 (speed switch)
+        if self.model.is_cgb() {
+            self.io[0x4D] = 0x00; // KEY1 (normal speed, no switch armed)
             self.io[0x4F] = 0xFF; // VBK (VRAM bank)
             self.io[0x70] = 0xFF; // SVBK (WRAM bank)
         }
     }
     
-    /// Reset MMU state
+    /// Choose the power-on contents of WRAM and HRAM, used from the next
+    /// reset (or `init_ram`)
+    pub fn set_ram_init(&mut self, ram_init: RamInit) {
+        self.ram_init = ram_init;
+    }
+    
+    /// Power-on contents of WRAM and HRAM
+    pub fn ram_init(&self) -> RamInit {
+        self.ram_init
+    }
+    
+    /// Choose which optional hardware quirks are emulated
+    pub fn set_accuracy(&mut self, accuracy: AccuracyProfile) {
+        self.accuracy = accuracy;
+    }
+    
+    /// Install (or remove) the boot ROM: 256 bytes for DMG, 2304 for CGB.
+    /// It is mapped over the cartridge from the next reset.
+    pub fn set_boot_rom(&mut self, boot_rom: Option<Vec<u8>>) -> Result<(), String> {
+        if let Some(data) = &boot_rom {
+            if data.len() != 0x100 && data.len() != 0x900 {
+                return Err(format!("Boot ROM must be 256 or 2304 bytes, got {}", data.len()));
+            }
+        }
+        
+        self.boot_rom = boot_rom;
+        Ok(())
+    }
+    
+    /// The installed boot ROM
+    pub fn boot_rom(&self) -> Option<&[u8]> {
+        self.boot_rom.as_deref()
+    }
+    
+    /// The boot ROM currently overlays the cartridge
+    pub fn boot_rom_mapped(&self) -> bool {
+        self.boot_rom_mapped
+    }
+    
+    /// Fill WRAM and HRAM with their power-on contents
+    pub fn init_ram(&mut self) {
+        self.ram_init.fill(&mut self.wram, 0);
+        self.ram_init.fill(&mut self.hram, 1);
+    }
+    
+    /// Reset MMU state to power-on, including WRAM and HRAM contents
     pub fn reset(&mut self) {
+        self.init_ram();
+        self.warm_reset();
+    }
+    
+    /// Reset everything the console's reset line reaches. WRAM and HRAM
+    /// stay powered, so they keep their contents.
+    pub fn warm_reset(&mut self) {
         self.vram.fill(0);
-        self.wram.fill(0);
         self.oam.fill(0);
-        self.hram.fill(0);
         self.io.fill(0);
+        self.io_bus.reset();
         self.ie = 0;
         self.vram_bank = 0;
         self.wram_bank = 1;
         self.dma_active = false;
         self.dma_byte = 0;
         self.dma_source = 0;
+        self.dma_cycles = 0;
         self.hdma_active = false;
         self.hdma_source = 0;
         self.hdma_dest = 0;
-        self.hdma_length = 0;
+        self.hdma_length = 0xFF;
         self.hdma_hblank = false;
-        self.button_state = 0xFF;
-        self.audio_writes.clear();
+        self.boot_rom_mapped = self.boot_rom.is_some();
         
         self.init_io_registers();
     }
     
+    /// Insert a different cartridge and reset to the power-on state for
+    /// `model`. Host settings on the bus components (audio output mode,
+    /// serial hooks) are kept.
+    pub fn load_cartridge(&mut self, cartridge: Cartridge, model: GbModel) {
+        self.cartridge = cartridge;
+        self.set_model(model);
+    }
+    
+    /// Switch to `model`'s memory layout and reset to its power-on state,
+    /// keeping the cartridge
+    pub fn set_model(&mut self, model: GbModel) {
+        let vram_banks = if model.is_cgb() { 2 } else { 1 };
+        let wram_banks = if model.is_cgb() { 8 } else { 2 };
+        
+        self.model = model;
+        self.vram = vec![0; VRAM_SIZE * vram_banks];
+        self.wram = vec![0; WRAM_BANK_SIZE * wram_banks];
+        self.io_bus.set_model(model);
+        self.reset();
+    }
+    
     /// Read a byte from memory
     pub fn read_byte(&self, addr: u16) -> u8 {
+        let value = self.read_mapped(addr);
+        self.open_bus.set(value);
+        #[cfg(feature = "instrumentation")]
+        {
+            if let Some(capture) = &self.bus_capture {
+                capture.borrow_mut().record(addr, value, false);
+            }
+            // Only the game's own reads count towards input latency
+            if addr == 0xFF00 {
+                self.io_bus.joypad.cpu_read();
+            }
+        }
+        value
+    }
+    
+    /// Read a byte without touching the bus (no open-bus update and no
+    /// capture), for the PPU's own fetches and for tools
+    pub fn peek_byte(&self, addr: u16) -> u8 {
+        self.read_mapped(addr)
+    }
+    
+    /// Start recording bus accesses; `now` is the current total cycle
+    /// count. Restarts a capture that is already running.
+    #[cfg(feature = "instrumentation")]
+    pub fn start_bus_capture(&mut self, now: u64) {
+        self.bus_capture = Some(RefCell::new(BusCapture::new(now)));
+    }
+    
+    /// Stop recording and return the accesses (empty if no capture was
+    /// running)
+    #[cfg(feature = "instrumentation")]
+    pub fn stop_bus_capture(&mut self) -> Vec<BusAccess> {
+        self.bus_capture.take()
+            .map_or_else(Vec::new, |capture| capture.into_inner().into_accesses())
+    }
+    
+    /// A bus capture is running
+    #[cfg(feature = "instrumentation")]
+    pub fn is_capturing_bus(&self) -> bool {
+        self.bus_capture.is_some()
+    }
+    
+    /// Start counting writes per frame from zero, or stop counting
+    #[cfg(feature = "instrumentation")]
+    pub fn set_telemetry(&mut self, enabled: bool) {
+        self.telemetry = enabled.then(Telemetry::default);
+    }
+    
+    /// Write counts so far, if telemetry is enabled
+    #[cfg(feature = "instrumentation")]
+    pub fn telemetry(&self) -> Option<&Telemetry> {
+        self.telemetry.as_ref()
+    }
+    
+    /// Close the frame in progress in the telemetry
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn end_telemetry_frame(&mut self) {
+        if let Some(telemetry) = &mut self.telemetry {
+            telemetry.end_frame();
+        }
+    }
+    
+    /// Set the total cycle count stamped on the next captured accesses
+    #[cfg(feature = "instrumentation")]
+    pub fn set_bus_capture_cycle(&mut self, now: u64) {
+        if let Some(capture) = &mut self.bus_capture {
+            capture.get_mut().set_cycle(now);
+        }
+    }
+    
+    /// Value read where nothing drives the bus: 0xFF, or the last value
+    /// on the bus with the open-bus quirk enabled
+    fn bus_value(&self) -> u8 {
+        if self.accuracy.open_bus { self.open_bus.get() } else { 0xFF }
+    }
+    
+    fn read_mapped(&self, addr: u16) -> u8 {
+        if let Some(value) = self.read_boot_rom(addr) {
+            return value;
+        }
+        
         match addr {
             // ROM Bank 0
             0x0000..=0x3FFF => self.cartridge.read_rom(addr),
@@ -255,24 +534,17 @@ impl Mmu {
             }
             
             // External RAM
-            0xA000..=0xBFFF => self.cartridge.read_ram(addr),
-            
-            // WRAM Bank 0
-            0xC000..=0xCFFF => {
-                let offset = (addr - 0xC000) as usize;
-                self.wram.get(offset).copied().unwrap_or(0xFF)
-            }
-            
-            // WRAM Bank N
-            0xD000..=0xDFFF => {
-                let offset = (addr - 0xD000) as usize;
-                let bank = self.wram_bank.max(1) as usize;
-                let bank_offset = bank * WRAM_BANK_SIZE;
-                self.wram.get(bank_offset + offset).copied().unwrap_or(0xFF)
+            0xA000..=0xBFFF => {
+                if self.cartridge.ram_mapping() == RamMapping::Disabled {
+                    self.bus_value()
+                } else {
+                    self.cartridge.read_ram(addr)
+                }
             }
             
-            // Echo RAM (mirror of C000-DDFF)
-            0xE000..=0xFDFF => self.read_byte(addr - 0x2000),
+            // WRAM, and Echo RAM (mirror of C000-DDFF, including the
+            // SVBK-selected bank at F000-FDFF)
+            0xC000..=0xFDFF => self.wram.get(self.wram_offset(addr)).copied().unwrap_or(0xFF),
             
             // OAM
             0xFE00..=0xFE9F => {
@@ -285,7 +557,7 @@ impl Mmu {
             }
             
             // Unusable
-            0xFEA0..=0xFEFF => 0xFF,
+            0xFEA0..=0xFEFF => self.read_unusable(addr),
             
             // I/O Registers
             0xFF00..=0xFF7F => self.read_io(addr),
@@ -298,8 +570,61 @@ impl Mmu {
         }
     }
     
+    /// Byte of the boot ROM at `addr`, while it overlays the cartridge.
+    /// The CGB boot ROM leaves a gap at 0x0100-0x01FF for the header.
+    fn read_boot_rom(&self, addr: u16) -> Option<u8> {
+        if !self.boot_rom_mapped || (0x0100..=0x01FF).contains(&addr) {
+            return None;
+        }
+        self.boot_rom.as_ref()?.get(addr as usize).copied()
+    }
+    
+    /// Offset into WRAM for 0xC000-0xFDFF. The echo region decodes the
+    /// same 13 address bits, so E000-EFFF is bank 0 and F000-FDFF is the
+    /// bank selected by SVBK.
+    fn wram_offset(&self, addr: u16) -> usize {
+        let addr = addr as usize & 0x1FFF;
+        if addr < WRAM_BANK_SIZE {
+            addr
+        } else {
+            let bank = self.wram_bank.max(1) as usize;
+            bank * WRAM_BANK_SIZE + (addr - WRAM_BANK_SIZE)
+        }
+    }
+    
+    /// Read from the unusable region 0xFEA0-0xFEFF
+    fn read_unusable(&self, addr: u16) -> u8 {
+        if !self.accuracy.unusable_memory {
+            return self.bus_value();
+        }
+        
+        match self.model {
+            // CGB repeats the high nibble of the address's low byte
+            GbModel::Cgb | GbModel::CgbDmg | GbModel::Agb => {
+                let nibble = (addr as u8) & 0xF0;
+                nibble | (nibble >> 4)
+            }
+            
+            // DMG reads 0x00, or 0xFF while OAM is blocked
+            GbModel::Dmg | GbModel::Pocket | GbModel::Sgb | GbModel::Sgb2 => {
+                let lcd = &self.io_bus.lcd;
+                let oam_blocked = lcd.lcdc & 0x80 != 0 && lcd.stat & 0x03 >= 2;
+                if self.dma_active || oam_blocked { 0xFF } else { 0x00 }
+            }
+        }
+    }
+    
     /// Write a byte to memory
     pub fn write_byte(&mut self, addr: u16, value: u8) {
+        self.open_bus.set(value);
+        #[cfg(feature = "instrumentation")]
+        if let Some(capture) = &mut self.bus_capture {
+            capture.get_mut().record(addr, value, true);
+        }
+        #[cfg(feature = "instrumentation")]
+        let mapping = (self.telemetry.is_some() && addr < 0x8000)
+            .then(|| (self.cartridge.rom_banks(), self.cartridge.ram_mapping()));
+        
         match addr {
             // ROM (writes go to MBC)
             0x0000..=0x7FFF => self.cartridge.write_rom(addr, value),
@@ -316,27 +641,14 @@ impl Mmu {
             // External RAM
             0xA000..=0xBFFF => self.cartridge.write_ram(addr, value),
             
-            // WRAM Bank 0
-            0xC000..=0xCFFF => {
-                let offset = (addr - 0xC000) as usize;
+            // WRAM and Echo RAM
+            0xC000..=0xFDFF => {
+                let offset = self.wram_offset(addr);
                 if let Some(byte) = self.wram.get_mut(offset) {
                     *byte = value;
                 }
             }
             
-            // WRAM Bank N
-            0xD000..=0xDFFF => {
-                let offset = (addr - 0xD000) as usize;
-                let bank = self.wram_bank.max(1) as usize;
-                let bank_offset = bank * WRAM_BANK_SIZE;
-                if let Some(byte) = self.wram.get_mut(bank_offset + offset) {
-                    *byte = value;
-                }
-            }
-            
-            // Echo RAM
-            0xE000..=0xFDFF => self.write_byte(addr - 0x2000, value),
-            
             // OAM
             0xFE00..=0xFE9F => {
                 if !self.dma_active {
@@ -356,97 +668,32 @@ impl Mmu {
             // Interrupt Enable
             0xFFFF => self.ie = value,
         }
+        
+        #[cfg(feature = "instrumentation")]
+        if let Some(telemetry) = &mut self.telemetry {
+            let switched = mapping.is_some_and(|mapping| {
+                mapping != (self.cartridge.rom_banks(), self.cartridge.ram_mapping())
+            });
+            telemetry.record_write(addr, switched);
+        }
     }
     
     /// Read from I/O register
     fn read_io(&self, addr: u16) -> u8 {
-        let reg = (addr & 0x7F) as usize;
+        if let Some(value) = self.io_bus.read(addr) {
+            return value;
+        }
         
         match addr {
-            // Joypad - calculate based on selection and button state
-            0xFF00 => {
-                let select = self.io[0x00];
-                let mut result = select | 0xC0; // Bits 6-7 always 1
-                
-                // Select action buttons (bit 5 = 0)
-                if select & 0x20 == 0 {
-                    result &= 0xF0 | ((self.button_state >> 4) & 0x0F);
-                }
-                
-                // Select d-pad (bit 4 = 0)
-                if select & 0x10 == 0 {
-                    result &= 0xF0 | (self.button_state & 0x0F);
-                }
-                
-                result
-            }
-            
-            // Serial transfer data
-            0xFF01 => self.io[0x01],
-            
-            // Serial transfer control
-            0xFF02 => self.io[0x02] | 0x7E,
-            
-            // DIV (upper bits of internal timer)
-            0xFF04 => self.io[0x04],
-            
-            // TIMA
-            0xFF05 => self.io[0x05],
-            
-            // TMA
-            0xFF06 => self.io[0x06],
-            
-            // TAC
-            0xFF07 => self.io[0x07] | 0xF8,
-            
             // IF (Interrupt Flag)
             0xFF0F => self.io[0x0F] | 0xE0,
             
-            // Audio registers
-            0xFF10..=0xFF26 => self.io[reg],
-            
-            // Wave pattern RAM
-            0xFF30..=0xFF3F => self.io[reg],
-            
-            // LCD Control
-            0xFF40 => self.io[0x40],
-            
-            // STAT
-            0xFF41 => self.io[0x41] | 0x80,
-            
-            // SCY
-            0xFF42 => self.io[0x42],
-            
-            // SCX
-            0xFF43 => self.io[0x43],
-            
-            // LY (current scanline)
-            0xFF44 => self.io[0x44],
-            
-            // LYC (LY compare)
-            0xFF45 => self.io[0x45],
-            
             // DMA
             0xFF46 => self.io[0x46],
             
-            // BGP
-            0xFF47 => self.io[0x47],
-            
-            // OBP0
-            0xFF48 => self.io[0x48],
-            
-            // OBP1
-            0xFF49 => self.io[0x49],
-            
-            // WY
-            0xFF4A => self.io[0x4A],
-            
-            // WX
-            0xFF4B => self.io[0x4B],
-            
             // CGB: KEY1 (speed switch)
             0xFF4D => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
+                if self.model.is_cgb() {
                     self.io[0x4D] | 0x7E
                 } else {
                     0xFF
@@ -455,7 +702,7 @@ impl Mmu {
             
             // CGB: VBK (VRAM bank)
             0xFF4F => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
+                if self.model.is_cgb() {
                     self.vram_bank | 0xFE
                 } else {
                     0xFF
@@ -464,61 +711,28 @@ impl Mmu {
             
             // CGB: HDMA registers
             0xFF51..=0xFF55 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
+                if self.model.is_cgb() {
                     match addr {
+                        // Bit 7 is clear while a transfer is active; a
+                        // stopped HBlank transfer keeps its remaining length
                         0xFF55 => {
                             if self.hdma_active {
                                 self.hdma_length & 0x7F
                             } else {
-                                0xFF
+                                self.hdma_length | 0x80
                             }
                         }
-                        _ => self.io[reg],
+                        // Source and destination are write-only
+                        _ => 0xFF,
                     }
                 } else {
                     0xFF
                 }
             }
             
-            // CGB: Background palette index
-            0xFF68 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x68]
-                } else {
-                    0xFF
-                }
-            }
-            
-            // CGB: Background palette data
-            0xFF69 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x69]
-                } else {
-                    0xFF
-                }
-            }
-            
-            // CGB: Object palette index
-            0xFF6A => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x6A]
-                } else {
-                    0xFF
-                }
-            }
-            
-            // CGB: Object palette data
-            0xFF6B => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x6B]
-                } else {
-                    0xFF
-                }
-            }
-            
             // CGB: SVBK (WRAM bank)
             0xFF70 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
+                if self.model.is_cgb() {
                     self.wram_bank | 0xF8
                 } else {
                     0xFF
@@ -526,181 +740,67 @@ impl Mmu {
             }
             
             // Undefined I/O
-            _ => 0xFF,
+            _ => self.bus_value(),
         }
     }
     
     /// Write to I/O register
     fn write_io(&mut self, addr: u16, value: u8) {
-        let reg = (addr & 0x7F) as usize;
+        if self.io_bus.write(addr, value) {
+            return;
+        }
         
         match addr {
-            // Joypad
-            0xFF00 => {
-                // Only bits 4-5 are writable (select lines)
-                self.io[0x00] = (self.io[0x00] & 0xCF) | (value & 0x30);
-                // Update joypad state based on selection
-            }
-            
-            // Serial
-            0xFF01 => self.io[0x01] = value,
-            0xFF02 => self.io[0x02] = value,
-            
-            // DIV - writing any value resets it to 0
-            0xFF04 => self.io[0x04] = 0,
-            
-            // Timer registers
-            0xFF05 => self.io[0x05] = value, // TIMA
-            0xFF06 => self.io[0x06] = value, // TMA
-            0xFF07 => self.io[0x07] = value & 0x07, // TAC
-            
             // IF
             0xFF0F => self.io[0x0F] = value & 0x1F,
             
-            // Audio registers - store in io AND queue for APU
-            0xFF10..=0xFF26 => {
-                self.io[reg] = value;
-                self.audio_writes.push((addr, value));
-            }
-            
-            // Wave pattern RAM - store in io AND queue for APU
-            0xFF30..=0xFF3F => {
-                self.io[reg] = value;
-                self.audio_writes.push((addr, value));
-            }
-            
-            // LCDC
-            0xFF40 => self.io[0x40] = value,
-            
-            // STAT
-            0xFF41 => {
-                // Bits 0-2 are read-only (mode and coincidence)
-                self.io[0x41] = (self.io[0x41] & 0x07) | (value & 0xF8);
-            }
-            
-            // SCY
-            0xFF42 => self.io[0x42] = value,
-            
-            // SCX
-            0xFF43 => self.io[0x43] = value,
-            
-            // LY is read-only (writing resets it on some models)
-            0xFF44 => {}
-            
-            // LYC
-            0xFF45 => self.io[0x45] = value,
-            
             // DMA transfer
             0xFF46 => {
                 self.io[0x46] = value;
                 self.start_dma(value);
             }
             
-            // BGP
-            0xFF47 => self.io[0x47] = value,
-            
-            // OBP0
-            0xFF48 => self.io[0x48] = value,
-            
-            // OBP1
-            0xFF49 => self.io[0x49] = value,
-            
-            // WY
-            0xFF4A => self.io[0x4A] = value,
-            
-            // WX
-            0xFF4B => self.io[0x4B] = value,
-            
             // CGB: KEY1
-            0xFF4D => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x4D] = (self.io[0x4D] & 0x80) | (value & 0x01);
-                }
+            0xFF4D if self.model.is_cgb() => {
+                self.io[0x4D] = (self.io[0x4D] & 0x80) | (value & 0x01);
             }
             
             // CGB: VBK
-            0xFF4F => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.vram_bank = value & 0x01;
-                }
+            0xFF4F if self.model.is_cgb() => {
+                self.vram_bank = value & 0x01;
             }
             
+            // Boot ROM disable (can't be mapped back in)
+            0xFF50 if value != 0 => self.boot_rom_mapped = false,
+            
             // CGB: HDMA source high
-            0xFF51 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.hdma_source = (self.hdma_source & 0x00FF) | ((value as u16) << 8);
-                }
+            0xFF51 if self.model.is_cgb() => {
+                self.hdma_source = (self.hdma_source & 0x00FF) | ((value as u16) << 8);
             }
             
             // CGB: HDMA source low
-            0xFF52 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.hdma_source = (self.hdma_source & 0xFF00) | ((value & 0xF0) as u16);
-                }
+            0xFF52 if self.model.is_cgb() => {
+                self.hdma_source = (self.hdma_source & 0xFF00) | ((value & 0xF0) as u16);
             }
             
             // CGB: HDMA dest high
-            0xFF53 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.hdma_dest = (self.hdma_dest & 0x00FF) | (((value & 0x1F) as u16) << 8);
-                }
+            0xFF53 if self.model.is_cgb() => {
+                self.hdma_dest = (self.hdma_dest & 0x00FF) | (((value & 0x1F) as u16) << 8);
             }
             
             // CGB: HDMA dest low
-            0xFF54 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.hdma_dest = (self.hdma_dest & 0xFF00) | ((value & 0xF0) as u16);
-                }
+            0xFF54 if self.model.is_cgb() => {
+                self.hdma_dest = (self.hdma_dest & 0xFF00) | ((value & 0xF0) as u16);
             }
             
             // CGB: HDMA control
-            0xFF55 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.start_hdma(value);
-                }
-            }
-            
-            // CGB: BGPI
-            0xFF68 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x68] = value;
-                }
-            }
-            
-            // CGB: BGPD
-            0xFF69 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x69] = value;
-                    // Auto-increment if bit 7 is set
-                    if self.io[0x68] & 0x80 != 0 {
-                        self.io[0x68] = (self.io[0x68] & 0xC0) | ((self.io[0x68] + 1) & 0x3F);
-                    }
-                }
-            }
-            
-            // CGB: OBPI
-            0xFF6A => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x6A] = value;
-                }
-            }
-            
-            // CGB: OBPD
-            0xFF6B => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.io[0x6B] = value;
-                    // Auto-increment if bit 7 is set
-                    if self.io[0x6A] & 0x80 != 0 {
-                        self.io[0x6A] = (self.io[0x6A] & 0xC0) | ((self.io[0x6A] + 1) & 0x3F);
-                    }
-                }
+            0xFF55 if self.model.is_cgb() => {
+                self.start_hdma(value);
             }
             
             // CGB: SVBK
-            0xFF70 => {
-                if matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
-                    self.wram_bank = (value & 0x07).max(1);
-                }
+            0xFF70 if self.model.is_cgb() => {
+                self.wram_bank = (value & 0x07).max(1);
             }
             
             _ => {}
@@ -714,6 +814,18 @@ impl Mmu {
         self.dma_source = (value as u16) << 8;
     }
     
+    /// Run OAM DMA for `cycles` T-cycles (at the CPU's speed). Cycles that
+    /// don't make up a whole M-cycle carry over to the next call, so a
+    /// transfer takes exactly 640 T-cycles however the instructions split
+    /// them.
+    pub fn advance_dma(&mut self, cycles: u32) {
+        self.dma_cycles += cycles;
+        for _ in 0..self.dma_cycles / 4 {
+            self.step_dma();
+        }
+        self.dma_cycles %= 4;
+    }
+    
     /// Step DMA transfer (call each M-cycle)
     pub fn step_dma(&mut self) {
         if !self.dma_active {
@@ -721,7 +833,7 @@ impl Mmu {
         }
         
         let src = self.dma_source + self.dma_byte as u16;
-        let value = self.read_byte(src);
+        let value = self.read_dma_source(src);
         self.oam[self.dma_byte as usize] = value;
         
         self.dma_byte += 1;
@@ -730,37 +842,82 @@ impl Mmu {
         }
     }
     
+    /// Read a byte from the OAM DMA source. The DMA unit has its own path
+    /// to memory, so the read doesn't touch the CPU's bus (open bus value,
+    /// bus capture) and isn't blocked by the OAM lock it imposes itself.
+    ///
+    /// Sources above 0xDFFF never reach OAM or I/O on hardware: DMG reads
+    /// the echo of WRAM, CGB decodes them like HDMA, as external RAM. With
+    /// `unusable_memory` off they read whatever is mapped there.
+    fn read_dma_source(&self, addr: u16) -> u8 {
+        match addr {
+            0xE000..=0xFFFF if self.accuracy.unusable_memory => {
+                if self.model.is_cgb() {
+                    self.read_mapped(addr - 0x4000)
+                } else {
+                    self.read_mapped(addr - 0x2000)
+                }
+            }
+            0xFE00..=0xFE9F => self.oam[(addr - 0xFE00) as usize],
+            _ => self.read_mapped(addr),
+        }
+    }
+    
     /// Start HDMA transfer (CGB only)
     fn start_hdma(&mut self, value: u8) {
+        if self.hdma_active && self.hdma_hblank && value & 0x80 == 0 {
+            // Writing bit 7 = 0 during an HBlank transfer stops it
+            self.hdma_active = false;
+            return;
+        }
+        
+        self.hdma_length = value & 0x7F;
+        self.hdma_active = true;
+        
         if value & 0x80 == 0 {
-            // General purpose DMA
-            self.hdma_length = value & 0x7F;
+            // General purpose DMA, transferred immediately
             self.hdma_hblank = false;
-            self.hdma_active = true;
-            
-            // Transfer immediately
             self.run_general_hdma();
         } else {
-            // HBlank DMA
-            self.hdma_length = value & 0x7F;
             self.hdma_hblank = true;
-            self.hdma_active = true;
         }
     }
     
+    /// Read a byte from the HDMA source. VRAM can't be a source (reads
+    /// 0xFF) and 0xE000-0xFFFF reads external RAM like 0xA000-0xBFFF.
+    /// The DMA has its own path to memory, so this leaves the CPU's open
+    /// bus and the bus capture alone.
+    fn read_hdma_source(&self, addr: u16) -> u8 {
+        match addr {
+            0x8000..=0x9FFF => 0xFF,
+            0xE000..=0xFFFF => self.read_mapped(addr - 0x4000),
+            _ => self.read_mapped(addr),
+        }
+    }
+    
+    /// Copy one 16-byte block into the selected VRAM bank and advance the
+    /// source and destination. The destination wraps within VRAM.
+    fn copy_hdma_block(&mut self) {
+        let bank_offset = self.vram_bank as usize * VRAM_SIZE;
+        for i in 0..16u16 {
+            let src = self.hdma_source.wrapping_add(i);
+            let dst = (self.hdma_dest.wrapping_add(i) & 0x1FFF) as usize;
+            let value = self.read_hdma_source(src);
+            if let Some(byte) = self.vram.get_mut(bank_offset + dst) {
+                *byte = value;
+            }
+        }
+        
+        self.hdma_source = self.hdma_source.wrapping_add(16);
+        self.hdma_dest = (self.hdma_dest + 16) & 0x1FF0;
+    }
+    
     /// Run general purpose HDMA (all at once)
     fn run_general_hdma(&mut self) {
         let blocks = self.hdma_length as u16 + 1;
         
         for _ in 0..blocks {
-            for i in 0..16u16 {
-                let src = self.hdma_source + i;
-                let dst = 0x8000 + (self.hdma_dest & 0x1FFF) + i;
-                let value = self.read_byte(src);
-                self.write_byte(dst, value);
-            }
-            self.hdma_source += 16;
-            self.hdma_dest += 16;
+            self.copy_hdma_block();
         }
         
         self.hdma_active = false;
@@ -773,22 +930,12 @@ impl Mmu {
             return;
         }
         
-        // Transfer 16 bytes
-        for i in 0..16u16 {
-            let src = self.hdma_source + i;
-            let dst = 0x8000 + (self.hdma_dest & 0x1FFF) + i;
-            let value = self.read_byte(src);
-            self.write_byte(dst, value);
-        }
-        
-        self.hdma_source += 16;
-        self.hdma_dest += 16;
+        self.copy_hdma_block();
         
-        if self.hdma_length == 0 {
+        // The length counter underflows to 0x7F when the last block is done
+        self.hdma_length = self.hdma_length.wrapping_sub(1);
+        if self.hdma_length == 0xFF {
             self.hdma_active = false;
-            self.hdma_length = 0xFF;
-        } else {
-            self.hdma_length -= 1;
         }
     }
     
@@ -797,9 +944,121 @@ impl Mmu {
         self.io[0x0F] |= flag;
     }
     
-    /// Update button state from Joypad component
-    pub fn update_joypad(&mut self, joypad: &Joypad) {
-        self.button_state = joypad.buttons();
+    /// CGB double-speed mode is active (KEY1 bit 7)
+    pub fn double_speed(&self) -> bool {
+        self.io[0x4D] & 0x80 != 0
+    }
+    
+    /// Perform a pending speed switch (called by STOP).
+    ///
+    /// The switch resets DIV like a write to it would, at the old speed, so
+    /// the timer and the APU frame sequencer see the same falling edges
+    /// (and everything clocked from DIV restarts in phase).
+    ///
+    /// Returns true if KEY1 was armed and the speed was toggled.
+    pub fn switch_speed(&mut self) -> bool {
+        if !self.model.is_cgb() || self.io[0x4D] & 0x01 == 0 {
+            return false;
+        }
+        
+        self.timer_mut().write_div();
+        self.io[0x4D] = (self.io[0x4D] ^ 0x80) & 0x80;
+        let double_speed = self.double_speed();
+        self.timer_mut().set_double_speed(double_speed);
+        self.apu_mut().set_double_speed(double_speed);
+        true
+    }
+    
+    /// Describe what is currently mapped at each region
+    pub fn memory_map(&self) -> MemoryMapReport {
+        let (rom_bank0, rom_bank) = self.cartridge.rom_banks();
+        
+        MemoryMapReport {
+            mbc: self.cartridge.mbc_type(),
+            rom_bank0,
+            rom_bank,
+            external_ram: self.cartridge.ram_mapping(),
+            vram_bank: self.vram_bank,
+            wram_bank: self.wram_bank,
+            boot_rom_mapped: self.boot_rom_mapped,
+            oam_dma_active: self.dma_active,
+            hdma: self.hdma_active.then_some(HdmaReport {
+                source: self.hdma_source,
+                dest: 0x8000 | (self.hdma_dest & 0x1FF0),
+                blocks_remaining: self.hdma_length as u16 + 1,
+                hblank: self.hdma_hblank,
+            }),
+        }
+    }
+    
+    /// Get the I/O bus
+    pub fn io_bus(&self) -> &IoBus {
+        &self.io_bus
+    }
+    
+    /// Get mutable I/O bus
+    pub fn io_bus_mut(&mut self) -> &mut IoBus {
+        &mut self.io_bus
+    }
+    
+    /// Get joypad
+    pub fn joypad(&self) -> &Joypad {
+        &self.io_bus.joypad
+    }
+    
+    /// Get mutable joypad
+    pub fn joypad_mut(&mut self) -> &mut Joypad {
+        &mut self.io_bus.joypad
+    }
+    
+    /// Get serial port
+    pub fn serial(&self) -> &Serial {
+        &self.io_bus.serial
+    }
+    
+    /// Get mutable serial port
+    pub fn serial_mut(&mut self) -> &mut Serial {
+        &mut self.io_bus.serial
+    }
+    
+    /// Get timer
+    pub fn timer(&self) -> &Timer {
+        &self.io_bus.timer
+    }
+    
+    /// Get mutable timer
+    pub fn timer_mut(&mut self) -> &mut Timer {
+        &mut self.io_bus.timer
+    }
+    
+    /// Get APU
+    pub fn apu(&self) -> &Apu {
+        &self.io_bus.apu
+    }
+    
+    /// Get mutable APU
+    pub fn apu_mut(&mut self) -> &mut Apu {
+        &mut self.io_bus.apu
+    }
+    
+    /// Get LCD registers for PPU access
+    pub fn lcd(&self) -> &LcdRegisters {
+        &self.io_bus.lcd
+    }
+    
+    /// Get mutable LCD registers for PPU access
+    pub fn lcd_mut(&mut self) -> &mut LcdRegisters {
+        &mut self.io_bus.lcd
+    }
+    
+    /// Get CGB palette RAM
+    pub fn palettes(&self) -> &CgbPalettes {
+        &self.io_bus.palettes
+    }
+    
+    /// Get mutable CGB palette RAM, e.g. for palette editors
+    pub fn palettes_mut(&mut self) -> &mut CgbPalettes {
+        &mut self.io_bus.palettes
     }
     
     /// Get cartridge reference
@@ -817,11 +1076,53 @@ impl Mmu {
         &self.vram
     }
     
+    /// Overwrite VRAM in a specific bank (0, or 1 on CGB) at `offset`
+    /// bytes from 0x8000, regardless of VBK or PPU mode, for debugging and
+    /// art tools
+    pub fn write_vram_bank(&mut self, bank: usize, offset: usize, data: &[u8]) -> Result<(), String> {
+        let banks = self.vram.len() / VRAM_SIZE;
+        if bank >= banks {
+            return Err(format!("VRAM bank {} doesn't exist ({} banks)", bank, banks));
+        }
+        if offset + data.len() > VRAM_SIZE {
+            return Err(format!("{} bytes at offset {:#06X} run past the end of VRAM", data.len(), offset));
+        }
+        
+        let start = bank * VRAM_SIZE + offset;
+        self.vram[start..start + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+    
+    /// Get WRAM (all banks)
+    pub fn wram(&self) -> &[u8] {
+        &self.wram
+    }
+    
+    /// Get mutable WRAM (all banks)
+    pub fn wram_mut(&mut self) -> &mut [u8] {
+        &mut self.wram
+    }
+    
     /// Get OAM for PPU access
     pub fn oam(&self) -> &[u8; OAM_SIZE] {
         &self.oam
     }
     
+    /// Get mutable OAM
+    pub fn oam_mut(&mut self) -> &mut [u8; OAM_SIZE] {
+        &mut self.oam
+    }
+    
+    /// Get HRAM
+    pub fn hram(&self) -> &[u8; HRAM_SIZE] {
+        &self.hram
+    }
+    
+    /// Get mutable HRAM
+    pub fn hram_mut(&mut self) -> &mut [u8; HRAM_SIZE] {
+        &mut self.hram
+    }
+    
     /// Get I/O registers
     pub fn io(&self) -> &[u8; IO_SIZE] {
         &self.io
@@ -851,16 +1152,48 @@ impl Mmu {
             hdma_dest: self.hdma_dest,
             hdma_length: self.hdma_length,
             hdma_hblank: self.hdma_hblank,
+            lcd: self.io_bus.lcd.clone(),
+            palettes: self.io_bus.palettes.clone(),
+            ram_init: self.ram_init,
+            boot_rom_mapped: self.boot_rom_mapped,
+            model: Some(self.model),
+            cartridge: Some(self.cartridge.state()),
+            dma_cycles: self.dma_cycles,
         }
     }
     
-    /// Load state from serialization
-    pub fn load_state(&mut self, state: MmuState) -> Result<(), String> {
-        if state.vram.len() != self.vram.len() {
-            return Err("VRAM size mismatch".to_string());
+    /// Check that a state fits this model without loading it
+    pub fn check_state(&self, state: &MmuState) -> Result<(), StateError> {
+        if let Some(saved) = state.model.filter(|&saved| saved != self.model) {
+            return Err(StateError::ModelMismatch { saved, current: self.model });
         }
         
-        self.vram = state.vram;
+        let vram_len = if state.vram.is_empty() { self.vram.len() } else { state.vram.len() };
+        let areas = [
+            ("VRAM", self.vram.len(), vram_len),
+            ("WRAM", self.wram.len(), state.wram.len()),
+            ("OAM", OAM_SIZE, state.oam.len()),
+            ("HRAM", HRAM_SIZE, state.hram.len()),
+            ("I/O", IO_SIZE, state.io.len()),
+        ];
+        for (area, expected, found) in areas {
+            if found != expected {
+                return Err(StateError::SizeMismatch { area, expected, found });
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Load state from serialization. Nothing is changed if the state
+    /// doesn't fit this model (see `check_state`). Empty VRAM (a
+    /// quick-resume state) keeps the current contents.
+    pub fn load_state(&mut self, state: MmuState) -> Result<(), StateError> {
+        self.check_state(&state)?;
+        
+        if !state.vram.is_empty() {
+            self.vram = state.vram;
+        }
         self.wram = state.wram;
         self.oam.copy_from_slice(&state.oam);
         self.hram.copy_from_slice(&state.hram);
@@ -871,17 +1204,21 @@ impl Mmu {
         self.dma_active = state.dma_active;
         self.dma_byte = state.dma_byte;
         self.dma_source = state.dma_source;
+        self.dma_cycles = state.dma_cycles;
         self.hdma_active = state.hdma_active;
         self.hdma_source = state.hdma_source;
         self.hdma_dest = state.hdma_dest;
         self.hdma_length = state.hdma_length;
         self.hdma_hblank = state.hdma_hblank;
+        self.io_bus.lcd = state.lcd;
+        self.io_bus.palettes = state.palettes;
+        self.io_bus.palettes.set_cgb_mode(self.model.is_cgb());
+        self.ram_init = state.ram_init;
+        self.boot_rom_mapped = state.boot_rom_mapped && self.boot_rom.is_some();
+        if let Some(cartridge) = state.cartridge {
+            self.cartridge.load_state(cartridge);
+        }
         
         Ok(())
     }
-    
-    /// Take pending audio writes and clear the queue
-    pub fn take_audio_writes(&mut self) -> Vec<(u16, u8)> {
-        std::mem::take(&mut self.audio_writes)
-    }
 }
\ No newline at end of file
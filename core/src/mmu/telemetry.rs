@@ -0,0 +1,88 @@
+//! Per-frame write telemetry
+//!
+//! Counts the writes that tell how a game drives the hardware: bank
+//! switches, DMA transfers and audio, timer and serial register writes.
+//! Useful for per-game performance tuning and for spotting games that
+//! lean on the timer and serial paths.
+
+use serde::{Serialize, Deserialize};
+
+/// Write counts for one frame (or summed over several)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct FrameActivity {
+    /// Writes to the cartridge's ROM area (MBC control)
+    pub rom_writes: u32,
+    
+    /// ROM area writes that changed a mapped ROM bank or the RAM mapping
+    pub bank_switches: u32,
+    
+    /// OAM DMA transfers started (writes to DMA)
+    pub oam_dma: u32,
+    
+    /// CGB HDMA transfers started or stopped (writes to HDMA5)
+    pub hdma: u32,
+    
+    /// Writes to the sound registers and wave RAM
+    pub audio_writes: u32,
+    
+    /// Writes to DIV, TIMA, TMA and TAC
+    pub timer_writes: u32,
+    
+    /// Writes to SB and SC
+    pub serial_writes: u32,
+}
+
+impl FrameActivity {
+    fn add(&mut self, other: &FrameActivity) {
+        self.rom_writes += other.rom_writes;
+        self.bank_switches += other.bank_switches;
+        self.oam_dma += other.oam_dma;
+        self.hdma += other.hdma;
+        self.audio_writes += other.audio_writes;
+        self.timer_writes += other.timer_writes;
+        self.serial_writes += other.serial_writes;
+    }
+}
+
+/// Write activity since telemetry was enabled (see `GameBoy::stats`)
+#[derive(Debug, Clone, Default)]
+pub struct Telemetry {
+    /// The frame in progress
+    current: FrameActivity,
+    
+    /// The last completed frame
+    pub last_frame: FrameActivity,
+    
+    /// Every completed frame added up
+    pub total: FrameActivity,
+    
+    /// Completed frames counted
+    pub frames: u64,
+}
+
+impl Telemetry {
+    /// Count a bus write; `bank_switched` tells whether a ROM area write
+    /// changed the mapping
+    pub(crate) fn record_write(&mut self, addr: u16, bank_switched: bool) {
+        let counts = &mut self.current;
+        match addr {
+            0x0000..=0x7FFF => {
+                counts.rom_writes += 1;
+                counts.bank_switches += bank_switched as u32;
+            }
+            0xFF01..=0xFF02 => counts.serial_writes += 1,
+            0xFF04..=0xFF07 => counts.timer_writes += 1,
+            0xFF10..=0xFF3F => counts.audio_writes += 1,
+            0xFF46 => counts.oam_dma += 1,
+            0xFF55 => counts.hdma += 1,
+            _ => {}
+        }
+    }
+    
+    /// Close the frame in progress
+    pub(crate) fn end_frame(&mut self) {
+        self.last_frame = std::mem::take(&mut self.current);
+        self.total.add(&self.last_frame);
+        self.frames += 1;
+    }
+}
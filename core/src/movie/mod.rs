@@ -0,0 +1,68 @@
+//! # Movie
+//!
+//! Input recordings for deterministic replay: a save state to start from,
+//! then the buttons held in every frame. Resets and power cycles are
+//! recorded in order between the frames, so a replay goes through them at
+//! the same point and ends in the same state.
+
+use serde::{Deserialize, Serialize};
+
+use crate::joypad::InputState;
+use crate::mmu::StateError;
+use crate::GameBoy;
+
+/// One recorded step of a movie
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MovieInput {
+    /// A frame run with these buttons held, one bit per `Button` code
+    /// (1 = pressed, as in `InputState::from_bits`)
+    Frame(u8),
+    
+    /// `GameBoy::reset`
+    Reset,
+    
+    /// `GameBoy::power_cycle`
+    PowerCycle,
+}
+
+/// An input movie (see `GameBoy::start_movie`).
+///
+/// Input is sampled once per frame, as it is held when the frame starts;
+/// buttons changed partway through a frame show up from the next one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Movie {
+    /// Save state (with configuration) the recording started from
+    pub start: Vec<u8>,
+    
+    /// Frames and console events, in the order they happened
+    pub inputs: Vec<MovieInput>,
+}
+
+impl Movie {
+    /// Start an empty movie from a save state
+    pub fn new(start: Vec<u8>) -> Self {
+        Self { start, inputs: Vec::new() }
+    }
+    
+    /// Number of frames recorded
+    pub fn frames(&self) -> usize {
+        self.inputs.iter().filter(|input| matches!(input, MovieInput::Frame(_))).count()
+    }
+    
+    /// Load the starting state into `gb` and replay every input. `gb`
+    /// must be running the ROM the movie was recorded with.
+    pub fn play(&self, gb: &mut GameBoy) -> Result<(), StateError> {
+        gb.load_state(&self.start)?;
+        
+        for &input in &self.inputs {
+            match input {
+                MovieInput::Frame(buttons) => {
+                    gb.frame_advance(InputState::from_bits(buttons));
+                }
+                MovieInput::Reset => gb.reset(),
+                MovieInput::PowerCycle => gb.power_cycle(),
+            }
+        }
+        Ok(())
+    }
+}
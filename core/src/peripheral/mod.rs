@@ -0,0 +1,42 @@
+//! # Peripherals
+//!
+//! Hardware add-ons (link cable devices, IR transceivers, rumble motors,
+//! cameras, accelerometers) plugged into a `GameBoy`. Every peripheral
+//! shares the same lifecycle: it is stepped after each instruction, reset
+//! with the console, and stored in save states under its name.
+//!
+//! Device-specific wiring stays with the device: a peripheral reaches the
+//! hardware through the `Mmu` it is stepped with (I/O registers,
+//! interrupts, cartridge).
+
+use crate::mmu::Mmu;
+
+/// A hardware add-on
+pub trait Peripheral {
+    /// Unique name, used to find the peripheral and its save-state data
+    fn name(&self) -> &str;
+    
+    /// Called after every CPU step with the cycles it took (CPU cycles, so
+    /// twice as many per second in CGB double speed)
+    fn step(&mut self, _cycles: u32, _mmu: &mut Mmu) {}
+    
+    /// Called when the console is reset or a new ROM is loaded
+    fn reset(&mut self) {}
+    
+    /// State to store in save states (none by default)
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+    
+    /// Check that `load_state` would accept `data`, without changing
+    /// anything. A save state is only applied once every part of it
+    /// passes its check.
+    fn check_state(&self, _data: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
+    
+    /// Restore state saved by `save_state`, after `check_state` accepted it
+    fn load_state(&mut self, _data: &[u8]) -> Result<(), String> {
+        Ok(())
+    }
+}
@@ -0,0 +1,180 @@
+//! # Post-processing Pipeline
+//!
+//! Runs expensive output stages (resampling, scaling filters, PNG encoding)
+//! on a worker thread so the emulation thread only copies each frame's
+//! output and moves on. The core stays deterministic: stages only see
+//! copies of the framebuffer and audio buffer.
+//!
+//! Not available on wasm, which has no threads.
+
+mod png;
+mod resample;
+mod scale;
+
+pub use png::{encode_png, PngEncoder};
+pub use resample::Resampler;
+pub use scale::{Scale, Scale2x};
+
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread::{self, JoinHandle};
+
+use crate::{GameBoy, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Frames waiting for the worker before `submit` starts dropping them
+const QUEUE_DEPTH: usize = 4;
+
+/// A video frame moving through the pipeline
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// `GameBoy::frame_count` when the frame was submitted
+    pub number: u64,
+    
+    pub width: usize,
+    pub height: usize,
+    
+    /// RGBA pixels, row by row
+    pub rgba: Vec<u8>,
+    
+    /// Encoded image, once a stage such as `PngEncoder` has produced one
+    pub encoded: Option<Vec<u8>>,
+}
+
+/// A step applied to every frame on the worker thread
+pub trait FrameStage: Send {
+    fn process(&mut self, frame: &mut Frame);
+}
+
+/// A step applied to the audio of every frame on the worker thread.
+/// Samples are interleaved stereo; stages may change the count.
+pub trait AudioStage: Send {
+    fn process(&mut self, samples: Vec<f32>) -> Vec<f32>;
+}
+
+/// Output of one submitted frame
+#[derive(Debug, Clone)]
+pub struct Processed {
+    pub frame: Frame,
+    
+    /// Audio produced since the previous processed frame
+    pub audio: Vec<f32>,
+}
+
+struct Job {
+    frame: Frame,
+    audio: Vec<f32>,
+}
+
+/// Worker thread running frame and audio stages in submission order
+pub struct Pipeline {
+    jobs: Option<SyncSender<Job>>,
+    results: Receiver<Processed>,
+    worker: Option<JoinHandle<()>>,
+    
+    /// Audio of dropped frames, sent with the next frame that fits
+    pending_audio: Vec<f32>,
+    
+    /// Frames dropped because the worker was behind
+    dropped: u64,
+}
+
+impl Pipeline {
+    /// Start a worker running `frame_stages` and `audio_stages` in order
+    pub fn new(frame_stages: Vec<Box<dyn FrameStage>>, audio_stages: Vec<Box<dyn AudioStage>>) -> Self {
+        let (jobs, job_rx) = mpsc::sync_channel::<Job>(QUEUE_DEPTH);
+        let (result_tx, results) = mpsc::channel();
+        
+        let worker = thread::spawn(move || {
+            let mut frame_stages = frame_stages;
+            let mut audio_stages = audio_stages;
+            
+            for Job { mut frame, mut audio } in job_rx {
+                for stage in &mut frame_stages {
+                    stage.process(&mut frame);
+                }
+                for stage in &mut audio_stages {
+                    audio = stage.process(audio);
+                }
+                if result_tx.send(Processed { frame, audio }).is_err() {
+                    break;
+                }
+            }
+        });
+        
+        Self {
+            jobs: Some(jobs),
+            results,
+            worker: Some(worker),
+            pending_audio: Vec::new(),
+            dropped: 0,
+        }
+    }
+    
+    /// Queue the current framebuffer and audio buffer without waiting.
+    ///
+    /// Returns false if the worker was too far behind: the frame is dropped
+    /// but its audio is kept and sent with the next frame, so the sound
+    /// has no gaps. The caller still clears the audio buffer either way.
+    pub fn submit(&mut self, gb: &GameBoy) -> bool {
+        self.pending_audio.extend_from_slice(gb.audio_buffer());
+        
+        let Some(jobs) = &self.jobs else {
+            return false;
+        };
+        let job = Job {
+            frame: Frame {
+                number: gb.frame_count(),
+                width: SCREEN_WIDTH,
+                height: SCREEN_HEIGHT,
+                rgba: gb.framebuffer().to_vec(),
+                encoded: None,
+            },
+            audio: std::mem::take(&mut self.pending_audio),
+        };
+        
+        match jobs.try_send(job) {
+            Ok(()) => true,
+            Err(TrySendError::Full(job) | TrySendError::Disconnected(job)) => {
+                self.pending_audio = job.audio;
+                self.dropped += 1;
+                false
+            }
+        }
+    }
+    
+    /// Next processed frame, if one is ready
+    pub fn try_recv(&mut self) -> Option<Processed> {
+        self.results.try_recv().ok()
+    }
+    
+    /// Wait for the next processed frame (`None` if a stage panicked and
+    /// stopped the worker)
+    pub fn recv(&mut self) -> Option<Processed> {
+        self.results.recv().ok()
+    }
+    
+    /// Frames dropped by `submit` so far
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped
+    }
+    
+    /// Stop accepting frames, wait for the worker to process what is
+    /// queued and return the results not yet received
+    pub fn finish(mut self) -> Vec<Processed> {
+        self.stop();
+        self.results.try_iter().collect()
+    }
+    
+    fn stop(&mut self) {
+        self.jobs = None;
+        if let Some(worker) = self.worker.take() {
+            // A panicking stage has already reported itself
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for Pipeline {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
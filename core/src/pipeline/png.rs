@@ -0,0 +1,92 @@
+//! PNG encoding
+//!
+//! Uncompressed (stored) deflate keeps the encoder small and dependency
+//! free; files are larger than a compressing encoder would produce.
+
+use super::{Frame, FrameStage};
+
+/// Largest stored deflate block
+const MAX_BLOCK: usize = 0xFFFF;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+fn push_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encode RGBA pixels as an 8-bit RGBA PNG file
+pub fn encode_png(rgba: &[u8], width: usize, height: usize) -> Vec<u8> {
+    // Each row starts with filter type 0 (none)
+    let mut raw = Vec::with_capacity(rgba.len() + height);
+    for row in rgba.chunks_exact(width * 4).take(height) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    
+    let mut zlib = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if raw.is_empty() { vec![&[]] } else { raw.chunks(MAX_BLOCK).collect() };
+    for (i, block) in blocks.iter().enumerate() {
+        let len = block.len() as u16;
+        // BFINAL on the last block, BTYPE 00 (stored)
+        zlib.push((i + 1 == blocks.len()) as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+    
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA
+    
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    push_chunk(&mut png, b"IHDR", &header);
+    push_chunk(&mut png, b"IDAT", &zlib);
+    push_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Stores each frame as a PNG file in `Frame::encoded`
+pub struct PngEncoder;
+
+impl FrameStage for PngEncoder {
+    fn process(&mut self, frame: &mut Frame) {
+        frame.encoded = Some(encode_png(&frame.rgba, frame.width, frame.height));
+    }
+}
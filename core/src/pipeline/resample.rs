@@ -0,0 +1,95 @@
+//! Band-limited resampling
+//!
+//! A Blackman-windowed sinc low-pass evaluated at each output position, so
+//! content above the output Nyquist frequency is filtered out instead of
+//! aliasing. Meant for the raw APU output (`AudioOutputMode::Raw`), where
+//! the APU's own decimation is skipped.
+
+use std::f64::consts::PI;
+
+use super::AudioStage;
+
+/// Zero crossings of the sinc on each side of the output position
+const ZERO_CROSSINGS: usize = 8;
+
+/// Converts interleaved stereo audio from one rate to another, keeping its
+/// filter history between calls so chunk boundaries are seamless
+pub struct Resampler {
+    /// Input samples per output sample
+    step: f64,
+    
+    /// Filter cutoff as a fraction of the input rate (0.5 = input Nyquist)
+    cutoff: f64,
+    
+    /// Filter half-width in input samples
+    half_width: usize,
+    
+    /// Stereo input not yet consumed, starting `half_width` samples of
+    /// silence before the first real input
+    history: Vec<[f32; 2]>,
+    
+    /// Position of the next output sample in `history`
+    position: f64,
+}
+
+impl Resampler {
+    pub fn new(input_rate: u32, output_rate: u32) -> Self {
+        let step = input_rate as f64 / output_rate as f64;
+        let cutoff = 0.5 / step.max(1.0);
+        let half_width = (ZERO_CROSSINGS as f64 / (2.0 * cutoff)).ceil() as usize;
+        
+        Self {
+            step,
+            cutoff,
+            half_width,
+            history: vec![[0.0; 2]; half_width],
+            position: half_width as f64,
+        }
+    }
+    
+    /// Filter weight for an input sample `t` samples from the output
+    /// position
+    fn kernel(&self, t: f64) -> f64 {
+        let x = t / self.half_width as f64;
+        if x.abs() >= 1.0 {
+            return 0.0;
+        }
+        let window = 0.42 + 0.5 * (PI * x).cos() + 0.08 * (2.0 * PI * x).cos();
+        let arg = 2.0 * self.cutoff * t;
+        let sinc = if arg == 0.0 { 1.0 } else { (PI * arg).sin() / (PI * arg) };
+        2.0 * self.cutoff * sinc * window
+    }
+    
+    /// Resample interleaved stereo `input`; output lags the input by the
+    /// filter's half-width
+    pub fn resample(&mut self, input: &[f32]) -> Vec<f32> {
+        self.history.extend(input.chunks_exact(2).map(|pair| [pair[0], pair[1]]));
+        
+        let mut output = Vec::new();
+        while self.position.floor() as usize + self.half_width < self.history.len() {
+            let center = self.position.floor() as usize;
+            let first = center + 1 - self.half_width;
+            let mut sum = [0.0f64; 2];
+            for (i, sample) in self.history[first..=center + self.half_width].iter().enumerate() {
+                let weight = self.kernel((first + i) as f64 - self.position);
+                sum[0] += weight * sample[0] as f64;
+                sum[1] += weight * sample[1] as f64;
+            }
+            output.extend_from_slice(&[sum[0] as f32, sum[1] as f32]);
+            self.position += self.step;
+        }
+        
+        // Drop input no future output position reaches
+        let consumed = (self.position.floor() as usize + 1).saturating_sub(self.half_width).min(self.history.len());
+        self.history.drain(..consumed);
+        self.position -= consumed as f64;
+        
+        output
+    }
+}
+
+impl AudioStage for Resampler {
+    fn process(&mut self, samples: Vec<f32>) -> Vec<f32> {
+        self.resample(&samples)
+    }
+}
@@ -0,0 +1,82 @@
+//! Scaling filters
+
+use super::{Frame, FrameStage};
+
+/// Nearest-neighbour upscaling by an integer factor
+pub struct Scale {
+    factor: usize,
+}
+
+impl Scale {
+    /// Scale by `factor` (at least 1)
+    pub fn new(factor: usize) -> Self {
+        Self { factor: factor.max(1) }
+    }
+}
+
+impl FrameStage for Scale {
+    fn process(&mut self, frame: &mut Frame) {
+        let factor = self.factor;
+        let width = frame.width * factor;
+        let mut rgba = Vec::with_capacity(frame.rgba.len() * factor * factor);
+        
+        for row in frame.rgba.chunks_exact(frame.width * 4) {
+            let start = rgba.len();
+            for pixel in row.chunks_exact(4) {
+                for _ in 0..factor {
+                    rgba.extend_from_slice(pixel);
+                }
+            }
+            for _ in 1..factor {
+                rgba.extend_from_within(start..start + width * 4);
+            }
+        }
+        
+        frame.width = width;
+        frame.height *= factor;
+        frame.rgba = rgba;
+    }
+}
+
+/// Scale2x (EPX) doubling, which smooths diagonal edges without blurring
+pub struct Scale2x;
+
+impl FrameStage for Scale2x {
+    fn process(&mut self, frame: &mut Frame) {
+        let (width, height) = (frame.width, frame.height);
+        let src = &frame.rgba;
+        let pixel = |x: usize, y: usize| &src[(y * width + x) * 4..][..4];
+        let mut rgba = vec![0; src.len() * 4];
+        
+        for y in 0..height {
+            for x in 0..width {
+                let p = pixel(x, y);
+                let a = pixel(x, y.saturating_sub(1));
+                let b = pixel((x + 1).min(width - 1), y);
+                let c = pixel(x.saturating_sub(1), y);
+                let d = pixel(x, (y + 1).min(height - 1));
+                
+                let corners = if c != b && a != d {
+                    [
+                        if c == a { a } else { p },
+                        if a == b { b } else { p },
+                        if d == c { c } else { p },
+                        if b == d { d } else { p },
+                    ]
+                } else {
+                    [p; 4]
+                };
+                
+                for (i, corner) in corners.into_iter().enumerate() {
+                    let (dx, dy) = (i % 2, i / 2);
+                    let offset = ((y * 2 + dy) * width * 2 + x * 2 + dx) * 4;
+                    rgba[offset..offset + 4].copy_from_slice(corner);
+                }
+            }
+        }
+        
+        frame.width = width * 2;
+        frame.height = height * 2;
+        frame.rgba = rgba;
+    }
+}
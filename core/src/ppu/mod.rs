@@ -5,10 +5,23 @@
 //! ## Modes
 //! - Mode 0: HBlank (204 cycles)
 //! - Mode 1: VBlank (4560 cycles)
-//! - Mode 2: OAM Search (80 cycles)
+//! - Mode 2: OAM Search (80 cycles), selecting up to 10 sprites for the
+//!   line from OAM as it is at the end of the mode
 //! - Mode 3: Pixel Transfer (172 cycles)
+//!
+//! When the LCD is switched back on, line 0 skips OAM search (STAT reads
+//! mode 0) and is 4 cycles short, and the first frame isn't displayed.
 
-use crate::mmu::Mmu;
+mod overlay;
+mod palettes;
+
+use std::ops::RangeInclusive;
+
+use overlay::{DebugOverlay, SpriteBox};
+pub use overlay::OVERLAY_SIZE;
+pub use palettes::CgbPalettes;
+
+use crate::mmu::{IoDevice, Mmu, StateError};
 use crate::GbModel;
 use serde::{Serialize, Deserialize};
 
@@ -25,6 +38,9 @@ const CYCLES_PER_LINE: u32 = 456;
 /// Total scanlines (including VBlank)
 const TOTAL_LINES: u8 = 154;
 
+/// CGB tile attribute bit: BG colors 1-3 are drawn above sprites
+const BG_ATTR_PRIORITY: u8 = 0x80;
+
 /// PPU modes
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PpuMode {
@@ -34,10 +50,189 @@ pub enum PpuMode {
     PixelTransfer = 3,
 }
 
+/// LCD controller registers (0xFF40-0xFF45, 0xFF47-0xFF4B)
+///
+/// Written by the CPU through the I/O bus; LY and the STAT mode/coincidence
+/// bits are driven by the PPU itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LcdRegisters {
+    /// LCD control
+    pub lcdc: u8,
+    /// LCD status (bits 0-2 owned by the PPU)
+    pub stat: u8,
+    /// Background scroll Y
+    pub scy: u8,
+    /// Background scroll X
+    pub scx: u8,
+    /// Current scanline
+    pub ly: u8,
+    /// LY compare
+    pub lyc: u8,
+    /// DMG background palette
+    pub bgp: u8,
+    /// DMG object palette 0
+    pub obp0: u8,
+    /// DMG object palette 1
+    pub obp1: u8,
+    /// Window Y position
+    pub wy: u8,
+    /// Window X position + 7
+    pub wx: u8,
+}
+
+impl LcdRegisters {
+    /// Create registers with post-boot ROM values
+    pub fn new() -> Self {
+        Self {
+            lcdc: 0x91,
+            stat: 0x85,
+            scy: 0x00,
+            scx: 0x00,
+            ly: 0x00,
+            lyc: 0x00,
+            bgp: 0xFC,
+            obp0: 0xFF,
+            obp1: 0xFF,
+            wy: 0x00,
+            wx: 0x00,
+        }
+    }
+    
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl IoDevice for LcdRegisters {
+    const IO_RANGES: &'static [RangeInclusive<u16>] = &[0xFF40..=0xFF45, 0xFF47..=0xFF4B];
+    
+    fn read_io(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF40 => self.lcdc,
+            0xFF41 => self.stat | 0x80,
+            0xFF42 => self.scy,
+            0xFF43 => self.scx,
+            0xFF44 => self.ly,
+            0xFF45 => self.lyc,
+            0xFF47 => self.bgp,
+            0xFF48 => self.obp0,
+            0xFF49 => self.obp1,
+            0xFF4A => self.wy,
+            0xFF4B => self.wx,
+            _ => 0xFF,
+        }
+    }
+    
+    fn write_io(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF40 => self.lcdc = value,
+            // Bits 0-2 are read-only (mode and coincidence)
+            0xFF41 => self.stat = (self.stat & 0x07) | (value & 0x78),
+            0xFF42 => self.scy = value,
+            0xFF43 => self.scx = value,
+            // LY is read-only
+            0xFF44 => {}
+            0xFF45 => self.lyc = value,
+            0xFF47 => self.bgp = value,
+            0xFF48 => self.obp0 = value,
+            0xFF49 => self.obp1 = value,
+            0xFF4A => self.wy = value,
+            0xFF4B => self.wx = value,
+            _ => {}
+        }
+    }
+}
+
+/// LCD power changes, for frontends that blank the screen like hardware
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LcdEvent {
+    /// LCDC bit 7 was cleared; nothing is drawn until it is set again
+    Disabled,
+    
+    /// LCDC bit 7 was set; the frame now starting is blank
+    Enabled,
+}
+
+/// One of the two 32x32 background tile maps
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileMapArea {
+    /// 0x9800-0x9BFF
+    Low,
+    
+    /// 0x9C00-0x9FFF
+    High,
+}
+
+impl TileMapArea {
+    /// Map the background uses (LCDC bit 3)
+    pub fn background(lcdc: u8) -> Self {
+        if lcdc & 0x08 != 0 { Self::High } else { Self::Low }
+    }
+    
+    /// Map the window uses (LCDC bit 6)
+    pub fn window(lcdc: u8) -> Self {
+        if lcdc & 0x40 != 0 { Self::High } else { Self::Low }
+    }
+    
+    /// First address of the map
+    pub fn base(self) -> u16 {
+        match self {
+            Self::Low => 0x9800,
+            Self::High => 0x9C00,
+        }
+    }
+}
+
+/// CGB tile map attributes (the byte at the same map address in VRAM
+/// bank 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TileAttributes {
+    /// BG palette (0-7)
+    pub palette: u8,
+    
+    /// VRAM bank holding the tile data
+    pub vram_bank: u8,
+    
+    pub x_flip: bool,
+    
+    pub y_flip: bool,
+    
+    /// BG colors 1-3 are drawn above sprites
+    pub priority: bool,
+}
+
+impl TileAttributes {
+    pub fn from_byte(byte: u8) -> Self {
+        Self {
+            palette: byte & 0x07,
+            vram_bank: (byte >> 3) & 0x01,
+            x_flip: byte & 0x20 != 0,
+            y_flip: byte & 0x40 != 0,
+            priority: byte & BG_ATTR_PRIORITY != 0,
+        }
+    }
+}
+
+/// One tile map entry, for debug views
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileMapEntry {
+    /// Tile index as stored in the map
+    pub tile: u8,
+    
+    /// Address of the tile data under the current LCDC.4 addressing mode
+    pub tile_addr: u16,
+    
+    /// CGB attributes (all zero outside CGB)
+    pub attributes: TileAttributes,
+}
+
 /// PPU step result
 pub struct PpuStepResult {
     pub vblank_interrupt: bool,
     pub stat_interrupt: bool,
+    
+    /// Mode 3 ended and HBlank began
+    pub hblank_started: bool,
 }
 
 /// Sprite data from OAM
@@ -50,6 +245,17 @@ struct Sprite {
 }
 
 impl Sprite {
+    /// Read OAM entry `index` (0-39)
+    fn from_oam(oam: &[u8], index: usize) -> Self {
+        let offset = index * 4;
+        Self {
+            y: oam[offset],
+            x: oam[offset + 1],
+            tile: oam[offset + 2],
+            flags: oam[offset + 3],
+        }
+    }
+    
     /// Priority (0 = above BG, 1 = behind BG colors 1-3)
     fn priority(&self) -> bool {
         self.flags & 0x80 != 0
@@ -71,13 +277,11 @@ impl Sprite {
     }
     
     /// VRAM bank (CGB only)
-    #[allow(dead_code)]
     fn vram_bank(&self) -> u8 {
         if self.flags & 0x08 != 0 { 1 } else { 0 }
     }
     
     /// CGB palette number
-    #[allow(dead_code)]
     fn cgb_palette(&self) -> u8 {
         self.flags & 0x07
     }
@@ -93,6 +297,25 @@ pub struct PpuState {
     pub stat_interrupt_line: bool,
     pub bg_palette: [[u8; 4]; 8],
     pub obj_palette: [[u8; 4]; 8],
+    #[serde(default)]
+    pub lcd_off: bool,
+    #[serde(default)]
+    pub first_line: bool,
+    #[serde(default)]
+    pub blank_frame: bool,
+    #[serde(default)]
+    pub wy_triggered: bool,
+    #[serde(default)]
+    pub line_sprites: Vec<u8>,
+    
+    /// RGBA framebuffer, run-length encoded as (count, R, G, B, A)
+    /// records; empty in older states
+    #[serde(default)]
+    pub framebuffer: Vec<u8>,
+    
+    /// Indexed framebuffer, run-length encoded as (count, index) records
+    #[serde(default)]
+    pub indexed: Vec<u8>,
 }
 
 /// Pixel Processing Unit
@@ -106,12 +329,24 @@ pub struct Ppu {
     /// Current scanline (LY)
     ly: u8,
     
-    /// Window internal line counter
+    /// Window internal line counter: advances only on lines where the
+    /// window was drawn, and restarts each frame
     window_line: u8,
     
+    /// LY matched WY at the start of a line this frame; from then on the
+    /// window may be drawn even if WY changes
+    wy_triggered: bool,
+    
+    /// OAM indices of the sprites selected for the current line by the
+    /// OAM scan, in OAM order
+    line_sprites: Vec<u8>,
+    
     /// Framebuffer (RGBA8888)
     framebuffer: Vec<u8>,
     
+    /// Indexed framebuffer (see `framebuffer_indexed`)
+    indexed: Vec<u8>,
+    
     /// Game Boy model
     model: GbModel,
     
@@ -124,13 +359,24 @@ pub struct Ppu {
     /// CGB object palettes
     obj_palette: [[u8; 4]; 8],
     
-    /// CGB background palette data (for future CGB support)
-    #[allow(dead_code)]
-    bg_palette_data: [u8; 64],
+    /// LCD was off at the last step
+    lcd_off: bool,
+    
+    /// On line 0 right after the LCD was switched on
+    first_line: bool,
+    
+    /// Drawing the frame after the LCD was switched on, which isn't shown
+    blank_frame: bool,
     
-    /// CGB object palette data (for future CGB support)
-    #[allow(dead_code)]
-    obj_palette_data: [u8; 64],
+    /// Latest LCD power change not yet taken
+    lcd_event: Option<LcdEvent>,
+    
+    /// Draw scanlines into the framebuffers (off to skip frames nobody
+    /// will see; timing and interrupts are unaffected)
+    rendering: bool,
+    
+    /// Layer outlines for debuggers (see `set_debug_overlay`)
+    overlay: Option<DebugOverlay>,
 }
 
 impl Ppu {
@@ -141,13 +387,20 @@ impl Ppu {
             cycles: 0,
             ly: 0,
             window_line: 0,
+            wy_triggered: false,
+            line_sprites: Vec::with_capacity(10),
             framebuffer: vec![0xFF; FRAMEBUFFER_SIZE],
+            indexed: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT],
             model,
             stat_interrupt_line: false,
             bg_palette: [[0; 4]; 8],
             obj_palette: [[0; 4]; 8],
-            bg_palette_data: [0xFF; 64],
-            obj_palette_data: [0xFF; 64],
+            lcd_off: false,
+            first_line: false,
+            blank_frame: false,
+            lcd_event: None,
+            rendering: true,
+            overlay: None,
         }
     }
     
@@ -157,8 +410,18 @@ impl Ppu {
         self.cycles = 0;
         self.ly = 0;
         self.window_line = 0;
+        self.wy_triggered = false;
+        self.line_sprites.clear();
         self.framebuffer.fill(0xFF);
+        self.indexed.fill(0);
         self.stat_interrupt_line = false;
+        self.lcd_off = false;
+        self.first_line = false;
+        self.blank_frame = false;
+        self.lcd_event = None;
+        if self.overlay.is_some() {
+            self.overlay = Some(DebugOverlay::new());
+        }
     }
     
     /// Step the PPU
@@ -166,20 +429,30 @@ impl Ppu {
         let mut result = PpuStepResult {
             vblank_interrupt: false,
             stat_interrupt: false,
+            hblank_started: false,
         };
         
-        let lcdc = mmu.io()[0x40];
+        let lcdc = mmu.lcd().lcdc;
         
         // LCD disabled
         if lcdc & 0x80 == 0 {
+            if !self.lcd_off {
+                self.lcd_off = true;
+                self.lcd_event = Some(LcdEvent::Disabled);
+            }
             self.mode = PpuMode::HBlank;
             self.ly = 0;
             self.cycles = 0;
-            mmu.io_mut()[0x44] = 0;
-            mmu.io_mut()[0x41] &= 0xFC;
+            mmu.lcd_mut().ly = 0;
+            mmu.lcd_mut().stat &= 0xFC;
+            self.stat_interrupt_line = false;
             return result;
         }
         
+        if self.lcd_off {
+            self.enable_lcd(mmu);
+        }
+        
         self.cycles += cycles;
         
         // Process mode transitions
@@ -187,7 +460,9 @@ impl Ppu {
             PpuMode::OamSearch => {
                 if self.cycles >= 80 {
                     self.cycles -= 80;
+                    self.scan_oam(mmu);
                     self.mode = PpuMode::PixelTransfer;
+                    self.first_line = false;
                 }
             }
             
@@ -195,18 +470,16 @@ impl Ppu {
                 if self.cycles >= 172 {
                     self.cycles -= 172;
                     self.mode = PpuMode::HBlank;
+                    result.hblank_started = true;
                     
                     // Render scanline
-                    if self.ly < SCREEN_HEIGHT as u8 {
+                    if self.blank_frame {
+                        self.clear_scanline();
+                    } else if self.ly < SCREEN_HEIGHT as u8 {
+                        self.draw_overlay_line(mmu);
                         self.render_scanline(mmu);
                     }
                     
-                    // HBlank STAT interrupt
-                    let stat = mmu.io()[0x41];
-                    if stat & 0x08 != 0 {
-                        result.stat_interrupt = self.check_stat_interrupt(mmu);
-                    }
-                    
                     // HBlank HDMA (CGB)
                     mmu.step_hblank_hdma();
                 }
@@ -216,30 +489,21 @@ impl Ppu {
                 if self.cycles >= 204 {
                     self.cycles -= 204;
                     self.ly += 1;
-                    mmu.io_mut()[0x44] = self.ly;
+                    mmu.lcd_mut().ly = self.ly;
                     
                     if self.ly == 144 {
                         self.mode = PpuMode::VBlank;
                         result.vblank_interrupt = true;
                         self.window_line = 0;
-                        
-                        // VBlank STAT interrupt
-                        let stat = mmu.io()[0x41];
-                        if stat & 0x10 != 0 {
-                            result.stat_interrupt = self.check_stat_interrupt(mmu);
+                        self.wy_triggered = false;
+                        self.blank_frame = false;
+                        if let Some(overlay) = &mut self.overlay {
+                            overlay.end_frame();
                         }
                     } else {
                         self.mode = PpuMode::OamSearch;
-                        
-                        // OAM STAT interrupt
-                        let stat = mmu.io()[0x41];
-                        if stat & 0x20 != 0 {
-                            result.stat_interrupt = self.check_stat_interrupt(mmu);
-                        }
+                        self.check_wy(mmu);
                     }
-                    
-                    // LYC=LY check
-                    self.check_lyc(mmu, &mut result);
                 }
             }
             
@@ -251,79 +515,172 @@ impl Ppu {
                     if self.ly >= TOTAL_LINES {
                         self.ly = 0;
                         self.mode = PpuMode::OamSearch;
-                        
-                        // OAM STAT interrupt
-                        let stat = mmu.io()[0x41];
-                        if stat & 0x20 != 0 {
-                            result.stat_interrupt = self.check_stat_interrupt(mmu);
-                        }
+                        self.check_wy(mmu);
                     }
                     
-                    mmu.io_mut()[0x44] = self.ly;
-                    self.check_lyc(mmu, &mut result);
+                    mmu.lcd_mut().ly = self.ly;
                 }
             }
         }
         
-        // Update STAT mode bits
-        let stat = mmu.io()[0x41];
-        mmu.io_mut()[0x41] = (stat & 0xFC) | (self.mode as u8);
+        // Update STAT mode bits (mode 0 during the first line's OAM search)
+        // and the LY=LYC flag, which also picks up LYC writes since the
+        // last step
+        let mode = if self.first_line { 0 } else { self.mode as u8 };
+        let lcd = mmu.lcd_mut();
+        let coincidence = if lcd.ly == lcd.lyc { 0x04 } else { 0 };
+        lcd.stat = (lcd.stat & 0xF8) | coincidence | mode;
+        
+        result.stat_interrupt = self.update_stat_line(lcd.stat);
         
         result
     }
     
-    /// Check LYC=LY and trigger STAT interrupt if needed
-    fn check_lyc(&mut self, mmu: &mut Mmu, result: &mut PpuStepResult) {
-        let lyc = mmu.io()[0x45];
-        let stat = mmu.io()[0x41];
+    /// Restart timing after the LCD is switched on: line 0 skips OAM
+    /// search and is 4 cycles short, and the frame is left blank
+    fn enable_lcd(&mut self, mmu: &Mmu) {
+        self.lcd_off = false;
+        self.mode = PpuMode::OamSearch;
+        self.cycles = 4;
+        self.ly = 0;
+        self.window_line = 0;
+        self.wy_triggered = false;
+        self.check_wy(mmu);
+        self.first_line = true;
+        self.blank_frame = true;
+        self.lcd_event = Some(LcdEvent::Enabled);
+    }
+    
+    /// Select the sprites on the current line: the first 10 in OAM order
+    /// whose rows cover LY
+    fn scan_oam(&mut self, mmu: &Mmu) {
+        let sprite_height: i32 = if mmu.lcd().lcdc & 0x04 != 0 { 16 } else { 8 };
+        let oam = mmu.oam();
+        let ly = self.ly as i32;
         
-        if self.ly == lyc {
-            // Set coincidence flag
-            mmu.io_mut()[0x41] = stat | 0x04;
+        self.line_sprites.clear();
+        for i in 0..40 {
+            // Sprite Y is offset by 16 (sprite.y = 16 means top of sprite at screen Y=0)
+            let sprite_y = oam[i * 4] as i32 - 16;
             
-            // LYC=LY STAT interrupt
-            if stat & 0x40 != 0 {
-                result.stat_interrupt = self.check_stat_interrupt(mmu);
+            if ly >= sprite_y && ly < sprite_y + sprite_height {
+                self.line_sprites.push(i as u8);
+                if self.line_sprites.len() >= 10 {
+                    break;
+                }
             }
-        } else {
-            // Clear coincidence flag
-            mmu.io_mut()[0x41] = stat & !0x04;
         }
     }
     
-    /// Check STAT interrupt with edge detection
-    fn check_stat_interrupt(&mut self, _mmu: &Mmu) -> bool {
+    /// Latch the window's WY condition at the start of a line
+    fn check_wy(&mut self, mmu: &Mmu) {
+        if self.ly == mmu.lcd().wy {
+            self.wy_triggered = true;
+        }
+    }
+    
+    /// Recompute the STAT interrupt line from every enabled source (the
+    /// mode 0/1/2 and LY=LYC selects in bits 3-6) and return true on a
+    /// rising edge. Sources that overlap keep the line high, so they
+    /// don't fire a second interrupt.
+    fn update_stat_line(&mut self, stat: u8) -> bool {
+        let mode = stat & 0x03;
+        let line = (stat & 0x08 != 0 && mode == 0)
+            || (stat & 0x10 != 0 && mode == 1)
+            || (stat & 0x20 != 0 && mode == 2)
+            || (stat & 0x40 != 0 && stat & 0x04 != 0);
+        
         let was_high = self.stat_interrupt_line;
-        self.stat_interrupt_line = true;
-        !was_high
+        self.stat_interrupt_line = line;
+        line && !was_high
+    }
+    
+    /// Clear the current scanline to white
+    fn clear_scanline(&mut self) {
+        if self.ly >= SCREEN_HEIGHT as u8 {
+            return;
+        }
+        
+        let offset = self.ly as usize * SCREEN_WIDTH * 4;
+        self.framebuffer[offset..offset + SCREEN_WIDTH * 4].fill(0xFF);
+        let row = self.ly as usize * SCREEN_WIDTH;
+        self.indexed[row..row + SCREEN_WIDTH].fill(0);
+    }
+    
+    /// Add the current line to the debug overlay: the window is drawn
+    /// where `render_window` would draw it and sprites are the ones the
+    /// OAM scan selected
+    fn draw_overlay_line(&mut self, mmu: &Mmu) {
+        if self.overlay.is_none() {
+            return;
+        }
+        
+        let lcd = mmu.lcd();
+        let layers = lcd.lcdc & 0x01 != 0 || self.cgb_mode();
+        let window = layers && lcd.lcdc & 0x20 != 0 && self.wy_triggered && lcd.wx <= 166;
+        let window_x = window.then(|| lcd.wx.saturating_sub(7) as usize);
+        
+        let height = if lcd.lcdc & 0x04 != 0 { 16 } else { 8 };
+        let sprites: Vec<SpriteBox> = if lcd.lcdc & 0x02 != 0 {
+            self.line_sprites.iter()
+                .map(|&index| {
+                    let entry = &mmu.oam()[index as usize * 4..];
+                    SpriteBox { x: entry[1] as i32 - 8, y: entry[0] as i32 - 16, height }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        if let Some(overlay) = &mut self.overlay {
+            overlay.draw_line(self.ly, lcd.scx, lcd.scy, window_x, &sprites);
+        }
+    }
+    
+    /// Draw layer outlines into a debug overlay (see `overlay`), or stop
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        if enabled != self.overlay.is_some() {
+            self.overlay = enabled.then(DebugOverlay::new);
+        }
+    }
+    
+    /// Layer outlines for the last complete frame, RGBA8888 at
+    /// `OVERLAY_SIZE`x`OVERLAY_SIZE` in background map space, if enabled
+    pub fn debug_overlay(&self) -> Option<&[u8]> {
+        self.overlay.as_ref().map(DebugOverlay::frame)
     }
     
     /// Render a single scanline
     fn render_scanline(&mut self, mmu: &Mmu) {
-        let lcdc = mmu.io()[0x40];
-        let ly = self.ly;
+        let lcdc = mmu.lcd().lcdc;
         
-        // Clear scanline to white
-        let offset = ly as usize * SCREEN_WIDTH * 4;
-        for x in 0..SCREEN_WIDTH {
-            let i = offset + x * 4;
-            self.framebuffer[i] = 0xFF;
-            self.framebuffer[i + 1] = 0xFF;
-            self.framebuffer[i + 2] = 0xFF;
-            self.framebuffer[i + 3] = 0xFF;
+        if !self.rendering {
+            // The window line counter is PPU state; keep it in step with
+            // what render_window would have done
+            let window = (lcdc & 0x01 != 0 || self.cgb_mode()) && lcdc & 0x20 != 0;
+            if window && self.wy_triggered && mmu.lcd().wx <= 166 {
+                self.window_line += 1;
+            }
+            return;
         }
         
-        // Background priority array (for sprite rendering)
+        self.clear_scanline();
+        
+        // Background priority array (for sprite rendering): color index,
+        // plus BG_ATTR_PRIORITY when the CGB tile attribute forces the BG
+        // above sprites
         let mut bg_priority = [0u8; SCREEN_WIDTH];
         
-        // Render background
-        if lcdc & 0x01 != 0 || matches!(self.model, GbModel::Cgb | GbModel::CgbDmg) {
+        // Outside CGB mode LCDC.0 blanks both background and window to
+        // color 0; in CGB mode it only takes away their priority
+        if lcdc & 0x01 != 0 || self.cgb_mode() {
             self.render_background(mmu, &mut bg_priority);
-        }
-        
-        // Render window
-        if lcdc & 0x20 != 0 {
-            self.render_window(mmu, &mut bg_priority);
+            
+            if lcdc & 0x20 != 0 {
+                self.render_window(mmu, &mut bg_priority);
+            }
+        } else {
+            self.blank_background();
         }
         
         // Render sprites
@@ -332,12 +689,87 @@ impl Ppu {
         }
     }
     
+    /// Draw the background as color 0 (LCDC.0 cleared outside CGB mode)
+    fn blank_background(&mut self) {
+        let color = self.apply_dmg_palette(0, 0);
+        for x in 0..SCREEN_WIDTH {
+            self.set_pixel(x, self.ly as usize, color, 0);
+        }
+    }
+    
+    /// CGB tile attributes for a tile map entry (0 outside CGB mode)
+    fn bg_attributes(&self, mmu: &Mmu, map_addr: u16) -> u8 {
+        if !self.is_cgb() {
+            return 0;
+        }
+        
+        // Attributes live in VRAM bank 1 at the same map address
+        let attr_offset = 0x2000 + (map_addr - 0x8000) as usize;
+        mmu.vram().get(attr_offset).copied().unwrap_or(0)
+    }
+    
+    /// Tile data for row `row` (0-7) of the tile map entry at `map_addr`,
+    /// as (low, high) bit planes with the leftmost pixel in bit 7. In CGB
+    /// mode the entry's attributes pick the VRAM bank and flip the tile.
+    fn bg_tile_row(&self, mmu: &Mmu, map_addr: u16, row: u16, signed_addressing: bool) -> (u8, u8) {
+        let tile_index = vram_byte(mmu, 0, map_addr);
+        let attributes = if self.cgb_mode() {
+            TileAttributes::from_byte(self.bg_attributes(mmu, map_addr))
+        } else {
+            TileAttributes::default()
+        };
+        
+        let row = if attributes.y_flip { 7 - row } else { row };
+        let tile_addr = if signed_addressing {
+            // Base is 0x9000, tile index is signed (-128 to 127)
+            let signed_index = tile_index as i8 as i16;
+            (0x9000i32 + (signed_index as i32 * 16) + (row as i32 * 2)) as u16
+        } else {
+            // Base is 0x8000, tile index is unsigned (0 to 255)
+            0x8000 + (tile_index as u16 * 16) + (row * 2)
+        };
+        
+        let low = vram_byte(mmu, attributes.vram_bank, tile_addr);
+        let high = vram_byte(mmu, attributes.vram_bank, tile_addr.wrapping_add(1));
+        if attributes.x_flip {
+            (low.reverse_bits(), high.reverse_bits())
+        } else {
+            (low, high)
+        }
+    }
+    
+    /// Decode a whole tile map, row by row (32x32 entries). Reads VRAM
+    /// directly, whatever bank VBK selects.
+    pub fn tile_map(&self, mmu: &Mmu, area: TileMapArea) -> Vec<TileMapEntry> {
+        let signed_addressing = mmu.lcd().lcdc & 0x10 == 0;
+        let vram = mmu.vram();
+        
+        (0..32 * 32)
+            .map(|i| {
+                let map_addr = area.base() + i;
+                let tile = vram[(map_addr - 0x8000) as usize];
+                let tile_addr = if signed_addressing {
+                    (0x9000i32 + tile as i8 as i32 * 16) as u16
+                } else {
+                    0x8000 + tile as u16 * 16
+                };
+                
+                TileMapEntry {
+                    tile,
+                    tile_addr,
+                    attributes: TileAttributes::from_byte(self.bg_attributes(mmu, map_addr)),
+                }
+            })
+            .collect()
+    }
+    
     /// Render background for current scanline
     fn render_background(&mut self, mmu: &Mmu, bg_priority: &mut [u8; SCREEN_WIDTH]) {
-        let lcdc = mmu.io()[0x40];
-        let scx = mmu.io()[0x43];
-        let scy = mmu.io()[0x42];
-        let bgp = mmu.io()[0x47];
+        let lcd = mmu.lcd();
+        let lcdc = lcd.lcdc;
+        let scx = lcd.scx;
+        let scy = lcd.scy;
+        let bgp = lcd.bgp;
         
         let tile_map_base: u16 = if lcdc & 0x08 != 0 { 0x9C00 } else { 0x9800 };
         let signed_addressing = lcdc & 0x10 == 0;
@@ -346,49 +778,37 @@ impl Ppu {
         let tile_row = (y / 8) as u16;
         let pixel_row = (y % 8) as u16;
         
-        for screen_x in 0..SCREEN_WIDTH {
+        for (screen_x, priority) in bg_priority.iter_mut().enumerate() {
             let x = (screen_x as u8).wrapping_add(scx);
             let tile_col = (x / 8) as u16;
             let pixel_col = 7 - (x % 8);
             
-            // Get tile index from tile map
+            // Get tile data for the tile map entry
             let map_addr = tile_map_base + (tile_row * 32) + tile_col;
-            let tile_index = mmu.read_byte(map_addr);
-            
-            // Calculate tile data address
-            let tile_addr = if signed_addressing {
-                // Base is 0x9000, tile index is signed (-128 to 127)
-                let signed_index = tile_index as i8 as i16;
-                (0x9000i32 + (signed_index as i32 * 16) + (pixel_row as i32 * 2)) as u16
-            } else {
-                // Base is 0x8000, tile index is unsigned (0 to 255)
-                0x8000 + (tile_index as u16 * 16) + (pixel_row * 2)
-            };
-            
-            // Get tile data
-            let low = mmu.read_byte(tile_addr);
-            let high = mmu.read_byte(tile_addr.wrapping_add(1));
+            let (low, high) = self.bg_tile_row(mmu, map_addr, pixel_row, signed_addressing);
             
             // Get color index
             let color_index = ((high >> pixel_col) & 1) << 1 | ((low >> pixel_col) & 1);
             
-            bg_priority[screen_x] = color_index;
+            *priority = color_index | (self.bg_attributes(mmu, map_addr) & BG_ATTR_PRIORITY);
             
             // Apply palette and draw pixel
-            let color = self.apply_dmg_palette(color_index, bgp);
-            self.set_pixel(screen_x, self.ly as usize, color);
+            let index = self.bg_pixel_index(mmu, map_addr, color_index, bgp);
+            let color = self.bg_color(mmu, index, color_index, bgp);
+            self.set_pixel(screen_x, self.ly as usize, color, index);
         }
     }
     
     /// Render window for current scanline
     fn render_window(&mut self, mmu: &Mmu, bg_priority: &mut [u8; SCREEN_WIDTH]) {
-        let lcdc = mmu.io()[0x40];
-        let wy = mmu.io()[0x4A];
-        let wx = mmu.io()[0x4B];
-        let bgp = mmu.io()[0x47];
+        let lcd = mmu.lcd();
+        let lcdc = lcd.lcdc;
+        let wx = lcd.wx;
+        let bgp = lcd.bgp;
         
-        // Window not visible on this line
-        if self.ly < wy || wx > 166 {
+        // Window not visible on this line. Once WY has matched LY this
+        // frame, later WY writes don't hide it again.
+        if !self.wy_triggered || wx > 166 {
             return;
         }
         
@@ -399,35 +819,26 @@ impl Ppu {
         let tile_row = (window_y / 8) as u16;
         let pixel_row = (window_y % 8) as u16;
         
+        // With WX below 7 the window's first columns are off screen
         let window_x_start = wx.saturating_sub(7) as usize;
+        let window_x_skip = 7u8.saturating_sub(wx);
         let mut drew_window = false;
         
-        for screen_x in window_x_start..SCREEN_WIDTH {
-            let window_x = (screen_x - window_x_start) as u8;
+        for (screen_x, priority) in bg_priority.iter_mut().enumerate().skip(window_x_start) {
+            let window_x = (screen_x - window_x_start) as u8 + window_x_skip;
             let tile_col = (window_x / 8) as u16;
             let pixel_col = 7 - (window_x % 8);
             
             let map_addr = tile_map_base + (tile_row * 32) + tile_col;
-            let tile_index = mmu.read_byte(map_addr);
-            
-            let tile_addr = if signed_addressing {
-                // Base is 0x9000, tile index is signed (-128 to 127)
-                let signed_index = tile_index as i8 as i16;
-                (0x9000i32 + (signed_index as i32 * 16) + (pixel_row as i32 * 2)) as u16
-            } else {
-                // Base is 0x8000, tile index is unsigned (0 to 255)
-                0x8000 + (tile_index as u16 * 16) + (pixel_row * 2)
-            };
-            
-            let low = mmu.read_byte(tile_addr);
-            let high = mmu.read_byte(tile_addr.wrapping_add(1));
+            let (low, high) = self.bg_tile_row(mmu, map_addr, pixel_row, signed_addressing);
             
             let color_index = ((high >> pixel_col) & 1) << 1 | ((low >> pixel_col) & 1);
             
-            bg_priority[screen_x] = color_index;
+            *priority = color_index | (self.bg_attributes(mmu, map_addr) & BG_ATTR_PRIORITY);
             
-            let color = self.apply_dmg_palette(color_index, bgp);
-            self.set_pixel(screen_x, self.ly as usize, color);
+            let index = self.bg_pixel_index(mmu, map_addr, color_index, bgp);
+            let color = self.bg_color(mmu, index, color_index, bgp);
+            self.set_pixel(screen_x, self.ly as usize, color, index);
             
             drew_window = true;
         }
@@ -439,51 +850,37 @@ impl Ppu {
     
     /// Render sprites for current scanline
     fn render_sprites(&mut self, mmu: &Mmu, bg_priority: &[u8; SCREEN_WIDTH]) {
-        let lcdc = mmu.io()[0x40];
-        let obp0 = mmu.io()[0x48];
-        let obp1 = mmu.io()[0x49];
+        let lcd = mmu.lcd();
+        let lcdc = lcd.lcdc;
+        let obp0 = lcd.obp0;
+        let obp1 = lcd.obp1;
         
         let sprite_height: i32 = if lcdc & 0x04 != 0 { 16 } else { 8 };
         let oam = mmu.oam();
         
-        // Collect sprites on this scanline (max 10)
-        let mut sprites: Vec<(usize, Sprite)> = Vec::with_capacity(10);
-        
         let ly = self.ly as i32;
         
-        for i in 0..40 {
-            let offset = i * 4;
-            let sprite = Sprite {
-                y: oam[offset],
-                x: oam[offset + 1],
-                tile: oam[offset + 2],
-                flags: oam[offset + 3],
-            };
-            
-            // Sprite Y is offset by 16 (sprite.y = 16 means top of sprite at screen Y=0)
-            let sprite_y = sprite.y as i32 - 16;
-            
-            // Check if sprite is on this scanline
-            if ly >= sprite_y && ly < sprite_y + sprite_height {
-                sprites.push((i, sprite));
-                if sprites.len() >= 10 {
-                    break;
-                }
-            }
+        // Sprites chosen by the OAM scan; their attributes are fetched now,
+        // so OAM writes since the scan still show up
+        let mut sprites: Vec<Sprite> = self.line_sprites.iter()
+            .map(|&i| Sprite::from_oam(oam, i as usize))
+            .filter(|sprite| {
+                let sprite_y = sprite.y as i32 - 16;
+                ly >= sprite_y && ly < sprite_y + sprite_height
+            })
+            .collect();
+        
+        // Highest priority first. The scan keeps OAM order, which is the
+        // priority in CGB mode; otherwise lower X wins, then lower index.
+        if !self.cgb_mode() {
+            sprites.sort_by_key(|sprite| sprite.x);
         }
         
-        // Sort by X coordinate (lower X = higher priority)
-        // For DMG, on equal X, lower OAM index wins
-        sprites.sort_by(|a, b| {
-            if a.1.x == b.1.x {
-                a.0.cmp(&b.0)
-            } else {
-                a.1.x.cmp(&b.1.x)
-            }
-        });
+        // Pixels where a higher-priority sprite is opaque. Lower sprites
+        // never show there, even when that sprite is behind the BG.
+        let mut claimed = [false; SCREEN_WIDTH];
         
-        // Render sprites in reverse order (so higher priority draws last)
-        for (_, sprite) in sprites.iter().rev() {
+        for sprite in &sprites {
             let sprite_x = sprite.x as i32 - 8;
             let sprite_y = sprite.y as i32 - 16;
             
@@ -506,10 +903,12 @@ impl Ppu {
             
             let row = row % 8;
             
-            // Get tile data (sprites always use 0x8000 addressing)
+            // Get tile data (sprites always use 0x8000 addressing, and in
+            // CGB mode the bank from their attributes)
+            let bank = if self.cgb_mode() { sprite.vram_bank() } else { 0 };
             let tile_addr = 0x8000 + (tile as u16 * 16) + (row as u16 * 2);
-            let low = mmu.read_byte(tile_addr);
-            let high = mmu.read_byte(tile_addr + 1);
+            let low = vram_byte(mmu, bank, tile_addr);
+            let high = vram_byte(mmu, bank, tile_addr + 1);
             
             // Draw each pixel of the sprite
             for pixel_x in 0..8i32 {
@@ -520,6 +919,9 @@ impl Ppu {
                 }
                 
                 let screen_x = screen_x as usize;
+                if claimed[screen_x] {
+                    continue;
+                }
                 
                 // Apply X flip
                 let bit = if sprite.x_flip() {
@@ -534,18 +936,31 @@ impl Ppu {
                 if color_index == 0 {
                     continue;
                 }
+                claimed[screen_x] = true;
                 
-                // Check BG priority
-                // If sprite has BG priority flag set AND bg pixel is not color 0, skip
-                if sprite.priority() && bg_priority[screen_x] != 0 {
+                // Check BG priority: BG colors 1-3 cover the sprite if the
+                // sprite's priority flag or (CGB) the tile's attribute asks
+                // for it, unless LCDC.0 clears BG priority in CGB mode
+                let bg = bg_priority[screen_x];
+                let bg_wins = bg & 0x03 != 0 && (sprite.priority() || bg & BG_ATTR_PRIORITY != 0);
+                if bg_wins && !(self.cgb_mode() && lcdc & 0x01 == 0) {
                     continue;
                 }
                 
                 // Apply palette
                 let palette = if sprite.palette() == 0 { obp0 } else { obp1 };
-                let color = self.apply_dmg_palette(color_index, palette);
+                let color = if self.cgb_mode() {
+                    mmu.palettes().obj_rgba(sprite.cgb_palette(), color_index)
+                } else {
+                    self.apply_dmg_palette(color_index, palette)
+                };
+                let index = if self.is_cgb() {
+                    0x20 | (sprite.cgb_palette() << 2) | color_index
+                } else {
+                    (palette >> (color_index * 2)) & 0x03
+                };
                 
-                self.set_pixel(screen_x, self.ly as usize, color);
+                self.set_pixel(screen_x, self.ly as usize, color, index);
             }
         }
     }
@@ -554,6 +969,12 @@ impl Ppu {
     fn apply_dmg_palette(&self, color_index: u8, palette: u8) -> [u8; 4] {
         let shade = (palette >> (color_index * 2)) & 0x03;
         
+        // The Pocket's screen has no green tint: plain greys
+        if self.model == GbModel::Pocket {
+            let level = [0xE8, 0xA8, 0x60, 0x1C][shade as usize];
+            return [level, level, level, 0xFF];
+        }
+        
         // Warm beige/sepia tones - easy on the eyes
         match shade {
             0 => [0xF5, 0xF0, 0xE6, 0xFF], // Lightest - warm white/cream
@@ -564,19 +985,94 @@ impl Ppu {
         }
     }
     
+    /// Color of a background/window pixel: from palette RAM in CGB mode,
+    /// through BGP otherwise. `index` is the pixel's `bg_pixel_index`.
+    fn bg_color(&self, mmu: &Mmu, index: u8, color_index: u8, bgp: u8) -> [u8; 4] {
+        if self.cgb_mode() {
+            mmu.palettes().bg_rgba(index >> 2, color_index)
+        } else {
+            self.apply_dmg_palette(color_index, bgp)
+        }
+    }
+    
+    fn is_cgb(&self) -> bool {
+        self.model.is_cgb()
+    }
+    
+    /// Running a CGB game, as opposed to DMG compatibility mode
+    fn cgb_mode(&self) -> bool {
+        matches!(self.model, GbModel::Cgb | GbModel::Agb)
+    }
+    
+    /// Indexed value of a background/window pixel: the BGP shade on DMG,
+    /// the tile's attribute palette and color index on CGB
+    fn bg_pixel_index(&self, mmu: &Mmu, map_addr: u16, color_index: u8, bgp: u8) -> u8 {
+        if self.is_cgb() {
+            let palette = self.bg_attributes(mmu, map_addr) & 0x07;
+            (palette << 2) | color_index
+        } else {
+            (bgp >> (color_index * 2)) & 0x03
+        }
+    }
+    
     /// Set pixel in framebuffer
-    fn set_pixel(&mut self, x: usize, y: usize, color: [u8; 4]) {
+    fn set_pixel(&mut self, x: usize, y: usize, color: [u8; 4], index: u8) {
         if x < SCREEN_WIDTH && y < SCREEN_HEIGHT {
             let offset = (y * SCREEN_WIDTH + x) * 4;
             self.framebuffer[offset..offset + 4].copy_from_slice(&color);
+            self.indexed[y * SCREEN_WIDTH + x] = index;
         }
     }
     
+    /// Take the latest LCD power change since the last call
+    pub fn take_lcd_event(&mut self) -> Option<LcdEvent> {
+        self.lcd_event.take()
+    }
+    
+    /// The frame being drawn is the blank one after the LCD was switched on
+    pub fn is_blank_frame(&self) -> bool {
+        self.blank_frame
+    }
+    
+    /// Skip (or resume) drawing scanlines. The framebuffers keep their
+    /// last contents while rendering is off.
+    pub fn set_rendering(&mut self, enabled: bool) {
+        self.rendering = enabled;
+    }
+    
     /// Get framebuffer
     pub fn framebuffer(&self) -> &[u8] {
         &self.framebuffer
     }
     
+    /// Exchange the framebuffer with a caller-owned buffer of
+    /// `FRAMEBUFFER_SIZE` bytes, without copying.
+    ///
+    /// Call this between frames: `buffer` receives the finished frame and
+    /// the PPU renders the next one into the buffer passed in. Lines that
+    /// aren't drawn (LCD off) keep whatever the new buffer held.
+    pub fn swap_framebuffer(&mut self, buffer: &mut Vec<u8>) -> Result<(), String> {
+        if buffer.len() != FRAMEBUFFER_SIZE {
+            return Err(format!(
+                "Framebuffer must be {} bytes, got {}",
+                FRAMEBUFFER_SIZE,
+                buffer.len()
+            ));
+        }
+        
+        std::mem::swap(&mut self.framebuffer, buffer);
+        Ok(())
+    }
+    
+    /// Get the frame as one color index per pixel (160x144), independent
+    /// of the RGBA palette:
+    /// - DMG: shade 0-3 after BGP/OBP0/OBP1 (0 = lightest)
+    /// - CGB: bits 0-1 color index, bits 2-4 palette number, bit 5 set for
+    ///   object pixels
+    pub fn framebuffer_indexed(&self) -> &[u8] {
+        &self.indexed
+    }
+    
     /// Get current state for serialization
     pub fn state(&self) -> PpuState {
         PpuState {
@@ -587,11 +1083,43 @@ impl Ppu {
             stat_interrupt_line: self.stat_interrupt_line,
             bg_palette: self.bg_palette,
             obj_palette: self.obj_palette,
+            lcd_off: self.lcd_off,
+            first_line: self.first_line,
+            blank_frame: self.blank_frame,
+            wy_triggered: self.wy_triggered,
+            line_sprites: self.line_sprites.clone(),
+            framebuffer: rle_encode(&self.framebuffer, 4),
+            indexed: rle_encode(&self.indexed, 1),
         }
     }
     
-    /// Load state from serialization
-    pub fn load_state(&mut self, state: PpuState) {
+    /// Check that a state's framebuffers, if it has them, decode to whole
+    /// frames
+    pub fn check_state(&self, state: &PpuState) -> Result<(), StateError> {
+        let frames = [
+            ("framebuffer", &state.framebuffer, 4, FRAMEBUFFER_SIZE),
+            ("indexed framebuffer", &state.indexed, 1, SCREEN_WIDTH * SCREEN_HEIGHT),
+        ];
+        for (area, encoded, unit, expected) in frames {
+            if encoded.is_empty() {
+                continue;
+            }
+            let found = rle_decoded_len(encoded, unit).ok_or(StateError::Truncated { area })?;
+            if found != expected {
+                return Err(StateError::SizeMismatch { area, expected, found });
+            }
+        }
+        Ok(())
+    }
+    
+    /// Load state from serialization. Nothing is changed if the state
+    /// fails `check_state`. States from before the framebuffer was saved
+    /// keep the current contents.
+    pub fn load_state(&mut self, state: PpuState) -> Result<(), StateError> {
+        self.check_state(&state)?;
+        let framebuffer = rle_decode(&state.framebuffer, 4);
+        let indexed = rle_decode(&state.indexed, 1);
+        
         self.mode = state.mode;
         self.cycles = state.cycles;
         self.ly = state.ly;
@@ -599,5 +1127,70 @@ impl Ppu {
         self.stat_interrupt_line = state.stat_interrupt_line;
         self.bg_palette = state.bg_palette;
         self.obj_palette = state.obj_palette;
+        self.lcd_off = state.lcd_off;
+        self.first_line = state.first_line;
+        self.blank_frame = state.blank_frame;
+        self.wy_triggered = state.wy_triggered;
+        self.line_sprites = state.line_sprites;
+        self.lcd_event = None;
+        
+        if let Some(framebuffer) = framebuffer {
+            self.framebuffer = framebuffer;
+        }
+        if let Some(indexed) = indexed {
+            self.indexed = indexed;
+        }
+        
+        Ok(())
+    }
+}
+
+/// Tile map or tile data byte at `addr` in VRAM bank `bank`, whatever
+/// bank VBK maps for the CPU
+fn vram_byte(mmu: &Mmu, bank: u8, addr: u16) -> u8 {
+    mmu.vram()[bank as usize * 0x2000 + (addr & 0x1FFF) as usize]
+}
+
+/// Run-length encode `data` in units of `unit` bytes as (count, unit)
+/// records, counts 1-255. Game Boy frames are mostly long runs.
+fn rle_encode(data: &[u8], unit: usize) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    let mut units = data.chunks_exact(unit).peekable();
+    
+    while let Some(value) = units.next() {
+        let mut count = 1u8;
+        while count < u8::MAX && units.peek() == Some(&value) {
+            units.next();
+            count += 1;
+        }
+        encoded.push(count);
+        encoded.extend_from_slice(value);
+    }
+    
+    encoded
+}
+
+/// Bytes `rle_encode` output expands to, or `None` if the last record is
+/// cut short
+fn rle_decoded_len(encoded: &[u8], unit: usize) -> Option<usize> {
+    let records = encoded.chunks_exact(unit + 1);
+    if !records.remainder().is_empty() {
+        return None;
+    }
+    Some(records.map(|record| record[0] as usize * unit).sum())
+}
+
+/// Decode `rle_encode` output; `None` for an empty (absent) encoding
+fn rle_decode(encoded: &[u8], unit: usize) -> Option<Vec<u8>> {
+    if encoded.is_empty() {
+        return None;
+    }
+    
+    let mut data = Vec::new();
+    for record in encoded.chunks_exact(unit + 1) {
+        for _ in 0..record[0] {
+            data.extend_from_slice(&record[1..]);
+        }
     }
+    Some(data)
 }
\ No newline at end of file
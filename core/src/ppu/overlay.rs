@@ -0,0 +1,95 @@
+//! Debug overlay for layer inspectors
+//!
+//! An RGBA image the size of a background tile map (256x256) showing, for
+//! every line the PPU drew, where the screen was scrolled to (the
+//! viewport outline), which part of it the window covered and the boxes
+//! of the sprites on it. Lines are placed with the scroll they were drawn
+//! with, so raster effects that change SCX/SCY mid-frame show up as they
+//! happened. The finished image is swapped in at VBlank, in step with the
+//! framebuffer.
+
+/// Width and height of the overlay in pixels
+pub const OVERLAY_SIZE: usize = 256;
+
+/// Viewport outline
+const VIEWPORT_COLOR: [u8; 4] = [0xFF, 0x40, 0x40, 0xFF];
+
+/// Window area (translucent)
+const WINDOW_COLOR: [u8; 4] = [0x40, 0xC0, 0x40, 0x60];
+
+/// Sprite bounding boxes
+const SPRITE_COLOR: [u8; 4] = [0x40, 0x80, 0xFF, 0xFF];
+
+/// Screen-space box of a sprite on the current line
+pub(super) struct SpriteBox {
+    pub x: i32,
+    pub y: i32,
+    pub height: i32,
+}
+
+/// Overlay being drawn this frame and the last finished one
+pub(super) struct DebugOverlay {
+    drawing: Vec<u8>,
+    frame: Vec<u8>,
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self {
+            drawing: vec![0; OVERLAY_SIZE * OVERLAY_SIZE * 4],
+            frame: vec![0; OVERLAY_SIZE * OVERLAY_SIZE * 4],
+        }
+    }
+    
+    /// Last finished overlay
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+    
+    /// Publish the overlay drawn this frame and start a new one
+    pub fn end_frame(&mut self) {
+        std::mem::swap(&mut self.drawing, &mut self.frame);
+        self.drawing.fill(0);
+    }
+    
+    /// Add screen line `ly`, drawn with the given scroll. `window_x` is the
+    /// first screen column the window covered, if it was drawn.
+    pub fn draw_line(&mut self, ly: u8, scx: u8, scy: u8, window_x: Option<usize>, sprites: &[SpriteBox]) {
+        let width = super::SCREEN_WIDTH as i32;
+        let ly = ly as i32;
+        let row = scy.wrapping_add(ly as u8) as usize;
+        let mut put = |x: i32, color: [u8; 4]| {
+            if (0..width).contains(&x) {
+                let col = scx.wrapping_add(x as u8) as usize;
+                let offset = (row * OVERLAY_SIZE + col) * 4;
+                self.drawing[offset..offset + 4].copy_from_slice(&color);
+            }
+        };
+        
+        if let Some(start) = window_x {
+            for x in start as i32..width {
+                put(x, WINDOW_COLOR);
+            }
+        }
+        
+        for sprite in sprites {
+            if ly == sprite.y || ly == sprite.y + sprite.height - 1 {
+                for x in sprite.x..sprite.x + 8 {
+                    put(x, SPRITE_COLOR);
+                }
+            } else {
+                put(sprite.x, SPRITE_COLOR);
+                put(sprite.x + 7, SPRITE_COLOR);
+            }
+        }
+        
+        if ly == 0 || ly == super::SCREEN_HEIGHT as i32 - 1 {
+            for x in 0..width {
+                put(x, VIEWPORT_COLOR);
+            }
+        } else {
+            put(0, VIEWPORT_COLOR);
+            put(width - 1, VIEWPORT_COLOR);
+        }
+    }
+}
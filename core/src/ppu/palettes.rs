@@ -0,0 +1,143 @@
+//! CGB palette RAM
+//!
+//! Eight background and eight object palettes of four RGB555 colors,
+//! reached through an index register (BCPS/OCPS) and a data register
+//! (BCPD/OCPD) each. Bit 7 of an index register advances it after every
+//! data write, so a game can upload a whole palette set with one index
+//! write followed by a run of data writes.
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::mmu::IoDevice;
+
+/// CGB palette registers (0xFF68-0xFF6B) and the palette RAM behind them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CgbPalettes {
+    /// BCPS: background palette RAM address (bits 0-5), auto-increment
+    /// (bit 7)
+    pub bg_index: u8,
+    /// OCPS: object palette RAM address and auto-increment
+    pub obj_index: u8,
+    /// Background palette RAM, one row of 4 little-endian RGB555 colors
+    /// per palette
+    pub bg_data: [[u8; 8]; 8],
+    /// Object palette RAM
+    pub obj_data: [[u8; 8]; 8],
+    
+    /// The registers exist (CGB hardware); set by the bus from the model
+    #[serde(skip)]
+    cgb_mode: bool,
+}
+
+impl CgbPalettes {
+    /// Create palettes as the CGB boot ROM leaves them: all white
+    pub fn new() -> Self {
+        Self {
+            bg_index: 0x00,
+            obj_index: 0x00,
+            bg_data: [[0xFF; 8]; 8],
+            obj_data: [[0xFF; 8]; 8],
+            cgb_mode: false,
+        }
+    }
+    
+    pub fn reset(&mut self) {
+        *self = Self { cgb_mode: self.cgb_mode, ..Self::new() };
+    }
+    
+    /// Enable the registers (CGB hardware) or leave them unmapped
+    pub fn set_cgb_mode(&mut self, cgb: bool) {
+        self.cgb_mode = cgb;
+    }
+    
+    /// Read a color as RGB555: `index` 0-31 are background colors
+    /// (palette * 4 + color), 32-63 object colors
+    pub fn color(&self, index: usize) -> u16 {
+        let data = if index & 0x20 == 0 { &self.bg_data } else { &self.obj_data };
+        let offset = (index & 0x03) * 2;
+        let row = &data[(index >> 2) & 0x07];
+        u16::from_le_bytes([row[offset], row[offset + 1]]) & 0x7FFF
+    }
+    
+    /// Overwrite a color (indexed like `color`), for debugging and art
+    /// tools. It shows from the next line drawn.
+    pub fn write_color(&mut self, index: usize, rgb555: u16) {
+        let data = if index & 0x20 == 0 { &mut self.bg_data } else { &mut self.obj_data };
+        let offset = (index & 0x03) * 2;
+        data[(index >> 2) & 0x07][offset..offset + 2].copy_from_slice(&(rgb555 & 0x7FFF).to_le_bytes());
+    }
+    
+    /// RGBA8888 of a background color
+    pub fn bg_rgba(&self, palette: u8, color: u8) -> [u8; 4] {
+        rgba(self.color(((palette & 0x07) << 2 | color & 0x03) as usize))
+    }
+    
+    /// RGBA8888 of an object color
+    pub fn obj_rgba(&self, palette: u8, color: u8) -> [u8; 4] {
+        rgba(self.color((0x20 | (palette & 0x07) << 2 | color & 0x03) as usize))
+    }
+}
+
+impl Default for CgbPalettes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Byte `index & 0x3F` of a palette RAM
+fn ram_byte(data: &[[u8; 8]; 8], index: u8) -> u8 {
+    data[(index as usize >> 3) & 0x07][index as usize & 0x07]
+}
+
+/// Write a data register at its index and advance the index if bit 7 of
+/// it is set
+fn write_data(data: &mut [[u8; 8]; 8], index: &mut u8, value: u8) {
+    data[(*index as usize >> 3) & 0x07][*index as usize & 0x07] = value;
+    if *index & 0x80 != 0 {
+        *index = 0x80 | (index.wrapping_add(1) & 0x3F);
+    }
+}
+
+/// Expand an RGB555 color to RGBA8888
+fn rgba(rgb555: u16) -> [u8; 4] {
+    let channel = |shift: u16| {
+        let value = ((rgb555 >> shift) & 0x1F) as u8;
+        (value << 3) | (value >> 2)
+    };
+    [channel(0), channel(5), channel(10), 0xFF]
+}
+
+impl IoDevice for CgbPalettes {
+    const IO_RANGES: &'static [RangeInclusive<u16>] = &[0xFF68..=0xFF6B];
+    
+    fn read_io(&self, addr: u16) -> u8 {
+        if !self.cgb_mode {
+            return 0xFF;
+        }
+        
+        match addr {
+            // Bit 6 of the index registers is unused and reads as 1
+            0xFF68 => self.bg_index | 0x40,
+            0xFF69 => ram_byte(&self.bg_data, self.bg_index),
+            0xFF6A => self.obj_index | 0x40,
+            0xFF6B => ram_byte(&self.obj_data, self.obj_index),
+            _ => 0xFF,
+        }
+    }
+    
+    fn write_io(&mut self, addr: u16, value: u8) {
+        if !self.cgb_mode {
+            return;
+        }
+        
+        match addr {
+            0xFF68 => self.bg_index = value & 0xBF,
+            0xFF69 => write_data(&mut self.bg_data, &mut self.bg_index, value),
+            0xFF6A => self.obj_index = value & 0xBF,
+            0xFF6B => write_data(&mut self.obj_data, &mut self.obj_index, value),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! ROM hashes and database lookup
+
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+/// Hashes of a ROM image, as listed in No-Intro DATs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RomHashes {
+    pub crc32: u32,
+    
+    /// Lowercase hex
+    pub sha1: String,
+}
+
+impl RomHashes {
+    pub fn new(rom: &[u8]) -> Self {
+        Self {
+            crc32: crc32fast::hash(rom),
+            sha1: sha1_smol::Sha1::from(rom).digest().to_string(),
+        }
+    }
+}
+
+/// A known dump
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RomEntry {
+    /// Canonical name, e.g. "Tetris (World) (Rev 1)"
+    pub name: String,
+    
+    #[serde(default)]
+    pub region: String,
+    
+    /// CRC32 in hex
+    #[serde(default)]
+    pub crc32: Option<String>,
+    
+    /// SHA-1 in hex
+    #[serde(default)]
+    pub sha1: Option<String>,
+    
+    /// Known good dump
+    #[serde(default)]
+    pub verified: bool,
+}
+
+/// Source of ROM identities (a DAT file, an online service, ...)
+pub trait RomDatabase {
+    /// Find the entry for a ROM
+    fn lookup(&self, hashes: &RomHashes) -> Option<RomEntry>;
+}
+
+/// In-memory database, matched by SHA-1 and then by CRC32
+#[derive(Debug, Clone, Default)]
+pub struct RomTable {
+    entries: Vec<RomEntry>,
+    by_sha1: HashMap<String, usize>,
+    by_crc32: HashMap<u32, usize>,
+}
+
+impl RomTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Load a JSON array of entries, e.g.
+    /// `[{"name": "...", "region": "World", "crc32": "46DF91AD", "sha1": "...", "verified": true}]`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let entries: Vec<RomEntry> = serde_json::from_str(json)
+            .map_err(|e| format!("Invalid ROM table: {}", e))?;
+        
+        let mut table = Self::new();
+        for entry in entries {
+            table.insert(entry)?;
+        }
+        Ok(table)
+    }
+    
+    /// Add an entry; it needs at least one hash
+    pub fn insert(&mut self, mut entry: RomEntry) -> Result<(), String> {
+        let crc32 = entry.crc32.as_deref()
+            .map(|hex| u32::from_str_radix(hex, 16)
+                .map_err(|_| format!("{}: invalid CRC32 {:?}", entry.name, hex)))
+            .transpose()?;
+        if let Some(sha1) = entry.sha1.as_mut() {
+            if sha1.len() != 40 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("{}: invalid SHA-1 {:?}", entry.name, sha1));
+            }
+            sha1.make_ascii_lowercase();
+        }
+        if crc32.is_none() && entry.sha1.is_none() {
+            return Err(format!("{}: no hash", entry.name));
+        }
+        
+        let index = self.entries.len();
+        if let Some(sha1) = &entry.sha1 {
+            self.by_sha1.insert(sha1.clone(), index);
+        }
+        if let Some(crc32) = crc32 {
+            self.by_crc32.insert(crc32, index);
+        }
+        self.entries.push(entry);
+        Ok(())
+    }
+    
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl RomDatabase for RomTable {
+    fn lookup(&self, hashes: &RomHashes) -> Option<RomEntry> {
+        self.by_sha1.get(&hashes.sha1)
+            .or_else(|| self.by_crc32.get(&hashes.crc32))
+            .map(|&index| self.entries[index].clone())
+    }
+}
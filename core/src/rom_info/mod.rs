@@ -0,0 +1,94 @@
+//! # ROM Info
+//!
+//! Cartridge header details for game libraries and, with the `rom-hash`
+//! feature, CRC32 and SHA-1 hashes to identify the dump against a
+//! database such as No-Intro. The database is pluggable (`RomDatabase`);
+//! `RomTable` is an in-memory one loaded from JSON. `preview_frame`
+//! renders a thumbnail for library grids.
+
+#[cfg(feature = "rom-hash")]
+mod database;
+
+#[cfg(feature = "rom-hash")]
+pub use database::{RomDatabase, RomEntry, RomHashes, RomTable};
+
+use serde::Serialize;
+
+use crate::cartridge::{Cartridge, MbcType};
+use crate::GameBoy;
+
+/// What is known about the loaded ROM
+#[derive(Debug, Clone, Serialize)]
+pub struct RomInfo {
+    /// Title from the header
+    pub title: String,
+    
+    /// CGB-enhanced or CGB-only
+    pub cgb: bool,
+    
+    /// Memory bank controller
+    pub mbc: MbcType,
+    
+    /// ROM size in bytes as loaded
+    pub rom_size: usize,
+    
+    /// External RAM size in bytes
+    pub ram_size: usize,
+    
+    /// Battery-backed RAM
+    pub battery: bool,
+    
+    /// The header checksum is correct
+    pub header_checksum_valid: bool,
+    
+    /// Hashes of the whole ROM
+    #[cfg(feature = "rom-hash")]
+    pub hashes: RomHashes,
+    
+    /// Database entry, once found by `identify`
+    #[cfg(feature = "rom-hash")]
+    pub entry: Option<RomEntry>,
+}
+
+impl RomInfo {
+    /// Read the header of a cartridge (and hash its ROM)
+    pub fn new(cartridge: &Cartridge) -> Self {
+        Self {
+            title: cartridge.title().to_string(),
+            cgb: cartridge.is_cgb(),
+            mbc: cartridge.mbc_type(),
+            rom_size: cartridge.rom().len(),
+            ram_size: cartridge.ram().len(),
+            battery: cartridge.has_battery(),
+            header_checksum_valid: cartridge.header_checksum_valid(),
+            #[cfg(feature = "rom-hash")]
+            hashes: RomHashes::new(cartridge.rom()),
+            #[cfg(feature = "rom-hash")]
+            entry: None,
+        }
+    }
+    
+    /// Look the ROM up in `database`, keeping the entry if found
+    #[cfg(feature = "rom-hash")]
+    pub fn identify(&mut self, database: &dyn RomDatabase) -> Option<&RomEntry> {
+        self.entry = database.lookup(&self.hashes);
+        self.entry.as_ref()
+    }
+}
+
+/// Run `rom` headlessly for `frames` frames and return the last frame as
+/// RGBA, for library thumbnails. No audio is produced and only the last
+/// frame is drawn, so this is much faster than running the frames
+/// normally; the emulation itself is identical.
+pub fn preview_frame(rom: &[u8], frames: u32) -> Result<Vec<u8>, String> {
+    let mut gb = GameBoy::new(rom)?;
+    gb.apu_mut().set_sample_output(false);
+    gb.ppu.set_rendering(false);
+    
+    for _ in 1..frames {
+        gb.run_frame_video();
+    }
+    gb.ppu.set_rendering(true);
+    
+    Ok(gb.run_frame_video().to_vec())
+}
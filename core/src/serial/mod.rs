@@ -1,8 +1,45 @@
 //! # Serial Module
 //! 
 //! Handles serial communication (Link Cable).
-//! For now, this is a minimal implementation that just handles
-//! the timing for internal clock mode.
+//! Internal clock transfers are timed here; when linked to another
+//! instance (see `Session`), clocked bits are exchanged with the partner
+//! and external clock transfers are driven by the partner's clock.
+//! Otherwise an `ExternalClock` hook or the `DisconnectedPolicy` decides
+//! what happens to externally clocked transfers.
+
+use std::ops::RangeInclusive;
+
+use crate::mmu::IoDevice;
+use serde::{Serialize, Deserialize};
+
+/// Source of the serial clock when the Game Boy is not the master
+pub trait ExternalClock {
+    /// Called on every step while an externally clocked transfer is in
+    /// progress. `bit_out` is the bit currently on our output line; return
+    /// `Some(bit)` to clock one bit in, or `None` if no edge arrived in
+    /// the last `cycles` cycles.
+    fn poll(&mut self, cycles: u32, bit_out: u8) -> Option<u8>;
+}
+
+/// Behavior of externally clocked transfers with nothing connected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DisconnectedPolicy {
+    /// The transfer waits forever, like real hardware with no cable
+    #[default]
+    NeverComplete,
+    
+    /// The transfer completes with 0xFF received after the given number of
+    /// cycles, for games that hang waiting on a partner
+    Timeout(u32),
+}
+
+/// Serial state for serialization
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SerialState {
+    pub data: u8,
+    pub control: u8,
+    pub bits_remaining: u8,
+}
 
 /// Serial port implementation
 pub struct Serial {
@@ -12,11 +49,32 @@ pub struct Serial {
     /// Serial control
     control: u8,
     
-    /// Transfer counter
-    transfer_counter: u32,
+    /// Running on CGB hardware (SC bit 1 selects the fast clock)
+    cgb: bool,
     
     /// Bits remaining to transfer
     bits_remaining: u8,
+    
+    /// Connected to a link partner
+    linked: bool,
+    
+    /// Level of the partner's serial output (1 when disconnected)
+    link_in: u8,
+    
+    /// Bits shifted out on our internal clock, not yet delivered
+    sent_bits: Vec<u8>,
+    
+    /// An externally clocked transfer completed
+    interrupt_pending: bool,
+    
+    /// Hook driving externally clocked transfers
+    external_clock: Option<Box<dyn ExternalClock>>,
+    
+    /// Behavior of externally clocked transfers without a partner or hook
+    disconnected_policy: DisconnectedPolicy,
+    
+    /// Cycles the current externally clocked transfer has waited
+    external_wait: u32,
 }
 
 impl Serial {
@@ -24,35 +82,124 @@ impl Serial {
         Self {
             data: 0,
             control: 0,
-            transfer_counter: 0,
+            cgb: false,
             bits_remaining: 0,
+            linked: false,
+            link_in: 1,
+            sent_bits: Vec::new(),
+            interrupt_pending: false,
+            external_clock: None,
+            disconnected_policy: DisconnectedPolicy::NeverComplete,
+            external_wait: 0,
         }
     }
     
     pub fn reset(&mut self) {
         self.data = 0;
         self.control = 0;
-        self.transfer_counter = 0;
         self.bits_remaining = 0;
+        self.link_in = 1;
+        self.sent_bits.clear();
+        self.interrupt_pending = false;
+        self.external_wait = 0;
+    }
+    
+    /// Select CGB or DMG hardware behavior
+    pub fn set_cgb_mode(&mut self, cgb: bool) {
+        self.cgb = cgb;
+    }
+    
+    /// DIV counter bit whose falling edge shifts one bit on the internal
+    /// clock: bit 8 for 8192 Hz, bit 3 for the CGB 262144 Hz fast clock
+    fn clock_bit(&self) -> u16 {
+        if self.cgb && self.control & 0x02 != 0 { 3 } else { 8 }
+    }
+    
+    /// Install (or remove) the hook that clocks external transfers
+    pub fn set_external_clock(&mut self, clock: Option<Box<dyn ExternalClock>>) {
+        self.external_clock = clock;
+    }
+    
+    /// Choose what external transfers do with nothing connected
+    pub fn set_disconnected_policy(&mut self, policy: DisconnectedPolicy) {
+        self.disconnected_policy = policy;
+    }
+    
+    /// Connect or disconnect a link partner
+    pub fn set_linked(&mut self, linked: bool) {
+        self.linked = linked;
+        self.link_in = 1;
+        self.sent_bits.clear();
+    }
+    
+    /// Present the partner's output level on our serial input
+    pub fn set_link_in(&mut self, bit: u8) {
+        self.link_in = bit & 1;
+    }
+    
+    /// Level of our serial output (bit 7 of SB)
+    pub fn link_out(&self) -> u8 {
+        self.data >> 7
+    }
+    
+    /// Take the bits shifted out on our internal clock since the last call
+    pub fn take_sent_bits(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.sent_bits)
+    }
+    
+    /// Clock one bit in from a partner driving the clock.
+    ///
+    /// Only shifts while an external clock transfer is in progress;
+    /// returns the bit we were presenting on our output.
+    pub fn external_clock(&mut self, bit_in: u8) -> u8 {
+        let bit_out = self.link_out();
+        
+        if self.control & 0x81 == 0x80 && self.bits_remaining > 0 {
+            self.data = (self.data << 1) | (bit_in & 1);
+            self.bits_remaining -= 1;
+            
+            if self.bits_remaining == 0 {
+                self.control &= !0x80;
+                self.interrupt_pending = true;
+            }
+        }
+        
+        bit_out
     }
     
-    /// Step serial transfer
+    /// Step serial transfer; `div` is the timer's internal DIV counter at
+    /// the start of the step, since the internal clock is derived from it.
     /// Returns true if serial interrupt should be requested
-    pub fn step(&mut self, cycles: u32) -> bool {
+    pub fn step(&mut self, cycles: u32, div: u16) -> bool {
+        if self.control & 0x81 == 0x80 && self.bits_remaining > 0 && !self.linked {
+            self.step_external(cycles);
+        }
+        
+        let external_done = std::mem::take(&mut self.interrupt_pending);
+        
         // Check if transfer is active with internal clock
         if self.control & 0x81 != 0x81 {
-            return false;
+            return external_done;
         }
         
-        self.transfer_counter += cycles;
+        let clock_bit = self.clock_bit();
         
-        // Transfer at 8192 Hz (512 cycles per bit)
-        while self.transfer_counter >= 512 && self.bits_remaining > 0 {
-            self.transfer_counter -= 512;
+        for i in 0..cycles as u16 {
+            let old_div = div.wrapping_add(i);
+            let new_div = old_div.wrapping_add(1);
+            
+            // One bit per falling edge of the selected DIV bit
+            if (old_div >> clock_bit) & 1 == 0 || (new_div >> clock_bit) & 1 == 1 {
+                continue;
+            }
+            
             self.bits_remaining -= 1;
             
-            // Shift in 1 (no external device connected)
-            self.data = (self.data << 1) | 1;
+            // Without a partner the input floats high
+            if self.linked {
+                self.sent_bits.push(self.link_out());
+            }
+            self.data = (self.data << 1) | self.link_in;
             
             if self.bits_remaining == 0 {
                 // Transfer complete
@@ -61,7 +208,47 @@ impl Serial {
             }
         }
         
-        false
+        external_done
+    }
+    
+    /// Advance an externally clocked transfer without a link partner
+    fn step_external(&mut self, cycles: u32) {
+        if let Some(mut clock) = self.external_clock.take() {
+            if let Some(bit) = clock.poll(cycles, self.link_out()) {
+                self.external_clock(bit);
+            }
+            self.external_clock = Some(clock);
+            return;
+        }
+        
+        if let DisconnectedPolicy::Timeout(timeout) = self.disconnected_policy {
+            self.external_wait += cycles;
+            if self.external_wait >= timeout {
+                self.data = 0xFF;
+                self.bits_remaining = 0;
+                self.control &= !0x80;
+                self.interrupt_pending = true;
+            }
+        }
+    }
+    
+    /// Get state for serialization
+    pub fn state(&self) -> SerialState {
+        SerialState {
+            data: self.data,
+            control: self.control,
+            bits_remaining: self.bits_remaining,
+        }
+    }
+    
+    /// Load state; bits in flight to or from a link partner are dropped
+    pub fn load_state(&mut self, state: SerialState) {
+        self.data = state.data;
+        self.control = state.control;
+        self.bits_remaining = state.bits_remaining;
+        self.sent_bits.clear();
+        self.interrupt_pending = false;
+        self.external_wait = 0;
     }
     
     /// Read serial data register
@@ -76,7 +263,9 @@ impl Serial {
     
     /// Read serial control register
     pub fn read_control(&self) -> u8 {
-        self.control | 0x7E
+        // Bit 7 stays set while a transfer is in progress; bit 1 (clock
+        // speed) only exists on CGB
+        if self.cgb { self.control | 0x7C } else { self.control | 0x7E }
     }
     
     /// Write serial control register
@@ -86,7 +275,25 @@ impl Serial {
         // Start transfer if bit 7 is set
         if value & 0x80 != 0 {
             self.bits_remaining = 8;
-            self.transfer_counter = 0;
+            self.external_wait = 0;
+        }
+    }
+}
+
+impl IoDevice for Serial {
+    const IO_RANGES: &'static [RangeInclusive<u16>] = &[0xFF01..=0xFF02];
+    
+    fn read_io(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF01 => self.read_data(),
+            _ => self.read_control(),
+        }
+    }
+    
+    fn write_io(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF01 => self.write_data(value),
+            _ => self.write_control(value),
         }
     }
 }
@@ -0,0 +1,186 @@
+//! DMG-07 Four Player Adapter
+//!
+//! The adapter is the clock master for every port. It runs in two phases:
+//!
+//! - **Ping**: it repeatedly sends `0xFE` followed by three status bytes
+//!   (connected-port mask in the upper nibble, the port's player ID in the
+//!   lower bits). Players answer `0x88 0x88 RATE SIZE`; player 1's RATE and
+//!   SIZE configure the link (the acknowledgement bytes aren't checked).
+//!   Player 1 ends the phase by answering a whole ping with `0xAA`, after
+//!   which the adapter sends `0xCC` four times.
+//! - **Transmission**: each round lasts `4 * SIZE` bytes. During the first
+//!   SIZE bytes every player sends its packet; meanwhile the adapter
+//!   broadcasts the previous round's packets of players 1-4 back to back
+//!   (missing players read as zero). A round where player 1 sends only
+//!   `0xFF` returns the adapter to the ping phase.
+//!
+//! Byte spacing is approximate: the real adapter's gaps depend on RATE in
+//! ways that are only partly documented.
+
+/// Cycles to clock one byte at 8192 Hz
+const BYTE_CYCLES: u32 = 8 * 512;
+
+/// Idle gap between ping bytes
+const PING_GAP_CYCLES: u32 = 0x2000;
+
+/// Extra gap per unit of RATE (low nibble) during transmission
+const RATE_GAP_CYCLES: u32 = 0x400;
+
+/// Player 1 answer that ends the ping phase
+const PING_END: u8 = 0xAA;
+
+/// Adapter bytes sent before transmission starts
+const TRANSMISSION_START: u8 = 0xCC;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Ping,
+    /// Sending the given number of remaining 0xCC bytes
+    Starting(u8),
+    Transmission,
+}
+
+/// Four Player Adapter state
+pub struct FourPlayerAdapter {
+    phase: Phase,
+    
+    /// Number of connected players (1-4)
+    players: usize,
+    
+    /// Cycles until the next byte is clocked
+    timer: u32,
+    
+    /// Position within the current ping packet or transmission round
+    byte_index: usize,
+    
+    /// Link speed chosen by player 1
+    rate: u8,
+    
+    /// Packet size chosen by player 1
+    size: usize,
+    
+    /// Player 1 answered every byte of the current ping with 0xAA
+    ping_end: bool,
+    
+    /// Packets received from each player this round
+    incoming: Vec<Vec<u8>>,
+    
+    /// Packets of all four players from the previous round
+    outgoing: Vec<u8>,
+}
+
+impl FourPlayerAdapter {
+    /// An adapter with `players` ports connected, starting in the ping
+    /// phase
+    pub fn new(players: usize) -> Self {
+        Self {
+            phase: Phase::Ping,
+            players,
+            timer: PING_GAP_CYCLES,
+            byte_index: 0,
+            rate: 0,
+            size: 1,
+            ping_end: true,
+            incoming: vec![Vec::new(); players],
+            outgoing: Vec::new(),
+        }
+    }
+    
+    /// Advance by `cycles`; returns true when the next byte is due
+    pub fn advance(&mut self, cycles: u32) -> bool {
+        if cycles < self.timer {
+            self.timer -= cycles;
+            return false;
+        }
+        
+        self.timer = BYTE_CYCLES + match self.phase {
+            Phase::Ping | Phase::Starting(_) => PING_GAP_CYCLES,
+            Phase::Transmission => (self.rate & 0x0F) as u32 * RATE_GAP_CYCLES,
+        };
+        true
+    }
+    
+    /// Byte the adapter sends to `player` (0-3) in the current slot
+    pub fn byte_for(&self, player: usize) -> u8 {
+        match self.phase {
+            Phase::Ping if self.byte_index == 0 => 0xFE,
+            Phase::Ping => {
+                let connected = ((1u8 << self.players) - 1) << 4;
+                connected | (player as u8 + 1)
+            }
+            Phase::Starting(_) => TRANSMISSION_START,
+            Phase::Transmission => self.outgoing.get(self.byte_index).copied().unwrap_or(0),
+        }
+    }
+    
+    /// Finish the current slot with the bytes each player sent
+    pub fn receive(&mut self, bytes: &[u8]) {
+        match self.phase {
+            Phase::Ping => self.receive_ping(bytes[0]),
+            Phase::Starting(remaining) => {
+                self.phase = if remaining > 1 {
+                    Phase::Starting(remaining - 1)
+                } else {
+                    self.start_round();
+                    Phase::Transmission
+                };
+            }
+            Phase::Transmission => self.receive_packet_byte(bytes),
+        }
+    }
+    
+    fn receive_ping(&mut self, player1: u8) {
+        self.ping_end &= player1 == PING_END;
+        
+        match self.byte_index {
+            2 if player1 != PING_END => self.rate = player1,
+            3 if player1 != PING_END => self.size = (player1 as usize).max(1),
+            _ => {}
+        }
+        
+        self.byte_index += 1;
+        if self.byte_index == 4 {
+            self.byte_index = 0;
+            if self.ping_end {
+                self.phase = Phase::Starting(4);
+            }
+            self.ping_end = true;
+        }
+    }
+    
+    fn receive_packet_byte(&mut self, bytes: &[u8]) {
+        if self.byte_index < self.size {
+            for (packet, &byte) in self.incoming.iter_mut().zip(bytes) {
+                packet.push(byte);
+            }
+        }
+        
+        self.byte_index += 1;
+        if self.byte_index < 4 * self.size {
+            return;
+        }
+        
+        if self.incoming[0].iter().all(|&byte| byte == 0xFF) {
+            // Player 1 asked to restart
+            self.phase = Phase::Ping;
+            self.outgoing.clear();
+            self.start_round();
+            return;
+        }
+        
+        self.outgoing = (0..4)
+            .flat_map(|player| match self.incoming.get(player) {
+                Some(packet) => packet.clone(),
+                None => vec![0; self.size],
+            })
+            .collect();
+        self.start_round();
+    }
+    
+    fn start_round(&mut self) {
+        self.byte_index = 0;
+        for packet in &mut self.incoming {
+            packet.clear();
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! # Link Sessions
+//!
+//! Runs several `GameBoy` instances connected by link cable or through a
+//! DMG-07 Four Player Adapter.
+//!
+//! Instances are stepped one instruction at a time, always advancing the
+//! one furthest behind, so they never drift apart by more than a single
+//! instruction. With a cable, serial bits are exchanged at the moment they
+//! are clocked: before an instance runs, its input line is set to its
+//! partner's output bit, and any bits it clocks out are delivered to the
+//! partner right after. With the adapter, the adapter clocks every port.
+
+mod dmg07;
+
+use crate::{GameBoy, CYCLES_PER_FRAME};
+
+pub use dmg07::FourPlayerAdapter;
+
+/// How the players are connected
+enum Link {
+    /// Two players on a link cable
+    Cable,
+    
+    /// Up to four players on a DMG-07, clocked by the adapter on player 1's
+    /// timeline
+    Adapter(FourPlayerAdapter),
+}
+
+/// A group of linked Game Boys
+pub struct Session {
+    players: Vec<GameBoy>,
+    link: Link,
+}
+
+impl Session {
+    /// Link two Game Boys with a cable
+    pub fn new(player1: GameBoy, player2: GameBoy) -> Self {
+        Self::with_players(vec![player1, player2])
+            .expect("two players can always be linked")
+    }
+    
+    /// Create a session from a list of players.
+    ///
+    /// A plain link cable connects exactly two players.
+    pub fn with_players(mut players: Vec<GameBoy>) -> Result<Self, String> {
+        if players.len() != 2 {
+            return Err(format!("A link cable connects 2 players, got {}", players.len()));
+        }
+        
+        for gb in &mut players {
+            gb.serial_mut().set_linked(true);
+        }
+        
+        Ok(Self { players, link: Link::Cable })
+    }
+    
+    /// Connect 1-4 players through a DMG-07 Four Player Adapter
+    pub fn with_adapter(mut players: Vec<GameBoy>) -> Result<Self, String> {
+        if players.is_empty() || players.len() > 4 {
+            return Err(format!("The DMG-07 connects 1-4 players, got {}", players.len()));
+        }
+        
+        for gb in &mut players {
+            gb.serial_mut().set_linked(true);
+        }
+        
+        let adapter = FourPlayerAdapter::new(players.len());
+        Ok(Self { players, link: Link::Adapter(adapter) })
+    }
+    
+    /// Disconnect and return the players
+    pub fn into_players(mut self) -> Vec<GameBoy> {
+        for gb in &mut self.players {
+            gb.serial_mut().set_linked(false);
+        }
+        self.players
+    }
+    
+    /// Number of players in the session
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+    
+    /// Get a player
+    pub fn player(&self, index: usize) -> &GameBoy {
+        &self.players[index]
+    }
+    
+    /// Get a mutable player
+    pub fn player_mut(&mut self, index: usize) -> &mut GameBoy {
+        &mut self.players[index]
+    }
+    
+    /// Run every player until its next frame is complete
+    pub fn run_frame(&mut self) {
+        for gb in &mut self.players {
+            gb.cycles_this_frame = 0;
+        }
+        
+        while let Some(index) = self.furthest_behind() {
+            self.step_player(index);
+        }
+        
+        for gb in &mut self.players {
+            gb.end_frame();
+        }
+    }
+    
+    /// The player with the fewest cycles this frame that hasn't finished it
+    fn furthest_behind(&self) -> Option<usize> {
+        self.players.iter()
+            .enumerate()
+            .filter(|(_, gb)| gb.cycles_this_frame < CYCLES_PER_FRAME)
+            .min_by_key(|(_, gb)| gb.cycles_this_frame)
+            .map(|(index, _)| index)
+    }
+    
+    /// Run one instruction on a player and exchange serial data
+    fn step_player(&mut self, index: usize) {
+        if matches!(self.link, Link::Adapter(_)) {
+            self.step_adapter_player(index);
+        } else {
+            self.step_cable_player(index);
+        }
+    }
+    
+    /// Cable link: exchange bits with the partner as they are clocked
+    fn step_cable_player(&mut self, index: usize) {
+        let partner = index ^ 1;
+        
+        let bit = self.players[partner].serial().link_out();
+        self.players[index].serial_mut().set_link_in(bit);
+        
+        self.players[index].step();
+        
+        for bit in self.players[index].serial_mut().take_sent_bits() {
+            self.players[partner].serial_mut().external_clock(bit);
+        }
+    }
+    
+    /// Adapter link: the adapter drives every port on player 1's timeline
+    fn step_adapter_player(&mut self, index: usize) {
+        let before = self.players[index].cycles_this_frame;
+        self.players[index].step();
+        
+        // The adapter is the only clock master; bits the players clock
+        // themselves go nowhere
+        self.players[index].serial_mut().take_sent_bits();
+        
+        if index != 0 {
+            return;
+        }
+        
+        let elapsed = self.players[0].cycles_this_frame - before;
+        let Link::Adapter(adapter) = &mut self.link else {
+            return;
+        };
+        
+        if !adapter.advance(elapsed) {
+            return;
+        }
+        
+        let received: Vec<u8> = self.players.iter_mut()
+            .enumerate()
+            .map(|(player, gb)| {
+                let sent = adapter.byte_for(player);
+                (0..8).rev().fold(0, |byte, bit| {
+                    (byte << 1) | gb.serial_mut().external_clock((sent >> bit) & 1)
+                })
+            })
+            .collect();
+        
+        adapter.receive(&received);
+    }
+    
+    /// Framebuffer of a player (RGBA8888, 160x144)
+    pub fn framebuffer(&self, index: usize) -> &[u8] {
+        self.players[index].framebuffer()
+    }
+    
+    /// Audio samples of a player (stereo interleaved)
+    pub fn audio_buffer(&self, index: usize) -> &[f32] {
+        self.players[index].audio_buffer()
+    }
+    
+    /// Mix every player's audio into one stereo buffer
+    pub fn mixed_audio(&self) -> Vec<f32> {
+        let len = self.players.iter()
+            .map(|gb| gb.audio_buffer().len())
+            .max()
+            .unwrap_or(0);
+        let scale = 1.0 / self.players.len() as f32;
+        
+        let mut mixed = vec![0.0f32; len];
+        for gb in &self.players {
+            for (out, sample) in mixed.iter_mut().zip(gb.audio_buffer()) {
+                *out += sample * scale;
+            }
+        }
+        mixed
+    }
+    
+    /// Clear every player's audio buffer after reading
+    pub fn clear_audio_buffers(&mut self) {
+        for gb in &mut self.players {
+            gb.clear_audio_buffer();
+        }
+    }
+}
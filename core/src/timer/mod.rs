@@ -6,6 +6,9 @@
 //! - TMA: Timer modulo
 //! - TAC: Timer control
 
+use std::ops::RangeInclusive;
+
+use crate::mmu::IoDevice;
 use serde::{Serialize, Deserialize};
 
 /// Timer state for serialization
@@ -38,6 +41,12 @@ pub struct Timer {
     
     /// TIMA reload cycle
     tima_reload_cycle: bool,
+    
+    /// CGB double-speed mode (selects the DIV-APU bit)
+    double_speed: bool,
+    
+    /// DIV-APU falling edges not yet delivered to the APU
+    div_apu_ticks: u32,
 }
 
 impl Timer {
@@ -49,16 +58,38 @@ impl Timer {
             tac: 0,
             tima_overflow: false,
             tima_reload_cycle: false,
+            double_speed: false,
+            div_apu_ticks: 0,
         }
     }
     
+    /// Reset to the post-boot state (DIV keeps running from the value the
+    /// boot ROM leaves)
     pub fn reset(&mut self) {
-        self.div_counter = 0;
+        self.div_counter = 0xABCC;
         self.tima = 0;
         self.tma = 0;
         self.tac = 0;
         self.tima_overflow = false;
         self.tima_reload_cycle = false;
+        self.double_speed = false;
+        self.div_apu_ticks = 0;
+    }
+    
+    /// Switch between normal and CGB double speed
+    pub fn set_double_speed(&mut self, double_speed: bool) {
+        self.double_speed = double_speed;
+    }
+    
+    /// DIV counter bit whose falling edge clocks the APU frame sequencer:
+    /// DIV bit 4 at normal speed, bit 5 in double speed (512 Hz either way)
+    fn div_apu_bit(&self) -> u16 {
+        if self.double_speed { 13 } else { 12 }
+    }
+    
+    /// Take the number of DIV-APU ticks since the last call
+    pub fn take_div_apu_ticks(&mut self) -> u32 {
+        std::mem::take(&mut self.div_apu_ticks)
     }
     
     /// Step the timer by CPU cycles
@@ -84,6 +115,11 @@ impl Timer {
             let old_div = self.div_counter;
             self.div_counter = self.div_counter.wrapping_add(1);
             
+            let apu_bit = self.div_apu_bit();
+            if (old_div >> apu_bit) & 1 == 1 && (self.div_counter >> apu_bit) & 1 == 0 {
+                self.div_apu_ticks += 1;
+            }
+            
             // Check if timer is enabled
             if self.tac & 0x04 != 0 {
                 let bit_pos = match self.tac & 0x03 {
@@ -110,6 +146,11 @@ impl Timer {
         interrupt
     }
     
+    /// Internal 16-bit DIV counter (other components derive clocks from it)
+    pub fn div_counter(&self) -> u16 {
+        self.div_counter
+    }
+    
     /// Read DIV register
     pub fn read_div(&self) -> u8 {
         (self.div_counter >> 8) as u8
@@ -118,7 +159,8 @@ impl Timer {
     /// Write DIV register (resets to 0)
     pub fn write_div(&mut self) {
         // Writing any value resets the entire counter
-        // This can cause a TIMA increment if the selected bit was 1
+        // This can cause a TIMA increment if the selected bit was 1,
+        // and a DIV-APU tick if the frame sequencer's bit was 1
         let bit_pos = match self.tac & 0x03 {
             0 => 9,
             1 => 3,
@@ -134,6 +176,11 @@ impl Timer {
             }
         }
         
+        // The same falling edge clocks the APU frame sequencer early
+        if (self.div_counter >> self.div_apu_bit()) & 1 == 1 {
+            self.div_apu_ticks += 1;
+        }
+        
         self.div_counter = 0;
     }
     
@@ -227,3 +274,27 @@ impl Timer {
         self.tima_reload_cycle = state.tima_reload_cycle;
     }
 }
+
+impl IoDevice for Timer {
+    const IO_RANGES: &'static [RangeInclusive<u16>] = &[0xFF04..=0xFF07];
+    
+    fn read_io(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF04 => self.read_div(),
+            0xFF05 => self.read_tima(),
+            0xFF06 => self.read_tma(),
+            0xFF07 => self.read_tac(),
+            _ => 0xFF,
+        }
+    }
+    
+    fn write_io(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF04 => self.write_div(),
+            0xFF05 => self.write_tima(value),
+            0xFF06 => self.write_tma(value),
+            0xFF07 => self.write_tac(value),
+            _ => {}
+        }
+    }
+}
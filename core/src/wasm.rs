@@ -2,8 +2,10 @@
 //! 
 //! Exposes the emulator core to JavaScript/TypeScript via wasm-bindgen.
 
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
-use crate::{GameBoy, Button};
+use crate::{GameBoy, Button, AudioOutputMode, SaveState};
+use crate::cpu::Cpu;
 
 #[wasm_bindgen]
 extern "C" {
@@ -17,10 +19,75 @@ pub fn init() {
     console_error_panic_hook::set_once();
 }
 
+/// CPU registers as handed to JavaScript (F as a plain byte)
+#[derive(Serialize)]
+struct Registers {
+    a: u8,
+    f: u8,
+    b: u8,
+    c: u8,
+    d: u8,
+    e: u8,
+    h: u8,
+    l: u8,
+    sp: u16,
+    pc: u16,
+    ime: bool,
+    halted: bool,
+}
+
+impl Registers {
+    fn of(cpu: &Cpu) -> Self {
+        let regs = &cpu.regs;
+        Self {
+            a: regs.a,
+            f: regs.f.bits(),
+            b: regs.b,
+            c: regs.c,
+            d: regs.d,
+            e: regs.e,
+            h: regs.h,
+            l: regs.l,
+            sp: regs.sp,
+            pc: regs.pc,
+            ime: cpu.ime,
+            halted: cpu.halted,
+        }
+    }
+}
+
+/// Result of a single `step`
+#[derive(Serialize)]
+struct StepResult {
+    cycles: u32,
+    registers: Registers,
+    
+    /// The next instruction is at a breakpoint
+    breakpoint: bool,
+}
+
+/// Convert to a plain JavaScript object
+fn to_js<T: Serialize>(value: &T) -> JsValue {
+    let json = serde_json::to_string(value).expect("plain structs serialize");
+    js_sys::JSON::parse(&json).expect("serde_json output is valid JSON")
+}
+
+/// Bytes per save state chunk
+const STATE_CHUNK_SIZE: usize = 64 * 1024;
+
 /// WASM-exposed Game Boy emulator
 #[wasm_bindgen]
 pub struct WasmGameBoy {
     inner: GameBoy,
+    
+    /// Save state being exported in chunks
+    state_export: Vec<u8>,
+    
+    /// Save state chunks received so far
+    state_import: Vec<u8>,
+    
+    /// Battery save as of the last sync (see `sram_delta`)
+    sram_baseline: Vec<u8>,
 }
 
 #[wasm_bindgen]
@@ -31,20 +98,44 @@ impl WasmGameBoy {
         let gb = GameBoy::new(rom_data)
             .map_err(|e| JsValue::from_str(&e))?;
         
-        Ok(WasmGameBoy { inner: gb })
+        Ok(WasmGameBoy {
+            inner: gb,
+            state_export: Vec::new(),
+            state_import: Vec::new(),
+            sram_baseline: Vec::new(),
+        })
     }
     
-    /// Reset the emulator
+    /// Press the reset button (RAM keeps its contents)
     #[wasm_bindgen]
     pub fn reset(&mut self) {
         self.inner.reset();
     }
     
+    /// Switch the console off and on again
+    #[wasm_bindgen]
+    pub fn power_cycle(&mut self) {
+        self.inner.power_cycle();
+    }
+    
+    /// Reset only the APU (recovers from glitched audio)
+    #[wasm_bindgen]
+    pub fn reset_apu(&mut self) {
+        self.inner.reset_apu();
+    }
+    
+    /// Reset only the PPU and LCD registers (recovers from a corrupted
+    /// picture)
+    #[wasm_bindgen]
+    pub fn reset_ppu(&mut self) {
+        self.inner.reset_ppu();
+    }
+    
     /// Run one frame and return pointer to framebuffer
     /// The framebuffer is RGBA8888 format, 160x144 pixels
     #[wasm_bindgen]
     pub fn run_frame(&mut self) -> *const u8 {
-        self.inner.run_frame().as_ptr()
+        self.inner.run_frame_video().as_ptr()
     }
     
     /// Get framebuffer as a copy (for safer JS access)
@@ -107,10 +198,77 @@ impl WasmGameBoy {
         self.inner.save_state()
     }
     
+    /// Create a quick-resume state (no VRAM or framebuffer) for frequent
+    /// autosaves; load it with `load_state`
+    #[wasm_bindgen]
+    pub fn save_quick_state(&self) -> Vec<u8> {
+        self.inner.save_quick_state()
+    }
+    
     /// Load a save state
     #[wasm_bindgen]
     pub fn load_state(&mut self, data: &[u8]) -> Result<(), JsValue> {
         self.inner.load_state(data)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+    
+    /// Snapshot a save state for chunked export and return the number of
+    /// chunks. Fetch them with `state_chunk` between frames so no single
+    /// call copies the whole state.
+    #[wasm_bindgen]
+    pub fn export_state(&mut self) -> u32 {
+        self.state_export = self.inner.save_state();
+        self.state_chunk_count()
+    }
+    
+    /// Number of chunks in the last `export_state` snapshot
+    #[wasm_bindgen]
+    pub fn state_chunk_count(&self) -> u32 {
+        self.state_export.len().div_ceil(STATE_CHUNK_SIZE) as u32
+    }
+    
+    /// Chunk `index` of the last `export_state` snapshot
+    #[wasm_bindgen]
+    pub fn state_chunk(&self, index: u32) -> Result<Vec<u8>, JsValue> {
+        self.state_export
+            .chunks(STATE_CHUNK_SIZE)
+            .nth(index as usize)
+            .map(|chunk| chunk.to_vec())
+            .ok_or_else(|| JsValue::from_str(&format!("No state chunk {}", index)))
+    }
+    
+    /// Append the next chunk of a save state being imported
+    #[wasm_bindgen]
+    pub fn push_state_chunk(&mut self, chunk: &[u8]) {
+        self.state_import.extend_from_slice(chunk);
+    }
+    
+    /// Load the save state assembled by `push_state_chunk`. The buffer is
+    /// cleared either way.
+    #[wasm_bindgen]
+    pub fn finish_state_import(&mut self) -> Result<(), JsValue> {
+        let data = std::mem::take(&mut self.state_import);
+        self.inner.load_state(&data)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+    
+    /// Battery save changes since the last `mark_sram_synced`, in the
+    /// `cartridge::delta` format (undefined without battery RAM)
+    #[wasm_bindgen]
+    pub fn sram_delta(&self) -> Option<Vec<u8>> {
+        self.inner.sram_delta(&self.sram_baseline)
+    }
+    
+    /// Record the current battery save as synced to the backend
+    #[wasm_bindgen]
+    pub fn mark_sram_synced(&mut self) {
+        self.sram_baseline = self.inner.save_sram().unwrap_or_default();
+    }
+    
+    /// Apply a battery save delta received from the backend
+    #[wasm_bindgen]
+    pub fn apply_sram_delta(&mut self, delta: &[u8]) -> Result<(), JsValue> {
+        self.inner.apply_sram_delta(delta)
             .map_err(|e| JsValue::from_str(&e))
     }
     
@@ -141,7 +299,142 @@ impl WasmGameBoy {
     /// Get audio sample rate
     #[wasm_bindgen]
     pub fn audio_sample_rate(&self) -> u32 {
-        crate::apu::SAMPLE_RATE
+        self.inner.audio_sample_rate()
+    }
+    
+    /// Audio buffer counters as `{produced, consumed, dropped, overflows,
+    /// buffered, high_watermark}`
+    #[wasm_bindgen]
+    pub fn audio_stats(&self) -> JsValue {
+        to_js(&self.inner.apu().stats())
+    }
+    
+    /// Scale the output volume (1.0 = unchanged)
+    #[wasm_bindgen]
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.inner.apu_mut().set_master_volume(volume);
+    }
+    
+    /// Mute or unmute the output
+    #[wasm_bindgen]
+    pub fn set_muted(&mut self, muted: bool) {
+        self.inner.apu_mut().set_muted(muted);
+    }
+    
+    /// Soft-clip peaks instead of clamping them
+    #[wasm_bindgen]
+    pub fn set_soft_clip(&mut self, enabled: bool) {
+        self.inner.apu_mut().set_soft_clip(enabled);
+    }
+    
+    /// Switch between resampled output and raw 2 MHz output
+    #[wasm_bindgen]
+    pub fn set_raw_audio(&mut self, raw: bool) {
+        let mode = if raw { AudioOutputMode::Raw } else { AudioOutputMode::Resampled };
+        self.inner.set_audio_output_mode(mode);
+    }
+    
+    /// Pause or resume emulation (run_frame keeps producing silent audio)
+    #[wasm_bindgen]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.inner.set_paused(paused);
+    }
+    
+    /// Whether emulation is paused
+    #[wasm_bindgen]
+    pub fn is_paused(&self) -> bool {
+        self.inner.is_paused()
+    }
+    
+    /// Read a byte without side effects (for memory viewers)
+    #[wasm_bindgen]
+    pub fn read_memory(&self, addr: u16) -> u8 {
+        self.inner.mmu.peek_byte(addr)
+    }
+    
+    /// Read `len` bytes from `addr`, wrapping at 0xFFFF
+    #[wasm_bindgen]
+    pub fn read_memory_range(&self, addr: u16, len: u32) -> Vec<u8> {
+        (0..len).map(|i| self.inner.mmu.peek_byte(addr.wrapping_add(i as u16))).collect()
+    }
+    
+    /// Write a byte through the bus, as the CPU would
+    #[wasm_bindgen]
+    pub fn write_memory(&mut self, addr: u16, value: u8) {
+        self.inner.mmu.write_byte(addr, value);
+    }
+    
+    /// CPU registers as `{a, f, b, c, d, e, h, l, sp, pc, ime, halted}`
+    #[wasm_bindgen]
+    pub fn registers(&self) -> JsValue {
+        to_js(&Registers::of(&self.inner.cpu))
+    }
+    
+    /// Set a register by name: a, f, b, c, d, e, h, l, af, bc, de, hl,
+    /// sp or pc
+    #[wasm_bindgen]
+    pub fn set_register(&mut self, name: &str, value: u16) -> Result<(), JsValue> {
+        let regs = &mut self.inner.cpu.regs;
+        let byte = value as u8;
+        match name {
+            "a" => regs.a = byte,
+            "f" => regs.set_af(((regs.a as u16) << 8) | byte as u16),
+            "b" => regs.b = byte,
+            "c" => regs.c = byte,
+            "d" => regs.d = byte,
+            "e" => regs.e = byte,
+            "h" => regs.h = byte,
+            "l" => regs.l = byte,
+            "af" => regs.set_af(value),
+            "bc" => regs.set_bc(value),
+            "de" => regs.set_de(value),
+            "hl" => regs.set_hl(value),
+            "sp" => regs.sp = value,
+            "pc" => regs.pc = value,
+            _ => return Err(JsValue::from_str(&format!("Unknown register: {}", name))),
+        }
+        Ok(())
+    }
+    
+    /// Execute one instruction and return
+    /// `{cycles, registers, breakpoint}`
+    #[wasm_bindgen]
+    pub fn step(&mut self) -> JsValue {
+        let cycles = self.inner.step();
+        let registers = Registers::of(&self.inner.cpu);
+        let breakpoint = self.inner.breakpoints().any(|addr| addr == registers.pc);
+        to_js(&StepResult { cycles, registers, breakpoint })
+    }
+    
+    /// Add a PC breakpoint
+    #[wasm_bindgen]
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.inner.add_breakpoint(addr);
+    }
+    
+    /// Remove a PC breakpoint
+    #[wasm_bindgen]
+    pub fn remove_breakpoint(&mut self, addr: u16) -> bool {
+        self.inner.remove_breakpoint(addr)
+    }
+    
+    /// Remove every breakpoint
+    #[wasm_bindgen]
+    pub fn clear_breakpoints(&mut self) {
+        self.inner.clear_breakpoints();
+    }
+    
+    /// Breakpoint addresses, in ascending order
+    #[wasm_bindgen]
+    pub fn breakpoints(&self) -> Vec<u16> {
+        self.inner.breakpoints().collect()
+    }
+    
+    /// Run the rest of the frame, stopping at a breakpoint. Returns the
+    /// breakpoint address, or undefined once the frame is complete.
+    #[wasm_bindgen]
+    pub fn run_frame_until_breakpoint(&mut self) -> Option<u16> {
+        self.inner.run_frame_until_breakpoint()
     }
     
     /// Get frame count
@@ -157,6 +450,22 @@ impl WasmGameBoy {
     }
 }
 
+/// Render a ROM's thumbnail: the RGBA frame after running it headlessly
+/// for `frames` frames
+#[wasm_bindgen]
+pub fn preview_frame(rom: &[u8], frames: u32) -> Result<Vec<u8>, JsValue> {
+    crate::preview_frame(rom, frames)
+        .map_err(|e| JsValue::from_str(&e))
+}
+
+/// Convert a JSON save state from an older version to the current layout
+/// (see `SaveState::from_legacy_json`)
+#[wasm_bindgen]
+pub fn upgrade_legacy_state(data: &[u8]) -> Result<Vec<u8>, JsValue> {
+    SaveState::from_legacy_json(data)
+        .map_err(|e| JsValue::from_str(&e))
+}
+
 // Button constants exported individually
 #[wasm_bindgen]
 pub fn button_right() -> u8 { 0 }
@@ -0,0 +1,67 @@
+//! RetroAchievements memory layout and frame hook
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gbemu_core::achievements::{AchievementHook, AchievementMemory, CONSOLE_GAMEBOY, CONSOLE_GAMEBOY_COLOR};
+use gbemu_core::demo_rom::{DEMO_ROM, FRAME_COUNTER};
+use gbemu_core::{GameBoy, GbModel};
+
+#[test]
+fn flat_addresses() {
+    let mut gb = GameBoy::new(&DEMO_ROM).unwrap();
+    gb.mmu.write_byte(0xC123, 0x5A);
+    let memory = gb.achievement_memory();
+    assert_eq!(memory.console_id(), CONSOLE_GAMEBOY);
+    assert_eq!(memory.size(), 0x10000);
+    assert_eq!(memory.read(0xC123), Some(0x5A));
+    assert_eq!(memory.read(0xE123), Some(0x5A));
+    assert_eq!(memory.read(0x10000), None);
+    
+    gb.set_model(GbModel::Cgb);
+    for bank in 1..8 {
+        gb.mmu.write_byte(0xFF70, bank);
+        gb.mmu.write_byte(0xD010, 0xB0 | bank);
+    }
+    gb.mmu.write_byte(0xFF70, 1);
+    
+    let memory = gb.achievement_memory();
+    assert_eq!(memory.console_id(), CONSOLE_GAMEBOY_COLOR);
+    assert_eq!(memory.size(), 0x16000);
+    assert_eq!(memory.read(0xD010), Some(0xB1));
+    let banks: Vec<_> = (0..6).map(|i| memory.read(0x10010 + i * 0x1000).unwrap()).collect();
+    assert_eq!(banks, [0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7]);
+    
+    let mut buffer = [0u8; 0x20];
+    assert_eq!(memory.read_block(0x15FF0, &mut buffer), 0x10);
+}
+
+struct FrameCounter {
+    ticks: Rc<Cell<u32>>,
+    last: Rc<Cell<u8>>,
+}
+
+impl AchievementHook for FrameCounter {
+    fn achievement_tick(&mut self, memory: &AchievementMemory<'_>) {
+        self.ticks.set(self.ticks.get() + 1);
+        self.last.set(memory.read(FRAME_COUNTER as u32).unwrap());
+    }
+}
+
+#[test]
+fn hook_runs_every_frame() {
+    let ticks = Rc::new(Cell::new(0));
+    let last = Rc::new(Cell::new(0));
+    let mut gb = GameBoy::new(&DEMO_ROM).unwrap();
+    gb.set_achievement_hook(Some(Box::new(FrameCounter { ticks: ticks.clone(), last: last.clone() })));
+    
+    for _ in 0..10 {
+        gb.run_frame();
+    }
+    assert_eq!(ticks.get(), 10);
+    assert_eq!(last.get(), gb.mmu.read_byte(FRAME_COUNTER));
+    
+    gb.set_achievement_hook(None);
+    gb.run_frame();
+    assert_eq!(ticks.get(), 10);
+}
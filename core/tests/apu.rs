@@ -0,0 +1,313 @@
+//! Audio output buffer accounting and register behavior
+
+mod common;
+
+use common::rom;
+use gbemu_core::timer::Timer;
+use gbemu_core::{EmulatorEvent, GameBoy, GbModel, LcdEvent};
+
+/// 32KB ROM that starts a full-volume square wave on channel 1
+fn tone_rom() -> Vec<u8> {
+    common::rom_with(&[
+        0x3E, 0xF0, 0xE0, 0x12, // NR12: volume 15
+        0x3E, 0x77, 0xE0, 0x24, // NR50: full master volume
+        0x3E, 0x87, 0xE0, 0x14, // NR14: trigger
+        0x18, 0xFE,             // loop
+    ])
+}
+
+/// Audio of the second frame with the given output settings
+fn tone(setup: impl Fn(&mut GameBoy)) -> Vec<f32> {
+    let mut gb = GameBoy::new(&tone_rom()).unwrap();
+    setup(&mut gb);
+    gb.run_frame();
+    gb.clear_audio_buffer();
+    gb.run_frame();
+    gb.audio_buffer().to_vec()
+}
+
+#[test]
+fn master_volume_mute_and_soft_clip() {
+    let full = tone(|_| {});
+    let half = tone(|gb| gb.apu_mut().set_master_volume(0.5));
+    assert!(full.iter().any(|&sample| sample != 0.0));
+    assert!(full.iter().zip(&half).all(|(full, half)| (full * 0.5 - half).abs() < 1e-6));
+    
+    assert!(tone(|gb| gb.apu_mut().set_muted(true)).iter().all(|&sample| sample == 0.0));
+    
+    // Boosted past full scale: hard clipping flattens peaks at 1.0, soft
+    // clipping stays below it
+    let peak = |samples: &[f32]| samples.iter().fold(0.0f32, |max, sample| max.max(sample.abs()));
+    let hard = tone(|gb| gb.apu_mut().set_master_volume(8.0));
+    let soft = tone(|gb| {
+        gb.apu_mut().set_master_volume(8.0);
+        gb.apu_mut().set_soft_clip(true);
+    });
+    assert_eq!(peak(&hard), 1.0);
+    assert!(peak(&soft) < 1.0 && peak(&soft) > 0.9);
+}
+
+#[test]
+fn save_state_resumes_audio_exactly() {
+    let mut gb = GameBoy::new(&tone_rom()).unwrap();
+    gb.run_cycles(12_345);
+    let state = gb.save_state();
+    gb.clear_audio_buffer();
+    gb.run_frame();
+    let expected = gb.audio_buffer().to_vec();
+    
+    // A fresh instance has the post-boot panning and sample phase
+    let mut loaded = GameBoy::new(&tone_rom()).unwrap();
+    loaded.load_state(&state).unwrap();
+    loaded.run_frame();
+    assert_eq!(loaded.audio_buffer(), expected.as_slice());
+}
+
+#[test]
+fn buffer_drops_oldest_at_cap() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.apu_mut().set_buffer_cap(Some(1000));
+    
+    gb.run_frame();
+    gb.clear_audio_buffer();
+    for _ in 0..3 {
+        gb.run_frame_video();
+    }
+    
+    let stats = gb.apu().stats();
+    assert!(stats.buffered <= 1000);
+    assert_eq!(stats.buffered * 2, gb.audio_buffer().len());
+    assert!(stats.overflows > 0);
+    assert_eq!(stats.high_watermark, 1000);
+    assert_eq!(stats.produced, stats.consumed + stats.dropped + stats.buffered as u64);
+}
+
+/// Whether channel 1 is still playing after NR52 power-off with a length
+/// write while off, then a trigger with length enabled
+fn length_survives_power_off(model: GbModel) -> bool {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.set_model(model);
+    let apu = gb.apu_mut();
+    apu.write_register(0xFF30, 0x5A);
+    apu.write_register(0xFF26, 0x00);
+    apu.write_register(0xFF11, 0x3F); // length 1
+    apu.write_register(0xFF26, 0x80);
+    assert_eq!(apu.read_register(0xFF30), 0x5A, "wave RAM is kept on power-off");
+    
+    apu.write_register(0xFF12, 0xF0);
+    apu.write_register(0xFF14, 0xC0);
+    gb.run_cycles(20_000);
+    gb.apu().read_register(0xFF26) & 0x01 != 0
+}
+
+#[test]
+fn power_off_keeps_dmg_length_counters() {
+    // DMG: the length write lands and expires the channel within a few
+    // frame sequencer steps. CGB: it is ignored, so trigger loads 64.
+    assert!(!length_survives_power_off(GbModel::Dmg));
+    assert!(length_survives_power_off(GbModel::Cgb));
+}
+
+#[test]
+fn apu_follows_speed_switches() {
+    // CGB ROM that arms KEY1 and executes STOP to switch to double speed
+    let rom = common::cgb_rom_with(&[
+        0x3E, 0x01, 0xE0, 0x4D, // KEY1: arm the switch
+        0x10, 0x00,             // STOP
+        0x18, 0xFE,             // loop
+    ]);
+    
+    let mut gb = GameBoy::new(&rom).unwrap();
+    assert_eq!(gb.model(), GbModel::Cgb);
+    let normal = gb.save_state();
+    assert!(!gb.apu().double_speed());
+    
+    gb.run_frame();
+    assert!(gb.apu().double_speed());
+    gb.reset_apu();
+    assert!(gb.apu().double_speed(), "APU reset keeps the CPU speed");
+    
+    gb.load_state(&normal).unwrap();
+    assert!(!gb.apu().double_speed());
+}
+
+#[test]
+fn run_frame_returns_one_frame_of_output() {
+    let mut gb = GameBoy::new(&tone_rom()).unwrap();
+    gb.run_frame();
+    
+    let output = gb.run_frame();
+    assert_eq!(output.frame, 2);
+    assert!(output.events.is_empty());
+    // About 735 stereo samples per frame at 44.1kHz, not two frames' worth
+    assert!((1400..1540).contains(&output.audio.len()), "{}", output.audio.len());
+    let (video, audio) = (output.video.to_vec(), output.audio.to_vec());
+    assert_eq!(video, gb.framebuffer());
+    assert_eq!(audio, gb.audio_buffer());
+    
+    gb.mmu.write_byte(0xFF40, 0x00);
+    let output = gb.run_frame();
+    assert_eq!(output.events, [EmulatorEvent::Lcd(LcdEvent::Disabled)]);
+    assert_eq!(gb.take_lcd_event(), None, "events are drained");
+}
+
+#[test]
+fn debug_state_reports_channel_timers() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.mmu.write_byte(0xFF10, 0x25); // NR10: period 2, shift 5
+    gb.mmu.write_byte(0xFF12, 0xF3); // NR12: volume 15, decreasing every 3
+    gb.mmu.write_byte(0xFF13, 0x00);
+    gb.mmu.write_byte(0xFF14, 0x87); // NR14: frequency 0x700, trigger
+    gb.mmu.write_byte(0xFF22, 0x21); // NR43: shift 2, divisor code 1
+    
+    let state = gb.apu().debug_state();
+    let ch1 = &state.channels[0];
+    assert!(ch1.enabled && ch1.dac_enabled);
+    assert_eq!(ch1.frequency, Some(0x700));
+    assert_eq!(ch1.output_hz, 512.0);
+    let envelope = ch1.envelope.unwrap();
+    assert_eq!((envelope.volume, envelope.period, envelope.increase), (15, 3, false));
+    let sweep = ch1.sweep.unwrap();
+    assert_eq!((sweep.period, sweep.shift, sweep.shadow_frequency), (2, 5, 0x700));
+    
+    assert_eq!(state.channels[1].sweep, None);
+    assert_eq!(state.channels[2].envelope, None);
+    assert_eq!(state.channels[3].frequency, None);
+    assert_eq!(state.channels[3].output_hz, 4_194_304.0 / 64.0);
+    
+    // One DIV-APU tick moves the sequencer on by a step
+    let step = state.frame_sequencer_step;
+    gb.run_cycles(8192);
+    let state = gb.apu().debug_state();
+    assert_eq!(state.frame_sequencer_step, (step + 1) % 8);
+    assert_eq!(state.next_clocks.length, state.frame_sequencer_step % 2 == 0);
+}
+
+/// Channel 1 volume after triggering with NR12 = `initial`, then writing
+/// NR12 = `rewrite` while it plays
+fn zombie_volume(initial: u8, rewrite: u8) -> u8 {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    let apu = gb.apu_mut();
+    apu.write_register(0xFF12, initial);
+    apu.write_register(0xFF14, 0x80);
+    apu.write_register(0xFF12, rewrite);
+    apu.debug_state().channels[0].envelope.unwrap().volume
+}
+
+#[test]
+fn nrx2_writes_nudge_playing_volume() {
+    // Old period 0: +1
+    assert_eq!(zombie_volume(0x80, 0x80), 9);
+    // Old mode decreasing with a period: +2
+    assert_eq!(zombie_volume(0x81, 0x81), 10);
+    // Old mode increasing with a period: unchanged
+    assert_eq!(zombie_volume(0x89, 0x89), 8);
+    // Direction flip inverts: 16 - v, after the increments above
+    assert_eq!(zombie_volume(0x59, 0x51), 11);
+    assert_eq!(zombie_volume(0x88, 0x80), 7);
+    assert_eq!(zombie_volume(0x51, 0x59), 9);
+    // Wraps within 4 bits
+    assert_eq!(zombie_volume(0xF0, 0xF0), 0);
+}
+
+/// Peak of each side of interleaved stereo samples
+fn stereo_peaks(samples: &[f32]) -> (f32, f32) {
+    samples.chunks(2).fold((0.0f32, 0.0f32), |(left, right), pair| {
+        (left.max(pair[0].abs()), right.max(pair[1].abs()))
+    })
+}
+
+#[test]
+fn nr51_routes_low_bits_right_and_high_bits_left() {
+    let (left, right) = stereo_peaks(&tone(|gb| gb.mmu.write_byte(0xFF25, 0x01)));
+    assert!(left == 0.0 && right > 0.0);
+    let (left, right) = stereo_peaks(&tone(|gb| gb.mmu.write_byte(0xFF25, 0x10)));
+    assert!(left > 0.0 && right == 0.0);
+    let (left, right) = stereo_peaks(&tone(|gb| gb.mmu.write_byte(0xFF25, 0x11)));
+    assert!(left > 0.0 && (left - right).abs() < 1e-6);
+    
+    // Other channels' bits don't route channel 1
+    let (left, right) = stereo_peaks(&tone(|gb| gb.mmu.write_byte(0xFF25, 0xEE)));
+    assert!(left == 0.0 && right == 0.0);
+    
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    for value in [0x01, 0x10, 0x8E, 0xF3] {
+        gb.mmu.write_byte(0xFF25, value);
+        assert_eq!(gb.mmu.read_byte(0xFF25), value);
+    }
+    gb.mmu.write_byte(0xFF24, 0x8F);
+    assert_eq!(gb.mmu.read_byte(0xFF24), 0x8F, "VIN bits read back");
+}
+
+/// Pans the way game sound effects do (the Link's Awakening intro among
+/// them): one channel rewritten between hard left and hard right through
+/// NR51 between frames. Commercial ROMs can't be bundled, so this replays
+/// the register pattern rather than the game.
+#[test]
+fn pan_sweep_follows_nr51_between_frames() {
+    let mut gb = GameBoy::new(&tone_rom()).unwrap();
+    gb.run_frame();
+    
+    for (nr51, left_side) in [(0x10, true), (0x01, false), (0x10, true), (0x01, false)] {
+        gb.mmu.write_byte(0xFF25, nr51);
+        gb.clear_audio_buffer();
+        gb.run_frame();
+        let (left, right) = stereo_peaks(gb.audio_buffer());
+        assert_eq!((left > 0.0, right > 0.0), (left_side, !left_side), "NR51 = {:#04X}", nr51);
+    }
+}
+
+#[test]
+fn frame_sequencer_follows_div_falling_edges() {
+    let mut timer = Timer::new();
+    timer.write_div();
+    timer.take_div_apu_ticks();
+    
+    // DIV bit 4 (counter bit 12) rising doesn't clock, falling does
+    timer.step(0x1000);
+    assert_eq!(timer.take_div_apu_ticks(), 0);
+    timer.step(0x0FFF);
+    assert_eq!(timer.take_div_apu_ticks(), 0);
+    timer.step(1);
+    assert_eq!(timer.take_div_apu_ticks(), 1);
+    timer.step(0x2000 * 3);
+    assert_eq!(timer.take_div_apu_ticks(), 3);
+    
+    // Double speed uses counter bit 13, so the rate stays 512 Hz
+    timer.set_double_speed(true);
+    timer.write_div();
+    timer.take_div_apu_ticks();
+    timer.step(0x2000);
+    assert_eq!(timer.take_div_apu_ticks(), 0);
+    timer.step(0x2000);
+    assert_eq!(timer.take_div_apu_ticks(), 1);
+    
+    // A DIV write is a falling edge if the bit was set, and not otherwise
+    timer.step(0x2000);
+    timer.write_div();
+    assert_eq!(timer.take_div_apu_ticks(), 1);
+    timer.step(0x1000);
+    timer.write_div();
+    assert_eq!(timer.take_div_apu_ticks(), 0);
+}
+
+#[test]
+fn div_writes_clock_the_frame_sequencer_early() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    let step = |gb: &GameBoy| gb.apu().debug_state().frame_sequencer_step;
+    
+    // Bit 4 of DIV clear: the write resets DIV without a tick
+    gb.mmu.write_byte(0xFF04, 0);
+    let before = step(&gb);
+    gb.run_cycles(0x800);
+    gb.mmu.write_byte(0xFF04, 0);
+    gb.step();
+    assert_eq!(step(&gb), before);
+    
+    // Bit 4 set: the write clocks the sequencer straight away
+    gb.run_cycles(0x1000);
+    assert_eq!(gb.mmu.read_byte(0xFF04) & 0x10, 0x10);
+    gb.mmu.write_byte(0xFF04, 0);
+    gb.step();
+    assert_eq!(step(&gb), (before + 1) % 8);
+}
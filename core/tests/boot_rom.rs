@@ -0,0 +1,62 @@
+//! Booting through a boot ROM, with good, corrupted and missing cartridges
+
+mod common;
+
+use gbemu_core::{GameBoy, GbConfig};
+
+/// Stand-in DMG boot ROM: hangs at 0x0008 unless the first logo byte is
+/// right, otherwise unmaps itself at 0x00FC and falls into the cartridge
+fn boot_rom() -> Vec<u8> {
+    let mut boot = vec![0u8; 0x100];
+    boot[0x00..0x0D].copy_from_slice(&[
+        0x31, 0xFE, 0xFF, // ld sp, 0xFFFE
+        0xFA, 0x04, 0x01, // ld a, (0x0104)
+        0xFE, 0xCE,       // cp 0xCE
+        0x20, 0xFE,       // jr nz, -2
+        0xC3, 0xFC, 0x00, // jp 0x00FC
+    ]);
+    boot[0xFC..0x100].copy_from_slice(&[0x3E, 0x01, 0xE0, 0x50]); // ldh (0x50), 1
+    boot
+}
+
+/// 32KB ROM that stores 0x42 at 0xC000 and loops
+fn rom(logo: u8) -> Vec<u8> {
+    let mut rom = common::rom_with(&[0x3E, 0x42, 0xEA, 0x00, 0xC0, 0x18, 0xFE]);
+    rom[0x104] = logo;
+    rom
+}
+
+#[test]
+fn boots_into_cartridge() {
+    let mut gb = GameBoy::with_boot_rom(Some(&rom(0xCE)), &boot_rom(), GbConfig::default()).unwrap();
+    assert!(gb.memory_map().boot_rom_mapped);
+    assert_eq!(gb.cpu.regs.pc, 0x0000);
+    assert_eq!(gb.mmu.read_byte(0x0000), 0x31);
+    
+    gb.run_frame();
+    assert!(!gb.memory_map().boot_rom_mapped);
+    assert_eq!(gb.mmu.read_byte(0xC000), 0x42);
+    assert_eq!(gb.mmu.read_byte(0x0000), 0x00);
+    
+    // Reset runs the boot ROM again
+    gb.reset();
+    assert!(gb.memory_map().boot_rom_mapped);
+    assert_eq!(gb.cpu.regs.pc, 0x0000);
+}
+
+#[test]
+fn bad_logo_and_empty_slot_hang() {
+    let bad = GameBoy::with_boot_rom(Some(&rom(0x00)), &boot_rom(), GbConfig::default()).unwrap();
+    let empty = GameBoy::with_boot_rom(None, &boot_rom(), GbConfig::default()).unwrap();
+    
+    for mut gb in [bad, empty] {
+        gb.run_frame();
+        assert!(gb.memory_map().boot_rom_mapped);
+        assert_eq!(gb.cpu.regs.pc, 0x0008);
+    }
+}
+
+#[test]
+fn boot_rom_size_is_checked() {
+    assert!(GameBoy::with_boot_rom(None, &[0; 0x200], GbConfig::default()).is_err());
+}
@@ -0,0 +1,282 @@
+//! Cartridge header handling
+
+use gbemu_core::cartridge::MbcType;
+use gbemu_core::{EmulatorEvent, GameBoy, GbConfig};
+
+#[test]
+fn unknown_mapper_fallback() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x147] = 0xFC;
+    assert!(GameBoy::new(&rom).is_err());
+    
+    let config = GbConfig { allow_unknown_mapper: true, ..Default::default() };
+    let gb = GameBoy::with_config(&rom, config).unwrap();
+    assert_eq!(gb.unknown_cartridge_type(), Some(0xFC));
+    assert_eq!(gb.mmu.cartridge().mbc_type(), MbcType::Mbc5);
+    assert_eq!(gb.mmu.cartridge().ram().len(), 32 * 1024);
+    assert!(gb.mmu.cartridge().has_battery());
+    
+    rom[0x147] = 0x1B;
+    assert_eq!(GameBoy::with_config(&rom, gb.config().clone()).unwrap().unknown_cartridge_type(), None);
+}
+
+#[test]
+fn sram_delta_round_trip() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x147] = 0x03;
+    rom[0x149] = 0x02;
+    let mut gb = GameBoy::new(&rom).unwrap();
+    let mut other = GameBoy::new(&rom).unwrap();
+    
+    let baseline = gb.save_sram().unwrap();
+    let mut sram = baseline.clone();
+    sram[0x10] = 1;
+    sram[0x14] = 2;
+    sram[0x1F00] = 3;
+    gb.load_sram(&sram).unwrap();
+    
+    // Nearby changes share a run, distant ones get their own
+    let delta = gb.sram_delta(&baseline).unwrap();
+    assert_eq!(delta.len(), 4 + (8 + 5) + (8 + 1));
+    
+    other.apply_sram_delta(&delta).unwrap();
+    assert_eq!(other.save_sram().unwrap(), sram);
+    assert_eq!(gb.sram_delta(&sram).unwrap().len(), 4);
+    assert!(other.apply_sram_delta(&delta[..10]).is_err());
+}
+
+#[test]
+fn rejected_sram_deltas_change_nothing() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x147] = 0x03;
+    rom[0x149] = 0x02;
+    let mut gb = GameBoy::new(&rom).unwrap();
+    let sram = gb.save_sram().unwrap();
+    
+    let run = |offset: u32, bytes: &[u8]| {
+        let mut run = offset.to_le_bytes().to_vec();
+        run.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        run.extend_from_slice(bytes);
+        run
+    };
+    let header = |length: u32| length.to_le_bytes().to_vec();
+    
+    let too_long = [header(u32::MAX), run(0, &[1])].concat();
+    let shorter = [header(0x100), run(0, &[1]), run(0x200, &[2])].concat();
+    let mut wrapping = [header(0x2000), run(0, &[1])].concat();
+    wrapping.extend_from_slice(&u32::MAX.to_le_bytes());
+    wrapping.extend_from_slice(&2u32.to_le_bytes());
+    wrapping.extend_from_slice(&[3, 4]);
+    
+    for delta in [too_long, shorter, wrapping] {
+        assert!(gb.apply_sram_delta(&delta).is_err());
+        assert_eq!(gb.save_sram().unwrap(), sram);
+    }
+}
+
+#[test]
+fn capabilities_cover_loadable_mappers() {
+    let mut rom = vec![0u8; 0x8000];
+    let caps = GameBoy::new(&rom).unwrap().capabilities();
+    for cart_type in 0..=0xFF {
+        rom[0x147] = cart_type;
+        if let Ok(gb) = GameBoy::new(&rom) {
+            assert!(caps.supports_mbc(gb.mmu.cartridge().mbc_type()), "type {cart_type:#04X}");
+        }
+    }
+    
+    let json = serde_json::to_value(&caps).unwrap();
+    assert_eq!(json["ppu"], "Scanline");
+    assert_eq!(json["sgb"], false);
+}
+
+/// Stand-in for the boot logo, only in bank 0
+const LOGO: std::ops::Range<usize> = 0x104..0x134;
+
+/// ROM of `banks` 16KB banks, each starting with its bank number
+fn numbered_rom(banks: usize, cart_type: u8) -> Vec<u8> {
+    let mut rom = vec![0u8; banks * 0x4000];
+    for bank in 0..banks {
+        rom[bank * 0x4000..bank * 0x4000 + 2].copy_from_slice(&(bank as u16).to_le_bytes());
+    }
+    rom[LOGO].fill(0xCE);
+    rom[0x147] = cart_type;
+    rom
+}
+
+fn mbc5_rom(banks: usize) -> Vec<u8> {
+    numbered_rom(banks, 0x19)
+}
+
+fn mapped_bank(gb: &mut GameBoy) -> u16 {
+    u16::from_le_bytes([gb.mmu.read_byte(0x4000), gb.mmu.read_byte(0x4001)])
+}
+
+fn mapped_bank0(gb: &mut GameBoy) -> u16 {
+    u16::from_le_bytes([gb.mmu.read_byte(0x0000), gb.mmu.read_byte(0x0001)])
+}
+
+/// Write MBC1 BANK1, BANK2 and the banking mode
+fn mbc1_select(gb: &mut GameBoy, bank1: u8, bank2: u8, mode: u8) {
+    gb.mmu.write_byte(0x2000, bank1);
+    gb.mmu.write_byte(0x4000, bank2);
+    gb.mmu.write_byte(0x6000, mode);
+}
+
+#[test]
+fn mbc1_rom_banking() {
+    // Like mooneye's mbc1/rom_512kb through rom_16Mb: BANK2 supplies bits
+    // 5-6 and everything wraps to the ROM size
+    for banks in [32, 64, 128] {
+        let mut gb = GameBoy::new(&numbered_rom(banks, 0x01)).unwrap();
+        for bank2 in 0..4u8 {
+            for bank1 in 0..0x20u8 {
+                mbc1_select(&mut gb, bank1, bank2, 0);
+                let expected = ((bank2 as usize) << 5 | bank1.max(1) as usize) % banks;
+                assert_eq!(mapped_bank(&mut gb) as usize, expected, "{banks} banks, {bank2}:{bank1:#04X}");
+                assert_eq!(mapped_bank0(&mut gb), 0);
+            }
+        }
+    }
+    
+    // The zero check only sees BANK1's 5 bits: 0x20 reads as 0 and maps
+    // bank 1, and with BANK2 set it maps 0x21, never 0x20
+    let mut gb = GameBoy::new(&numbered_rom(128, 0x01)).unwrap();
+    mbc1_select(&mut gb, 0x20, 0, 0);
+    assert_eq!(mapped_bank(&mut gb), 0x01);
+    for bank2 in 1..4u8 {
+        mbc1_select(&mut gb, 0x00, bank2, 0);
+        assert_eq!(mapped_bank(&mut gb), (bank2 as u16) << 5 | 1);
+    }
+    
+    // Mode 1 maps BANK2 alone at 0x0000: banks 0x20/0x40/0x60
+    for bank2 in 0..4u8 {
+        mbc1_select(&mut gb, 0x01, bank2, 1);
+        assert_eq!(mapped_bank0(&mut gb), (bank2 as u16) << 5);
+        assert_eq!(mapped_bank(&mut gb), (bank2 as u16) << 5 | 1);
+    }
+    
+    // ... wrapped to the ROM size like the switchable bank
+    let mut gb = GameBoy::new(&numbered_rom(64, 0x01)).unwrap();
+    mbc1_select(&mut gb, 0x01, 3, 1);
+    assert_eq!((mapped_bank0(&mut gb), mapped_bank(&mut gb)), (0x20, 0x21));
+    let mut gb = GameBoy::new(&numbered_rom(32, 0x01)).unwrap();
+    mbc1_select(&mut gb, 0x01, 3, 1);
+    assert_eq!((mapped_bank0(&mut gb), mapped_bank(&mut gb)), (0x00, 0x01));
+}
+
+#[test]
+fn mbc1_multicart_banking() {
+    // 1MB with the logo repeated at the start of each 256KB game
+    let mut rom = numbered_rom(64, 0x01);
+    for game in 1..4 {
+        rom[game * 0x40000 + LOGO.start..game * 0x40000 + LOGO.end].fill(0xCE);
+    }
+    let mut gb = GameBoy::new(&rom).unwrap();
+    
+    // BANK1 bit 4 is unconnected and BANK2 supplies bits 4-5
+    mbc1_select(&mut gb, 0x12, 1, 0);
+    assert_eq!(mapped_bank(&mut gb), 0x12);
+    mbc1_select(&mut gb, 0x12, 3, 0);
+    assert_eq!(mapped_bank(&mut gb), 0x32);
+    
+    // BANK1 = 0x10 passes the 5-bit zero check, then loses bit 4: bank 0
+    // of the selected game
+    mbc1_select(&mut gb, 0x10, 2, 0);
+    assert_eq!(mapped_bank(&mut gb), 0x20);
+    mbc1_select(&mut gb, 0x00, 2, 0);
+    assert_eq!(mapped_bank(&mut gb), 0x21);
+    
+    // Mode 1 puts each game's bank 0 at 0x0000
+    for bank2 in 0..4u8 {
+        mbc1_select(&mut gb, 0x01, bank2, 1);
+        assert_eq!(mapped_bank0(&mut gb), (bank2 as u16) << 4);
+    }
+    
+    // Without the repeated logo, a 1MB ROM is a plain MBC1
+    let mut gb = GameBoy::new(&numbered_rom(64, 0x01)).unwrap();
+    mbc1_select(&mut gb, 0x12, 1, 0);
+    assert_eq!(mapped_bank(&mut gb), 0x32);
+}
+
+#[test]
+fn mbc5_rom_banking() {
+    // Like mooneye's mbc5/rom_512kb through rom_64Mb: every 9-bit bank
+    // number, including 0, maps that bank wrapped to the ROM size
+    for banks in [32, 64, 128, 256, 512] {
+        let mut gb = GameBoy::new(&mbc5_rom(banks)).unwrap();
+        assert_eq!(mapped_bank(&mut gb), 1);
+        for bank in 0..0x200u16 {
+            gb.mmu.write_byte(0x2000, bank as u8);
+            gb.mmu.write_byte(0x3000, (bank >> 8) as u8);
+            assert_eq!(mapped_bank(&mut gb), bank % banks as u16, "{banks} banks, bank {bank}");
+        }
+    }
+    
+    // 0x3000-0x3FFF only takes bit 0 and doesn't touch the low byte
+    let mut gb = GameBoy::new(&mbc5_rom(512)).unwrap();
+    gb.mmu.write_byte(0x2100, 0x23);
+    gb.mmu.write_byte(0x3F00, 0xFF);
+    assert_eq!(mapped_bank(&mut gb), 0x123);
+    gb.mmu.write_byte(0x3000, 0xFE);
+    assert_eq!(mapped_bank(&mut gb), 0x23);
+    
+    // A truncated dump wraps like the next chip size up, with the missing
+    // banks open bus
+    let mut gb = GameBoy::new(&mbc5_rom(6)).unwrap();
+    gb.mmu.write_byte(0x2000, 6);
+    assert_eq!(mapped_bank(&mut gb), 0xFFFF);
+    gb.mmu.write_byte(0x2000, 13);
+    assert_eq!(mapped_bank(&mut gb), 5);
+}
+
+#[test]
+fn autosave_after_writes_settle() {
+    let mut rom = vec![0u8; 0x8000];
+    // ld a, 0x0A; ld (0x0000), a; ld a, 0x42; ld (0xA000), a; jr @
+    rom[0x100..0x10C].copy_from_slice(&[0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x3E, 0x42, 0xEA, 0x00, 0xA0, 0x18, 0xFE]);
+    rom[0x147] = 0x03;
+    rom[0x149] = 0x02;
+    let config = GbConfig { autosave_frames: 10, ..Default::default() };
+    let mut gb = GameBoy::with_config(&rom, config).unwrap();
+    
+    let saves: Vec<_> = (1..=30u64)
+        .flat_map(|frame| gb.run_frame().events.into_iter().map(move |event| (frame, event)))
+        .filter_map(|(frame, event)| match event {
+            EmulatorEvent::Autosave(sram) => Some((frame, sram)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(saves.len(), 1);
+    assert_eq!(saves[0].0, 11);
+    assert_eq!(saves[0].1[0], 0x42);
+    
+    // Off by default
+    let mut gb = GameBoy::new(&rom).unwrap();
+    for _ in 0..30 {
+        gb.run_frame();
+    }
+    assert_eq!(gb.take_autosave(), None);
+}
+
+#[test]
+fn ram_banks_past_the_end_are_open_bus() {
+    // MBC1 and MBC5, each with a single 8KB RAM bank
+    for cart_type in [0x03, 0x1B] {
+        let mut rom = vec![0u8; 0x8000];
+        rom[0x147] = cart_type;
+        rom[0x149] = 0x02;
+        let mut gb = GameBoy::new(&rom).unwrap();
+        gb.mmu.write_byte(0x0000, 0x0A);
+        gb.mmu.write_byte(0x6000, 0x01); // MBC1 RAM banking mode
+        gb.mmu.write_byte(0xA000, 0x42);
+        
+        gb.mmu.write_byte(0x4000, 0x01);
+        assert_eq!(gb.mmu.read_byte(0xA000), 0xFF, "type {cart_type:#04X}");
+        gb.mmu.write_byte(0xA000, 0x99);
+        
+        gb.mmu.write_byte(0x4000, 0x00);
+        assert_eq!(gb.mmu.read_byte(0xA000), 0x42, "type {cart_type:#04X}: bank 1 aliased bank 0");
+        assert_eq!(gb.save_sram().unwrap().len(), 0x2000);
+    }
+}
@@ -0,0 +1,25 @@
+//! Test cartridges shared by the integration tests
+
+// Each test binary only uses some of these
+#![allow(dead_code)]
+
+/// 32KB ROM with `nop; jp 0x0150` at the entry point and `program` at
+/// 0x0150
+pub fn rom_with(program: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x100..0x104].copy_from_slice(&[0x00, 0xC3, 0x50, 0x01]);
+    rom[0x150..0x150 + program.len()].copy_from_slice(program);
+    rom
+}
+
+/// 32KB ROM that idles at 0x0150 (`jr @`)
+pub fn rom() -> Vec<u8> {
+    rom_with(&[0x18, 0xFE])
+}
+
+/// `rom_with` flagged as a CGB game
+pub fn cgb_rom_with(program: &[u8]) -> Vec<u8> {
+    let mut rom = rom_with(program);
+    rom[0x143] = 0x80;
+    rom
+}
@@ -0,0 +1,43 @@
+//! ROM execution coverage
+
+use gbemu_core::coverage::BANK_SIZE;
+use gbemu_core::GameBoy;
+
+#[test]
+fn marks_executed_bytes_per_bank() {
+    // MBC1 with four ROM banks, calling into bank 2
+    let mut rom = vec![0u8; 0x10000];
+    rom[0x147] = 0x01;
+    rom[0x148] = 0x01;
+    rom[0x101..0x104].copy_from_slice(&[0xC3, 0x50, 0x01]);
+    rom[0x150..0x15A].copy_from_slice(&[
+        0x3E, 0x02, 0xEA, 0x00, 0x20, // bank 2
+        0xCD, 0x00, 0x40,             // call 0x4000
+        0x18, 0xFE,                   // loop
+    ]);
+    rom[2 * BANK_SIZE] = 0xC9; // ret
+    
+    let mut gb = GameBoy::new(&rom).unwrap();
+    assert!(gb.coverage().is_none());
+    gb.set_coverage(true);
+    gb.run_frame();
+    
+    let coverage = gb.coverage().unwrap();
+    assert_eq!(coverage.banks(), 4);
+    assert_eq!(coverage.bank_bitmap(0).len(), BANK_SIZE / 8);
+    for offset in (0x100..0x104).chain(0x150..0x15A) {
+        assert!(coverage.is_executed(offset), "{offset:#X} not marked");
+    }
+    assert!(coverage.is_executed(2 * BANK_SIZE));
+    assert!(!coverage.is_executed(0x104));
+    assert!(!coverage.is_executed(0x4000));
+    assert!(!coverage.is_executed(2 * BANK_SIZE + 1));
+    assert_eq!(coverage.executed_bytes(), 15);
+    assert_eq!(coverage.bank_bitmap(2)[0], 0x01);
+    assert_eq!(coverage.bank_percentage(1), 0.0);
+    assert!(coverage.bank_percentage(0) > 0.0);
+    assert_eq!(coverage.percentage(), 15.0 * 100.0 / 0x10000 as f64);
+    
+    gb.set_coverage(false);
+    assert!(gb.coverage().is_none());
+}
@@ -0,0 +1,173 @@
+//! Opcode table against the instructions as executed
+
+use gbemu_core::cpu::opcodes::{lookup, Opcode};
+use gbemu_core::cpu::InterruptTiming;
+use gbemu_core::{GameBoy, GbConfig};
+
+/// Execute `bytes` from 0xC000 with the given flags; returns the cycles
+/// taken and the PC afterwards
+fn execute(bytes: &[u8], flags: u8) -> (u32, u16) {
+    let mut gb = GameBoy::new(&vec![0u8; 0x8000]).unwrap();
+    for (i, &byte) in bytes.iter().enumerate() {
+        gb.mmu.write_byte(0xC000 + i as u16, byte);
+    }
+    gb.cpu.regs.set_af(flags as u16);
+    gb.cpu.regs.set_bc(0xC200);
+    gb.cpu.regs.set_de(0xC210);
+    gb.cpu.regs.set_hl(0xC220);
+    gb.cpu.regs.sp = 0xDFF0;
+    gb.cpu.regs.pc = 0xC001;
+    
+    let cycles = gb.cpu.execute(bytes[0], &mut gb.mmu);
+    (cycles, gb.cpu.regs.pc)
+}
+
+fn is_jump(opcode: &Opcode) -> bool {
+    ["JP", "JR", "CALL", "RET", "RST"].iter().any(|name| opcode.mnemonic.starts_with(name))
+}
+
+#[test]
+fn table_matches_execution() {
+    let instructions = (0..=0xFFu8)
+        .filter(|&op| op != 0xCB && op != 0x10 && op != 0x76)
+        .map(|op| vec![op, 0x10, 0xC1])
+        .chain((0..=0xFFu8).map(|op| vec![0xCB, op, 0x00]));
+    
+    for bytes in instructions {
+        let opcode = lookup(&bytes);
+        let runs = [execute(&bytes, 0x00), execute(&bytes, 0xF0)];
+        
+        for (cycles, pc) in runs {
+            let taken = is_jump(opcode) && pc != 0xC000 + opcode.length as u16;
+            let expected = if taken { opcode.branch_cycles } else { opcode.cycles };
+            assert_eq!(cycles, expected as u32, "{:02X?} {}", bytes, opcode.mnemonic);
+            if !is_jump(opcode) {
+                assert_eq!(pc, 0xC000 + opcode.length as u16, "{:02X?} {}", bytes, opcode.mnemonic);
+            }
+        }
+        if opcode.is_conditional() {
+            assert_ne!(runs[0].0, runs[1].0, "{} takes one branch of each", opcode.mnemonic);
+        }
+    }
+}
+
+#[test]
+fn disassembles_operands() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x150..0x15D].copy_from_slice(&[
+        0x21, 0x34, 0x12, // LD HL, $1234
+        0xE0, 0x40,       // LDH ($40), A
+        0x18, 0xFE,       // JR $0155
+        0xF8, 0xFC,       // LD HL, SP-4
+        0xCB, 0x7E,       // BIT 7, (HL)
+        0xDF,             // RST 18H
+        0xD3,             // illegal
+    ]);
+    let gb = GameBoy::new(&rom).unwrap();
+    
+    let mut addr = 0x150;
+    let mut listing = Vec::new();
+    while addr < 0x15D {
+        let (text, length) = gb.disassemble(addr);
+        listing.push(text);
+        addr += length as u16;
+    }
+    assert_eq!(listing, [
+        "LD HL, $1234",
+        "LDH ($40), A",
+        "JR $0155",
+        "LD HL, SP-4",
+        "BIT 7, (HL)",
+        "RST 18H",
+        "ILLEGAL",
+    ]);
+}
+
+/// A CPU at 0xC000 (a NOP) with IME set and the timer and VBlank
+/// interrupts enabled, with `config`'s interrupt timing
+fn interrupt_ready(config: GbConfig) -> GameBoy {
+    let mut gb = GameBoy::with_config(&vec![0u8; 0x8000], config).unwrap();
+    gb.mmu.write_byte(0xC000, 0x00);
+    gb.mmu.write_byte(0xFFFF, 0x05);
+    gb.mmu.write_byte(0xFF0F, 0x00);
+    gb.cpu.regs.pc = 0xC000;
+    gb.cpu.regs.sp = 0xDFF0;
+    gb.cpu.ime = true;
+    gb
+}
+
+#[test]
+fn interrupt_dispatch_timing() {
+    // Dispatch: 5 M-cycles, PC pushed, IF bit acknowledged, IME cleared
+    let mut gb = interrupt_ready(GbConfig::default());
+    gb.mmu.write_byte(0xFF0F, 0x04);
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 20);
+    assert_eq!((gb.cpu.regs.pc, gb.cpu.regs.sp), (0x0050, 0xDFEE));
+    assert_eq!((gb.mmu.read_byte(0xDFEE), gb.mmu.read_byte(0xDFEF)), (0x00, 0xC0));
+    assert_eq!(gb.mmu.read_byte(0xFF0F) & 0x1F, 0x00);
+    assert!(!gb.cpu.ime);
+    
+    // VBlank has priority, and the other request stays pending
+    let mut gb = interrupt_ready(GbConfig::default());
+    gb.mmu.write_byte(0xFF0F, 0x05);
+    gb.cpu.step(&mut gb.mmu);
+    assert_eq!(gb.cpu.regs.pc, 0x0040);
+    assert_eq!(gb.mmu.read_byte(0xFF0F) & 0x1F, 0x04);
+    
+    // Leaving HALT costs one more M-cycle before the dispatch
+    let mut gb = interrupt_ready(GbConfig::default());
+    gb.cpu.halted = true;
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 4, "idle in HALT");
+    gb.mmu.write_byte(0xFF0F, 0x04);
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 24);
+    assert_eq!(gb.cpu.regs.pc, 0x0050);
+    
+    // With IME off HALT just ends, after the same extra M-cycle
+    let mut gb = interrupt_ready(GbConfig::default());
+    gb.cpu.ime = false;
+    gb.cpu.halted = true;
+    gb.mmu.write_byte(0xFF0F, 0x04);
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 4);
+    assert!(!gb.cpu.halted);
+    assert_eq!(gb.cpu.regs.pc, 0xC000);
+    
+    // Both costs are configurable
+    let timing = InterruptTiming { dispatch_cycles: 24, halt_exit_cycles: 0 };
+    let mut gb = interrupt_ready(GbConfig { interrupt_timing: timing, ..GbConfig::default() });
+    gb.cpu.halted = true;
+    gb.mmu.write_byte(0xFF0F, 0x04);
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 24);
+}
+
+#[test]
+fn interrupt_waits_for_the_instruction_after_ei() {
+    // ei; nop; nop with the timer interrupt already requested
+    let mut gb = interrupt_ready(GbConfig::default());
+    gb.cpu.ime = false;
+    for (i, byte) in [0xFB, 0x00, 0x00].into_iter().enumerate() {
+        gb.mmu.write_byte(0xC000 + i as u16, byte);
+    }
+    gb.mmu.write_byte(0xFF0F, 0x04);
+    
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 4);
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 4, "the instruction after EI still runs");
+    assert_eq!(gb.cpu.regs.pc, 0xC002);
+    assert_eq!(gb.cpu.step(&mut gb.mmu), 20);
+    assert_eq!(gb.cpu.regs.pc, 0x0050);
+}
+
+#[test]
+fn di_right_after_ei_cancels_it() {
+    let mut gb = interrupt_ready(GbConfig::default());
+    gb.cpu.ime = false;
+    for (i, byte) in [0xFB, 0xF3, 0x00].into_iter().enumerate() {
+        gb.mmu.write_byte(0xC000 + i as u16, byte);
+    }
+    gb.mmu.write_byte(0xFF0F, 0x04);
+    
+    for _ in 0..3 {
+        assert_eq!(gb.cpu.step(&mut gb.mmu), 4);
+    }
+    assert!(!gb.cpu.ime);
+    assert_eq!(gb.cpu.regs.pc, 0xC003);
+}
@@ -0,0 +1,69 @@
+//! Breakpoints and instruction streams
+
+mod common;
+
+use gbemu_core::GameBoy;
+
+/// 32KB ROM counting in B forever: 0x150 inc b; 0x151 jr -3
+fn rom() -> Vec<u8> {
+    common::rom_with(&[0x04, 0x18, 0xFD])
+}
+
+#[test]
+fn run_frame_stops_at_breakpoints() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.add_breakpoint(0x0150);
+    
+    assert_eq!(gb.run_frame_until_breakpoint(), Some(0x0150));
+    let b = gb.cpu.regs.b;
+    
+    // Resuming runs the instruction at the breakpoint, then stops again
+    assert_eq!(gb.run_frame_until_breakpoint(), Some(0x0150));
+    assert_eq!(gb.cpu.regs.b, b.wrapping_add(1));
+    assert_eq!(gb.frame_count(), 0);
+    
+    assert!(gb.remove_breakpoint(0x0150));
+    assert_eq!(gb.breakpoints().count(), 0);
+    assert_eq!(gb.run_frame_until_breakpoint(), None);
+    assert_eq!(gb.frame_count(), 1);
+}
+
+#[test]
+fn step_iter_streams_executed_instructions() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    let trace: Vec<_> = gb.step_iter().take(5).collect();
+    
+    let executed: Vec<(u16, u8, u32)> = trace.iter().map(|i| (i.pc, i.opcode, i.cycles)).collect();
+    assert_eq!(executed, [
+        (0x0100, 0x00, 4),
+        (0x0101, 0xC3, 16),
+        (0x0150, 0x04, 4),
+        (0x0151, 0x18, 12),
+        (0x0150, 0x04, 4),
+    ]);
+    assert!(trace.iter().all(|i| i.bank == 0));
+    assert_eq!(trace[4].regs_after.b, trace[2].regs_after.b.wrapping_add(1));
+    assert_eq!(gb.cpu.regs.pc, 0x0151);
+    
+    // A CPU halted with no interrupts enabled never runs another instruction
+    let mut rom = rom();
+    rom[0x150] = 0x76;
+    let mut gb = GameBoy::new(&rom).unwrap();
+    gb.mmu.write_byte(0xFFFF, 0x00);
+    assert_eq!(gb.step_iter().count(), 3);
+}
+
+#[test]
+fn run_until_pc_and_frame() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    
+    // nop, jp 0x0150, inc b
+    assert_eq!(gb.run_until_pc(0x0151, 1000), Some(4 + 16 + 4));
+    assert_eq!(gb.run_until_pc(0x0151, 1000), Some(0));
+    assert_eq!(gb.run_until_pc(0x0200, 100_000), None);
+    assert_eq!(gb.frame_count(), 1);
+    
+    assert_eq!(gb.run_until_frame(3), 2);
+    assert_eq!(gb.run_until_frame(2), 0);
+    assert_eq!(gb.frame_count(), 3);
+}
@@ -0,0 +1,37 @@
+//! The generated demo cartridge
+
+use std::collections::HashSet;
+
+use gbemu_core::demo_rom::{DEMO_ROM, FRAME_COUNTER};
+use gbemu_core::{GameBoy, RomInfo};
+
+#[test]
+fn demo_rom_is_a_valid_cartridge() {
+    let gb = GameBoy::new(&DEMO_ROM).unwrap();
+    let info = RomInfo::new(gb.mmu.cartridge());
+    assert_eq!(info.title, "GBEMU DEMO");
+    assert!(info.header_checksum_valid);
+    
+    let global = DEMO_ROM.iter().enumerate()
+        .filter(|&(i, _)| i != 0x14E && i != 0x14F)
+        .fold(0u16, |sum, (_, &byte)| sum.wrapping_add(byte as u16));
+    assert_eq!(global, u16::from_be_bytes([DEMO_ROM[0x14E], DEMO_ROM[0x14F]]));
+}
+
+#[test]
+fn demo_scrolls_a_checkerboard() {
+    let mut gb = GameBoy::new(&DEMO_ROM).unwrap();
+    gb.run_until_frame(10);
+    
+    // The frame counter drives the scroll registers
+    let counter = gb.mmu.read_byte(FRAME_COUNTER);
+    assert!(counter >= 8, "{counter}");
+    assert_eq!(gb.mmu.read_byte(0xFF43), counter);
+    
+    let shades: HashSet<u8> = gb.framebuffer_indexed().iter().copied().collect();
+    assert_eq!(shades.len(), 3, "{shades:?}");
+    
+    let before = gb.frame_hash();
+    gb.run_frame();
+    assert_ne!(gb.frame_hash(), before);
+}
@@ -0,0 +1,102 @@
+//! OAM DMA and CGB HDMA source mapping and timing
+
+use gbemu_core::{AccuracyProfile, GameBoy, GbConfig};
+
+fn gb(cgb: bool, accuracy: AccuracyProfile) -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x143] = if cgb { 0x80 } else { 0x00 };
+    rom[0x147] = 0x03;
+    rom[0x149] = 0x02;
+    
+    let config = GbConfig { accuracy, ..Default::default() };
+    let mut gb = GameBoy::with_config(&rom, config).unwrap();
+    gb.mmu.write_byte(0x0000, 0x0A);
+    for i in 0..0xA0u16 {
+        gb.mmu.write_byte(0xA000 + i, 0xA0 ^ i as u8);
+        gb.mmu.write_byte(0xC000 + i, 0xC0 ^ i as u8);
+        gb.mmu.write_byte(0xDE00 + i, 0xDE ^ i as u8);
+        gb.mmu.write_byte(0xFE00 + i, 0xFE ^ i as u8);
+    }
+    gb
+}
+
+fn dma(gb: &mut GameBoy, source: u8) -> Vec<u8> {
+    gb.mmu.write_byte(0xFF46, source);
+    for _ in 0..0xA0 {
+        gb.mmu.step_dma();
+    }
+    (0..0xA0).map(|i| gb.mmu.read_byte(0xFE00 + i)).collect()
+}
+
+fn pattern(high: u8) -> Vec<u8> {
+    (0..0xA0u16).map(|i| high ^ i as u8).collect()
+}
+
+#[test]
+fn high_sources_follow_the_model() {
+    let mut dmg = gb(false, AccuracyProfile::accurate());
+    assert_eq!(dma(&mut dmg, 0xE0), pattern(0xC0));
+    assert_eq!(dma(&mut dmg, 0xFE), pattern(0xDE));
+    
+    let mut cgb = gb(true, AccuracyProfile::accurate());
+    assert_eq!(dma(&mut cgb, 0xE0), pattern(0xA0));
+}
+
+#[test]
+fn oam_source_is_not_locked_by_its_own_transfer() {
+    let mut gb = gb(false, AccuracyProfile::default());
+    assert_eq!(dma(&mut gb, 0xFE), pattern(0xFE));
+    assert_eq!(dma(&mut gb, 0xC0), pattern(0xC0));
+}
+
+#[test]
+fn transfer_takes_640_cycles_however_they_are_split() {
+    let mut gb = gb(false, AccuracyProfile::default());
+    for chunk in [1, 3, 6, 20] {
+        gb.mmu.write_byte(0xFF46, 0xC0);
+        let mut cycles = 0;
+        while gb.mmu.memory_map().oam_dma_active {
+            gb.mmu.advance_dma(chunk);
+            cycles += chunk;
+        }
+        assert!((640..640 + chunk).contains(&cycles), "{chunk}-cycle steps: {cycles}");
+    }
+}
+
+/// Run a one-block general purpose HDMA into VRAM bank `bank` and return
+/// the 16 bytes it wrote
+fn hdma(gb: &mut GameBoy, source: u16, dest: u16, bank: u8) -> Vec<u8> {
+    gb.mmu.write_byte(0xFF4F, bank);
+    gb.mmu.write_byte(0xFF51, (source >> 8) as u8);
+    gb.mmu.write_byte(0xFF52, source as u8);
+    gb.mmu.write_byte(0xFF53, (dest >> 8) as u8);
+    gb.mmu.write_byte(0xFF54, dest as u8);
+    gb.mmu.write_byte(0xFF55, 0x00);
+    
+    let start = bank as usize * 0x2000 + (dest & 0x1FF0) as usize;
+    gb.mmu.vram()[start..start + 16].to_vec()
+}
+
+#[test]
+fn hdma_sources_and_vram_bank() {
+    let mut gb = gb(true, AccuracyProfile::default());
+    assert_eq!(hdma(&mut gb, 0xC000, 0x8100, 1), pattern(0xC0)[..16]);
+    assert_eq!(hdma(&mut gb, 0xE000, 0x8200, 0), pattern(0xA0)[..16], "0xE000 reads external RAM");
+    assert_eq!(hdma(&mut gb, 0x8000, 0x8300, 1), [0xFF; 16], "VRAM can't be a source");
+    
+    // The other bank is untouched
+    assert_eq!(gb.mmu.vram()[0x0100..0x0110], [0; 16]);
+}
+
+#[cfg(feature = "instrumentation")]
+#[test]
+fn hdma_copies_stay_off_the_cpu_bus() {
+    let mut gb = gb(true, AccuracyProfile::default());
+    gb.start_bus_capture();
+    hdma(&mut gb, 0xC000, 0x8000, 0);
+    let accesses = gb.stop_bus_capture();
+    
+    // Only the register writes that set the transfer up
+    let addrs: Vec<u16> = accesses.iter().map(|access| access.addr).collect();
+    assert_eq!(addrs, [0xFF4F, 0xFF51, 0xFF52, 0xFF53, 0xFF54, 0xFF55]);
+}
@@ -0,0 +1,96 @@
+//! Learning environment episodes
+
+mod common;
+
+use gbemu_core::{Env, EnvConfig, ObservationMode};
+
+/// Copies the first 256 bytes of WRAM (random at power-on) into tiles
+/// 0-15 and then scrolls the background by the d-pad lines, so both the
+/// seed and the actions show up on screen
+fn rom() -> Vec<u8> {
+    common::rom_with(&[
+        0xAF, 0xE0, 0x40, // xor a; ldh (LCDC), a
+        0x21, 0x00, 0xC0, // ld hl, 0xC000
+        0x11, 0x00, 0x80, // ld de, 0x8000
+        0x06, 0x00, // ld b, 0
+        0x2A, 0x12, 0x13, 0x05, 0x20, 0xFA, // copy: ld a, (hl+); ld (de), a; inc de; dec b; jr nz, copy
+        0x3E, 0x91, 0xE0, 0x40, // ld a, 0x91; ldh (LCDC), a
+        0x3E, 0x20, 0xE0, 0x00, // main: ld a, 0x20; ldh (P1), a
+        0xF0, 0x00, 0xE0, 0x43, // ldh a, (P1); ldh (SCX), a
+        0x18, 0xF6, // jr main
+    ])
+}
+
+/// Observations after each action in `actions`
+fn episode(env: &mut Env, actions: &[u8]) -> Vec<Vec<u8>> {
+    actions.iter().map(|&action| env.step(action).0.to_vec()).collect()
+}
+
+fn config(seed: u64) -> EnvConfig {
+    EnvConfig { frame_skip: 2, seed: Some(seed), ..EnvConfig::default() }
+}
+
+#[test]
+fn episodes_are_deterministic() {
+    let actions = [0x00, 0x01, 0x01, 0x04, 0x00, 0x0A, 0x02, 0x00];
+    let mut env = Env::new(&rom(), config(7)).unwrap();
+    let first = episode(&mut env, &actions);
+    
+    // The actions show up in the observations
+    assert_ne!(first[0], first[1]);
+    
+    // Same seed and actions, in a new instance or after a reset
+    assert_eq!(episode(&mut Env::new(&rom(), config(7)).unwrap(), &actions), first);
+    env.reset();
+    assert_eq!(env.episode_frames(), 0);
+    assert_eq!(episode(&mut env, &actions), first);
+    
+    // Another seed powers on with other RAM
+    env.reset_with_seed(8);
+    assert_ne!(episode(&mut env, &actions), first);
+    env.reset_with_seed(7);
+    assert_eq!(episode(&mut env, &actions), first);
+}
+
+#[test]
+fn frame_skip_and_max_frames_end_the_episode() {
+    let config = EnvConfig { frame_skip: 3, max_frames: Some(7), ..EnvConfig::default() };
+    let mut env = Env::new(&rom(), config).unwrap();
+    
+    let steps: Vec<(u32, bool)> = (0..4)
+        .map(|_| {
+            let (_, frames, done) = env.step(0);
+            (frames, done)
+        })
+        .collect();
+    assert_eq!(steps, [(3, false), (3, false), (1, true), (0, true)]);
+    assert_eq!(env.episode_frames(), 7);
+    
+    // A reset starts a new episode
+    env.reset();
+    assert!(!env.is_done());
+    assert_eq!(env.step(0).1, 3);
+}
+
+#[test]
+fn observation_shapes() {
+    let modes = [
+        (ObservationMode::Grayscale, 2, (80, 72, 1)),
+        (ObservationMode::Rgba, 4, (40, 36, 4)),
+        (ObservationMode::Indexed, 1, (160, 144, 1)),
+    ];
+    for (observation, downscale, shape) in modes {
+        let config = EnvConfig { observation, downscale, ..EnvConfig::default() };
+        let mut env = Env::new(&rom(), config).unwrap();
+        assert_eq!(env.observation_shape(), shape);
+        assert_eq!(env.step(0).0.len(), shape.0 * shape.1 * shape.2);
+    }
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let frame_skip = EnvConfig { frame_skip: 0, ..EnvConfig::default() };
+    assert!(Env::new(&rom(), frame_skip).is_err());
+    let downscale = EnvConfig { downscale: 3, ..EnvConfig::default() };
+    assert!(Env::new(&rom(), downscale).is_err());
+}
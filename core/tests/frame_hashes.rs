@@ -0,0 +1,177 @@
+//! Golden frame hashes.
+//!
+//! Each case runs a ROM for a number of frames and compares
+//! `GameBoy::frame_hash` with the recorded value, so changes to the PPU,
+//! bus or CPU that alter output show up here. The built-in cases are tiny
+//! hand-assembled programs; test ROMs in `tests/roms` are checked too
+//! (see the README there).
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use gbemu_core::demo_rom::DEMO_ROM;
+use gbemu_core::GameBoy;
+
+/// Wait for VBlank, turn the LCD off, load tile 1 with a striped pattern,
+/// fill both tile maps with alternating tiles 0 and 1 and set BGP
+const SETUP: &[u8] = &[
+    0xF0, 0x44,       // wait: ldh a, (LY)
+    0xFE, 0x90,       // cp 144
+    0x20, 0xFA,       // jr nz, wait
+    0xAF,             // xor a
+    0xE0, 0x40,       // ldh (LCDC), a
+    0x21, 0x10, 0x80, // ld hl, 0x8010
+    0x0E, 0x08,       // ld c, 8
+    0x3E, 0xF0,       // tile: ld a, 0xF0
+    0x22,             // ld (hl+), a
+    0x3E, 0x3C,       // ld a, 0x3C
+    0x22,             // ld (hl+), a
+    0x0D,             // dec c
+    0x20, 0xF7,       // jr nz, tile
+    0x21, 0x00, 0x98, // ld hl, 0x9800
+    0x01, 0x00, 0x08, // ld bc, 0x0800
+    0x7D,             // map: ld a, l
+    0xE6, 0x01,       // and 1
+    0x22,             // ld (hl+), a
+    0x0B,             // dec bc
+    0x78,             // ld a, b
+    0xB1,             // or c
+    0x20, 0xF7,       // jr nz, map
+    0x3E, 0xE4,       // ld a, 0xE4
+    0xE0, 0x47,       // ldh (BGP), a
+];
+
+/// Background only, scrolled by 3 pixels
+const BACKGROUND: &[u8] = &[
+    0x3E, 0x03,       // ld a, 3
+    0xE0, 0x43,       // ldh (SCX), a
+    0x3E, 0x91,       // ld a, 0x91
+    0xE0, 0x40,       // ldh (LCDC), a
+    0x18, 0xFE,       // jr @
+];
+
+/// Background, window from (84, 80) and one sprite with an inverted
+/// palette (both offset from the tile grid so they stand out)
+const WINDOW_AND_SPRITE: &[u8] = &[
+    0x21, 0x00, 0xFE, // ld hl, 0xFE00
+    0x3E, 0x38,       // ld a, 56 (Y)
+    0x22,             // ld (hl+), a
+    0x3E, 0x34,       // ld a, 52 (X)
+    0x22,             // ld (hl+), a
+    0x3E, 0x01,       // ld a, 1 (tile)
+    0x22,             // ld (hl+), a
+    0xAF,             // xor a (attributes)
+    0x22,             // ld (hl+), a
+    0x3E, 0x1B,       // ld a, 0x1B
+    0xE0, 0x48,       // ldh (OBP0), a
+    0x3E, 0x50,       // ld a, 80
+    0xE0, 0x4A,       // ldh (WY), a
+    0x3E, 0x5B,       // ld a, 91
+    0xE0, 0x4B,       // ldh (WX), a
+    0x3E, 0xF3,       // ld a, 0xF3
+    0xE0, 0x40,       // ldh (LCDC), a
+    0x18, 0xFE,       // jr @
+];
+
+/// CGB: background palettes 0 and 1 written through BCPD with
+/// auto-increment, and every other pair of map columns switched to
+/// palette 1 through the attribute map in VRAM bank 1
+fn cgb_palettes() -> Vec<u8> {
+    let colors: [u16; 8] = [0x7FFF, 0x03E0, 0x001F, 0x0000, 0x7C00, 0x7FE0, 0x4210, 0x0010];
+    let mut program = vec![
+        0x3E, 0x80,       // ld a, 0x80
+        0xE0, 0x68,       // ldh (BCPS), a
+    ];
+    for byte in colors.iter().flat_map(|color| color.to_le_bytes()) {
+        program.extend([0x3E, byte, 0xE0, 0x69]); // ld a, byte; ldh (BCPD), a
+    }
+    program.extend([
+        0x3E, 0x01,       // ld a, 1
+        0xE0, 0x4F,       // ldh (VBK), a
+        0x21, 0x00, 0x98, // ld hl, 0x9800
+        0x01, 0x00, 0x04, // ld bc, 0x0400
+        0x7D,             // attr: ld a, l
+        0x0F,             // rrca
+        0xE6, 0x01,       // and 1
+        0x22,             // ld (hl+), a
+        0x0B,             // dec bc
+        0x78,             // ld a, b
+        0xB1,             // or c
+        0x20, 0xF6,       // jr nz, attr
+    ]);
+    program.extend(BACKGROUND);
+    program
+}
+
+/// Build a 32KB ROM that runs `SETUP` then `program` from 0x0150
+fn build_rom(program: &[u8]) -> Vec<u8> {
+    common::rom_with(&[SETUP, program].concat())
+}
+
+fn run(rom: &[u8], frames: u32) -> u64 {
+    let mut gb = GameBoy::new(rom).unwrap();
+    for _ in 0..frames {
+        gb.run_frame();
+    }
+    gb.frame_hash()
+}
+
+#[test]
+fn background() {
+    assert_eq!(run(&build_rom(BACKGROUND), 10), 0xFF52_3982_270C_B8A5);
+}
+
+#[test]
+fn window_and_sprite() {
+    assert_eq!(run(&build_rom(WINDOW_AND_SPRITE), 10), 0xE075_A98A_9F86_BDA5);
+}
+
+#[test]
+fn cgb_palette_ram() {
+    let mut rom = build_rom(&cgb_palettes());
+    rom[0x143] = 0x80;
+    assert_eq!(run(&rom, 10), 0xAAF1_4CCA_FA9F_3725);
+}
+
+/// The crate's own demo cartridge (`demo_rom`, MIT like the crate)
+#[test]
+fn demo_rom() {
+    assert_eq!(run(&DEMO_ROM, 60), 0x52C0_11D4_CAB9_D825);
+}
+
+/// Every `<rom>.hash` file in `tests/roms` holds the frame count and the
+/// expected hash in hex, e.g. `60 8f3e...`, for the ROM next to it. An
+/// empty directory fails: the goldens there are the conformance check.
+#[test]
+fn rom_directory() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/roms");
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().map_or(true, |ext| ext != "hash") {
+            continue;
+        }
+        
+        let golden = fs::read_to_string(&path).unwrap();
+        let mut fields = golden.split_whitespace();
+        let frames: u32 = fields.next().and_then(|f| f.parse().ok())
+            .unwrap_or_else(|| panic!("{}: missing frame count", path.display()));
+        let expected = fields.next().and_then(|h| u64::from_str_radix(h, 16).ok())
+            .unwrap_or_else(|| panic!("{}: missing hash", path.display()));
+        
+        let rom = fs::read(path.with_extension(""))
+            .unwrap_or_else(|err| panic!("{}: {}", path.display(), err));
+        let actual = run(&rom, frames);
+        checked += 1;
+        if actual != expected {
+            failures.push(format!("{}: expected {:016x}, got {:016x}", path.display(), expected, actual));
+        }
+    }
+    
+    assert!(checked > 0, "no ROMs with .hash goldens in {} (see README.md there)", dir.display());
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,119 @@
+//! Named memory locations read through game maps
+
+mod common;
+
+use gbemu_core::game_map::{Symbol, ValueType};
+use gbemu_core::{GameBoy, GameMap, Region};
+
+/// CGB game on an MBC5 with four 8KB RAM banks, RAM enabled
+fn gb() -> GameBoy {
+    let mut rom = common::cgb_rom_with(&[0x18, 0xFE]);
+    rom[0x147] = 0x1B;
+    rom[0x149] = 0x03;
+    let mut gb = GameBoy::new(&rom).unwrap();
+    gb.mmu.write_byte(0x0000, 0x0A);
+    gb
+}
+
+#[test]
+fn symbol_parse_errors() {
+    let invalid = [
+        ("XRAM:0xC000 u8", "Unknown region"),
+        ("WRAM:0xC000 u64", "Unknown type"),
+        ("WRAM:0xC000", "Expected"),
+        ("WRAM:0xC000 u8 extra", "Expected"),
+        ("WRAM:1:2:0xC000 u8", "Invalid location"),
+        ("WRAM:0xZZ u8", "Invalid number"),
+        ("WRAM:0x10000 u8", "Address out of range"),
+        // Outside the region, or running past its end
+        ("WRAM:0xA000 u8", "outside"),
+        ("HRAM:0xFFFE u16", "outside"),
+        ("SRAM:1:0xBFFE u32", "outside"),
+        // Only ROM, VRAM, SRAM and WRAM have banks
+        ("HRAM:1:0xFF80 u8", "no banks"),
+        ("OAM:0:0xFE00 u8", "no banks"),
+        ("IO:1:0xFF40 u8", "no banks"),
+    ];
+    for (spec, error) in invalid {
+        let message = Symbol::parse(spec).unwrap_err();
+        assert!(message.contains(error), "{}: {}", spec, message);
+    }
+    
+    let symbol = Symbol::parse("wram:2:0xD350 i16").unwrap();
+    assert_eq!(symbol.region, Region::Wram);
+    assert_eq!((symbol.bank, symbol.address, symbol.value_type), (Some(2), 0xD350, ValueType::I16));
+    
+    // Map errors name the entry
+    let error = GameMap::from_json(r#"{"lives": "HRAM:1:0xFF80 u8"}"#).unwrap_err();
+    assert!(error.starts_with("lives: "), "{}", error);
+    assert!(GameMap::from_json("[1, 2]").is_err());
+}
+
+#[test]
+fn banked_reads_ignore_the_current_mapping() {
+    let mut gb = gb();
+    for bank in 1..8u8 {
+        gb.mmu.write_byte(0xFF70, bank);
+        gb.mmu.write_byte(0xD000, 0x10 + bank);
+    }
+    for bank in 0..4u8 {
+        gb.mmu.write_byte(0x4000, bank);
+        gb.mmu.write_byte(0xA000, 0xA0 + bank);
+    }
+    gb.mmu.write_byte(0xFF70, 7);
+    gb.mmu.write_byte(0x4000, 3);
+    
+    gb.set_game_map(GameMap::from_json(r#"{
+        "wram": "WRAM:0xD000 u8",
+        "wram_2": "WRAM:2:0xD000 u8",
+        "sram": "SRAM:0xA000 u8",
+        "sram_1": "SRAM:1:0xA000 u8"
+    }"#).unwrap());
+    
+    assert_eq!(gb.value::<u8>("wram").unwrap(), 0x17);
+    assert_eq!(gb.value::<u8>("wram_2").unwrap(), 0x12);
+    assert_eq!(gb.value::<u8>("sram").unwrap(), 0xA3);
+    assert_eq!(gb.value::<u8>("sram_1").unwrap(), 0xA1);
+    
+    // Switching banks moves only the unbanked symbols
+    gb.mmu.write_byte(0xFF70, 2);
+    gb.mmu.write_byte(0x4000, 0);
+    assert_eq!(gb.value::<u8>("wram").unwrap(), 0x12);
+    assert_eq!(gb.value::<u8>("sram").unwrap(), 0xA0);
+    assert_eq!(gb.value::<u8>("sram_1").unwrap(), 0xA1);
+    
+    // C000-CFFF is bank 0 whatever the bank given
+    gb.mmu.write_byte(0xC000, 0x55);
+    gb.set_game_map(GameMap::from_json(r#"{"low": "WRAM:5:0xC000 u8"}"#).unwrap());
+    assert_eq!(gb.value::<u8>("low").unwrap(), 0x55);
+}
+
+#[test]
+fn signed_values_are_sign_extended() {
+    let mut gb = gb();
+    for (i, byte) in [0xFE, 0xFF, 0xFF, 0x7F, 0x34, 0x12].into_iter().enumerate() {
+        gb.mmu.write_byte(0xC100 + i as u16, byte);
+    }
+    gb.set_game_map(GameMap::from_json(r#"{
+        "i8": "WRAM:0xC100 i8",
+        "u8": "WRAM:0xC100 u8",
+        "i16": "WRAM:0xC100 i16",
+        "u16": "WRAM:0xC100 u16",
+        "i16_positive": "WRAM:0xC102 i16",
+        "i32": "WRAM:0xC100 i32",
+        "u32": "WRAM:0xC100 u32"
+    }"#).unwrap());
+    
+    assert_eq!(gb.value::<i64>("i8").unwrap(), -2);
+    assert_eq!(gb.value::<i64>("u8").unwrap(), 0xFE);
+    assert_eq!(gb.value::<i64>("i16").unwrap(), -2);
+    assert_eq!(gb.value::<i64>("u16").unwrap(), 0xFFFE);
+    assert_eq!(gb.value::<i16>("i16_positive").unwrap(), 0x7FFF);
+    assert_eq!(gb.value::<i32>("i32").unwrap(), 0x7FFF_FFFE);
+    assert_eq!(gb.value::<u32>("u32").unwrap(), 0x7FFF_FFFE);
+    
+    // Values that don't fit the requested type, and unknown names
+    assert!(gb.value::<u8>("i8").is_err());
+    assert!(gb.value::<i8>("u8").is_err());
+    assert!(gb.value::<u8>("missing").is_err());
+}
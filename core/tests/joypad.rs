@@ -0,0 +1,155 @@
+//! Joypad register and STOP wake-up
+
+use gbemu_core::demo_rom::DEMO_ROM;
+use gbemu_core::{Button, EmulatorEvent, GameBoy, GbConfig, InputState, JoypadIrq, SoftReset};
+
+/// Selects the d-pad, then stops
+fn rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    // ld a, 0x20; ldh (0x00), a; stop; jr @
+    rom[0x100..0x108].copy_from_slice(&[0x3E, 0x20, 0xE0, 0x00, 0x10, 0x00, 0x18, 0xFE]);
+    rom
+}
+
+#[test]
+fn select_writes_apply_immediately() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.press_button(Button::A);
+    gb.press_button(Button::Down);
+    
+    gb.mmu.write_byte(0xFF00, 0x10);
+    assert_eq!(gb.mmu.read_byte(0xFF00), 0xDE);
+    gb.mmu.write_byte(0xFF00, 0x20);
+    assert_eq!(gb.mmu.read_byte(0xFF00), 0xE7);
+    gb.mmu.write_byte(0xFF00, 0x30);
+    assert_eq!(gb.mmu.read_byte(0xFF00), 0xFF);
+}
+
+#[test]
+fn stop_wakes_on_selected_lines_only() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    while !gb.cpu.stopped {
+        gb.step();
+    }
+    
+    // Buttons aren't selected
+    gb.press_button(Button::Start);
+    for _ in 0..100 {
+        gb.step();
+    }
+    assert!(gb.cpu.stopped);
+    
+    // Selecting them from outside wakes the CPU right away
+    gb.mmu.write_byte(0xFF00, 0x10);
+    gb.step();
+    assert!(!gb.cpu.stopped);
+}
+
+/// Spins with interrupts disabled and the d-pad selected
+fn spinning(irq: JoypadIrq) -> GameBoy {
+    let mut rom = vec![0u8; 0x8000];
+    // di; jr @
+    rom[0x100..0x103].copy_from_slice(&[0xF3, 0x18, 0xFE]);
+    let config = GbConfig { joypad_irq: irq, ..Default::default() };
+    let mut gb = GameBoy::with_config(&rom, config).unwrap();
+    gb.step();
+    gb.mmu.write_byte(0xFF00, 0x20);
+    gb
+}
+
+/// Step once and take the joypad interrupt request
+fn take_irq(gb: &mut GameBoy) -> bool {
+    gb.step();
+    let requested = gb.mmu.read_byte(0xFF0F) & 0x10 != 0;
+    gb.mmu.write_byte(0xFF0F, 0);
+    requested
+}
+
+#[test]
+fn joypad_irq_models() {
+    let mut gb = spinning(JoypadIrq::AnyPress);
+    gb.press_button(Button::A);
+    assert!(take_irq(&mut gb));
+    
+    // Only selected lines count, and selecting a held row is an edge too
+    let mut gb = spinning(JoypadIrq::Edge);
+    gb.press_button(Button::A);
+    assert!(!take_irq(&mut gb));
+    gb.press_button(Button::Left);
+    assert!(take_irq(&mut gb));
+    gb.mmu.write_byte(0xFF00, 0x10);
+    assert!(take_irq(&mut gb));
+    for _ in 0..1000 {
+        assert!(!take_irq(&mut gb));
+    }
+}
+
+#[test]
+fn joypad_irq_bounces_while_held() {
+    let mut gb = spinning(JoypadIrq::Bounce { bounces: 3, interval: 256 });
+    gb.press_button(Button::Down);
+    let mut irqs = 0;
+    for _ in 0..1000 {
+        irqs += take_irq(&mut gb) as u32;
+    }
+    assert_eq!(irqs, 4);
+    
+    // Releasing stops the bouncing
+    gb.release_button(Button::Down);
+    gb.press_button(Button::Down);
+    assert!(take_irq(&mut gb));
+    gb.release_button(Button::Down);
+    for _ in 0..1000 {
+        assert!(!take_irq(&mut gb));
+    }
+}
+
+fn soft_reset_events(mode: SoftReset) -> (GameBoy, Vec<u64>) {
+    let config = GbConfig { soft_reset: mode, ..Default::default() };
+    let mut gb = GameBoy::with_config(&DEMO_ROM, config).unwrap();
+    let combo = InputState { a: true, b: true, select: true, start: true, ..Default::default() };
+    let mut frames = Vec::new();
+    for frame in 1..=20 {
+        // Held from frame 10 on
+        let input = if frame >= 10 { combo } else { InputState::default() };
+        if gb.frame_advance(input).events.contains(&EmulatorEvent::SoftReset) {
+            frames.push(frame);
+        }
+    }
+    (gb, frames)
+}
+
+#[test]
+fn soft_reset_combo() {
+    assert!(soft_reset_events(SoftReset::Off).1.is_empty());
+    
+    let (gb, frames) = soft_reset_events(SoftReset::Event);
+    assert_eq!(frames, [10]);
+    assert_eq!(gb.frame_count(), 20);
+    
+    // The console restarts once and the buttons stay held
+    let (gb, frames) = soft_reset_events(SoftReset::Reset);
+    assert_eq!(frames, [10]);
+    assert_eq!(gb.frame_count(), 11);
+    assert!(gb.mmu.joypad().is_pressed(Button::Start));
+}
+
+#[cfg(feature = "instrumentation")]
+#[test]
+fn input_latency_ignores_peeks() {
+    // ld a, 0x20; ldh (0x00), a; jr @
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x100..0x106].copy_from_slice(&[0x3E, 0x20, 0xE0, 0x00, 0x18, 0xFE]);
+    let mut gb = GameBoy::new(&rom).unwrap();
+    gb.set_input_latency_tracking(true);
+    gb.run_frame();
+    
+    gb.press_button(Button::Down);
+    gb.mmu.peek_byte(0xFF00);
+    gb.run_frame();
+    assert!(gb.input_latency().unwrap().samples().is_empty());
+    
+    gb.mmu.read_byte(0xFF00);
+    gb.step();
+    assert_eq!(gb.input_latency().unwrap().samples().len(), 1);
+}
@@ -0,0 +1,47 @@
+//! Memory dumps round-trip through files
+
+mod common;
+
+use gbemu_core::memory_dump::{DumpHeader, HEADER_SIZE};
+use gbemu_core::{GameBoy, Region};
+
+/// 32KB CGB ROM (8 WRAM banks, 2 VRAM banks) that loops forever
+fn rom() -> Vec<u8> {
+    common::cgb_rom_with(&[0x18, 0xFE])
+}
+
+#[test]
+fn wram_round_trip() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.mmu.wram_mut()[0x7123] = 0x42;
+    
+    let dump = gb.dump_memory(Region::Wram);
+    let header = DumpHeader::parse(&dump).unwrap();
+    assert_eq!(header.region, Region::Wram);
+    assert_eq!((header.banks, header.bank_size, header.selected_bank), (8, 0x1000, 1));
+    assert_eq!(dump.len(), HEADER_SIZE + 0x8000);
+    
+    gb.mmu.wram_mut().fill(0);
+    gb.restore_memory(Region::Wram, &dump).unwrap();
+    assert_eq!(gb.mmu.wram()[0x7123], 0x42);
+}
+
+#[test]
+fn restore_checks_layout() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    let vram = gb.dump_memory(Region::Vram);
+    assert_eq!(DumpHeader::parse(&vram).unwrap().banks, 2);
+    
+    assert!(gb.restore_memory(Region::Oam, &vram).is_err());
+    assert!(gb.restore_memory(Region::Vram, &vram[..vram.len() - 1]).is_err());
+    assert!(gb.restore_memory(Region::Rom, &gb.dump_memory(Region::Rom)).is_err());
+    
+    // A DMG has a single VRAM bank
+    let mut dmg_rom = rom();
+    dmg_rom[0x143] = 0;
+    let mut dmg = GameBoy::new(&dmg_rom).unwrap();
+    assert!(dmg.restore_memory(Region::Vram, &vram).is_err());
+    
+    let oam = gb.dump_memory(Region::Oam);
+    dmg.restore_memory(Region::Oam, &oam).unwrap();
+}
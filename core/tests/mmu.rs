@@ -0,0 +1,51 @@
+//! Memory map details
+
+mod common;
+
+use gbemu_core::GameBoy;
+
+#[test]
+fn echo_ram_follows_svbk() {
+    let mut gb = GameBoy::new(&common::cgb_rom_with(&[0x18, 0xFE])).unwrap();
+    for bank in 1..8 {
+        gb.mmu.write_byte(0xFF70, bank);
+        gb.mmu.write_byte(0xD123, 0x30 | bank);
+    }
+    gb.mmu.write_byte(0xC123, 0x20);
+    
+    for bank in 1..8 {
+        gb.mmu.write_byte(0xFF70, bank);
+        assert_eq!(gb.mmu.read_byte(0xF123), 0x30 | bank, "bank {}", bank);
+        // E000-EFFF always mirrors bank 0
+        assert_eq!(gb.mmu.read_byte(0xE123), 0x20);
+    }
+    
+    // SVBK 0 selects bank 1, in the echo too
+    gb.mmu.write_byte(0xFF70, 0);
+    assert_eq!(gb.mmu.read_byte(0xF123), 0x31);
+    
+    // Writes through the echo land in the selected bank
+    gb.mmu.write_byte(0xFF70, 5);
+    gb.mmu.write_byte(0xF200, 0x55);
+    gb.mmu.write_byte(0xE200, 0x05);
+    assert_eq!(gb.mmu.wram()[5 * 0x1000 + 0x200], 0x55);
+    assert_eq!(gb.mmu.wram()[0x200], 0x05);
+    assert_eq!(gb.mmu.read_byte(0xD200), 0x55);
+    gb.mmu.write_byte(0xFF70, 1);
+    assert_eq!(gb.mmu.read_byte(0xF200), 0x00);
+}
+
+#[test]
+fn dmg_echo_ram_ignores_svbk() {
+    let mut gb = GameBoy::new(&common::rom()).unwrap();
+    gb.mmu.write_byte(0xD123, 0x42);
+    gb.mmu.write_byte(0xFF70, 3);
+    assert_eq!(gb.mmu.read_byte(0xF123), 0x42);
+    
+    // The echo stops at FDFF: FE00 is OAM, not a mirror of DE00
+    gb.mmu.write_byte(0xDE00, 0x11);
+    gb.mmu.write_byte(0xFE00, 0x22);
+    assert_eq!(gb.mmu.read_byte(0xFDFF), gb.mmu.read_byte(0xDDFF));
+    assert_eq!(gb.mmu.read_byte(0xDE00), 0x11);
+    assert_eq!(gb.mmu.oam()[0], 0x22);
+}
@@ -0,0 +1,61 @@
+//! Model selection and per-model power-on differences
+
+mod common;
+
+use gbemu_core::{GameBoy, GbModel};
+
+/// `common::rom` with an MBC1 and 8KB of battery RAM
+fn rom() -> Vec<u8> {
+    let mut rom = common::rom();
+    rom[0x147] = 0x03;
+    rom[0x149] = 0x02;
+    rom
+}
+
+#[test]
+fn models_leave_their_own_registers() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    assert_eq!(gb.model(), GbModel::Dmg);
+    assert_eq!(gb.cpu.regs.a, 0x01);
+    
+    let expected = [
+        (GbModel::Pocket, 0xFF, 0x00, 0x13),
+        (GbModel::Sgb, 0x01, 0x00, 0x14),
+        (GbModel::Sgb2, 0xFF, 0x00, 0x14),
+        (GbModel::Cgb, 0x11, 0x00, 0x00),
+        (GbModel::Agb, 0x11, 0x01, 0x00),
+    ];
+    for (model, a, b, c) in expected {
+        gb.set_model(model);
+        assert_eq!(gb.model(), model);
+        assert_eq!((gb.cpu.regs.a, gb.cpu.regs.b, gb.cpu.regs.c), (a, b, c), "{:?}", model);
+    }
+}
+
+#[test]
+fn set_model_keeps_the_cartridge() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    let mut sram = gb.save_sram().unwrap();
+    sram[0] = 0x42;
+    gb.load_sram(&sram).unwrap();
+    
+    // CGB hardware has banked WRAM
+    gb.set_model(GbModel::Agb);
+    gb.mmu.write_byte(0xFF70, 0x03);
+    gb.mmu.write_byte(0xD000, 0x33);
+    gb.mmu.write_byte(0xFF70, 0x02);
+    assert_ne!(gb.mmu.read_byte(0xD000), 0x33);
+    assert_eq!(gb.save_sram().unwrap()[0], 0x42);
+}
+
+#[test]
+fn pocket_screen_is_grey() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.set_model(GbModel::Pocket);
+    gb.run_frame();
+    gb.run_frame();
+    
+    let pixel = &gb.framebuffer()[..4];
+    assert_eq!(pixel[0], pixel[1]);
+    assert_eq!(pixel[1], pixel[2]);
+}
@@ -0,0 +1,93 @@
+//! Recording and replaying input movies
+
+mod common;
+
+use common::rom_with;
+use gbemu_core::{Button, EmulatorEvent, GameBoy, GbConfig, InputState, Movie, MovieInput, RamInit, SoftReset};
+
+/// Counts loop iterations in 0xC000 and copies the d-pad lines to 0xC001,
+/// so both WRAM surviving a reset and the input reach the state
+fn counter_rom() -> Vec<u8> {
+    rom_with(&[
+        0x21, 0x00, 0xC0, // ld hl, 0xC000
+        0x3E, 0x20,       // loop: ld a, 0x20 (select the d-pad)
+        0xE0, 0x00,       // ldh (0x00), a
+        0xF0, 0x00,       // ldh a, (0x00)
+        0xEA, 0x01, 0xC0, // ld (0xC001), a
+        0x34,             // inc (hl)
+        0x18, 0xF4,       // jr loop
+    ])
+}
+
+fn config() -> GbConfig {
+    GbConfig { ram_init: RamInit::Random(99), ..GbConfig::default() }
+}
+
+/// Record a few frames of input around a reset and a power cycle
+fn record(gb: &mut GameBoy) -> Movie {
+    gb.start_movie();
+    gb.frame_advance(InputState::from_bits(1 << Button::Right as u8));
+    gb.run_frame();
+    gb.reset();
+    gb.frame_advance(InputState::from_bits(1 << Button::Down as u8));
+    gb.power_cycle();
+    gb.press_button(Button::Up);
+    gb.run_frame();
+    gb.stop_movie().unwrap()
+}
+
+#[test]
+fn movies_record_frames_resets_and_power_cycles() {
+    let mut gb = GameBoy::with_config(&counter_rom(), config()).unwrap();
+    gb.run_frame();
+    let movie = record(&mut gb);
+    
+    assert_eq!(movie.inputs, [
+        MovieInput::Frame(0x01),
+        MovieInput::Frame(0x01),
+        MovieInput::Reset,
+        MovieInput::Frame(0x08),
+        MovieInput::PowerCycle,
+        MovieInput::Frame(0x04),
+    ]);
+    assert_eq!(movie.frames(), 4);
+    assert!(gb.stop_movie().is_none());
+}
+
+#[test]
+fn replays_go_through_resets_and_power_cycles() {
+    let mut gb = GameBoy::with_config(&counter_rom(), config()).unwrap();
+    gb.run_frame();
+    let movie = record(&mut gb);
+    
+    // The movie's starting state brings the RAM seed with it
+    let mut replay = GameBoy::new(&counter_rom()).unwrap();
+    movie.play(&mut replay).unwrap();
+    assert_eq!(replay.save_state(), gb.save_state());
+    
+    // Leaving the events out changes the outcome
+    let frames_only = Movie {
+        inputs: movie.inputs.iter().copied().filter(|input| matches!(input, MovieInput::Frame(_))).collect(),
+        ..movie.clone()
+    };
+    frames_only.play(&mut replay).unwrap();
+    assert_ne!(replay.save_state(), gb.save_state());
+}
+
+#[test]
+fn soft_reset_combo_replays_from_input() {
+    let config = GbConfig { soft_reset: SoftReset::Reset, ..config() };
+    let mut gb = GameBoy::with_config(&counter_rom(), config).unwrap();
+    gb.start_movie();
+    gb.run_frame();
+    let output = gb.frame_advance(InputState::from_bits(0xF0));
+    assert!(output.events.contains(&EmulatorEvent::SoftReset));
+    gb.run_frame();
+    let movie = gb.stop_movie().unwrap();
+    
+    assert_eq!(movie.frames(), movie.inputs.len());
+    
+    let mut replay = GameBoy::new(&counter_rom()).unwrap();
+    movie.play(&mut replay).unwrap();
+    assert_eq!(replay.save_state(), gb.save_state());
+}
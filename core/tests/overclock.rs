@@ -0,0 +1,31 @@
+//! CPU overclocking against fixed system timing
+
+mod common;
+
+use gbemu_core::config::GbConfig;
+use gbemu_core::GameBoy;
+
+/// Loop iterations (BC) and DIV after one frame at the given multiplier
+fn frame(overclock: u32) -> (u16, u8) {
+    // inc bc; jr -3
+    let rom = common::rom_with(&[0x03, 0x18, 0xFD]);
+    
+    let config = GbConfig { overclock, ..GbConfig::default() };
+    let mut gb = GameBoy::with_config(&rom, config).unwrap();
+    gb.cpu.regs.set_bc(0);
+    gb.run_frame();
+    (gb.cpu.regs.bc(), gb.mmu.read_byte(0xFF04))
+}
+
+#[test]
+fn overclock_multiplies_cpu_work_per_frame() {
+    let (normal, div) = frame(1);
+    assert_eq!(frame(0), (normal, div));
+    
+    for factor in [2, 3] {
+        let (loops, overclocked_div) = frame(factor);
+        assert_eq!(overclocked_div, div, "timers keep their speed");
+        let expected = normal as u32 * factor;
+        assert!(loops as u32 >= expected - 2 && loops as u32 <= expected + 2, "{factor}x: {loops} loops");
+    }
+}
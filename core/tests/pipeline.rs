@@ -0,0 +1,89 @@
+//! Post-processing on the worker thread
+
+mod common;
+
+use common::rom;
+use gbemu_core::pipeline::{encode_png, AudioStage, FrameStage, PngEncoder, Resampler, Scale, Scale2x};
+use gbemu_core::{AudioOutputMode, GameBoy, Pipeline, RAW_SAMPLE_RATE, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Peak of the second half of `samples` (left channel), after the
+/// resampler's delay has passed
+fn peak(samples: &[f32]) -> f32 {
+    samples[samples.len() / 2..].iter().step_by(2).fold(0.0, |max, s| max.max(s.abs()))
+}
+
+fn sine(frequency: f64, rate: u32, len: usize) -> Vec<f32> {
+    (0..len).flat_map(|i| {
+        let s = (2.0 * std::f64::consts::PI * frequency * i as f64 / rate as f64).sin() as f32;
+        [s, s]
+    }).collect()
+}
+
+#[test]
+fn frames_come_back_processed_in_order() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.apu_mut().set_output_mode(AudioOutputMode::Raw);
+    let stages: Vec<Box<dyn FrameStage>> = vec![Box::new(Scale::new(3)), Box::new(PngEncoder)];
+    let audio: Vec<Box<dyn AudioStage>> = vec![Box::new(Resampler::new(RAW_SAMPLE_RATE, 48000))];
+    let mut pipeline = Pipeline::new(stages, audio);
+    
+    let mut submitted = Vec::new();
+    let mut samples = 0;
+    for _ in 0..3 {
+        gb.run_frame();
+        samples += gb.audio_buffer().len();
+        if pipeline.submit(&gb) {
+            submitted.push(gb.frame_count());
+        }
+        gb.clear_audio_buffer();
+    }
+    
+    let results = pipeline.finish();
+    let numbers: Vec<u64> = results.iter().map(|p| p.frame.number).collect();
+    assert_eq!(numbers, submitted);
+    
+    let frame = &results[0].frame;
+    assert_eq!((frame.width, frame.height), (SCREEN_WIDTH * 3, SCREEN_HEIGHT * 3));
+    assert_eq!(frame.rgba.len(), frame.width * frame.height * 4);
+    assert!(frame.encoded.as_ref().unwrap().starts_with(b"\x89PNG\r\n\x1a\n"));
+    
+    // Resampled to 48 kHz, less the filter delay
+    let resampled: usize = results.iter().map(|p| p.audio.len()).sum();
+    let expected = (samples as f64 * 48000.0 / RAW_SAMPLE_RATE as f64) as usize;
+    assert!(resampled <= expected && resampled > expected * 9 / 10, "{} of {}", resampled, expected);
+}
+
+#[test]
+fn resampler_filters_above_nyquist() {
+    let mut resampler = Resampler::new(192000, 48000);
+    
+    // Split into uneven chunks to cross chunk boundaries
+    let pass: Vec<f32> = sine(1000.0, 192000, 19200).chunks(1234)
+        .flat_map(|chunk| resampler.resample(chunk))
+        .collect();
+    // The filter reaches 32 input samples ahead, holding back 8 outputs
+    assert_eq!(pass.len(), 2 * (4800 - 8));
+    assert!((peak(&pass) - 1.0).abs() < 0.01, "{}", peak(&pass));
+    
+    let mut resampler = Resampler::new(192000, 48000);
+    let stop = resampler.resample(&sine(40000.0, 192000, 19200));
+    assert!(peak(&stop) < 0.01, "{}", peak(&stop));
+}
+
+#[test]
+fn scale2x_smooths_diagonals() {
+    let (w, b) = ([0xFF; 4], [0x00, 0x00, 0x00, 0xFF]);
+    // Black pixels on the anti-diagonal of a 2x2 image
+    let rgba = [w, b, b, w].concat();
+    let mut frame = gbemu_core::pipeline::Frame { number: 0, width: 2, height: 2, rgba, encoded: None };
+    Scale2x.process(&mut frame);
+    
+    let pixel = |x: usize, y: usize| &frame.rgba[(y * 4 + x) * 4..][..4];
+    assert_eq!((frame.width, frame.height), (4, 4));
+    assert_eq!(pixel(0, 0), w);
+    assert_eq!(pixel(1, 1), b);
+    assert_eq!(pixel(3, 3), w);
+    
+    let png = encode_png(&frame.rgba, 4, 4);
+    assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+}
@@ -0,0 +1,491 @@
+//! PPU behaviors, mostly those dmg-acid2 checks. Scenes are set up
+//! directly in VRAM and OAM and read back from the indexed framebuffer
+//! (DMG shades 0-3).
+
+mod common;
+
+use gbemu_core::ppu::SCREEN_WIDTH;
+use gbemu_core::{preview_frame, GameBoy, SyncPoint, TileAttributes, TileMapArea, OVERLAY_SIZE};
+
+/// Tile 1: solid color 3
+const TILE_COLOR3: [u8; 16] = [0xFF; 16];
+
+/// Tile 2: solid color 1
+const TILE_COLOR1: [u8; 16] = [0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00,
+                               0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00];
+
+/// A Game Boy idling in a loop with the LCD off, tiles 1 and 2 loaded,
+/// both tile maps cleared to tile 0 and identity palettes
+fn setup() -> GameBoy {
+    let mut gb = GameBoy::new(&common::rom()).unwrap();
+    gb.mmu.write_byte(0xFF40, 0x00);
+    gb.run_frame();
+    
+    gb.mmu.write_vram_bank(0, 0x10, &TILE_COLOR3).unwrap();
+    gb.mmu.write_vram_bank(0, 0x20, &TILE_COLOR1).unwrap();
+    gb.mmu.write_vram_bank(0, 0x1800, &[0; 0x800]).unwrap();
+    gb.mmu.write_byte(0xFF47, 0xE4);
+    gb.mmu.write_byte(0xFF48, 0xE4);
+    gb
+}
+
+fn set_sprite(gb: &mut GameBoy, index: u16, x: u8, y: u8, tile: u8, flags: u8) {
+    let addr = 0xFE00 + index * 4;
+    for (offset, value) in [y + 16, x + 8, tile, flags].into_iter().enumerate() {
+        gb.mmu.write_byte(addr + offset as u16, value);
+    }
+}
+
+/// Switch the LCD on with `lcdc` and return row `y` of the first frame
+/// that is shown
+fn render_row(gb: &mut GameBoy, lcdc: u8, y: usize) -> Vec<u8> {
+    gb.mmu.write_byte(0xFF40, lcdc);
+    gb.run_frame();
+    gb.run_frame();
+    gb.framebuffer_indexed()[y * SCREEN_WIDTH..(y + 1) * SCREEN_WIDTH].to_vec()
+}
+
+#[test]
+fn sprite_behind_background_masks_lower_priority_sprites() {
+    let mut gb = setup();
+    
+    // BG color 1 at x 8-15 of tile row 1
+    gb.mmu.write_vram_bank(0, 0x1800 + 32 + 1, &[2]).unwrap();
+    
+    // The left sprite wins on X but sits behind BG colors 1-3; the one
+    // overlapping it from the right must not show through it
+    set_sprite(&mut gb, 0, 8, 8, 1, 0x80);
+    set_sprite(&mut gb, 1, 12, 8, 1, 0x00);
+    
+    let row = render_row(&mut gb, 0x93, 8);
+    assert_eq!(&row[8..16], &[1; 8]);
+    assert_eq!(&row[16..20], &[3; 4]);
+    assert_eq!(row[20], 0);
+}
+
+#[test]
+fn window_left_of_screen_edge() {
+    let mut gb = setup();
+    
+    // Window column 0 is color 1, column 1 color 0
+    gb.mmu.write_vram_bank(0, 0x1C00, &[2]).unwrap();
+    gb.mmu.write_byte(0xFF4A, 0);
+    gb.mmu.write_byte(0xFF4B, 3);
+    
+    // WX = 3 hides the first 4 window pixels
+    let row = render_row(&mut gb, 0xF1, 0);
+    assert_eq!(&row[0..4], &[1; 4]);
+    assert_eq!(&row[4..12], &[0; 8]);
+}
+
+#[test]
+fn lcdc0_blanks_background_and_window_but_not_sprites() {
+    let mut gb = setup();
+    
+    gb.mmu.write_vram_bank(0, 0x1800, &[2; 0x800]).unwrap();
+    gb.mmu.write_byte(0xFF4A, 0);
+    gb.mmu.write_byte(0xFF4B, 7);
+    set_sprite(&mut gb, 0, 0, 0, 1, 0x80);
+    
+    let row = render_row(&mut gb, 0xF2, 0);
+    assert_eq!(&row[0..8], &[3; 8]);
+    assert_eq!(&row[8..16], &[0; 8]);
+}
+
+#[test]
+fn tile_map_reports_cgb_attributes() {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x143] = 0x80;
+    let mut gb = GameBoy::new(&rom).unwrap();
+    
+    gb.mmu.write_vram_bank(0, 0x1C00 + 33, &[0x80]).unwrap();
+    gb.mmu.write_vram_bank(1, 0x1C00 + 33, &[0xED]).unwrap();
+    gb.mmu.write_byte(0xFF40, 0x81);
+    
+    let entry = gb.tile_map(TileMapArea::High)[33];
+    assert_eq!(entry.tile, 0x80);
+    assert_eq!(entry.tile_addr, 0x8800);
+    assert_eq!(entry.attributes, TileAttributes {
+        palette: 5,
+        vram_bank: 1,
+        x_flip: true,
+        y_flip: true,
+        priority: true,
+    });
+}
+
+#[test]
+fn scheduled_writes_wait_for_their_sync_point() {
+    let mut gb = setup();
+    gb.mmu.write_byte(0xFF40, 0x91);
+    gb.run_frame();
+    
+    gb.write_at(SyncPoint::NextVBlank, 0xC000, 0x42);
+    gb.write_at(SyncPoint::NextHBlank, 0xC001, 0x24);
+    
+    let mut hblank_ly = None;
+    while gb.mmu.read_byte(0xC000) != 0x42 {
+        gb.step();
+        if hblank_ly.is_none() && gb.mmu.read_byte(0xC001) == 0x24 {
+            hblank_ly = Some(gb.mmu.read_byte(0xFF44));
+            assert_eq!(gb.mmu.read_byte(0xFF41) & 0x03, 0);
+        }
+    }
+    
+    assert!(hblank_ly.is_some_and(|ly| ly < 144));
+    assert_eq!(gb.mmu.read_byte(0xFF44), 144);
+    assert_eq!(gb.mmu.read_byte(0xFF41) & 0x03, 1);
+}
+
+#[test]
+fn save_state_restores_the_framebuffer() {
+    let mut gb = setup();
+    gb.mmu.write_vram_bank(0, 0x1800, &[1, 2, 1, 2]).unwrap();
+    set_sprite(&mut gb, 0, 40, 30, 2, 0x00);
+    gb.mmu.write_byte(0xFF40, 0x93);
+    gb.run_frame();
+    gb.run_frame();
+    while gb.mmu.read_byte(0xFF44) != 72 {
+        gb.step();
+    }
+    let state = gb.save_state();
+    
+    // Loaded into a Game Boy that has not drawn anything yet
+    let mut loaded = setup();
+    loaded.load_state(&state).unwrap();
+    assert_eq!(loaded.framebuffer(), gb.framebuffer());
+    assert_eq!(loaded.framebuffer_indexed(), gb.framebuffer_indexed());
+    assert_eq!(loaded.save_state(), state);
+    
+    gb.run_frame();
+    loaded.run_frame();
+    assert_eq!(loaded.frame_hash(), gb.frame_hash());
+}
+
+#[test]
+fn preview_matches_a_normal_run() {
+    let program = [
+        0x21, 0x10, 0x80, 0x06, 0x10, 0x3E, 0xFF, // ld hl,$8010; ld b,16; ld a,$FF
+        0x22, 0x05, 0x20, 0xFC,                   // tile 1: ld (hl+),a; dec b; jr nz
+        0x3E, 0x01, 0xEA, 0x00, 0x98, 0xEA, 0x21, 0x98, // map entries
+        0x3E, 0x48, 0xE0, 0x4A, 0x3E, 0x07, 0xE0, 0x4B, // WY = 72, WX = 7
+        0x3E, 0xB1, 0xE0, 0x40,                   // LCD, window and BG on
+        0x18, 0xFE,
+    ];
+    let rom = common::rom_with(&program);
+    
+    let mut gb = GameBoy::new(&rom).unwrap();
+    for _ in 0..5 {
+        gb.run_frame();
+    }
+    
+    let preview = preview_frame(&rom, 5).unwrap();
+    assert_eq!(preview, gb.framebuffer());
+    assert!(preview.chunks(4).any(|pixel| pixel != &preview[..4]));
+}
+
+#[test]
+fn frames_end_at_vblank_start() {
+    let mut gb = setup();
+    gb.mmu.write_byte(0xFF40, 0x91);
+    // Stop partway through a frame so the next one has to line back up
+    gb.run_cycles(12_345);
+    
+    gb.run_frame();
+    let mut start = gb.total_cycles();
+    for _ in 0..5 {
+        assert_eq!(gb.mmu.read_byte(0xFF44), 144);
+        gb.run_frame();
+        // Whole frames, give or take the last instruction
+        let length = gb.total_cycles() - start;
+        assert!((70_224 - 24..=70_224 + 24).contains(&length), "{length}");
+        start = gb.total_cycles();
+    }
+    
+    // With the LCD off, frames are counted in cycles instead
+    gb.mmu.write_byte(0xFF40, 0x00);
+    let frame = gb.frame_count();
+    gb.run_frame();
+    gb.run_frame();
+    assert_eq!(gb.frame_count(), frame + 2);
+}
+
+#[test]
+fn debug_overlay_outlines_layers() {
+    let mut gb = setup();
+    assert_eq!(gb.debug_overlay(), None);
+    gb.set_debug_overlay(true);
+    
+    set_sprite(&mut gb, 0, 30, 50, 1, 0);
+    gb.mmu.write_byte(0xFF42, 20); // SCY
+    gb.mmu.write_byte(0xFF43, 10); // SCX
+    gb.mmu.write_byte(0xFF4A, 100); // WY
+    gb.mmu.write_byte(0xFF4B, 87); // WX
+    gb.mmu.write_byte(0xFF40, 0xA3);
+    gb.run_frame();
+    gb.run_frame();
+    
+    let overlay = gb.debug_overlay().unwrap();
+    assert_eq!(overlay.len(), OVERLAY_SIZE * OVERLAY_SIZE * 4);
+    // Screen coordinates land at their background map position
+    let pixel = |x: usize, y: usize| {
+        let offset = ((y + 20) % OVERLAY_SIZE * OVERLAY_SIZE + (x + 10) % OVERLAY_SIZE) * 4;
+        overlay[offset..offset + 4].to_vec()
+    };
+    
+    // Viewport outline, wrapping at the map edge
+    assert_eq!(pixel(0, 0), [0xFF, 0x40, 0x40, 0xFF]);
+    assert_eq!(pixel(159, 143), [0xFF, 0x40, 0x40, 0xFF]);
+    assert_eq!(pixel(50, 10), [0; 4]);
+    
+    // Window from (80, 100)
+    assert_eq!(pixel(80, 100)[3], 0x60);
+    assert_eq!(pixel(79, 100), [0; 4]);
+    assert_eq!(pixel(80, 99), [0; 4]);
+    
+    // Sprite box from (30, 50) to (37, 57)
+    assert_eq!(pixel(30, 50), [0x40, 0x80, 0xFF, 0xFF]);
+    assert_eq!(pixel(37, 57), [0x40, 0x80, 0xFF, 0xFF]);
+    assert_eq!(pixel(33, 53), [0; 4]);
+}
+
+/// Run until the PPU starts OAM search for line `ly`, so register writes
+/// apply to that line
+fn run_to_line(gb: &mut GameBoy, ly: u8) {
+    while !(gb.mmu.read_byte(0xFF44) == ly && gb.mmu.read_byte(0xFF41) & 0x03 == 2) {
+        gb.step();
+    }
+}
+
+/// Window map rows 0-2 filled with shades 1, 3 and 1, so the shade on a
+/// line tells which window row the line counter reached. Returns the
+/// shade at x = 0 of every line in a frame with the given mid-frame
+/// writes, each applied at the start of its line.
+fn window_rows(wy: u8, writes: &[(u8, u16, u8)]) -> Vec<u8> {
+    let mut gb = setup();
+    for (row, tile) in [2, 1, 2].into_iter().enumerate() {
+        gb.mmu.write_vram_bank(0, 0x1C00 + row * 32, &[tile; 32]).unwrap();
+    }
+    gb.mmu.write_byte(0xFF4A, wy);
+    gb.mmu.write_byte(0xFF4B, 7);
+    gb.mmu.write_byte(0xFF40, 0xF1);
+    gb.run_frame();
+    gb.run_frame();
+    
+    for &(ly, addr, value) in writes {
+        run_to_line(&mut gb, ly);
+        gb.mmu.write_byte(addr, value);
+    }
+    gb.run_frame();
+    gb.framebuffer_indexed().iter().step_by(SCREEN_WIDTH).copied().collect()
+}
+
+#[test]
+fn window_line_counter_skips_lines_without_the_window() {
+    // Lines 8-15 without the window, by LCDC.5 or by WX past the edge:
+    // line 16 continues with window line 8 (row 1), not LY's row 2
+    let hidden = [
+        [(8, 0xFF40, 0xD1), (16, 0xFF40, 0xF1)],
+        [(8, 0xFF4B, 167), (16, 0xFF4B, 7)],
+    ];
+    for writes in hidden {
+        let rows = window_rows(0, &writes);
+        assert_eq!(&rows[0..8], &[1; 8]);
+        assert_eq!(&rows[8..16], &[0; 8]);
+        assert_eq!(&rows[16..24], &[3; 8]);
+        assert_eq!(&rows[24..32], &[1; 8]);
+    }
+    
+    // Without the gap every row follows LY
+    let rows = window_rows(0, &[]);
+    assert_eq!((rows[4], rows[12], rows[20]), (1, 3, 1));
+}
+
+#[test]
+fn window_wy_is_latched_per_frame() {
+    // WY moved to a line already drawn: the window never starts
+    assert!(window_rows(200, &[(20, 0xFF4A, 10)]).iter().all(|&shade| shade == 0));
+    
+    // WY moved ahead to a line still to come: it starts there
+    let rows = window_rows(200, &[(20, 0xFF4A, 30)]);
+    assert_eq!(&rows[..30], &[0; 30]);
+    assert_eq!((rows[30], rows[38], rows[46]), (1, 3, 1));
+    
+    // Once started, moving WY away doesn't hide it or restart the count
+    let rows = window_rows(10, &[(20, 0xFF4A, 100)]);
+    assert_eq!((rows[9], rows[10], rows[18], rows[26], rows[100]), (0, 1, 3, 1, 0));
+}
+
+/// Write LYC partway through line `ly` and return the LY=LYC flag and
+/// whether a STAT interrupt was requested by the next step
+fn lyc_write(gb: &mut GameBoy, ly: u8, lyc: u8) -> (bool, bool) {
+    run_to_line(gb, ly);
+    gb.step();
+    gb.mmu.write_byte(0xFF0F, 0x00);
+    gb.mmu.write_byte(0xFF45, lyc);
+    gb.step();
+    assert_eq!(gb.mmu.read_byte(0xFF44), ly, "still on the same line");
+    (gb.mmu.read_byte(0xFF41) & 0x04 != 0, gb.mmu.read_byte(0xFF0F) & 0x02 != 0)
+}
+
+#[test]
+fn lyc_writes_take_effect_mid_line() {
+    let mut gb = setup();
+    gb.mmu.write_byte(0xFF45, 0xFF);
+    gb.mmu.write_byte(0xFF40, 0x91);
+    gb.mmu.write_byte(0xFF41, 0x40);
+    
+    // Matching the current line sets the flag and fires at once
+    assert_eq!(lyc_write(&mut gb, 40, 40), (true, true));
+    
+    // Moving away clears the flag, and a match later on the same line
+    // is a new rising edge
+    assert_eq!(lyc_write(&mut gb, 41, 50), (false, false));
+    assert_eq!(lyc_write(&mut gb, 42, 42), (true, true));
+    
+    // Rewriting the same match keeps the line high, so no second interrupt
+    assert_eq!(lyc_write(&mut gb, 42, 42), (true, false));
+    
+    // With the LYC source disabled only the flag follows
+    gb.mmu.write_byte(0xFF41, 0x00);
+    assert_eq!(lyc_write(&mut gb, 60, 60), (true, false));
+}
+
+#[test]
+fn lyc_match_during_another_stat_source_does_not_fire() {
+    // The mode 2 source already holds the line high through OAM search
+    let mut gb = setup();
+    gb.mmu.write_byte(0xFF45, 0xFF);
+    gb.mmu.write_byte(0xFF40, 0x91);
+    gb.mmu.write_byte(0xFF41, 0x60);
+    
+    run_to_line(&mut gb, 30);
+    gb.mmu.write_byte(0xFF0F, 0x00);
+    gb.mmu.write_byte(0xFF45, 30);
+    gb.step();
+    assert_eq!(gb.mmu.read_byte(0xFF41) & 0x07, 0x06);
+    assert_eq!(gb.mmu.read_byte(0xFF0F) & 0x02, 0);
+}
+
+/// A CGB game with tile 1 (color 3) at the top-left of the background,
+/// attribute palette 2, and a sprite of tile 1 with palette 5 at (16, 0)
+fn cgb_scene() -> GameBoy {
+    let mut gb = GameBoy::new(&common::cgb_rom_with(&[0x18, 0xFE])).unwrap();
+    gb.mmu.write_byte(0xFF40, 0x00);
+    gb.run_frame();
+    
+    gb.mmu.write_vram_bank(0, 0x10, &TILE_COLOR3).unwrap();
+    gb.mmu.write_vram_bank(0, 0x1800, &[0; 0x400]).unwrap();
+    gb.mmu.write_vram_bank(0, 0x1800, &[1]).unwrap();
+    gb.mmu.write_vram_bank(1, 0x1800, &[0x02]).unwrap();
+    set_sprite(&mut gb, 0, 16, 0, 1, 0x05);
+    gb.mmu.write_byte(0xFF40, 0x93);
+    gb.run_frame();
+    gb
+}
+
+fn pixel(gb: &GameBoy, x: usize, y: usize) -> [u8; 4] {
+    let offset = (y * SCREEN_WIDTH + x) * 4;
+    gb.framebuffer()[offset..offset + 4].try_into().unwrap()
+}
+
+#[test]
+fn cgb_palette_registers() {
+    let mut gb = cgb_scene();
+    
+    // Auto-increment walks the index through palette RAM
+    gb.mmu.write_byte(0xFF68, 0x80 | 0x10);
+    for byte in [0x1F, 0x00, 0xE0, 0x03] {
+        gb.mmu.write_byte(0xFF69, byte);
+    }
+    assert_eq!(gb.mmu.read_byte(0xFF68), 0xC0 | 0x14);
+    assert_eq!((gb.cgb_palette(8), gb.cgb_palette(9)), (0x001F, 0x03E0));
+    
+    // Without auto-increment reads and writes stay put
+    gb.mmu.write_byte(0xFF6A, 0x3F);
+    gb.mmu.write_byte(0xFF6B, 0x7C);
+    gb.mmu.write_byte(0xFF6B, 0x7F);
+    assert_eq!(gb.mmu.read_byte(0xFF6A), 0x7F);
+    assert_eq!(gb.mmu.read_byte(0xFF6B), 0x7F);
+    assert_eq!(gb.cgb_palette(63), 0x7FFF);
+    
+    // Past the end the index wraps
+    gb.mmu.write_byte(0xFF6A, 0xBF);
+    gb.mmu.write_byte(0xFF6B, 0x00);
+    assert_eq!(gb.mmu.read_byte(0xFF6A), 0xC0);
+    
+    // The registers don't exist on a DMG
+    let mut dmg = setup();
+    dmg.mmu.write_byte(0xFF68, 0x00);
+    dmg.mmu.write_byte(0xFF69, 0x00);
+    assert_eq!((dmg.mmu.read_byte(0xFF68), dmg.mmu.read_byte(0xFF69)), (0xFF, 0xFF));
+    assert_eq!(dmg.cgb_palette(0), 0x7FFF);
+}
+
+#[test]
+fn cgb_palette_ram_is_rendered() {
+    let mut gb = cgb_scene();
+    
+    // BG palette 2 color 3 and OBJ palette 5 color 3, through the registers
+    gb.mmu.write_byte(0xFF68, 2 * 8 + 6);
+    gb.mmu.write_byte(0xFF69, 0x1F);
+    gb.mmu.write_byte(0xFF68, 2 * 8 + 7);
+    gb.mmu.write_byte(0xFF69, 0x00);
+    gb.mmu.write_byte(0xFF6A, 0x80 | (5 * 8 + 6));
+    gb.mmu.write_byte(0xFF6B, 0xE0);
+    gb.mmu.write_byte(0xFF6B, 0x03);
+    gb.run_frame();
+    
+    assert_eq!(pixel(&gb, 0, 0), [0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(pixel(&gb, 16, 0), [0x00, 0xFF, 0x00, 0xFF]);
+    // Color 0 of tile 0 in palette 0 is still white
+    assert_eq!(pixel(&gb, 8, 0), [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(gb.framebuffer_indexed()[0], (2 << 2) | 3);
+    
+    // A live edit shows in the next frame
+    gb.write_cgb_palette(2 * 4 + 3, 0x7C00);
+    gb.write_cgb_palette(0x20 | (5 * 4 + 3), 0x4210);
+    gb.run_frame();
+    assert_eq!(pixel(&gb, 0, 0), [0x00, 0x00, 0xFF, 0xFF]);
+    assert_eq!(pixel(&gb, 16, 0), [0x84, 0x84, 0x84, 0xFF]);
+}
+
+#[test]
+fn cgb_tiles_are_fetched_from_bank_0_whatever_vbk_selects() {
+    let mut gb = cgb_scene();
+    gb.write_cgb_palette(2 * 4 + 3, 0x001F);
+    gb.mmu.write_byte(0xFF4F, 0x01);
+    gb.run_frame();
+    assert_eq!(pixel(&gb, 0, 0), [0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(gb.framebuffer_indexed()[0], (2 << 2) | 3);
+}
+
+#[test]
+fn cgb_attributes_pick_the_bank_and_flip_tiles() {
+    let mut gb = cgb_scene();
+    let index = |gb: &GameBoy, x: usize, y: usize| gb.framebuffer_indexed()[y * SCREEN_WIDTH + x];
+    
+    // Tile 1 in bank 1: color 1 at the top left, color 2 at the bottom left
+    let mut tile = [0u8; 16];
+    tile[0] = 0x80;
+    tile[15] = 0x80;
+    gb.mmu.write_vram_bank(1, 0x10, &tile).unwrap();
+    
+    gb.mmu.write_vram_bank(1, 0x1800, &[0x02 | 0x08]).unwrap();
+    gb.run_frame();
+    assert_eq!((index(&gb, 0, 0), index(&gb, 1, 0)), ((2 << 2) | 1, 2 << 2));
+    assert_eq!(index(&gb, 0, 7), (2 << 2) | 2);
+    
+    gb.mmu.write_vram_bank(1, 0x1800, &[0x02 | 0x08 | 0x20]).unwrap();
+    gb.run_frame();
+    assert_eq!((index(&gb, 0, 0), index(&gb, 7, 0)), (2 << 2, (2 << 2) | 1));
+    
+    gb.mmu.write_vram_bank(1, 0x1800, &[0x02 | 0x08 | 0x40]).unwrap();
+    gb.run_frame();
+    assert_eq!((index(&gb, 0, 0), index(&gb, 0, 7)), ((2 << 2) | 2, (2 << 2) | 1));
+    
+    // Objects take the bank from OAM bit 3
+    set_sprite(&mut gb, 0, 16, 0, 1, 0x05 | 0x08);
+    gb.run_frame();
+    assert_eq!((index(&gb, 16, 0), index(&gb, 17, 0)), (0x20 | (5 << 2) | 1, 0));
+}
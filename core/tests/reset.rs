@@ -0,0 +1,132 @@
+//! Full and per-component resets, and power cycles
+
+mod common;
+
+use common::{rom, rom_with};
+use gbemu_core::{Button, GameBoy, GbConfig, RamInit, SoftReset};
+
+/// 64KB MBC1 cartridge with 8KB of RAM, battery-backed or not, idling
+/// at 0x0150. Each ROM bank starts with its bank number.
+fn mbc1_rom(battery: bool) -> Vec<u8> {
+    let mut rom = rom_with(&[0x18, 0xFE]);
+    rom.resize(0x10000, 0);
+    rom[0x147] = if battery { 0x03 } else { 0x02 };
+    rom[0x148] = 0x01;
+    rom[0x149] = 0x02;
+    for bank in 1..4 {
+        rom[bank * 0x4000] = bank as u8;
+    }
+    rom
+}
+
+/// Leave marks in WRAM, HRAM and SRAM and switch to ROM bank 2
+fn scribble(gb: &mut GameBoy) {
+    gb.mmu.write_byte(0xC000, 0x42);
+    gb.mmu.write_byte(0xFF80, 0x24);
+    gb.mmu.write_byte(0x0000, 0x0A);
+    gb.mmu.write_byte(0xA000, 0x99);
+    gb.mmu.write_byte(0x2000, 0x02);
+    assert_eq!(gb.mmu.read_byte(0x4000), 2);
+}
+
+/// SRAM byte 0, read with RAM enabled
+fn sram(gb: &mut GameBoy) -> u8 {
+    gb.mmu.write_byte(0x0000, 0x0A);
+    gb.mmu.read_byte(0xA000)
+}
+
+#[test]
+fn component_resets_restore_post_boot_registers() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    let fresh: Vec<u8> = (0xFF00..=0xFF4B).map(|addr| gb.mmu.read_byte(addr)).collect();
+    gb.run_frame();
+    gb.mmu.write_byte(0xC000, 0x42);
+    
+    gb.mmu.write_byte(0xFF24, 0x00);
+    gb.mmu.write_byte(0xFF07, 0x05);
+    gb.mmu.write_byte(0xFF43, 0x10);
+    gb.mmu.write_byte(0xFF47, 0x1B);
+    gb.mmu.write_byte(0xFF00, 0x10);
+    gb.mmu.write_byte(0xFF02, 0x81);
+    let pc = gb.cpu.regs.pc;
+    
+    gb.reset_apu();
+    gb.reset_timer();
+    gb.reset_ppu();
+    gb.reset_joypad();
+    gb.reset_serial();
+    
+    // DIV keeps running from its post-boot value, and LY/STAT follow the
+    // PPU, which has just restarted the frame
+    for (i, addr) in (0xFF00..=0xFF4Bu16).enumerate() {
+        if !matches!(addr, 0xFF04 | 0xFF41 | 0xFF44) {
+            assert_eq!(gb.mmu.read_byte(addr), fresh[i], "{:04X}", addr);
+        }
+    }
+    assert_eq!(gb.mmu.read_byte(0xFF44), 0);
+    assert_eq!(gb.cpu.regs.pc, pc);
+    assert_eq!(gb.mmu.read_byte(0xC000), 0x42);
+}
+
+#[test]
+fn full_reset_matches_power_on() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    let fresh = gb.save_state();
+    gb.run_frame();
+    gb.reset();
+    assert_eq!(gb.save_state(), fresh);
+}
+
+#[test]
+fn power_cycle_matches_power_on() {
+    let config = GbConfig { ram_init: RamInit::Random(7), ..GbConfig::default() };
+    let mut gb = GameBoy::with_config(&mbc1_rom(true), config.clone()).unwrap();
+    let fresh = gb.save_state();
+    gb.run_frame();
+    scribble(&mut gb);
+    gb.mmu.write_byte(0x0000, 0x00);
+    
+    gb.power_cycle();
+    
+    // Battery-backed SRAM is the only thing that survives
+    let mut expected = GameBoy::with_config(&mbc1_rom(true), config).unwrap();
+    expected.mmu.cartridge_mut().ram_mut()[0] = 0x99;
+    assert_ne!(gb.save_state(), fresh);
+    assert_eq!(gb.save_state(), expected.save_state());
+}
+
+#[test]
+fn reset_keeps_ram_and_restarts_the_mapper() {
+    let mut gb = GameBoy::new(&mbc1_rom(false)).unwrap();
+    gb.run_frame();
+    scribble(&mut gb);
+    
+    gb.reset();
+    assert_eq!(gb.cpu.regs.pc, 0x0100);
+    assert_eq!(gb.mmu.read_byte(0xC000), 0x42);
+    assert_eq!(gb.mmu.read_byte(0xFF80), 0x24);
+    assert_eq!(gb.mmu.read_byte(0x4000), 1);
+    assert_eq!(gb.mmu.read_byte(0xA000), 0xFF, "RAM is disabled again");
+    assert_eq!(sram(&mut gb), 0x99);
+    
+    // Without a battery, switching off loses cartridge RAM too
+    gb.power_cycle();
+    assert_eq!(gb.mmu.read_byte(0xC000), 0x00);
+    assert_eq!(gb.mmu.read_byte(0xFF80), 0x00);
+    assert_eq!(sram(&mut gb), 0x00);
+}
+
+#[test]
+fn soft_reset_combo_keeps_ram() {
+    let mut gb = GameBoy::new(&rom()).unwrap();
+    gb.apply_config(GbConfig { soft_reset: SoftReset::Reset, ..GbConfig::default() });
+    gb.run_frame();
+    gb.mmu.write_byte(0xC000, 0x42);
+    
+    for button in [Button::A, Button::B, Button::Select, Button::Start] {
+        gb.press_button(button);
+    }
+    gb.run_frame();
+    assert_eq!(gb.frame_count(), 1, "reset at the start of the frame");
+    assert_eq!(gb.mmu.read_byte(0xC000), 0x42);
+}
@@ -0,0 +1,52 @@
+//! ROM header details, hashes and database lookup
+
+use gbemu_core::cartridge::MbcType;
+use gbemu_core::rom_info::{RomDatabase, RomTable};
+use gbemu_core::GameBoy;
+
+/// 32KB MBC1 ROM titled "HASHTEST" with a correct header checksum
+fn rom() -> Vec<u8> {
+    let mut rom = vec![0u8; 0x8000];
+    rom[0x134..0x13C].copy_from_slice(b"HASHTEST");
+    rom[0x147] = 0x01;
+    rom[0x14D] = rom[0x134..0x14D].iter().fold(0u8, |sum, &b| sum.wrapping_sub(b).wrapping_sub(1));
+    rom
+}
+
+#[test]
+fn header() {
+    let info = GameBoy::new(&rom()).unwrap().rom_info();
+    assert_eq!(info.title, "HASHTEST");
+    assert_eq!(info.mbc, MbcType::Mbc1);
+    assert_eq!(info.rom_size, 0x8000);
+    assert!(info.header_checksum_valid);
+    assert_eq!(info.hashes.crc32, 0xC8FA_8C1E);
+    assert_eq!(info.hashes.sha1, "9307902ff299635ec2f546b7371c4591f02ef81d");
+    
+    let mut bad = rom();
+    bad[0x14D] ^= 1;
+    assert!(!GameBoy::new(&bad).unwrap().rom_info().header_checksum_valid);
+}
+
+#[test]
+fn lookup_by_sha1_then_crc32() {
+    let mut info = GameBoy::new(&rom()).unwrap().rom_info();
+    let json = format!(
+        r#"[{{"name": "By SHA-1", "sha1": "{}", "verified": true}},
+            {{"name": "By CRC", "region": "Japan", "crc32": "{:08X}"}}]"#,
+        info.hashes.sha1.to_uppercase(),
+        info.hashes.crc32,
+    );
+    let table = RomTable::from_json(&json).unwrap();
+    assert_eq!(table.len(), 2);
+    
+    let entry = info.identify(&table).unwrap();
+    assert_eq!(entry.name, "By SHA-1");
+    assert!(entry.verified);
+    
+    let crc_only = RomTable::from_json(&format!(r#"[{{"name": "By CRC", "crc32": "{:08x}"}}]"#, info.hashes.crc32)).unwrap();
+    assert_eq!(crc_only.lookup(&info.hashes).unwrap().name, "By CRC");
+    
+    assert!(RomTable::from_json(r#"[{"name": "No hash"}]"#).is_err());
+    assert!(RomTable::from_json(r#"[{"name": "Bad", "sha1": "xyz"}]"#).is_err());
+}
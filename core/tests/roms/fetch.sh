@@ -0,0 +1,9 @@
+#!/bin/bash
+# Download the test ROMs listed in README.md into this directory. Commit
+# each one with its .hash file once a run matches the reference image.
+set -e
+
+cd "$(dirname "$0")"
+
+curl -fLo dmg-acid2.gb https://github.com/mattcurrie/dmg-acid2/releases/download/v1.0/dmg-acid2.gb
+curl -fLo cgb-acid2.gbc https://github.com/mattcurrie/cgb-acid2/releases/download/v1.0/cgb-acid2.gbc
@@ -0,0 +1,195 @@
+//! Save state compatibility checks
+
+use gbemu_core::{GameBoy, Peripheral, SaveState, StateError};
+
+fn rom(cgb: bool) -> Vec<u8> {
+    let mut rom = vec![0u8; 0x10000];
+    rom[0x143] = if cgb { 0x80 } else { 0x00 };
+    rom[0x147] = 0x03;
+    rom[0x148] = 0x01;
+    rom[0x149] = 0x02;
+    rom
+}
+
+#[test]
+fn mmu_state_carries_cartridge_banking() {
+    let mut gb = GameBoy::new(&rom(false)).unwrap();
+    gb.mmu.write_byte(0x2000, 0x03);
+    gb.mmu.write_byte(0x0000, 0x0A);
+    gb.mmu.write_byte(0xA000, 0x5A);
+    
+    let mut other = GameBoy::new(&rom(false)).unwrap();
+    other.mmu.load_state(gb.mmu.state()).unwrap();
+    assert_eq!(other.mmu.cartridge().rom_banks(), (0, 3));
+    assert_eq!(other.mmu.read_byte(0xA000), 0x5A);
+}
+
+#[test]
+fn incompatible_states_are_rejected() {
+    let cgb = GameBoy::new(&rom(true)).unwrap();
+    let mut dmg = GameBoy::new(&rom(false)).unwrap();
+    dmg.mmu.write_byte(0xC000, 0x42);
+    
+    let error = dmg.mmu.load_state(cgb.mmu.state()).unwrap_err();
+    assert!(matches!(error, StateError::ModelMismatch { .. }));
+    assert!(dmg.load_state(&cgb.save_state()).is_err());
+    assert_eq!(dmg.mmu.read_byte(0xC000), 0x42);
+    
+    let mut state = dmg.mmu.state();
+    state.wram.truncate(0x1000);
+    assert_eq!(dmg.mmu.load_state(state).unwrap_err(),
+               StateError::SizeMismatch { area: "WRAM", expected: 0x2000, found: 0x1000 });
+}
+
+#[test]
+fn quick_state_skips_video_memory() {
+    let mut gb = GameBoy::new(&rom(false)).unwrap();
+    gb.mmu.write_vram_bank(0, 0x10, &[0xFF; 16]).unwrap();
+    gb.run_frame();
+    gb.mmu.write_byte(0xC000, 0x11);
+    
+    let quick = gb.save_quick_state();
+    let full = gb.save_state();
+    // At least "0," for each of the 8KB of VRAM
+    assert!(full.len() - quick.len() > 2 * 0x2000, "{} vs {}", quick.len(), full.len());
+    
+    gb.run_frame();
+    gb.mmu.write_byte(0xC000, 0x22);
+    gb.load_state(&quick).unwrap();
+    assert_eq!(gb.mmu.read_byte(0xC000), 0x11);
+    assert_eq!(gb.mmu.read_byte(0x8010), 0xFF);
+    
+    // Resuming into the same game ends up where the full state would
+    let mut reference = GameBoy::new(&rom(false)).unwrap();
+    reference.load_state(&full).unwrap();
+    gb.run_frame();
+    reference.run_frame();
+    assert_eq!(gb.save_state(), reference.save_state());
+}
+
+/// A CGB game with background palette 0 programmed through BCPD: color
+/// 0 (what an empty screen shows) red, color 1 green. The index is left
+/// on auto-increment at color 2.
+fn programmed_palettes() -> GameBoy {
+    let mut gb = GameBoy::new(&rom(true)).unwrap();
+    gb.mmu.write_byte(0xFF68, 0x80);
+    for byte in [0x1F, 0x00, 0xE0, 0x03] {
+        gb.mmu.write_byte(0xFF69, byte);
+    }
+    gb.mmu.write_byte(0xFF6A, 0x3E);
+    gb.mmu.write_byte(0xFF6B, 0x55);
+    gb
+}
+
+#[test]
+fn cgb_palettes_survive_save_state() {
+    let mut gb = programmed_palettes();
+    let state = gb.save_state();
+    
+    let mut loaded = GameBoy::new(&rom(true)).unwrap();
+    loaded.load_state(&state).unwrap();
+    assert_eq!((loaded.cgb_palette(0), loaded.cgb_palette(1)), (0x001F, 0x03E0));
+    assert_eq!((loaded.mmu.read_byte(0xFF68), loaded.mmu.read_byte(0xFF6A)), (0xC4, 0x7E));
+    assert_eq!(loaded.mmu.read_byte(0xFF6B), 0x55);
+    
+    // Both draw the same red frame, and auto-increment carries on
+    gb.run_frame();
+    loaded.run_frame();
+    assert_eq!(loaded.framebuffer()[..4], [0xFF, 0x00, 0x00, 0xFF]);
+    assert_eq!(loaded.frame_hash(), gb.frame_hash());
+    loaded.mmu.write_byte(0xFF69, 0x00);
+    assert_eq!(loaded.mmu.read_byte(0xFF68), 0xC5);
+}
+
+#[test]
+fn legacy_states_keep_palette_indices_and_ram() {
+    let gb = programmed_palettes();
+    let mut legacy: serde_json::Value = serde_json::from_slice(&gb.save_state()).unwrap();
+    
+    // Before palette RAM moved to the I/O bus: the indices were I/O bytes
+    // and the PPU held its own copy of the RAM
+    let mmu = legacy["mmu"].as_object_mut().unwrap();
+    mmu.remove("palettes");
+    mmu.remove("lcd");
+    mmu["io"][0x68] = 0x83.into();
+    mmu["io"][0x6A] = 0x05.into();
+    let mut bg = vec![0xFFu8; 64];
+    bg[8..10].copy_from_slice(&[0x00, 0x7C]);
+    legacy["ppu"]["bg_palette_data"] = bg.into();
+    
+    let converted = SaveState::from_legacy_json(&serde_json::to_vec(&legacy).unwrap()).unwrap();
+    let mut loaded = GameBoy::new(&rom(true)).unwrap();
+    loaded.load_state(&converted).unwrap();
+    assert_eq!((loaded.mmu.read_byte(0xFF68), loaded.mmu.read_byte(0xFF6A)), (0xC3, 0x45));
+    assert_eq!(loaded.cgb_palette(4), 0x7C00);
+    assert_eq!(loaded.cgb_palette(0x20), 0x7FFF, "no object RAM in the state");
+}
+
+/// Saves one byte and only accepts states where it is below 0x80
+struct Counter(u8);
+
+impl Peripheral for Counter {
+    fn name(&self) -> &str {
+        "counter"
+    }
+    
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Some(vec![self.0])
+    }
+    
+    fn check_state(&self, data: &[u8]) -> Result<(), String> {
+        match data {
+            [value] if *value < 0x80 => Ok(()),
+            _ => Err("bad counter".to_string()),
+        }
+    }
+    
+    fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+        self.0 = data[0];
+        Ok(())
+    }
+}
+
+#[test]
+fn rejected_states_change_nothing() {
+    let mut gb = GameBoy::new(&rom(false)).unwrap();
+    gb.add_peripheral(Box::new(Counter(1))).unwrap();
+    gb.mmu.write_byte(0xC000, 0x11);
+    gb.run_frame();
+    let mut state: serde_json::Value = serde_json::from_slice(&gb.save_state()).unwrap();
+    
+    gb.mmu.write_byte(0xC000, 0x22);
+    gb.run_frame();
+    let before = gb.save_state();
+    
+    // Each of these fails after the CPU and memory parts were read
+    let mut truncated = state.clone();
+    truncated["ppu"]["indexed"].as_array_mut().unwrap().pop();
+    let mut short = state.clone();
+    short["ppu"]["framebuffer"] = serde_json::json!([1, 0, 0, 0, 0]);
+    state["peripherals"]["counter"] = serde_json::json!([0x80]);
+    let cases = [
+        (truncated, StateError::Truncated { area: "indexed framebuffer" }),
+        (short, StateError::SizeMismatch { area: "framebuffer", expected: 160 * 144 * 4, found: 4 }),
+        (state, StateError::Peripheral { name: "counter".to_string(), message: "bad counter".to_string() }),
+    ];
+    for (state, error) in cases {
+        assert_eq!(gb.load_state(&serde_json::to_vec(&state).unwrap()), Err(error));
+        assert_eq!(gb.save_state(), before);
+    }
+    
+    assert!(matches!(gb.load_state(b"{}"), Err(StateError::Parse(_))));
+    assert_eq!(gb.save_state(), before);
+}
+
+#[test]
+fn peripheral_state_round_trips() {
+    let mut gb = GameBoy::new(&rom(false)).unwrap();
+    gb.add_peripheral(Box::new(Counter(0x12))).unwrap();
+    let state = gb.save_state();
+    
+    let mut loaded = GameBoy::new(&rom(false)).unwrap();
+    loaded.add_peripheral(Box::new(Counter(0))).unwrap();
+    loaded.load_state(&state).unwrap();
+    assert_eq!(loaded.peripheral("counter").unwrap().save_state(), Some(vec![0x12]));
+}
@@ -0,0 +1,73 @@
+//! Serial transfer timing on the internal clock
+
+use gbemu_core::serial::Serial;
+
+/// Serial port with `SB` = 0 and a transfer started with `SC` = `control`
+fn start(cgb: bool, control: u8) -> Serial {
+    let mut serial = Serial::new();
+    serial.set_cgb_mode(cgb);
+    serial.write_data(0x00);
+    serial.write_control(control);
+    serial
+}
+
+/// Step one cycle at a time from `div` until the transfer completes;
+/// returns the DIV counter value it completed at
+fn run(serial: &mut Serial, mut div: u16) -> u16 {
+    loop {
+        let done = serial.step(1, div);
+        div = div.wrapping_add(1);
+        if done {
+            return div;
+        }
+        assert_eq!(serial.read_control() & 0x80, 0x80, "SC bit 7 cleared early at {:#06X}", div);
+    }
+}
+
+#[test]
+fn normal_clock_shifts_on_div_bit_8_falling_edges() {
+    let mut serial = start(false, 0x81);
+    assert_eq!(run(&mut serial, 0), 0x1000);
+    // Nothing connected: the input floats high
+    assert_eq!(serial.read_data(), 0xFF);
+    assert_eq!(serial.read_control(), 0x7F);
+    
+    // Bits come at DIV edges, not a full period after the SC write
+    let mut serial = start(false, 0x81);
+    assert!(!serial.step(0x7F, 0x180));
+    assert_eq!(serial.read_data(), 0x00);
+    assert!(!serial.step(1, 0x1FF));
+    assert_eq!(serial.read_data(), 0x01);
+    assert_eq!(run(&mut serial, 0x200), 0x1000);
+}
+
+#[test]
+fn cgb_fast_clock_shifts_on_div_bit_3() {
+    let mut serial = start(true, 0x83);
+    assert_eq!(run(&mut serial, 0), 0x80);
+    assert_eq!(serial.read_control(), 0x7F);
+    
+    // Started mid-period, the first bit comes at the next edge
+    let mut serial = start(true, 0x83);
+    assert_eq!(run(&mut serial, 0x0C), 0x80);
+    
+    // DMG has no fast clock: SC bit 1 is ignored
+    let mut serial = start(false, 0x83);
+    assert_eq!(serial.read_control(), 0xFF);
+    assert_eq!(run(&mut serial, 0), 0x1000);
+}
+
+#[test]
+fn external_clock_transfers_wait_for_the_partner() {
+    let mut serial = start(false, 0x80);
+    assert!(!serial.step(0x10000 - 1, 0));
+    assert_eq!(serial.read_control(), 0xFE);
+    
+    for _ in 0..7 {
+        serial.external_clock(0);
+    }
+    assert_eq!(serial.read_control() & 0x80, 0x80);
+    serial.external_clock(1);
+    assert!(serial.step(4, 0));
+    assert_eq!((serial.read_data(), serial.read_control()), (0x01, 0x7E));
+}
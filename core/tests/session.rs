@@ -0,0 +1,165 @@
+//! Link sessions between several instances, and the DMG-07 protocol
+
+mod common;
+
+use gbemu_core::session::FourPlayerAdapter;
+use gbemu_core::{GameBoy, Session};
+
+/// Load `data` into SB, start a transfer with `control`, then idle. An
+/// internal clock transfer starts a few instructions later, so the other
+/// side is already waiting for its first bit, as games arrange it.
+fn transfer_rom(data: u8, control: u8) -> Vec<u8> {
+    let delay = if control & 0x01 != 0 { 8 } else { 0 };
+    let mut program = vec![0x3E, data, 0xE0, 0x01]; // ld a, data; ldh (SB), a
+    program.extend(std::iter::repeat(0x00).take(delay));
+    program.extend([
+        0x3E, control, 0xE0, 0x02, // ld a, control; ldh (SC), a
+        0x18, 0xFE, // jr @
+    ]);
+    common::rom_with(&program)
+}
+
+#[test]
+fn cable_exchanges_bytes_both_ways() {
+    let master = GameBoy::new(&transfer_rom(0x42, 0x81)).unwrap();
+    let slave = GameBoy::new(&transfer_rom(0x99, 0x80)).unwrap();
+    let mut session = Session::new(master, slave);
+    session.run_frame();
+    
+    for (index, received) in [(0, 0x99), (1, 0x42)] {
+        let gb = session.player_mut(index);
+        assert_eq!(gb.mmu.read_byte(0xFF01), received, "player {}", index + 1);
+        assert_eq!(gb.mmu.read_byte(0xFF02) & 0x80, 0, "transfer finished");
+        assert_ne!(gb.mmu.read_byte(0xFF0F) & 0x08, 0, "serial interrupt");
+    }
+}
+
+#[test]
+fn external_clock_waits_without_a_master() {
+    // Both sides waiting for the other's clock: nothing moves
+    let a = GameBoy::new(&transfer_rom(0x42, 0x80)).unwrap();
+    let b = GameBoy::new(&transfer_rom(0x99, 0x80)).unwrap();
+    let mut session = Session::new(a, b);
+    session.run_frame();
+    
+    for (index, sent) in [(0, 0x42), (1, 0x99)] {
+        let gb = session.player_mut(index);
+        assert_eq!(gb.mmu.read_byte(0xFF01), sent);
+        assert_ne!(gb.mmu.read_byte(0xFF02) & 0x80, 0);
+        assert_eq!(gb.mmu.read_byte(0xFF0F) & 0x08, 0);
+    }
+}
+
+#[test]
+fn session_player_counts() {
+    let gb = || GameBoy::new(&common::rom()).unwrap();
+    assert!(Session::with_players(vec![gb()]).is_err());
+    assert!(Session::with_players(vec![gb(), gb(), gb()]).is_err());
+    assert!(Session::with_adapter(Vec::new()).is_err());
+    assert!(Session::with_adapter((0..5).map(|_| gb()).collect()).is_err());
+    assert_eq!(Session::with_adapter((0..4).map(|_| gb()).collect()).unwrap().player_count(), 4);
+}
+
+/// Wait for the adapter's next byte slot, answering with `sent` (one
+/// byte per connected player). Returns the bytes the adapter sent.
+fn exchange(adapter: &mut FourPlayerAdapter, players: usize, sent: &[u8]) -> Vec<u8> {
+    while !adapter.advance(4) {}
+    let received = (0..players).map(|player| adapter.byte_for(player)).collect();
+    adapter.receive(sent);
+    received
+}
+
+/// Answer pings until the adapter starts transmission, configuring the
+/// link with `rate` and `size`
+fn start_transmission(adapter: &mut FourPlayerAdapter, players: usize, rate: u8, size: u8) {
+    for byte in [0x88, 0x88, rate, size] {
+        exchange(adapter, players, &vec![byte; players]);
+    }
+    for _ in 0..4 {
+        exchange(adapter, players, &vec![0xAA; players]);
+    }
+    for _ in 0..4 {
+        assert_eq!(exchange(adapter, players, &vec![0; players]), vec![0xCC; players]);
+    }
+}
+
+#[test]
+fn dmg07_ping_reports_ports() {
+    let mut adapter = FourPlayerAdapter::new(3);
+    for _ in 0..2 {
+        assert_eq!(exchange(&mut adapter, 3, &[0x88; 3]), [0xFE; 3]);
+        for _ in 0..3 {
+            assert_eq!(exchange(&mut adapter, 3, &[0x88; 3]), [0x71, 0x72, 0x73]);
+        }
+    }
+    
+    // Only a whole ping answered with 0xAA ends the phase
+    for byte in [0xAA, 0xAA, 0x00, 0xAA] {
+        exchange(&mut adapter, 3, &[byte; 3]);
+    }
+    assert_eq!(exchange(&mut adapter, 3, &[0xAA; 3]), [0xFE; 3]);
+}
+
+#[test]
+fn dmg07_ping_byte_timing() {
+    let mut adapter = FourPlayerAdapter::new(1);
+    assert!(!adapter.advance(0x1FFF));
+    assert!(adapter.advance(1));
+    
+    // A byte plus the ping gap
+    assert!(!adapter.advance(0x1000 + 0x2000 - 1));
+    assert!(adapter.advance(1));
+}
+
+#[test]
+fn dmg07_transmission_rounds() {
+    let mut adapter = FourPlayerAdapter::new(2);
+    start_transmission(&mut adapter, 2, 0x02, 2);
+    
+    // Each round is 4 * SIZE bytes; the first one has nothing to relay
+    let mut round = |sent: [[u8; 2]; 2]| -> Vec<Vec<u8>> {
+        (0..8)
+            .map(|i| {
+                let bytes: Vec<u8> = sent.iter().map(|packet| packet.get(i).copied().unwrap_or(0)).collect();
+                exchange(&mut adapter, 2, &bytes)
+            })
+            .collect()
+    };
+    assert!(round([[0x11, 0x12], [0x21, 0x22]]).iter().all(|bytes| bytes == &[0, 0]));
+    
+    // The next round relays every packet to everyone, missing players as 0
+    let relayed = round([[0x13, 0x14], [0x23, 0x24]]);
+    let expected = [0x11, 0x12, 0x21, 0x22, 0, 0, 0, 0];
+    for (bytes, &byte) in relayed.iter().zip(&expected) {
+        assert_eq!(bytes, &[byte, byte]);
+    }
+    assert_eq!(round([[0; 2]; 2])[0], [0x13, 0x13]);
+}
+
+#[test]
+fn dmg07_transmission_gap_follows_rate() {
+    let mut adapter = FourPlayerAdapter::new(1);
+    start_transmission(&mut adapter, 1, 0x03, 1);
+    
+    // Right after the slot that started transmission: a byte plus RATE gaps
+    while !adapter.advance(4) {}
+    assert!(!adapter.advance(0x1000 + 3 * 0x400 - 1));
+    assert!(adapter.advance(1));
+}
+
+#[test]
+fn dmg07_player1_ff_packet_restarts_ping() {
+    let mut adapter = FourPlayerAdapter::new(2);
+    start_transmission(&mut adapter, 2, 0x00, 1);
+    
+    // Player 2 sending 0xFF doesn't matter, only player 1 does
+    for _ in 0..4 {
+        exchange(&mut adapter, 2, &[0x00, 0xFF]);
+    }
+    assert_ne!(exchange(&mut adapter, 2, &[0xFF, 0x00]), [0xFE; 2]);
+    for _ in 0..3 {
+        exchange(&mut adapter, 2, &[0x00, 0x00]);
+    }
+    assert_eq!(exchange(&mut adapter, 2, &[0x88; 2]), [0xFE; 2]);
+    assert_eq!(exchange(&mut adapter, 2, &[0x88; 2]), [0x31, 0x32]);
+}
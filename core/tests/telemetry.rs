@@ -0,0 +1,43 @@
+//! Per-frame write telemetry
+
+use gbemu_core::{FrameActivity, GameBoy};
+
+#[test]
+fn counts_writes_per_frame() {
+    // MBC1 with four ROM banks
+    let mut rom = vec![0u8; 0x10000];
+    rom[0x147] = 0x01;
+    rom[0x148] = 0x01;
+    rom[0x101..0x104].copy_from_slice(&[0xC3, 0x50, 0x01]);
+    rom[0x150..0x166].copy_from_slice(&[
+        0x3E, 0x02, 0xEA, 0x00, 0x20, // bank 2
+        0xEA, 0x00, 0x20,             // bank 2 again: no switch
+        0x3E, 0x80, 0xE0, 0x26,       // NR52
+        0xE0, 0x05,                   // TIMA
+        0x3E, 0xC0, 0xE0, 0x46,       // OAM DMA from 0xC000
+        0x76, 0x00,                   // halt
+        0x18, 0xFC,                   // loop
+    ]);
+    
+    let mut gb = GameBoy::new(&rom).unwrap();
+    assert!(gb.stats().is_none());
+    gb.set_telemetry(true);
+    
+    gb.run_frame();
+    let expected = FrameActivity {
+        rom_writes: 2,
+        bank_switches: 1,
+        oam_dma: 1,
+        audio_writes: 1,
+        timer_writes: 1,
+        ..FrameActivity::default()
+    };
+    assert_eq!(gb.stats().unwrap().last_frame, expected);
+    
+    // Halted with interrupts disabled, nothing more is written
+    gb.run_frame();
+    let stats = gb.stats().unwrap();
+    assert_eq!(stats.last_frame, FrameActivity::default());
+    assert_eq!(stats.total, expected);
+    assert_eq!(stats.frames, 2);
+}